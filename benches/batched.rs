@@ -0,0 +1,46 @@
+//! Per-point cost of the pair scan with dense covariances for dimensions from 2 to 100, where the
+//! scan evaluates whole chunks of the line at once above `d = 2`. Run with
+//! `cargo bench --bench batched`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use ndarray::{Array2, Array3};
+use moebius::{olr_with_stats, OlrConfig, OlrStats};
+
+const DIMS: [usize; 6] = [2, 3, 5, 10, 30, 100];
+const N_COMP: usize = 3;
+const REPEATS: usize = 5;
+
+fn mixture(dim: usize) -> (Vec<f64>, Array2<f64>, Array3<f64>) {
+    let w = vec![1.0 / N_COMP as f64; N_COMP];
+    let means = Array2::from_shape_fn((N_COMP, dim), |(i, k)| i as f64 * 0.5 + (k as f64 * 0.1).sin());
+    let covs = Array3::from_shape_fn((N_COMP, dim, dim), |(i, r, c)| {
+        if r == c { 1.0 + 0.1 * i as f64 } else { 0.3 / (1.0 + r.abs_diff(c) as f64) }
+    });
+
+    (w, means, covs)
+}
+
+/// Fastest of `REPEATS` runs, in seconds per density evaluation of the pair.
+fn per_point(dim: usize, config: &OlrConfig) -> f64 {
+    let (w, means, covs) = mixture(dim);
+
+    (0..REPEATS)
+        .map(|_| {
+            let start = Instant::now();
+            let mut stats = OlrStats::default();
+            black_box(olr_with_stats(&w, &means, &covs, config, &mut stats).unwrap());
+
+            start.elapsed().as_secs_f64() / stats.pdf_evaluations as f64
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    let config = OlrConfig { n_points: 4000, n_threads: Some(1), ..OlrConfig::default() };
+
+    for dim in DIMS {
+        println!("d = {:>3}: {:>10.1} ns per pdf evaluation", dim, per_point(dim, &config) * 1e9);
+    }
+}
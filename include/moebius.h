@@ -0,0 +1,39 @@
+#ifndef MOEBIUS_H
+#define MOEBIUS_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    MOEBIUS_OK = 0,
+    MOEBIUS_VALIDATION = 1,
+    MOEBIUS_SINGULAR_COVARIANCE = 2,
+    MOEBIUS_CANCELLED = 3,
+    MOEBIUS_INTERNAL = 4,
+    MOEBIUS_PANIC = 5
+} MoebiusStatus;
+
+/*
+ * Pairwise OLR values of a mixture of n_comp components of dimension dim. means (n_comp * dim)
+ * and covs (n_comp * dim * dim) are row-major. out must hold n_comp * (n_comp - 1) / 2 values.
+ */
+int moebius_olr(const double *w, size_t n_comp, const double *means, size_t dim,
+                const double *covs, double *out, size_t out_len);
+
+/*
+ * Copies the message of the last failed call on this thread into buf, truncated to len - 1
+ * bytes and NUL-terminated. Returns the length of the full message.
+ */
+size_t moebius_last_error_message(char *buf, size_t len);
+
+/* Static name of a status code, e.g. "MOEBIUS_VALIDATION". */
+const char *moebius_error_name(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -1,3 +1,5 @@
+import json
+
 import numpy as np
 
 from .moebius import *
@@ -27,4 +29,21 @@ def parameters_from_flat(components: int, dims: int, data: list[float]):
 def olr_from_flat(components: int, dims: int, data: list[float]) -> list[float]:
     weights, means, covs = parameters_from_flat(components, dims, data)
 
-    return olr(weights, means, covs)
+    return olr_list(weights, means, covs)
+
+def export_sklearn(gmm, path: str):
+    """
+    Writes parameters of a fitted sklearn.mixture.GaussianMixture to JSON file which can be read
+    by the command line tool (--input-format sklearn). Covariances are stored in the layout of
+    gmm.covariances_ for gmm.covariance_type.
+    """
+
+    data = {
+        "weights": np.asarray(gmm.weights_, dtype=float).tolist(),
+        "means": np.asarray(gmm.means_, dtype=float).tolist(),
+        "covariances": np.asarray(gmm.covariances_, dtype=float).tolist(),
+        "covariance_type": gmm.covariance_type,
+    }
+
+    with open(path, "w") as f:
+        json.dump(data, f)
@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::error::MoebiusError;
+use crate::mixture::GaussianMixture;
+use crate::olr::{olr_with_config, OlrConfig};
+
+/// Computes OLR values of every mixture on up to `jobs` threads. Mixtures are processed
+/// independently and results are returned in input order. With more than one job, every mixture
+/// is computed on a single thread.
+pub fn olr_batch(mixtures: &[GaussianMixture], jobs: usize) -> Vec<Result<Vec<f64>, MoebiusError>> {
+    let jobs = jobs.clamp(1, mixtures.len().max(1));
+    if jobs == 1 {
+        return mixtures.iter().map(|gmm| gmm.olr()).collect();
+    }
+
+    let serial = OlrConfig { n_threads: Some(1), ..OlrConfig::default() };
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<Result<Vec<f64>, MoebiusError>>> = (0..mixtures.len())
+        .map(|_| None)
+        .collect();
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| scope.spawn(|| {
+                let mut done = Vec::new();
+                loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    if idx >= mixtures.len() {
+                        break;
+                    }
+                    let gmm = &mixtures[idx];
+                    done.push((idx, olr_with_config(&gmm.weights, &gmm.means, &gmm.covs, &serial)));
+                }
+
+                done
+            }))
+            .collect();
+
+        for worker in workers {
+            for (idx, result) in worker.join().unwrap() {
+                results[idx] = Some(result);
+            }
+        }
+    });
+
+    results.into_iter().map(|result| result.unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::batch::olr_batch;
+    use crate::mixture::GaussianMixture;
+
+    fn one_dim(distance: f64) -> GaussianMixture {
+        GaussianMixture::new(
+            vec![0.5, 0.5],
+            arr2(&[[0.0], [distance]]),
+            arr3(&[[[0.5]], [[0.5]]])
+        ).unwrap()
+    }
+
+    #[test]
+    fn parallel_matches_serial_order() {
+        let mut mixtures: Vec<_> = [1.0, 2.0, 3.0, 4.0, 5.0].iter().map(|d| one_dim(*d)).collect();
+        mixtures[2].covs[[0, 0, 0]] = -1.0;
+
+        let serial = olr_batch(&mixtures, 1);
+        let parallel = olr_batch(&mixtures, 3);
+
+        assert_eq!(serial.len(), parallel.len());
+        for (s, p) in serial.iter().zip(parallel.iter()) {
+            match (s, p) {
+                (Ok(s), Ok(p)) => assert_eq!(s, p),
+                (Err(_), Err(_)) => {}
+                _ => panic!("serial and parallel results differ"),
+            }
+        }
+        assert!(parallel[2].is_err());
+    }
+
+    #[test]
+    fn empty_batch() {
+        assert!(olr_batch(&[], 4).is_empty());
+    }
+}
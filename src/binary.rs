@@ -0,0 +1,215 @@
+//! Compact binary serialization of pairwise results, for passing large matrices between
+//! processes.
+//!
+//! A stream starts with a 16-byte header
+//!
+//! ```text
+//! magic "MOLR" | version: u16 | flags: u16 | n_components: u64
+//! ```
+//!
+//! followed by the condensed OLR values as `f64`. If `FLAG_DETAILS` is set, three blocks with
+//! one entry per pair follow: `ln_peak` and `ln_saddle` as `f64`, `NaN` standing for `None`, and
+//! a flag byte with `extreme_range` in bit 0 and a timed-out status in bit 1. All numbers are little-endian.
+//! The refinement of `Resolution::Auto` is not stored and reads back as `None`.
+
+use std::io::{self, Read, Write};
+
+use crate::error::MoebiusError;
+use crate::olr::{PairDetail, PairStatus};
+use crate::result::{checked_condensed_len, OlrMatrix};
+
+pub const BINARY_MAGIC: [u8; 4] = *b"MOLR";
+/// Version written by `write_to`. Streams of any other version are rejected.
+pub const BINARY_VERSION: u16 = 1;
+/// Set in the header of streams carrying per-pair details.
+pub const FLAG_DETAILS: u16 = 1;
+
+impl OlrMatrix {
+    /// Writes the matrix in the binary format, without details.
+    pub fn write_to(&self, out: &mut impl Write) -> Result<(), MoebiusError> {
+        write_stream(self, None, out)
+    }
+
+    /// Writes the matrix followed by the details of every pair, in the order of `values`.
+    pub fn write_with_details(&self, details: &[PairDetail], out: &mut impl Write) -> Result<(), MoebiusError> {
+        if details.len() != self.values().len() {
+            return Err(MoebiusError::Shape(format!(
+                "{} details given for {} pairs", details.len(), self.values().len()
+            )));
+        }
+
+        write_stream(self, Some(details), out)
+    }
+
+    /// Reads a matrix written by `write_to` or `write_with_details`, skipping any details.
+    pub fn read_from(input: &mut impl Read) -> Result<OlrMatrix, MoebiusError> {
+        Ok(OlrMatrix::read_with_details(input)?.0)
+    }
+
+    /// Reads a matrix together with its details, `None` if the stream carries none.
+    pub fn read_with_details(input: &mut impl Read) -> Result<(OlrMatrix, Option<Vec<PairDetail>>), MoebiusError> {
+        let header: [u8; 16] = read_block(input, 16, "header")?.try_into().unwrap();
+        if header[..4] != BINARY_MAGIC {
+            return Err(MoebiusError::Parse("not an OLR stream: bad magic bytes".to_string()));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != BINARY_VERSION {
+            return Err(MoebiusError::Parse(format!(
+                "unsupported OLR stream version {}, expected {}", version, BINARY_VERSION
+            )));
+        }
+        let flags = u16::from_le_bytes([header[6], header[7]]);
+        if flags & !FLAG_DETAILS != 0 {
+            return Err(MoebiusError::Parse(format!("unknown flags {:#06x} in OLR stream", flags)));
+        }
+        let n_components = usize::try_from(u64::from_le_bytes(header[8..].try_into().unwrap()))
+            .ok()
+            .filter(|&n| checked_condensed_len(n).is_some())
+            .ok_or_else(|| MoebiusError::Parse("number of components in OLR stream is too large".to_string()))?;
+        let n_pairs = checked_condensed_len(n_components).unwrap();
+
+        let values = read_f64s(input, n_pairs, "values")?;
+        let details = if flags & FLAG_DETAILS != 0 {
+            let ln_peaks = read_f64s(input, n_pairs, "peaks")?;
+            let ln_saddles = read_f64s(input, n_pairs, "saddles")?;
+            let pair_flags = read_block(input, n_pairs, "pair flags")?;
+            let optional = |v: f64| if v.is_nan() { None } else { Some(v) };
+
+            Some((0..n_pairs)
+                .map(|k| PairDetail {
+                    value: values[k],
+                    ln_peak: optional(ln_peaks[k]),
+                    ln_saddle: optional(ln_saddles[k]),
+                    extreme_range: pair_flags[k] & 1 != 0,
+                    status: if pair_flags[k] & 2 != 0 { PairStatus::TimedOut } else { PairStatus::Complete },
+                    ..PairDetail::complete(values[k])
+                })
+                .collect())
+        } else {
+            None
+        };
+
+        Ok((OlrMatrix::new(n_components, values)?, details))
+    }
+}
+
+fn write_stream(matrix: &OlrMatrix, details: Option<&[PairDetail]>, out: &mut impl Write) -> Result<(), MoebiusError> {
+    let flags = if details.is_some() { FLAG_DETAILS } else { 0 };
+    let mut bytes = Vec::with_capacity(16 + 8 * matrix.values().len());
+    bytes.extend_from_slice(&BINARY_MAGIC);
+    bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&flags.to_le_bytes());
+    bytes.extend_from_slice(&(matrix.n_components() as u64).to_le_bytes());
+    bytes.extend(matrix.values().iter().flat_map(|v| v.to_le_bytes()));
+
+    if let Some(details) = details {
+        let or_nan = |v: Option<f64>| v.unwrap_or(f64::NAN).to_le_bytes();
+        bytes.extend(details.iter().flat_map(|detail| or_nan(detail.ln_peak)));
+        bytes.extend(details.iter().flat_map(|detail| or_nan(detail.ln_saddle)));
+        bytes.extend(details.iter().map(|detail| detail.extreme_range as u8 | ((detail.status == PairStatus::TimedOut) as u8) << 1));
+    }
+
+    out.write_all(&bytes)?;
+
+    Ok(())
+}
+
+/// Reads exactly `len` bytes. The buffer grows as data arrives, so that a corrupted length in the
+/// header cannot allocate more memory than the stream holds.
+fn read_block(input: &mut impl Read, len: usize, name: &str) -> Result<Vec<u8>, MoebiusError> {
+    let mut bytes = Vec::new();
+    input.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < len {
+        return Err(MoebiusError::Io(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("OLR stream is truncated: {} holds {} of {} bytes", name, bytes.len(), len)
+        )));
+    }
+
+    Ok(bytes)
+}
+
+fn read_f64s(input: &mut impl Read, len: usize, name: &str) -> Result<Vec<f64>, MoebiusError> {
+    let n_bytes = len.checked_mul(8)
+        .ok_or_else(|| MoebiusError::Parse(format!("{} of {} entries in OLR stream are too large to read", name, len)))?;
+    let bytes = read_block(input, n_bytes, name)?;
+
+    Ok(bytes.chunks_exact(8).map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binary::BINARY_VERSION;
+    use crate::error::MoebiusError;
+    use crate::olr::{PairDetail, PairStatus};
+    use crate::result::OlrMatrix;
+
+    fn matrix() -> OlrMatrix {
+        OlrMatrix::new(4, vec![0.1, 1.0, 0.25, 1e-300, 0.5, f64::MIN_POSITIVE]).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut bytes = Vec::new();
+        matrix().write_to(&mut bytes).unwrap();
+        assert_eq!(16 + 8 * 6, bytes.len());
+        assert_eq!(matrix(), OlrMatrix::read_from(&mut bytes.as_slice()).unwrap());
+        assert_eq!(None, OlrMatrix::read_with_details(&mut bytes.as_slice()).unwrap().1);
+
+        let details: Vec<PairDetail> = matrix().values().iter().enumerate()
+            .map(|(k, &value)| PairDetail {
+                value,
+                ln_peak: (k % 2 == 0).then_some(-1.5 * k as f64),
+                ln_saddle: (k % 2 == 0).then_some(-2.5 * k as f64),
+                extreme_range: k == 3,
+                status: if k >= 4 { PairStatus::TimedOut } else { PairStatus::Complete },
+                ..PairDetail::complete(value)
+            })
+            .collect();
+        let mut bytes = Vec::new();
+        matrix().write_with_details(&details, &mut bytes).unwrap();
+        let (read, read_details) = OlrMatrix::read_with_details(&mut bytes.as_slice()).unwrap();
+        assert_eq!(matrix(), read);
+        assert_eq!(Some(details.clone()), read_details);
+        assert_eq!(matrix(), OlrMatrix::read_from(&mut bytes.as_slice()).unwrap());
+        assert!(matrix().write_with_details(&details[1..], &mut Vec::new()).is_err());
+
+        let mut bytes = Vec::new();
+        OlrMatrix::new(1, vec![]).unwrap().write_to(&mut bytes).unwrap();
+        assert_eq!(0, OlrMatrix::read_from(&mut bytes.as_slice()).unwrap().values().len());
+    }
+
+    #[test]
+    fn rejects_corrupted_streams() {
+        let mut bytes = Vec::new();
+        matrix().write_to(&mut bytes).unwrap();
+
+        for len in [0, 10, 16, bytes.len() - 1] {
+            let error = OlrMatrix::read_from(&mut &bytes[..len]).unwrap_err();
+            assert!(error.to_string().contains("truncated"), "{}", error);
+        }
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4..6].copy_from_slice(&(BINARY_VERSION + 1).to_le_bytes());
+        let error = OlrMatrix::read_from(&mut wrong_version.as_slice()).unwrap_err();
+        assert!(error.to_string().contains("unsupported OLR stream version 2"), "{}", error);
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(OlrMatrix::read_from(&mut wrong_magic.as_slice()).is_err());
+
+        let mut unknown_flags = bytes.clone();
+        unknown_flags[6] = 0x80;
+        assert!(OlrMatrix::read_from(&mut unknown_flags.as_slice()).is_err());
+
+        let mut huge = bytes.clone();
+        huge[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(OlrMatrix::read_from(&mut huge.as_slice()).is_err());
+
+        // The pairs of 2^32 components fit into a usize, but their bytes do not.
+        let mut overflowing = bytes;
+        overflowing[8..16].copy_from_slice(&(1u64 << 32).to_le_bytes());
+        let error = OlrMatrix::read_from(&mut &overflowing[..16]).unwrap_err();
+        assert!(matches!(error, MoebiusError::Parse(_)), "{}", error);
+    }
+}
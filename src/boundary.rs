@@ -0,0 +1,118 @@
+//! Decision boundary between two weighted Gaussian components.
+//!
+//! The boundary is the set of points where `w1 N(x; m1, C1) = w2 N(x; m2, C2)`. Taking the
+//! logarithm of the ratio of both sides gives the quadratic discriminant
+//!
+//! ```text
+//! f(x) = x^T A x + b^T x + c,    A = (P2 - P1) / 2,    b = P1 m1 - P2 m2,
+//! c = (m2^T P2 m2 - m1^T P1 m1) / 2 + ln(w1 / w2) - (ln |C1| - ln |C2|) / 2
+//! ```
+//!
+//! with the precision matrices `P = C^-1`. `f` is positive where the first component dominates,
+//! and equal covariances cancel `A`, leaving the linear boundary of linear discriminant analysis.
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::linalg::SpdFactor;
+
+/// Coefficients of the quadratic discriminant `x^T a x + b^T x + c`, see the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundaryParams {
+    pub a: Array2<f64>,
+    pub b: Array1<f64>,
+    pub c: f64,
+}
+
+impl BoundaryParams {
+    /// Value of the discriminant at `x`: zero on the boundary, the logarithm of the ratio of the
+    /// weighted densities elsewhere.
+    pub fn eval(&self, x: ArrayView1<f64>) -> f64 {
+        x.dot(&self.a.dot(&x)) + self.b.dot(&x) + self.c
+    }
+}
+
+/// Quadratic discriminant of the components `w1 N(m1, C1)` and `w2 N(m2, C2)`. Weights must be
+/// positive and the covariances positive definite.
+pub fn decision_boundary(
+    w1: f64,
+    m1: &Array1<f64>,
+    c1: &Array2<f64>,
+    w2: f64,
+    m2: &Array1<f64>,
+    c2: &Array2<f64>
+) -> Result<BoundaryParams, MoebiusError> {
+    let dim = m1.len();
+    if m2.len() != dim || c1.dim() != (dim, dim) || c2.dim() != (dim, dim) {
+        return Err(MoebiusError::Shape(format!(
+            "means of lengths {} and {} and covariances of shapes {:?} and {:?} do not match",
+            dim, m2.len(), c1.dim(), c2.dim()
+        )));
+    }
+    for (name, w) in [("w1", w1), ("w2", w2)] {
+        if !(w.is_finite() && w > 0.0) {
+            return Err(MoebiusError::Distribution(format!("{} must be positive and finite, got {}", name, w)));
+        }
+    }
+    let factor_1 = SpdFactor::new(c1.view(), "covariance of the first component")?;
+    let factor_2 = SpdFactor::new(c2.view(), "covariance of the second component")?;
+
+    let precision = |factor: &SpdFactor| {
+        let mut precision = Array2::zeros((dim, dim));
+        for (k, unit) in Array2::<f64>::eye(dim).outer_iter().enumerate() {
+            precision.column_mut(k).assign(&factor.solve(unit));
+        }
+
+        precision
+    };
+    let (precision_1, precision_2) = (precision(&factor_1), precision(&factor_2));
+    let (solved_1, solved_2) = (factor_1.solve(m1.view()), factor_2.solve(m2.view()));
+
+    let a = (&precision_2 - &precision_1) / 2.0;
+    let b = &solved_1 - &solved_2;
+    let c = (m2.dot(&solved_2) - m1.dot(&solved_1)) / 2.0 + (w1 / w2).ln() - (factor_1.ln_det() - factor_2.ln_det()) / 2.0;
+
+    Ok(BoundaryParams { a, b, c })
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr1, arr2, Array2};
+    use crate::boundary::decision_boundary;
+    use crate::gaussian::Gaussian;
+
+    #[test]
+    fn equal_covariances_give_linear_boundary() {
+        let cov = arr2(&[[1.0, 0.3], [0.3, 0.5]]);
+        let (m1, m2) = (arr1(&[0.0, 1.0]), arr1(&[2.0, -1.0]));
+
+        let params = decision_boundary(0.5, &m1, &cov, 0.5, &m2, &cov).unwrap();
+        assert_abs_diff_eq!(Array2::<f64>::zeros((2, 2)), params.a, epsilon = 1e-12);
+        // Equal weights put the midpoint on the boundary, with the first mean on its positive side.
+        assert_abs_diff_eq!(0.0, params.eval(arr1(&[1.0, 0.0]).view()), epsilon = 1e-12);
+        assert!(params.eval(m1.view()) > 0.0);
+
+        // A heavier first component moves the boundary towards the second mean.
+        let params = decision_boundary(0.8, &m1, &cov, 0.2, &m2, &cov).unwrap();
+        assert_abs_diff_eq!(0.8f64.ln() - 0.2f64.ln(), params.eval(arr1(&[1.0, 0.0]).view()), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn discriminant_is_log_density_ratio() {
+        let (m1, c1) = (arr1(&[0.5, -1.0]), arr2(&[[2.0, 0.4], [0.4, 0.7]]));
+        let (m2, c2) = (arr1(&[1.5, 0.5]), arr2(&[[0.6, -0.1], [-0.1, 1.3]]));
+        let params = decision_boundary(0.3, &m1, &c1, 0.7, &m2, &c2).unwrap();
+        let gaussian_1 = Gaussian::new(m1.view(), c1.view(), 0, None).unwrap();
+        let gaussian_2 = Gaussian::new(m2.view(), c2.view(), 1, None).unwrap();
+
+        for x in [[0.0, 0.0], [1.0, 2.0], [-3.0, 0.5]] {
+            let x = arr1(&x);
+            let expected = (0.3 * gaussian_1.pdf(&x)).ln() - (0.7 * gaussian_2.pdf(&x)).ln();
+            assert_abs_diff_eq!(expected, params.eval(x.view()), epsilon = 1e-10);
+        }
+
+        assert!(decision_boundary(0.0, &m1, &c1, 0.7, &m2, &c2).is_err());
+        assert!(decision_boundary(0.3, &m1, &c1, 0.7, &arr1(&[1.0]), &c2).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! OLR of every pair within a bounding box.
+//!
+//! `olr_bounded` scans the line of every pair on the grid of `olr`, but only evaluates the points
+//! inside the hyper-rectangle `[lower, upper]`, e.g. the domain of the data. The box is convex, so
+//! the points inside form one run of the grid and the profile is that of the clipped line. Its
+//! ends are no extrema, as in `olr`: a component whose mode lies outside the box forms no peak,
+//! and a pair with a single peak inside the box is merged there. A line that misses the box has
+//! no peak at all, and both are resolved by `config.no_saddle`.
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::gaussian::{ComponentCache, Covariances, Gaussian};
+use crate::olr::{
+    check_pairs, check_shapes, ln_pdf_pair, pair_log_weights, prepare_covs, profile_extrema_in, refine_resolution,
+    resolve_no_saddle, scan_line_in, scan_pairs, values, OlrConfig, PairClock, PairDetail, PairStatus, MAX_PREALLOCATED_POINTS
+};
+use crate::result::condensed_len;
+use crate::validation::{check_finite, normalize_weights};
+use crate::workspace::Workspace;
+
+/// OLR of every pair, in the order of `olr`, from the profile of the line between the means
+/// clipped to the box `[lower, upper]` as described in the module documentation. Bounds may be
+/// infinite, but `lower[k] < upper[k]` must hold in every dimension.
+pub fn olr_bounded(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    lower: &[f64],
+    upper: &[f64],
+    config: &OlrConfig
+) -> Result<Vec<f64>, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    check_bounds(means.ncols(), lower, upper)?;
+    config.validate()?;
+    check_pairs(w.len(), config)?;
+    let w = &*normalize_weights(w, config)?;
+
+    let covs = prepare_covs(covs.view(), config);
+    let cache = ComponentCache::new(means.view(), Covariances::Dense(covs.view()));
+    if config.strict {
+        cache.check_all()?;
+    }
+    let pre_score = |i: usize, j: usize| match cache.pair(i, j) {
+        Ok((gaussian_i, gaussian_j)) => {
+            -(gaussian_i.quadratic(means.row(j)).sqrt() + gaussian_j.quadratic(means.row(i)).sqrt()) / 2.0
+        }
+        Err(_) => f64::NEG_INFINITY,
+    };
+
+    let points = vec![config.n_points; condensed_len(w.len())];
+    scan_pairs(w.len(), config, &points, None, pre_score, None, None, |i, j, pair_config, workspace| {
+        let densities = cache.pair(i, j)?;
+
+        bounded_pair(w, means.view(), densities, (i, j), (lower, upper), pair_config, workspace)
+    }).map(values)
+}
+
+/// Fails unless `lower` and `upper` have `dim` entries each and `lower[k] < upper[k]`.
+fn check_bounds(dim: usize, lower: &[f64], upper: &[f64]) -> Result<(), MoebiusError> {
+    if lower.len() != dim || upper.len() != dim {
+        return Err(MoebiusError::Shape(format!(
+            "lower has {} and upper {} entries, expected {} for the dimension of the means", lower.len(), upper.len(), dim
+        )));
+    }
+    if let Some(k) = (0..dim).find(|&k| lower[k].partial_cmp(&upper[k]) != Some(std::cmp::Ordering::Less)) {
+        return Err(MoebiusError::Distribution(format!(
+            "lower must be below upper in every dimension, got {} and {} in dimension {}", lower[k], upper[k], k
+        )));
+    }
+
+    Ok(())
+}
+
+/// Profile extrema of pair `(i, j)` as in `olr_pair`, from the points of the line inside the box
+/// `(lower, upper)`.
+fn bounded_pair(
+    w: &[f64],
+    means: ArrayView2<f64>,
+    (density_i, density_j): (&Gaussian, &Gaussian),
+    (i, j): (usize, usize),
+    (lower, upper): (&[f64], &[f64]),
+    config: &OlrConfig,
+    workspace: &mut Workspace
+) -> Result<(PairDetail, usize), MoebiusError> {
+    let (ln_w1, ln_w2) = pair_log_weights(w[i], w[j]);
+    let clock = PairClock::start(config);
+    let inside = |point: &Array1<f64>| point.iter().zip(lower.iter().zip(upper)).all(|(x, (lo, hi))| lo <= x && x <= hi);
+
+    refine_resolution(config, |config| resolve_no_saddle(i, j, config, |config| {
+        let Workspace { profile, point, delta, extrema, scratch, .. } = &mut *workspace;
+        profile.clear();
+        profile.reserve(config.grid_len().min(MAX_PREALLOCATED_POINTS));
+        // The line leaves the box at most once after entering it, and the scan stops there.
+        let mut left = false;
+        let complete = scan_line_in(means.row(i), means.row(j), config, (point, delta), |point| {
+            if !inside(point) {
+                left = !profile.is_empty();
+                return !left;
+            }
+            if clock.expired(profile.len()) {
+                return false;
+            }
+            profile.push(ln_pdf_pair(point, ln_w1, density_i, ln_w2, density_j, scratch));
+            true
+        });
+
+        let mut detail = profile_extrema_in(profile, &config.detector, extrema);
+        if !complete && !left {
+            detail.status = PairStatus::TimedOut;
+        }
+
+        detail
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::bounded::olr_bounded;
+    use crate::error::MoebiusError;
+    use crate::olr::{olr_with_config, OlrConfig};
+
+    #[test]
+    fn excluding_a_mode_merges_the_pair() {
+        let w = [0.5, 0.5];
+        let means = arr2(&[[0.0, 0.0], [4.0, 0.0]]);
+        let covs = arr3(&[[[1.0, 0.0], [0.0, 1.0]], [[1.0, 0.0], [0.0, 1.0]]]);
+        let config = OlrConfig::default();
+        let olr = |lower: &[f64], upper: &[f64]| olr_bounded(&w, &means, &covs, lower, upper, &config).unwrap()[0];
+
+        // A box around the whole line gives the value of `olr`, bounds reaching into the extension
+        // of the line barely change it.
+        let unbounded = olr(&[f64::NEG_INFINITY; 2], &[f64::INFINITY; 2]);
+        assert!((unbounded - olr_with_config(&w, &means, &covs, &config).unwrap()[0]).abs() < 1e-12);
+        assert!(unbounded < 0.5, "{}", unbounded);
+        assert!((olr(&[-1.0, -1.0], &[4.5, 1.0]) - unbounded).abs() < 1e-9);
+
+        // Cutting the line between the saddle at 2 and the second mode leaves a single peak.
+        assert_eq!(1.0, olr(&[-1.0, -1.0], &[3.0, 1.0]));
+        assert_eq!(1.0, olr(&[1.0, -1.0], &[5.0, 1.0]));
+        // A box the line misses has no peak at all.
+        assert_eq!(1.0, olr(&[-1.0, 1.0], &[5.0, 2.0]));
+    }
+
+    #[test]
+    fn bounds_are_validated() {
+        let w = [0.5, 0.5];
+        let means = arr2(&[[0.0], [4.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]]]);
+        let config = OlrConfig::default();
+
+        assert!(matches!(olr_bounded(&w, &means, &covs, &[0.0, 0.0], &[1.0, 1.0], &config), Err(MoebiusError::Shape(_))));
+        for (lower, upper) in [(1.0, 1.0), (2.0, 1.0), (f64::NAN, 1.0)] {
+            let err = olr_bounded(&w, &means, &covs, &[lower], &[upper], &config).unwrap_err();
+            assert!(err.to_string().contains("dimension 0"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Optional features this build was compiled with, for telling builds apart when debugging
+//! differences between environments, and for choosing code paths by what a build supports.
+
+/// Cargo features the crate was compiled with, in alphabetical order. Parallel scans through
+/// rayon are always built in and not listed.
+pub fn features() -> Vec<&'static str> {
+    let mut enabled = Vec::new();
+    if cfg!(feature = "cli") {
+        enabled.push("cli");
+    }
+    if cfg!(feature = "invariants") {
+        enabled.push("invariants");
+    }
+    if cfg!(feature = "serde") {
+        enabled.push("serde");
+    }
+
+    enabled
+}
+
+/// What this build supports, see `capabilities`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Version of the crate.
+    pub version: &'static str,
+    /// Same as `features`.
+    pub features: Vec<&'static str>,
+    /// Whether pairs can be computed on several threads, see `OlrConfig::n_threads`.
+    pub parallel: bool,
+    /// Ways of computing the OLR of a pair: `"line"` for the scan of `olr`, `"exact"` for
+    /// `olr_exact` and `"multi"` for `olr_multi`.
+    pub methods: Vec<&'static str>,
+    /// Names accepted by `Detector::from_str`.
+    pub detectors: Vec<&'static str>,
+    /// Layouts covariances can be given in: the variants of `Covariance`, and the `tied` and
+    /// `spherical` layouts of scikit-learn with the `serde` feature.
+    pub covariance_inputs: Vec<&'static str>,
+    /// Widest SIMD instruction set the build was compiled for, e.g. `"avx2"`. The kernels are
+    /// vectorized by the compiler rather than dispatched at runtime, so the CPU running the build
+    /// does not change it.
+    pub simd: Option<&'static str>,
+}
+
+/// Capabilities of this build, from its Cargo features and target.
+pub fn capabilities() -> Capabilities {
+    let mut covariance_inputs = vec!["full", "diag", "block_diagonal", "low_rank"];
+    if cfg!(feature = "serde") {
+        covariance_inputs.extend(["tied", "spherical"]);
+    }
+
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        features: features(),
+        parallel: true,
+        methods: vec!["line", "exact", "multi"],
+        detectors: vec!["sign_change", "parabolic"],
+        covariance_inputs,
+        simd: simd(),
+    }
+}
+
+fn simd() -> Option<&'static str> {
+    if cfg!(target_feature = "avx512f") {
+        Some("avx512f")
+    } else if cfg!(target_feature = "avx2") {
+        Some("avx2")
+    } else if cfg!(target_feature = "sse4.2") {
+        Some("sse4.2")
+    } else if cfg!(target_feature = "sse2") {
+        Some("sse2")
+    } else if cfg!(target_feature = "neon") {
+        Some("neon")
+    } else if cfg!(target_feature = "simd128") {
+        Some("simd128")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build_info::{capabilities, features};
+    use crate::extrema::Detector;
+
+    #[test]
+    fn lists_enabled_features() {
+        let enabled = features();
+
+        assert_eq!(cfg!(feature = "cli"), enabled.contains(&"cli"));
+        assert_eq!(cfg!(feature = "serde"), enabled.contains(&"serde"));
+        assert_eq!(cfg!(feature = "invariants"), enabled.contains(&"invariants"));
+        // `cli` implies `serde`.
+        assert!(!enabled.contains(&"cli") || enabled.contains(&"serde"));
+        assert!(enabled.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn capabilities_reflect_the_build() {
+        let capabilities = capabilities();
+
+        assert_eq!(features(), capabilities.features);
+        assert_eq!(env!("CARGO_PKG_VERSION"), capabilities.version);
+        assert!(capabilities.detectors.iter().all(|name| name.parse::<Detector>().is_ok()));
+        assert_eq!(cfg!(feature = "serde"), capabilities.covariance_inputs.contains(&"spherical"));
+        if cfg!(target_arch = "x86_64") {
+            // SSE2 is part of the x86-64 baseline.
+            assert!(capabilities.simd.is_some());
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Caching of the pairwise OLR matrix on disk.
+//!
+//! The cache file is a JSON record `{"hash": "...", "n_components": n, "values": [...]}`, where
+//! `hash` identifies the weights, means and covariances the values were computed from. The hash
+//! is computed from the bit patterns of the inputs, so it is stable between runs and platforms
+//! but any change of an input, however small, invalidates the cache.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use ndarray::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::MoebiusError;
+use crate::olr::{olr_with_config, OlrConfig};
+use crate::result::OlrMatrix;
+
+/// Bumped whenever the computation changes in a way that invalidates existing cache files.
+const CACHE_VERSION: u64 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheRecord {
+    hash: String,
+    n_components: usize,
+    values: Vec<f64>,
+}
+
+/// Same as `try_olr`, reusing the result stored at `path` if it was computed from the same
+/// inputs. Otherwise the matrix is computed and written to `path`, replacing any previous file.
+/// Files that cannot be parsed are treated as stale.
+pub fn olr_matrix_cached(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    path: impl AsRef<Path>
+) -> Result<OlrMatrix, MoebiusError> {
+    let path = path.as_ref();
+    let hash = format!("{:016x}", input_hash(w, means, covs));
+
+    match fs::read_to_string(path) {
+        Ok(contents) => {
+            if let Ok(record) = serde_json::from_str::<CacheRecord>(&contents) {
+                if record.hash == hash {
+                    return OlrMatrix::new(record.n_components, record.values);
+                }
+            }
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let matrix = OlrMatrix::new(w.len(), olr_with_config(w, means, covs, &OlrConfig::default())?)?;
+    let record = CacheRecord { hash, n_components: matrix.n_components(), values: matrix.values().to_vec() };
+    let contents = serde_json::to_string(&record).map_err(|e| MoebiusError::Parse(e.to_string()))?;
+    fs::write(path, contents)?;
+
+    Ok(matrix)
+}
+
+/// 64-bit FNV-1a hash of the shapes and bit patterns of the inputs.
+fn input_hash(w: &[f64], means: &Array2<f64>, covs: &Array3<f64>) -> u64 {
+    let shape = [w.len(), means.nrows(), means.ncols(), covs.len_of(Axis(1)), covs.len_of(Axis(2))];
+    let words = std::iter::once(CACHE_VERSION)
+        .chain(shape.iter().map(|&len| len as u64))
+        .chain(w.iter().chain(means.iter()).chain(covs.iter()).map(|v| v.to_bits()));
+
+    words.fold(0xcbf29ce484222325, |hash, word| {
+        word.to_le_bytes().iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ndarray::{arr2, arr3};
+    use crate::cache::{input_hash, olr_matrix_cached};
+    use crate::olr::try_olr;
+
+    #[test]
+    fn second_call_reads_cache() {
+        let path = std::env::temp_dir().join(format!("moebius-cache-{}.json", std::process::id()));
+        let w = vec![0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0, 0.0], [1.5, 0.5], [4.0, -1.0]]);
+        let covs = arr3(&[[[1.0, 0.2], [0.2, 0.8]], [[0.7, 0.0], [0.0, 1.1]], [[1.2, -0.3], [-0.3, 0.9]]]);
+        let _ = fs::remove_file(&path);
+
+        let computed = olr_matrix_cached(&w, &means, &covs, &path).unwrap();
+        assert_eq!(try_olr(w.clone(), means.clone(), covs.clone()).unwrap(), computed.values());
+        let cached = olr_matrix_cached(&w, &means, &covs, &path).unwrap();
+        assert_eq!(computed, cached);
+
+        // Values planted in the file are returned as long as the hash matches.
+        let contents = fs::read_to_string(&path).unwrap();
+        let planted = contents.replace(&serde_json::to_string(computed.values()).unwrap(), "[0.25,0.5,0.75]");
+        fs::write(&path, planted).unwrap();
+        assert_eq!(vec![0.25, 0.5, 0.75], olr_matrix_cached(&w, &means, &covs, &path).unwrap().into_values());
+
+        // Any change of the inputs recomputes the matrix and replaces the file.
+        let mut shifted = means.clone();
+        shifted[[2, 0]] += 1e-12;
+        let recomputed = olr_matrix_cached(&w, &shifted, &covs, &path).unwrap();
+        assert_eq!(try_olr(w.clone(), shifted, covs.clone()).unwrap(), recomputed.values());
+
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(computed, olr_matrix_cached(&w, &means, &covs, &path).unwrap());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn hash_depends_on_shape() {
+        let w = [1.0];
+
+        assert_ne!(
+            input_hash(&w, &arr2(&[[0.0, 0.0]]), &arr3(&[[[1.0, 0.0], [0.0, 1.0]]])),
+            input_hash(&w, &arr2(&[[0.0], [0.0]]), &arr3(&[[[1.0], [0.0]], [[0.0], [1.0]]]))
+        );
+    }
+}
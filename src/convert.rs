@@ -0,0 +1,209 @@
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+
+/// Converts `n` rows of `d` means into an `n x d` array, rejecting ragged rows.
+pub fn means_from_nested(v: Vec<Vec<f64>>) -> Result<Array2<f64>, MoebiusError> {
+    vec_to_array2(v)
+}
+
+/// Converts `n` matrices of `d x d` into an `n x d x d` array, rejecting ragged or non-square
+/// matrices.
+pub fn covs_from_nested(v: Vec<Vec<Vec<f64>>>) -> Result<Array3<f64>, MoebiusError> {
+    let covs = vec_to_array3(v)?;
+    let (_, nrows, ncols) = covs.dim();
+    if nrows != ncols {
+        return Err(MoebiusError::Shape(format!(
+            "covariance matrices must be square, got {} x {}", nrows, ncols
+        )));
+    }
+
+    Ok(covs)
+}
+
+/// Means of `n` one-dimensional components as an `n x 1` array.
+pub fn means_from_scalars(v: Vec<f64>) -> Array2<f64> {
+    let n_comp = v.len();
+
+    Array2::from_shape_vec((n_comp, 1), v).unwrap()
+}
+
+/// Variances of `n` one-dimensional components as an `n x 1 x 1` array.
+pub fn covs_from_variances(v: Vec<f64>) -> Array3<f64> {
+    let n_comp = v.len();
+
+    Array3::from_shape_vec((n_comp, 1, 1), v).unwrap()
+}
+
+/// Upper triangles of `n` symmetric `d x d` covariances, packed row by row into an
+/// `n x d (d + 1) / 2` array as `cov[np.triu_indices(d)]` in NumPy. The lower triangles are
+/// dropped without checking that they mirror the upper ones.
+pub fn pack_covariances(covs: &Array3<f64>) -> Array2<f64> {
+    let (n_comp, dim, _) = covs.dim();
+    let mut packed = Array2::zeros((n_comp, packed_len(dim)));
+    for (cov, mut row) in covs.outer_iter().zip(packed.outer_iter_mut()) {
+        let upper = (0..dim).flat_map(|r| (r..dim).map(move |c| (r, c)));
+        for (value, (r, c)) in row.iter_mut().zip(upper) {
+            *value = cov[[r, c]];
+        }
+    }
+
+    packed
+}
+
+/// Inverse of `pack_covariances`: the `n x d x d` symmetric covariances of packed upper triangles,
+/// with `d` inferred from the number of columns.
+pub fn unpack_covariances(packed: &Array2<f64>) -> Result<Array3<f64>, MoebiusError> {
+    let dim = packed_dim(packed.ncols())?;
+    let mut covs = Array3::zeros((packed.nrows(), dim, dim));
+    for (row, mut cov) in packed.outer_iter().zip(covs.outer_iter_mut()) {
+        unpack_into(row, &mut cov);
+    }
+
+    Ok(covs)
+}
+
+/// Number of values `d (d + 1) / 2` of a packed `d x d` covariance.
+pub(crate) fn packed_len(dim: usize) -> usize {
+    dim * (dim + 1) / 2
+}
+
+/// Dimension of packed covariances of `len` values each.
+pub(crate) fn packed_dim(len: usize) -> Result<usize, MoebiusError> {
+    let dim = ((((8 * len + 1) as f64).sqrt() - 1.0) / 2.0).round() as usize;
+    if packed_len(dim) != len {
+        return Err(MoebiusError::Shape(format!(
+            "packed covariances have {} values, which is no d (d + 1) / 2", len
+        )));
+    }
+
+    Ok(dim)
+}
+
+/// Writes the symmetric matrix of the packed upper triangle `row` into `cov`.
+pub(crate) fn unpack_into(row: ArrayView1<f64>, cov: &mut ArrayViewMut2<f64>) {
+    let dim = cov.nrows();
+    let upper = (0..dim).flat_map(|r| (r..dim).map(move |c| (r, c)));
+    for (&value, (r, c)) in row.iter().zip(upper) {
+        cov[[r, c]] = value;
+        cov[[c, r]] = value;
+    }
+}
+
+pub(crate) fn vec_to_array2<T: Clone>(v: Vec<Vec<T>>) -> Result<Array2<T>, MoebiusError> {
+    let nrows = v.len();
+    let ncols = v.first().map_or(0, |row| row.len());
+    let mut data = Vec::with_capacity(nrows * ncols);
+    for (i, row) in v.iter().enumerate() {
+        if row.len() != ncols {
+            return Err(MoebiusError::Shape(format!(
+                "row {} has {} entries, expected {}", i, row.len(), ncols
+            )));
+        }
+        data.extend_from_slice(row);
+    }
+
+    Ok(Array2::from_shape_vec((nrows, ncols), data).unwrap())
+}
+
+pub(crate) fn vec_to_array3<T: Clone>(v: Vec<Vec<Vec<T>>>) -> Result<Array3<T>, MoebiusError> {
+    let nrows = v.len();
+    let ncols = v.first().map_or(0, |row| row.len());
+    let nitems = v.first().and_then(|row| row.first()).map_or(0, |col| col.len());
+    let mut data = Vec::with_capacity(nrows * ncols * nitems);
+    for (i, row) in v.iter().enumerate() {
+        if row.len() != ncols {
+            return Err(MoebiusError::Shape(format!(
+                "matrix {} has {} rows, expected {}", i, row.len(), ncols
+            )));
+        }
+        for (j, col) in row.iter().enumerate() {
+            if col.len() != nitems {
+                return Err(MoebiusError::Shape(format!(
+                    "row {} of matrix {} has {} entries, expected {}", j, i, col.len(), nitems
+                )));
+            }
+            data.extend_from_slice(col);
+        }
+    }
+
+    Ok(Array3::from_shape_vec((nrows, ncols, nitems), data).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3, Array2, Array3};
+    use approx::assert_abs_diff_eq;
+    use crate::convert::{covs_from_nested, covs_from_variances, means_from_nested, means_from_scalars, pack_covariances, unpack_covariances};
+    use crate::olr::olr;
+
+    #[test]
+    fn valid_means() {
+        let means = means_from_nested(vec![vec![1.0, 2.0], vec![3.0, 4.0]]).unwrap();
+
+        assert_eq!(arr2(&[[1.0, 2.0], [3.0, 4.0]]), means);
+    }
+
+    #[test]
+    fn valid_covs() {
+        let covs = covs_from_nested(vec![
+            vec![vec![1.0, 0.5], vec![0.5, 1.0]],
+            vec![vec![2.0, 0.0], vec![0.0, 2.0]]
+        ]).unwrap();
+
+        assert_eq!(arr3(&[[[1.0, 0.5], [0.5, 1.0]], [[2.0, 0.0], [0.0, 2.0]]]), covs);
+    }
+
+    #[test]
+    fn empty_inputs() {
+        assert_eq!(Array2::<f64>::zeros((0, 0)), means_from_nested(vec![]).unwrap());
+        assert_eq!(Array3::<f64>::zeros((0, 0, 0)), covs_from_nested(vec![]).unwrap());
+        assert_eq!(Array3::<f64>::zeros((1, 0, 0)), covs_from_nested(vec![vec![]]).unwrap());
+    }
+
+    #[test]
+    fn ragged_means() {
+        let err = means_from_nested(vec![vec![1.0, 2.0], vec![3.0]]).unwrap_err();
+
+        assert_eq!("invalid shape: row 1 has 1 entries, expected 2", err.to_string());
+    }
+
+    #[test]
+    fn ragged_covs() {
+        assert!(covs_from_nested(vec![
+            vec![vec![1.0, 0.5], vec![0.5, 1.0]],
+            vec![vec![2.0, 0.0]]
+        ]).is_err());
+
+        assert!(covs_from_nested(vec![
+            vec![vec![1.0, 0.5], vec![0.5, 1.0]],
+            vec![vec![2.0, 0.0], vec![0.0]]
+        ]).is_err());
+    }
+
+    #[test]
+    fn non_square_covs() {
+        assert!(covs_from_nested(vec![vec![vec![1.0, 0.5, 0.1], vec![0.5, 1.0, 0.1]]]).is_err());
+    }
+
+    #[test]
+    fn one_dim_promotion() {
+        let means = means_from_scalars(vec![5.0, 2.0]);
+        let covs = covs_from_variances(vec![0.5, 0.5]);
+
+        assert_eq!(arr2(&[[5.0], [2.0]]), means);
+        assert_eq!(arr3(&[[[0.5]], [[0.5]]]), covs);
+        assert_abs_diff_eq!(0.21077243773848037, olr(vec![0.5, 0.5], means, covs)[0], epsilon = 1e-4);
+    }
+
+    #[test]
+    fn packing_round_trip() {
+        let covs = arr3(&[[[1.0, 0.2, 0.3], [0.2, 2.0, 0.4], [0.3, 0.4, 3.0]], [[1.0, 0.0, 0.0], [0.0, 1.0, 0.5], [0.0, 0.5, 1.0]]]);
+
+        let packed = pack_covariances(&covs);
+        assert_eq!(arr2(&[[1.0, 0.2, 0.3, 2.0, 0.4, 3.0], [1.0, 0.0, 0.0, 1.0, 0.5, 1.0]]), packed);
+        assert_eq!(covs, unpack_covariances(&packed).unwrap());
+        assert_eq!(Array3::<f64>::zeros((2, 0, 0)), unpack_covariances(&Array2::zeros((2, 0))).unwrap());
+        assert!(unpack_covariances(&Array2::zeros((2, 4))).is_err());
+    }
+}
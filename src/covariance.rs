@@ -0,0 +1,174 @@
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+
+/// Covariance matrix of a single component.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Covariance {
+    /// Dense `d x d` matrix.
+    Full(Array2<f64>),
+    /// Variances of `d` independent dimensions.
+    Diagonal(Array1<f64>),
+    /// Groups of correlated dimensions, independent across groups. Every block lists the indices
+    /// of its dimensions and their `k x k` covariance matrix. The index groups must partition
+    /// `0..d`.
+    BlockDiagonal(Vec<(Vec<usize>, Array2<f64>)>),
+    /// `factors * factors^T + diag(diag)` with `d x q` factors, `q < d`, as in mixtures of factor
+    /// analyzers. Densities are evaluated with the Woodbury identity in `O(d q)` per point.
+    LowRank { factors: Array2<f64>, diag: Array1<f64> },
+}
+
+impl Covariance {
+    /// Number of dimensions.
+    pub fn dim(&self) -> usize {
+        match self {
+            Covariance::Full(cov) => cov.nrows(),
+            Covariance::Diagonal(variances) => variances.len(),
+            Covariance::BlockDiagonal(blocks) => blocks.iter().map(|(indices, _)| indices.len()).sum(),
+            Covariance::LowRank { diag, .. } => diag.len(),
+        }
+    }
+
+    /// Checks that the covariance describes a `dim`-dimensional matrix, for block-diagonal
+    /// covariances that the blocks partition the dimensions and for low-rank covariances that
+    /// there are fewer factors than dimensions.
+    pub fn validate(&self, dim: usize) -> Result<(), MoebiusError> {
+        match self {
+            Covariance::Full(cov) if cov.dim() != (dim, dim) => Err(MoebiusError::Shape(format!(
+                "covariance has shape {:?}, expected {:?}", cov.dim(), (dim, dim)
+            ))),
+            Covariance::Diagonal(variances) if variances.len() != dim => Err(MoebiusError::Shape(format!(
+                "{} variances given, expected {}", variances.len(), dim
+            ))),
+            Covariance::BlockDiagonal(blocks) => {
+                let mut covered = vec![false; dim];
+                for (k, (indices, cov)) in blocks.iter().enumerate() {
+                    if indices.is_empty() {
+                        return Err(MoebiusError::Shape(format!("block {} is empty", k)));
+                    }
+                    if cov.dim() != (indices.len(), indices.len()) {
+                        return Err(MoebiusError::Shape(format!(
+                            "block {} has {} indices, but its matrix has shape {:?}", k, indices.len(), cov.dim()
+                        )));
+                    }
+                    for &index in indices {
+                        match covered.get_mut(index) {
+                            None => return Err(MoebiusError::Shape(format!(
+                                "block {} contains index {}, but the dimension is {}", k, index, dim
+                            ))),
+                            Some(true) => return Err(MoebiusError::Shape(format!(
+                                "index {} appears in more than one block", index
+                            ))),
+                            Some(seen) => *seen = true,
+                        }
+                    }
+                }
+
+                match covered.iter().position(|seen| !seen) {
+                    Some(index) => Err(MoebiusError::Shape(format!("index {} is not covered by any block", index))),
+                    None => Ok(()),
+                }
+            }
+            Covariance::LowRank { factors, diag } => {
+                if diag.len() != dim || factors.nrows() != dim {
+                    return Err(MoebiusError::Shape(format!(
+                        "low-rank covariance has factors of shape {:?} and {} diagonal entries, expected {} rows",
+                        factors.dim(), diag.len(), dim
+                    )));
+                }
+                if factors.ncols() >= dim {
+                    return Err(MoebiusError::Shape(format!(
+                        "low-rank covariance has {} factors, expected fewer than the dimension {}", factors.ncols(), dim
+                    )));
+                }
+
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Dense `d x d` matrix.
+    pub fn to_dense(&self) -> Array2<f64> {
+        match self {
+            Covariance::Full(cov) => cov.clone(),
+            Covariance::Diagonal(variances) => Array2::from_diag(variances),
+            Covariance::BlockDiagonal(blocks) => {
+                let mut dense = Array2::zeros((self.dim(), self.dim()));
+                for (indices, cov) in blocks {
+                    for (r, &row) in indices.iter().enumerate() {
+                        for (c, &col) in indices.iter().enumerate() {
+                            dense[[row, col]] = cov[[r, c]];
+                        }
+                    }
+                }
+
+                dense
+            }
+            Covariance::LowRank { factors, diag } => factors.dot(&factors.t()) + Array2::from_diag(diag),
+        }
+    }
+
+    /// Applies `f` to every dense matrix of the covariance, or to the diagonal matrix of the
+    /// variances. Only the diagonal part of low-rank covariances is mapped.
+    pub(crate) fn map_matrices(&self, f: impl Fn(ArrayView2<f64>) -> Array2<f64>) -> Covariance {
+        match self {
+            Covariance::Full(cov) => Covariance::Full(f(cov.view())),
+            Covariance::Diagonal(variances) => Covariance::Diagonal(f(Array2::from_diag(variances).view()).diag().to_owned()),
+            Covariance::BlockDiagonal(blocks) => Covariance::BlockDiagonal(
+                blocks.iter().map(|(indices, cov)| (indices.clone(), f(cov.view()))).collect()
+            ),
+            Covariance::LowRank { factors, diag } => Covariance::LowRank {
+                factors: factors.clone(),
+                diag: f(Array2::from_diag(diag).view()).diag().to_owned(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2};
+    use crate::covariance::Covariance;
+
+    fn blocks() -> Covariance {
+        Covariance::BlockDiagonal(vec![
+            (vec![0, 2], arr2(&[[2.0, 0.5], [0.5, 1.0]])),
+            (vec![1], arr2(&[[3.0]])),
+        ])
+    }
+
+    #[test]
+    fn dense_from_blocks() {
+        assert_eq!(arr2(&[[2.0, 0.0, 0.5], [0.0, 3.0, 0.0], [0.5, 0.0, 1.0]]), blocks().to_dense());
+        assert_eq!(arr2(&[[1.0, 0.0], [0.0, 2.0]]), Covariance::Diagonal(arr1(&[1.0, 2.0])).to_dense());
+    }
+
+    #[test]
+    fn blocks_partition_dimensions() {
+        assert!(blocks().validate(3).is_ok());
+        assert!(blocks().validate(4).unwrap_err().to_string().contains("index 3 is not covered"));
+        assert!(blocks().validate(2).unwrap_err().to_string().contains("index 2"));
+
+        let overlapping = Covariance::BlockDiagonal(vec![
+            (vec![0, 1], arr2(&[[1.0, 0.0], [0.0, 1.0]])),
+            (vec![1], arr2(&[[1.0]])),
+        ]);
+        assert!(overlapping.validate(2).unwrap_err().to_string().contains("more than one block"));
+
+        let mismatched = Covariance::BlockDiagonal(vec![(vec![0, 1], arr2(&[[1.0]]))]);
+        assert!(mismatched.validate(2).is_err());
+    }
+
+    #[test]
+    fn low_rank() {
+        let cov = Covariance::LowRank { factors: arr2(&[[1.0], [2.0], [0.0]]), diag: arr1(&[0.5, 0.5, 1.0]) };
+
+        assert_eq!(arr2(&[[1.5, 2.0, 0.0], [2.0, 4.5, 0.0], [0.0, 0.0, 1.0]]), cov.to_dense());
+        assert!(cov.validate(3).is_ok());
+        assert!(cov.validate(2).is_err());
+
+        let full_rank = Covariance::LowRank { factors: arr2(&[[1.0, 0.0], [0.0, 1.0]]), diag: arr1(&[1.0, 1.0]) };
+        assert!(full_rank.validate(2).unwrap_err().to_string().contains("fewer than the dimension"));
+    }
+}
@@ -0,0 +1,98 @@
+use ndarray::concatenate;
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::mixture::GaussianMixture;
+use crate::olr::try_olr;
+use crate::result::OlrMatrix;
+
+/// OLR between every component of `a` and every component of `b`, as an `n_a x n_b` matrix. The
+/// value at `(i, j)` is the OLR of the two-component mixture of `a`'s component `i` and `b`'s
+/// component `j` with their original weights.
+pub fn olr_cross(a: &GaussianMixture, b: &GaussianMixture) -> Result<Array2<f64>, MoebiusError> {
+    if a.dim() != b.dim() {
+        return Err(MoebiusError::Shape(format!(
+            "mixtures have dimensions {} and {}", a.dim(), b.dim()
+        )));
+    }
+
+    let n_a = a.n_components();
+    let weights = a.weights.iter().chain(&b.weights).copied().collect::<Vec<_>>();
+    let means = concatenate![Axis(0), a.means, b.means];
+    let covs = concatenate![Axis(0), a.covs, b.covs];
+    let values = OlrMatrix::new(weights.len(), try_olr(weights, means, covs)?)?;
+
+    Ok(values.to_square().slice(s![..n_a, n_a..]).to_owned())
+}
+
+/// Same as `olr_cross` for mixtures fitted on different but overlapping feature sets: `a` is
+/// projected onto its dimensions `dims_a` and `b` onto `dims_b`, where `dims_a[k]` and
+/// `dims_b[k]` are the same feature.
+pub fn olr_cross_subspace(
+    a: &GaussianMixture,
+    b: &GaussianMixture,
+    dims_a: &[usize],
+    dims_b: &[usize]
+) -> Result<Array2<f64>, MoebiusError> {
+    if dims_a.len() != dims_b.len() {
+        return Err(MoebiusError::Shape(format!(
+            "subspaces have {} and {} dimensions", dims_a.len(), dims_b.len()
+        )));
+    }
+    if dims_a.is_empty() {
+        return Err(MoebiusError::Shape("subspaces are empty".to_string()));
+    }
+
+    olr_cross(&a.project(dims_a)?, &b.project(dims_b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::cross::{olr_cross, olr_cross_subspace};
+    use crate::mixture::GaussianMixture;
+
+    #[test]
+    fn projects_onto_shared_dimensions() {
+        // 3D model with features (x, y, z) and 2D model with features (z, x).
+        let a = GaussianMixture::new(
+            vec![0.4, 0.6],
+            arr2(&[[0.0, 5.0, 1.0], [2.0, -3.0, 0.0]]),
+            arr3(&[
+                [[1.0, 0.3, 0.2], [0.3, 2.0, 0.1], [0.2, 0.1, 1.5]],
+                [[0.8, 0.0, -0.1], [0.0, 1.0, 0.0], [-0.1, 0.0, 0.6]],
+            ])
+        ).unwrap();
+        let b = GaussianMixture::new(
+            vec![0.5, 0.5],
+            arr2(&[[0.5, 1.0], [3.0, -1.0]]),
+            arr3(&[[[1.0, 0.1], [0.1, 1.2]], [[0.7, 0.0], [0.0, 0.9]]])
+        ).unwrap();
+
+        let projected = olr_cross_subspace(&a, &b, &[0, 2], &[1, 0]).unwrap();
+
+        let a_2d = GaussianMixture::new(
+            vec![0.4, 0.6],
+            arr2(&[[0.0, 1.0], [2.0, 0.0]]),
+            arr3(&[[[1.0, 0.2], [0.2, 1.5]], [[0.8, -0.1], [-0.1, 0.6]]])
+        ).unwrap();
+        let b_2d = GaussianMixture::new(
+            vec![0.5, 0.5],
+            arr2(&[[1.0, 0.5], [-1.0, 3.0]]),
+            arr3(&[[[1.2, 0.1], [0.1, 1.0]], [[0.9, 0.0], [0.0, 0.7]]])
+        ).unwrap();
+
+        assert_eq!(olr_cross(&a_2d, &b_2d).unwrap(), projected);
+        assert_eq!((2, 2), projected.dim());
+    }
+
+    #[test]
+    fn rejects_invalid_subspaces() {
+        let a = GaussianMixture::new(vec![1.0], arr2(&[[0.0, 0.0]]), arr3(&[[[1.0, 0.0], [0.0, 1.0]]])).unwrap();
+
+        assert!(olr_cross_subspace(&a, &a, &[0, 1], &[0]).unwrap_err().to_string().contains("1 dimensions"));
+        assert!(olr_cross_subspace(&a, &a, &[0, 2], &[0, 1]).unwrap_err().to_string().contains("out of range"));
+        assert!(olr_cross_subspace(&a, &a, &[0, 0], &[0, 1]).unwrap_err().to_string().contains("twice"));
+        assert!(olr_cross(&a, &a.project(&[1]).unwrap()).is_err());
+    }
+}
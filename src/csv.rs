@@ -0,0 +1,136 @@
+//! Reading of mixtures from CSV tables with one row per component.
+//!
+//! The first line is a header and every following line holds
+//!
+//! ```text
+//! weight, mean_1, ..., mean_d, covariance entries
+//! ```
+//!
+//! where the covariance entries are the `d` variances with `CsvCovariance::Diag`, or the `d x d`
+//! matrix flattened row by row with `CsvCovariance::Full`. The dimension follows from the number
+//! of columns of the header. Blank lines are skipped and fields are trimmed; quoting is not
+//! supported.
+
+use std::str::FromStr;
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::mixture::GaussianMixture;
+
+/// Layout of the covariance columns of a CSV table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvCovariance {
+    /// `d` variances of a diagonal matrix.
+    Diag,
+    /// All `d * d` entries, row by row.
+    #[default]
+    Full,
+}
+
+impl FromStr for CsvCovariance {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "diag" => Ok(CsvCovariance::Diag),
+            "full" => Ok(CsvCovariance::Full),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown covariance layout {:?}, expected \"diag\" or \"full\"", s
+            ))),
+        }
+    }
+}
+
+impl CsvCovariance {
+    /// Dimension of the means of a table with `n_columns` columns.
+    fn dim(self, n_columns: usize) -> Option<usize> {
+        let covariance_columns = |d: usize| match self {
+            CsvCovariance::Diag => d,
+            CsvCovariance::Full => d * d,
+        };
+
+        (1..n_columns).find(|&d| 1 + d + covariance_columns(d) >= n_columns)
+            .filter(|&d| 1 + d + covariance_columns(d) == n_columns)
+    }
+}
+
+impl GaussianMixture {
+    /// Parses a CSV table laid out as described in the module documentation. Errors name the
+    /// line of the offending row.
+    pub fn from_csv_str(s: &str, covariance: CsvCovariance) -> Result<Self, MoebiusError> {
+        let mut lines = s.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let (_, header) = lines.next().ok_or_else(|| MoebiusError::Parse("CSV input is empty".to_string()))?;
+        let n_columns = header.split(',').count();
+        let dim = covariance.dim(n_columns).ok_or_else(|| MoebiusError::Shape(format!(
+            "line 1: {} columns do not match a weight, d means and {} covariance entries",
+            n_columns, match covariance { CsvCovariance::Diag => "d", CsvCovariance::Full => "d * d" }
+        )))?;
+
+        let mut weights = Vec::new();
+        let mut means = Vec::new();
+        let mut covs = Vec::new();
+        for (idx, line) in lines {
+            let line_no = idx + 1;
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != n_columns {
+                return Err(MoebiusError::Shape(format!(
+                    "line {}: {} columns, expected {} as in the header", line_no, fields.len(), n_columns
+                )));
+            }
+            let values = fields.iter()
+                .map(|field| field.parse::<f64>().map_err(|_| MoebiusError::Parse(format!(
+                    "line {}: {:?} is not a number", line_no, field
+                ))))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            weights.push(values[0]);
+            means.extend_from_slice(&values[1..=dim]);
+            let cov = &values[1 + dim..];
+            match covariance {
+                CsvCovariance::Diag => covs.extend(Array2::from_diag(&arr1(cov))),
+                CsvCovariance::Full => covs.extend_from_slice(cov),
+            }
+        }
+
+        let n_comp = weights.len();
+        GaussianMixture::new(
+            weights,
+            Array2::from_shape_vec((n_comp, dim), means).unwrap(),
+            Array3::from_shape_vec((n_comp, dim, dim), covs).unwrap()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::csv::CsvCovariance;
+    use crate::mixture::GaussianMixture;
+
+    #[test]
+    fn diagonal_and_full_tables() {
+        let diag = "w,m1,m2,v1,v2\n0.4, 0.0, 1.0, 1.0, 2.0\n\n0.6,3.0,-1.0,0.5,1.5\n";
+        let mixture = GaussianMixture::from_csv_str(diag, CsvCovariance::Diag).unwrap();
+        assert_eq!(vec![0.4, 0.6], mixture.weights);
+        assert_eq!(arr2(&[[0.0, 1.0], [3.0, -1.0]]), mixture.means);
+        assert_eq!(arr3(&[[[1.0, 0.0], [0.0, 2.0]], [[0.5, 0.0], [0.0, 1.5]]]), mixture.covs);
+
+        let full = "w,m1,m2,c11,c12,c21,c22\n0.4,0.0,1.0,1.0,0.2,0.2,2.0\n0.6,3.0,-1.0,0.5,0.0,0.0,1.5\n";
+        let mixture = GaussianMixture::from_csv_str(full, CsvCovariance::Full).unwrap();
+        assert_eq!(arr3(&[[[1.0, 0.2], [0.2, 2.0]], [[0.5, 0.0], [0.0, 1.5]]]), mixture.covs);
+        // Five columns are no full layout.
+        assert!(GaussianMixture::from_csv_str(diag, CsvCovariance::Full).is_err());
+    }
+
+    #[test]
+    fn malformed_rows_name_their_line() {
+        let error = |input: &str| GaussianMixture::from_csv_str(input, CsvCovariance::Diag).unwrap_err().to_string();
+
+        assert!(error("w,m,v\n0.5,0.0,1.0\n0.5,2.0\n").contains("line 3: 2 columns"));
+        assert!(error("w,m,v\n0.5,0.0,1.0\n\n0.5,x,1.0\n").contains("line 4: \"x\" is not a number"));
+        assert!(error("w,m\n0.5,0.0\n").contains("line 1"));
+        assert!(error("").contains("empty"));
+        assert!("spherical".parse::<CsvCovariance>().is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Component densities the OLR scan can be run on. The scan only evaluates pointwise
+//! log-densities along the line between two means, so any unimodal component works.
+
+use std::f64::consts::PI;
+
+use ndarray::prelude::*;
+use statrs::function::gamma::ln_gamma;
+
+use crate::error::MoebiusError;
+use crate::gaussian::Gaussian;
+use crate::workspace::Scratch;
+
+/// Log-density of a mixture component.
+pub trait ComponentDensity {
+    fn ln_pdf(&self, x: ArrayView1<f64>) -> f64;
+
+    /// Same as `ln_pdf`. The built-in densities keep their temporaries in `scratch`, which the
+    /// pair scan reuses for every point.
+    fn ln_pdf_with(&self, x: ArrayView1<f64>, _scratch: &mut Scratch) -> f64 {
+        self.ln_pdf(x)
+    }
+
+    /// Appends `ln_pdf` of every column of `points` to `out`. Gaussians evaluate all columns at
+    /// once; other densities evaluate them one by one with `ln_pdf_with`.
+    fn ln_pdf_columns(&self, points: ArrayView2<f64>, out: &mut Vec<f64>, scratch: &mut Scratch) {
+        let mut column = std::mem::take(&mut scratch.column);
+        for x in points.columns() {
+            column.clear();
+            column.extend(x);
+            out.push(self.ln_pdf_with(ArrayView1::from(&column[..]), scratch));
+        }
+        scratch.column = column;
+    }
+}
+
+impl<D: ComponentDensity + ?Sized> ComponentDensity for Box<D> {
+    fn ln_pdf(&self, x: ArrayView1<f64>) -> f64 {
+        (**self).ln_pdf(x)
+    }
+
+    fn ln_pdf_with(&self, x: ArrayView1<f64>, scratch: &mut Scratch) -> f64 {
+        (**self).ln_pdf_with(x, scratch)
+    }
+
+    fn ln_pdf_columns(&self, points: ArrayView2<f64>, out: &mut Vec<f64>, scratch: &mut Scratch) {
+        (**self).ln_pdf_columns(points, out, scratch)
+    }
+}
+
+impl ComponentDensity for Gaussian {
+    fn ln_pdf(&self, x: ArrayView1<f64>) -> f64 {
+        Gaussian::ln_pdf(self, x)
+    }
+
+    fn ln_pdf_with(&self, x: ArrayView1<f64>, scratch: &mut Scratch) -> f64 {
+        Gaussian::ln_pdf_with(self, x, scratch)
+    }
+
+    fn ln_pdf_columns(&self, points: ArrayView2<f64>, out: &mut Vec<f64>, scratch: &mut Scratch) {
+        Gaussian::ln_pdf_columns(self, points, out, scratch)
+    }
+}
+
+/// Component `index` of a mixture whose densities are given by a closure `pdf(x, index)`.
+pub(crate) struct PdfComponent<'a, F> {
+    pub(crate) pdf: &'a F,
+    pub(crate) index: usize,
+}
+
+impl<F: Fn(&Array1<f64>, usize) -> f64> ComponentDensity for PdfComponent<'_, F> {
+    fn ln_pdf(&self, x: ArrayView1<f64>) -> f64 {
+        (self.pdf)(&x.to_owned(), self.index).ln()
+    }
+}
+
+/// Gaussian component with its covariance multiplied by `scale`, sharing the factorization of
+/// the original covariance: `cov * s = (sqrt(s) L) (sqrt(s) L)^T`.
+pub(crate) struct Inflated<'a> {
+    gaussian: &'a Gaussian,
+    inv_scale: f64,
+    ln_norm: f64,
+}
+
+impl<'a> Inflated<'a> {
+    pub(crate) fn new(gaussian: &'a Gaussian, scale: f64) -> Self {
+        let dim = gaussian.dim() as f64;
+        let ln_norm = -(dim * (2.0 * PI).ln() + gaussian.ln_det() + dim * scale.ln()) / 2.0;
+
+        Inflated { gaussian, inv_scale: 1.0 / scale, ln_norm }
+    }
+}
+
+impl ComponentDensity for Inflated<'_> {
+    fn ln_pdf(&self, x: ArrayView1<f64>) -> f64 {
+        self.ln_pdf_with(x, &mut Scratch::default())
+    }
+
+    fn ln_pdf_with(&self, x: ArrayView1<f64>, scratch: &mut Scratch) -> f64 {
+        self.ln_norm - self.gaussian.quadratic_with(x, scratch) * self.inv_scale / 2.0
+    }
+}
+
+/// Multivariate Student-t distribution with location `mean`, scale matrix `scale` and `dof`
+/// degrees of freedom. Tends to the normal distribution `N(mean, scale)` as `dof` grows.
+#[derive(Debug, Clone)]
+pub struct StudentT {
+    /// Normal distribution with the same location and scale, for the Mahalanobis distance.
+    gaussian: Gaussian,
+    dof: f64,
+    ln_norm: f64,
+}
+
+impl StudentT {
+    pub fn new(mean: ArrayView1<f64>, scale: ArrayView2<f64>, dof: f64) -> Result<Self, MoebiusError> {
+        StudentT::component(mean, scale, dof, 0)
+    }
+
+    /// Same as `new`, naming component `component` in errors.
+    pub(crate) fn component(
+        mean: ArrayView1<f64>,
+        scale: ArrayView2<f64>,
+        dof: f64,
+        component: usize
+    ) -> Result<Self, MoebiusError> {
+        if !dof.is_finite() || dof <= 0.0 {
+            return Err(MoebiusError::Distribution(format!(
+                "degrees of freedom of component {} must be positive and finite, got {}", component, dof
+            )));
+        }
+        let gaussian = Gaussian::new(mean, scale, component, None)?;
+        let dim = mean.len() as f64;
+        let ln_norm = ln_gamma((dof + dim) / 2.0) - ln_gamma(dof / 2.0)
+            - dim / 2.0 * (dof * PI).ln()
+            - gaussian.ln_det() / 2.0;
+
+        Ok(StudentT { gaussian, dof, ln_norm })
+    }
+}
+
+impl ComponentDensity for StudentT {
+    fn ln_pdf(&self, x: ArrayView1<f64>) -> f64 {
+        self.ln_pdf_with(x, &mut Scratch::default())
+    }
+
+    fn ln_pdf_with(&self, x: ArrayView1<f64>, scratch: &mut Scratch) -> f64 {
+        let dim = x.len() as f64;
+
+        self.ln_norm - (self.dof + dim) / 2.0 * (self.gaussian.quadratic_with(x, scratch) / self.dof).ln_1p()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr1, arr2};
+    use statrs::distribution::{Continuous, StudentsT};
+    use crate::density::{ComponentDensity, StudentT};
+
+    #[test]
+    fn matches_statrs_in_one_dimension() {
+        let t = StudentT::new(arr1(&[1.0]).view(), arr2(&[[4.0]]).view(), 3.0).unwrap();
+        let reference = StudentsT::new(1.0, 2.0, 3.0).unwrap();
+
+        for x in [-5.0, 0.0, 1.0, 2.5, 30.0] {
+            assert_abs_diff_eq!(reference.ln_pdf(x), t.ln_pdf(arr1(&[x]).view()), epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_dof() {
+        for dof in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(StudentT::new(arr1(&[0.0]).view(), arr2(&[[1.0]]).view(), dof).is_err());
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Conditioning diagnostics of the covariances of a mixture.
+//!
+//! Unexpected OLR values often come from covariances that are nearly singular: their Cholesky
+//! factor loses precision, and a component that is very narrow along the line to another one is
+//! sampled by few grid points. `diagnose` reports both, per component and per pair.
+
+use nalgebra::DMatrix;
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::linalg::SpdFactor;
+use crate::olr::check_shapes;
+use crate::result::condensed_len;
+use crate::validation::check_finite;
+
+/// Condition number above which a covariance needs jitter, i.e. `OlrConfig::reg_covar`. Beyond it
+/// the Cholesky factor has lost half of the digits of `f64`.
+pub const CONDITION_THRESHOLD: f64 = 1e8;
+
+/// Conditioning of the covariance of a single component.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComponentDiagnostics {
+    /// Ratio of the largest to the smallest eigenvalue, infinite if the smallest is not positive.
+    pub condition_number: f64,
+    pub min_eigenvalue: f64,
+    /// Whether the covariance is not positive definite or its condition number exceeds
+    /// `CONDITION_THRESHOLD`.
+    pub needs_jitter: bool,
+}
+
+/// Widths of the two components of a pair along the line connecting their means.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairDiagnostics {
+    pub pair: (usize, usize),
+    /// Ratio of the larger to the smaller Mahalanobis scale `(u^T C_k^-1 u)^(-1/2)` of the two
+    /// components along the unit direction `u` between the means: `1` for components equally
+    /// wide along the line, infinite if one of them cannot be factorized. `None` if the means
+    /// coincide.
+    pub scale_ratio: Option<f64>,
+}
+
+/// Diagnostics of every component, and of every pair in the order of `olr`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics {
+    pub components: Vec<ComponentDiagnostics>,
+    pub pairs: Vec<PairDiagnostics>,
+}
+
+impl Diagnostics {
+    /// Components that need jitter.
+    pub fn flagged(&self) -> Vec<usize> {
+        self.components.iter().enumerate().filter(|(_, c)| c.needs_jitter).map(|(k, _)| k).collect()
+    }
+}
+
+/// Condition numbers, smallest eigenvalues and scale ratios of the mixture. Eigenvalues are
+/// computed from the symmetric part of every covariance.
+pub fn diagnose(w: &[f64], means: &Array2<f64>, covs: &Array3<f64>) -> Result<Diagnostics, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+
+    let factors: Vec<Option<SpdFactor>> = covs.outer_iter()
+        .map(|cov| SpdFactor::new(cov, "covariance").ok())
+        .collect();
+    let components = covs.outer_iter().zip(&factors)
+        .map(|(cov, factor)| {
+            let dim = cov.nrows();
+            let symmetric = DMatrix::from_fn(dim, dim, |r, c| (cov[[r, c]] + cov[[c, r]]) / 2.0);
+            let eigenvalues = symmetric.symmetric_eigenvalues();
+            let min_eigenvalue = eigenvalues.iter().copied().fold(f64::INFINITY, f64::min);
+            let max_eigenvalue = eigenvalues.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let condition_number = if min_eigenvalue > 0.0 { max_eigenvalue / min_eigenvalue } else { f64::INFINITY };
+
+            ComponentDiagnostics {
+                condition_number,
+                min_eigenvalue,
+                needs_jitter: factor.is_none() || condition_number > CONDITION_THRESHOLD,
+            }
+        })
+        .collect();
+
+    let n_comp = w.len();
+    let mut pairs = Vec::with_capacity(condensed_len(n_comp));
+    for i in 0..n_comp {
+        for j in (i + 1)..n_comp {
+            let direction = &means.row(j) - &means.row(i);
+            let norm = direction.dot(&direction).sqrt();
+            let scale_ratio = (norm > 0.0).then(|| {
+                let unit = direction / norm;
+                match (&factors[i], &factors[j]) {
+                    (Some(factor_i), Some(factor_j)) => {
+                        let (precision_i, precision_j) = (factor_i.quadratic(unit.view()), factor_j.quadratic(unit.view()));
+                        (precision_i / precision_j).max(precision_j / precision_i).sqrt()
+                    }
+                    _ => f64::INFINITY,
+                }
+            });
+
+            pairs.push(PairDiagnostics { pair: (i, j), scale_ratio });
+        }
+    }
+
+    Ok(Diagnostics { components, pairs })
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr2, arr3};
+    use crate::diagnostics::diagnose;
+
+    #[test]
+    fn flags_singular_and_ill_conditioned() {
+        // The covariances of `tests/fixtures/singular.json`, and a nearly singular one.
+        let w = [0.25; 4];
+        let means = arr2(&[[0.0, 0.0], [1.0, 1.0], [2.0, 0.0], [3.0, 1.0]]);
+        let covs = arr3(&[
+            [[1.0, 0.0], [0.0, 1.0]],
+            [[4.0, 0.0], [0.0, 1.0]],
+            [[1.0, 1.0], [1.0, 1.0]],
+            [[1.0, 0.0], [0.0, 1e-10]],
+        ]);
+
+        let diagnostics = diagnose(&w, &means, &covs).unwrap();
+        assert_eq!(vec![2, 3], diagnostics.flagged());
+        assert_abs_diff_eq!(1.0, diagnostics.components[0].condition_number, epsilon = 1e-12);
+        assert_abs_diff_eq!(4.0, diagnostics.components[1].condition_number, epsilon = 1e-12);
+        assert!(diagnostics.components[2].min_eigenvalue.abs() < 1e-12);
+        assert_abs_diff_eq!(1e10, diagnostics.components[3].condition_number, epsilon = 1.0);
+
+        // Along (1, 1) / sqrt(2), component 1 has scale sqrt(1.6) and component 0 scale 1.
+        assert_eq!((0, 1), diagnostics.pairs[0].pair);
+        assert_abs_diff_eq!(1.6f64.sqrt(), diagnostics.pairs[0].scale_ratio.unwrap(), epsilon = 1e-12);
+        assert_eq!(Some(f64::INFINITY), diagnostics.pairs[1].scale_ratio);
+        assert_eq!(6, diagnostics.pairs.len());
+
+        let coincident = diagnose(&w[..2], &arr2(&[[0.0, 0.0], [0.0, 0.0]]), &covs.slice(ndarray::s![..2, .., ..]).to_owned()).unwrap();
+        assert!(coincident.flagged().is_empty());
+        assert_eq!(None, coincident.pairs[0].scale_ratio);
+    }
+}
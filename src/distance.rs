@@ -0,0 +1,133 @@
+//! Geometric separation of component pairs, in the condensed order of `olr`.
+
+use std::str::FromStr;
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::gaussian::{ComponentCache, Covariances, Gaussian};
+use crate::olr::check_shapes;
+use crate::result::condensed_len;
+use crate::validation::check_finite;
+
+/// Covariance the Mahalanobis distance between the means of components `i < j` is measured with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MahalanobisMode {
+    /// Covariance of component `i`.
+    First,
+    /// Covariance of component `j`.
+    Second,
+    /// Average `(cov_i + cov_j) / 2` of both covariances.
+    Pooled,
+    /// Average of the distances measured with either covariance.
+    Symmetric,
+}
+
+impl FromStr for MahalanobisMode {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(MahalanobisMode::First),
+            "second" => Ok(MahalanobisMode::Second),
+            "pooled" => Ok(MahalanobisMode::Pooled),
+            "symmetric" => Ok(MahalanobisMode::Symmetric),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown Mahalanobis mode {:?}, expected \"first\", \"second\", \"pooled\" or \"symmetric\"", s
+            ))),
+        }
+    }
+}
+
+/// Mahalanobis distance between the means of every pair `i < j`.
+pub fn mahalanobis_pairs(means: &Array2<f64>, covs: &Array3<f64>, mode: MahalanobisMode) -> Result<Vec<f64>, MoebiusError> {
+    let n_comp = means.nrows();
+    check_shapes(n_comp, means, covs)?;
+    check_finite(&[], means, covs)?;
+
+    let cache = ComponentCache::new(means.view(), Covariances::Dense(covs.view()));
+    let mut distances = Vec::with_capacity(condensed_len(n_comp));
+    for i in 0..n_comp {
+        for j in (i + 1)..n_comp {
+            let distance = match mode {
+                MahalanobisMode::First => cache.pair(i, j)?.0.quadratic(means.row(j)).sqrt(),
+                MahalanobisMode::Second => cache.pair(i, j)?.1.quadratic(means.row(i)).sqrt(),
+                MahalanobisMode::Symmetric => {
+                    let (gaussian_i, gaussian_j) = cache.pair(i, j)?;
+
+                    (gaussian_i.quadratic(means.row(j)).sqrt() + gaussian_j.quadratic(means.row(i)).sqrt()) / 2.0
+                }
+                MahalanobisMode::Pooled => {
+                    let pooled = (&covs.index_axis(Axis(0), i) + &covs.index_axis(Axis(0), j)) / 2.0;
+
+                    Gaussian::new(means.row(i), pooled.view(), i, Some((i, j)))?.quadratic(means.row(j)).sqrt()
+                }
+            };
+            distances.push(distance);
+        }
+    }
+
+    Ok(distances)
+}
+
+/// Euclidean distance between the means of every pair `i < j`.
+pub fn euclidean_pairs(means: &Array2<f64>) -> Vec<f64> {
+    let n_comp = means.nrows();
+
+    (0..n_comp)
+        .flat_map(|i| ((i + 1)..n_comp).map(move |j| (i, j)))
+        .map(|(i, j)| (&means.row(i) - &means.row(j)).mapv(|v| v * v).sum().sqrt())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr2, arr3, Axis};
+    use crate::distance::{euclidean_pairs, mahalanobis_pairs, MahalanobisMode};
+
+    #[test]
+    fn one_dimension() {
+        let means = arr2(&[[0.0], [3.0]]);
+        let covs = arr3(&[[[1.0]], [[4.0]]]);
+        let distance = |mode| mahalanobis_pairs(&means, &covs, mode).unwrap()[0];
+
+        assert_abs_diff_eq!(3.0, distance(MahalanobisMode::First), epsilon = 1e-12);
+        assert_abs_diff_eq!(1.5, distance(MahalanobisMode::Second), epsilon = 1e-12);
+        assert_abs_diff_eq!(3.0 / 2.5f64.sqrt(), distance(MahalanobisMode::Pooled), epsilon = 1e-12);
+        assert_abs_diff_eq!(2.25, distance(MahalanobisMode::Symmetric), epsilon = 1e-12);
+        assert_eq!(vec![3.0], euclidean_pairs(&means));
+    }
+
+    #[test]
+    fn two_dimensions() {
+        let means = arr2(&[[0.0, 0.0], [3.0, 4.0], [0.0, 1.0]]);
+        let covs = arr3(&[[[2.0, 1.0], [1.0, 2.0]], [[1.0, 0.0], [0.0, 1.0]], [[4.0, 0.0], [0.0, 1.0]]]);
+
+        assert_eq!(vec![5.0, 1.0, 18.0f64.sqrt()], euclidean_pairs(&means));
+        // cov_0^-1 = [[2, -1], [-1, 2]] / 3, so d^2 = (2 * 9 - 2 * 12 + 2 * 16) / 3 = 26 / 3.
+        let first = mahalanobis_pairs(&means, &covs, MahalanobisMode::First).unwrap();
+        assert_abs_diff_eq!((26.0f64 / 3.0).sqrt(), first[0], epsilon = 1e-12);
+        // (0, 2) measured with cov_2 = diag(4, 1).
+        let second = mahalanobis_pairs(&means, &covs, MahalanobisMode::Second).unwrap();
+        assert_abs_diff_eq!(1.0, second[1], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn pooled_is_symmetric() {
+        let means = arr2(&[[0.0, 0.0], [3.0, 4.0], [-1.0, 2.0]]);
+        let covs = arr3(&[[[2.0, 1.0], [1.0, 2.0]], [[1.0, 0.3], [0.3, 0.5]], [[4.0, 0.0], [0.0, 1.0]]]);
+        let reversed = [2, 1, 0];
+
+        for mode in [MahalanobisMode::Pooled, MahalanobisMode::Symmetric] {
+            let forward = mahalanobis_pairs(&means, &covs, mode).unwrap();
+            let backward = mahalanobis_pairs(&means.select(Axis(0), &reversed), &covs.select(Axis(0), &reversed), mode).unwrap();
+
+            // Pairs (0, 1), (0, 2), (1, 2) are (2, 1), (2, 0), (1, 0) of the reversed mixture.
+            for (k, l) in [(0, 2), (1, 1), (2, 0)] {
+                assert_abs_diff_eq!(forward[k], backward[l], epsilon = 1e-12);
+            }
+        }
+        assert!("mean".parse::<MahalanobisMode>().is_err());
+    }
+}
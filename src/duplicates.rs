@@ -0,0 +1,52 @@
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::olr::check_shapes;
+
+/// Pairs `(i, j)`, `i < j`, of components whose means and covariances differ by at most `tol` in
+/// every entry. Such components describe the same cluster, which usually means the model has
+/// more components than the data supports.
+pub fn find_duplicates(means: &Array2<f64>, covs: &Array3<f64>, tol: f64) -> Result<Vec<(usize, usize)>, MoebiusError> {
+    let n_comp = means.nrows();
+    check_shapes(n_comp, means, covs)?;
+    if tol.is_nan() || tol < 0.0 {
+        return Err(MoebiusError::InvalidConfig(format!("tolerance must be non-negative, got {}", tol)));
+    }
+
+    let close = |a: ArrayViewD<f64>, b: ArrayViewD<f64>| a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() <= tol);
+    let mut duplicates = Vec::new();
+    for i in 0..n_comp {
+        for j in (i + 1)..n_comp {
+            let same_mean = close(means.row(i).into_dyn(), means.row(j).into_dyn());
+            if same_mean && close(covs.index_axis(Axis(0), i).into_dyn(), covs.index_axis(Axis(0), j).into_dyn()) {
+                duplicates.push((i, j));
+            }
+        }
+    }
+
+    Ok(duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::duplicates::find_duplicates;
+
+    #[test]
+    fn near_duplicate_pair() {
+        let means = arr2(&[
+            [1.1987e+00, 1.1542e+00],
+            [4.1592e+00, 4.1487e+00],
+            [4.1592e+00, 4.1487e+00]
+        ]);
+        let covs = arr3(&[
+            [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+            [[1.5160e+00, 1.1009e+00], [1.1009e+00, 1.5178e+00]]
+        ]);
+
+        assert_eq!(vec![(1, 2)], find_duplicates(&means, &covs, 1e-3).unwrap());
+        assert!(find_duplicates(&means, &covs, 1e-5).unwrap().is_empty());
+        assert!(find_duplicates(&means, &covs, -1.0).is_err());
+    }
+}
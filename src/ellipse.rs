@@ -0,0 +1,88 @@
+use nalgebra::DMatrix;
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+
+/// Principal axes of the `n_sigma` confidence ellipsoid of a `d x d` covariance matrix.
+///
+/// Returns the `d` semi-axis lengths `n_sigma * sqrt(lambda)`, in decreasing order, and a `d x d`
+/// rotation matrix whose `k`-th column is the unit direction of the `k`-th axis. The sign of each
+/// column is chosen so that its entry of largest magnitude is positive. In 2D the rotation angle
+/// of the ellipse is `atan2(rotation[[1, 0]], rotation[[0, 0]])`.
+pub fn ellipse_params(cov: &Array2<f64>, n_sigma: f64) -> Result<(Vec<f64>, Array2<f64>), MoebiusError> {
+    let (nrows, ncols) = cov.dim();
+    if nrows != ncols {
+        return Err(MoebiusError::Shape(format!(
+            "covariance matrix must be square, got {} x {}", nrows, ncols
+        )));
+    }
+
+    let matrix = DMatrix::from_row_slice(nrows, ncols, &cov.iter().copied().collect::<Vec<_>>());
+    let eigen = matrix.symmetric_eigen();
+
+    let mut order: Vec<usize> = (0..nrows).collect();
+    order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+
+    let mut axes = Vec::with_capacity(nrows);
+    let mut rotation = Array2::zeros((nrows, nrows));
+    for (k, &idx) in order.iter().enumerate() {
+        let eigenvalue = eigen.eigenvalues[idx];
+        if eigenvalue < 0.0 {
+            return Err(MoebiusError::Distribution(format!(
+                "covariance matrix has a negative eigenvalue {}", eigenvalue
+            )));
+        }
+        axes.push(n_sigma * eigenvalue.sqrt());
+
+        let vector = eigen.eigenvectors.column(idx);
+        let dominant = vector.iter().copied().fold(0.0f64, |m, v| if v.abs() > m.abs() { v } else { m });
+        let sign = if dominant < 0.0 { -1.0 } else { 1.0 };
+        for r in 0..nrows {
+            rotation[[r, k]] = sign * vector[r];
+        }
+    }
+
+    Ok((axes, rotation))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::arr2;
+    use crate::ellipse::ellipse_params;
+
+    #[test]
+    fn diagonal_covariance_is_axis_aligned() {
+        let (axes, rotation) = ellipse_params(&arr2(&[[4.0, 0.0], [0.0, 1.0]]), 2.0).unwrap();
+
+        assert_abs_diff_eq!(4.0, axes[0], epsilon = 1e-12);
+        assert_abs_diff_eq!(2.0, axes[1], epsilon = 1e-12);
+        assert_abs_diff_eq!(arr2(&[[1.0, 0.0], [0.0, 1.0]]), rotation, epsilon = 1e-12);
+
+        let (axes, rotation) = ellipse_params(&arr2(&[[1.0, 0.0], [0.0, 9.0]]), 1.0).unwrap();
+
+        assert_abs_diff_eq!(3.0, axes[0], epsilon = 1e-12);
+        assert_abs_diff_eq!(1.0, axes[1], epsilon = 1e-12);
+        assert_abs_diff_eq!(arr2(&[[0.0, 1.0], [1.0, 0.0]]), rotation, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn correlated_covariance() {
+        let cov = arr2(&[[2.0, 1.0], [1.0, 2.0]]);
+        let (axes, rotation) = ellipse_params(&cov, 1.0).unwrap();
+
+        assert_abs_diff_eq!(3.0f64.sqrt(), axes[0], epsilon = 1e-12);
+        assert_abs_diff_eq!(1.0, axes[1], epsilon = 1e-12);
+        assert_abs_diff_eq!(std::f64::consts::FRAC_PI_4, rotation[[1, 0]].atan2(rotation[[0, 0]]), epsilon = 1e-12);
+
+        // R diag(axes^2) R^T reconstructs the covariance.
+        let scaled = &rotation * &arr2(&[[axes[0].powi(2), axes[1].powi(2)]]);
+        assert_abs_diff_eq!(cov, scaled.dot(&rotation.t()), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn invalid_covariance() {
+        assert!(ellipse_params(&arr2(&[[1.0, 0.0]]), 1.0).is_err());
+        assert!(ellipse_params(&arr2(&[[1.0, 2.0], [2.0, 1.0]]), 1.0).is_err());
+    }
+}
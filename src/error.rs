@@ -0,0 +1,86 @@
+use std::fmt;
+
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyTimeoutError, PyValueError};
+use pyo3::PyErr;
+
+#[derive(Debug)]
+pub enum MoebiusError {
+    Io(std::io::Error),
+    Parse(String),
+    Shape(String),
+    Distribution(String),
+    /// Cholesky factorization of a component's covariance failed. `pair` is the pair being
+    /// processed when the failure occurred, if any.
+    NotPositiveDefinite { component: usize, pair: Option<(usize, usize)> },
+    InvalidConfig(String),
+    /// The covariance matrix of `component` has shape `cov_shape` instead of `dim x dim`, `dim`
+    /// being the number of columns of the means.
+    DimensionMismatch { component: usize, dim: usize, cov_shape: (usize, usize) },
+    /// `pair` exceeded `OlrConfig::pair_timeout` or `OlrConfig::deadline` under
+    /// `TimeoutPolicy::Abort`.
+    Timeout { pair: (usize, usize) },
+    /// An internal consistency check failed on `pair`, which debug builds and builds with the
+    /// `invariants` feature run on every pair. The value computed for the pair would be wrong.
+    Invariant { pair: (usize, usize), message: String },
+}
+
+impl fmt::Display for MoebiusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoebiusError::Io(e) => write!(f, "I/O error: {}", e),
+            MoebiusError::Parse(msg) => write!(f, "parse error: {}", msg),
+            MoebiusError::Shape(msg) => write!(f, "invalid shape: {}", msg),
+            MoebiusError::Distribution(msg) => write!(f, "invalid distribution parameters: {}", msg),
+            MoebiusError::NotPositiveDefinite { component, pair } => {
+                write!(f, "covariance of component {} is not positive definite", component)?;
+                if let Some((i, j)) = pair {
+                    write!(f, " (while processing pair ({}, {}))", i, j)?;
+                }
+
+                write!(f, "; consider adding a small regularization, e.g. reg_covar=1e-6")
+            }
+            MoebiusError::InvalidConfig(msg) => write!(f, "invalid config: {}", msg),
+            MoebiusError::DimensionMismatch { component, dim, cov_shape } => write!(
+                f,
+                "covariance of component {} is {} x {}, but the means have {} dimensions",
+                component, cov_shape.0, cov_shape.1, dim
+            ),
+            MoebiusError::Timeout { pair: (i, j) } => write!(f, "timed out while processing pair ({}, {})", i, j),
+            MoebiusError::Invariant { pair: (i, j), message } => {
+                write!(f, "invariant violated while processing pair ({}, {}): {}", i, j, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoebiusError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MoebiusError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for MoebiusError {
+    fn from(e: std::io::Error) -> Self {
+        MoebiusError::Io(e)
+    }
+}
+
+impl From<statrs::StatsError> for MoebiusError {
+    fn from(e: statrs::StatsError) -> Self {
+        MoebiusError::Distribution(e.to_string())
+    }
+}
+
+impl From<MoebiusError> for PyErr {
+    fn from(e: MoebiusError) -> Self {
+        match e {
+            MoebiusError::Io(_) => PyIOError::new_err(e.to_string()),
+            MoebiusError::Timeout { .. } => PyTimeoutError::new_err(e.to_string()),
+            MoebiusError::Invariant { .. } => PyRuntimeError::new_err(e.to_string()),
+            _ => PyValueError::new_err(e.to_string()),
+        }
+    }
+}
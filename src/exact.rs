@@ -0,0 +1,199 @@
+//! Reference OLR values that do not depend on the sampling step.
+//!
+//! The fast path reads the peaks and the saddle of a pair off a grid of `n_points` steps between
+//! the means, so every extremum is off by up to half a step along the line. `olr_exact` uses a
+//! grid ten times finer only to bracket the extrema and then refines each of them with Brent's
+//! method until its position along the line is known to `tol`. Values are therefore limited by
+//! floating-point precision rather than by the step, at the cost of roughly ten times as many
+//! density evaluations as `olr` with the default parameters plus a few dozen per extremum.
+//!
+//! The line is scanned over the same range as with `DEFAULT_N_POINTS` and `DEFAULT_EXTENSION`, and
+//! the value is chosen from the extrema by the same rule, so both paths agree up to the
+//! discretization error of the fast one.
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::gaussian::{ComponentCache, Covariances, Gaussian};
+use crate::olr::{check_shapes, ln_pdf_pair, DEFAULT_EXTENSION, DEFAULT_N_POINTS};
+use crate::result::condensed_len;
+use crate::validation::check_finite;
+use crate::workspace::Scratch;
+
+/// Steps of the grid used to bracket the extrema, relative to the fast path.
+const BRACKET_REFINEMENT: usize = 10;
+
+/// Upper bound on Brent iterations per extremum, far above what `f64` precision requires.
+const MAX_ITERATIONS: usize = 200;
+
+/// OLR of every pair, in the order of `olr`, with the extrema of every profile located to within
+/// `tol` along the line, in units of the distance between the two means.
+pub fn olr_exact(w: &[f64], means: &Array2<f64>, covs: &Array3<f64>, tol: f64) -> Result<Vec<f64>, MoebiusError> {
+    if !(tol > 0.0 && tol.is_finite()) {
+        return Err(MoebiusError::InvalidConfig(format!("tol must be positive and finite, got {}", tol)));
+    }
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+
+    let n_comp = w.len();
+    let cache = ComponentCache::new(means.view(), Covariances::Dense(covs.view()));
+    let mut values = Vec::with_capacity(condensed_len(n_comp));
+    for i in 0..n_comp {
+        for j in (i + 1)..n_comp {
+            let (gaussian_i, gaussian_j) = cache.pair(i, j)?;
+            values.push(exact_pair(w[i], means.row(i), gaussian_i, w[j], means.row(j), gaussian_j, tol));
+        }
+    }
+
+    Ok(values)
+}
+
+fn exact_pair(
+    w1: f64,
+    mean_1: ArrayView1<f64>,
+    gaussian_1: &Gaussian,
+    w2: f64,
+    mean_2: ArrayView1<f64>,
+    gaussian_2: &Gaussian,
+    tol: f64
+) -> f64 {
+    // Same weights as in the fast path.
+    let w1_new = if w1 + w2 == 0.0 { 0.5 } else { w1 / (w1 + w2) };
+    let (ln_w1, ln_w2) = (w1_new.ln(), (1.0 - w1_new).ln());
+    let delta = &mean_2 - &mean_1;
+    let profile = |t: f64| ln_pdf_pair(&(&mean_1 + &(t * &delta)), ln_w1, gaussian_1, ln_w2, gaussian_2, &mut Scratch::default());
+
+    let n_steps = BRACKET_REFINEMENT * (DEFAULT_N_POINTS + 3 * DEFAULT_EXTENSION);
+    let start = -(DEFAULT_EXTENSION as f64) / DEFAULT_N_POINTS as f64;
+    let step = 1.0 / (BRACKET_REFINEMENT * DEFAULT_N_POINTS) as f64;
+    let grid: Vec<f64> = (0..=n_steps).map(|k| start + k as f64 * step).collect();
+    let scan: Vec<f64> = grid.iter().map(|&t| profile(t)).collect();
+
+    let mut peaks = Vec::new();
+    let mut saddles = Vec::new();
+    for k in 1..n_steps {
+        let (a, b) = (grid[k - 1], grid[k + 1]);
+        if scan[k] > scan[k - 1] && scan[k] > scan[k + 1] {
+            peaks.push(-minimize(|t| -profile(t), a, b, tol).min(-scan[k]));
+        }
+        if scan[k] < scan[k - 1] && scan[k] < scan[k + 1] {
+            saddles.push(minimize(profile, a, b, tol).min(scan[k]));
+        }
+    }
+
+    if peaks.len() == 1 || saddles.is_empty() {
+        return 1.0;
+    }
+
+    let ln_peak = peaks.into_iter().fold(f64::INFINITY, f64::min);
+
+    (saddles[0] - ln_peak).exp()
+}
+
+/// Minimum of `f` on `[a, b]` by Brent's method, stopping once the minimizer is known to within
+/// `tol`.
+fn minimize(f: impl Fn(f64) -> f64, mut a: f64, mut b: f64, tol: f64) -> f64 {
+    const GOLDEN: f64 = 0.381_966_011_250_105_1;
+
+    let mut x = a + GOLDEN * (b - a);
+    let (mut v, mut w) = (x, x);
+    let mut fx = f(x);
+    let (mut fv, mut fw) = (fx, fx);
+    let (mut d, mut e) = (0.0f64, 0.0f64);
+
+    for _ in 0..MAX_ITERATIONS {
+        let middle = 0.5 * (a + b);
+        let tol1 = tol + f64::EPSILON * x.abs();
+        let tol2 = 2.0 * tol1;
+        if (x - middle).abs() <= tol2 - 0.5 * (b - a) {
+            break;
+        }
+
+        let mut golden = true;
+        if e.abs() > tol1 {
+            // Parabola through x, v and w.
+            let r = (x - w) * (fx - fv);
+            let q = (x - v) * (fx - fw);
+            let mut p = (x - v) * q - (x - w) * r;
+            let mut q = 2.0 * (q - r);
+            if q > 0.0 {
+                p = -p;
+            }
+            q = q.abs();
+
+            if p.abs() < (0.5 * q * e).abs() && p > q * (a - x) && p < q * (b - x) {
+                e = d;
+                d = p / q;
+                let u = x + d;
+                if u - a < tol2 || b - u < tol2 {
+                    d = tol1.copysign(middle - x);
+                }
+                golden = false;
+            }
+        }
+        if golden {
+            e = if x >= middle { a - x } else { b - x };
+            d = GOLDEN * e;
+        }
+
+        let u = if d.abs() >= tol1 { x + d } else { x + tol1.copysign(d) };
+        let fu = f(u);
+        if fu <= fx {
+            if u >= x { a = x } else { b = x }
+            (v, fv, w, fw, x, fx) = (w, fw, x, fx, u, fu);
+        } else {
+            if u < x { a = u } else { b = u }
+            if fu <= fw || w == x {
+                (v, fv, w, fw) = (w, fw, u, fu);
+            } else if fu <= fv || v == x || v == w {
+                (v, fv) = (u, fu);
+            }
+        }
+    }
+
+    fx
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr2, arr3};
+    use crate::exact::{minimize, olr_exact};
+    use crate::olr::try_olr;
+
+    #[test]
+    fn brent_finds_minimum() {
+        assert_abs_diff_eq!(-1.0, minimize(|t| (t - 0.3).powi(2) - 1.0, 0.0, 1.0, 1e-12), epsilon = 1e-15);
+        assert_abs_diff_eq!(-1.0, minimize(|t: f64| t.cos(), 2.0, 4.0, 1e-12), epsilon = 1e-15);
+    }
+
+    #[test]
+    fn fast_path_within_epsilon() {
+        let w = vec![5.2194e-01, 4.7806e-01, 5.2194e-01];
+        let means = arr2(&[[1.1987e+00, 1.1542e+00], [4.1592e+00, 4.1487e+00], [4.1592e+00, 4.1487e+00]]);
+        let covs = arr3(&[
+            [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+            [[1.5160e+00, 1.1009e+00], [1.1009e+00, 1.5178e+00]],
+        ]);
+        let exact = olr_exact(&w, &means, &covs, 1e-12).unwrap();
+        for (fast, exact) in try_olr(w, means, covs).unwrap().into_iter().zip(exact) {
+            assert_abs_diff_eq!(exact, fast, epsilon = 1e-4);
+        }
+
+        let means = arr2(&[[5.0], [2.0]]);
+        let covs = arr3(&[[[0.5]], [[0.5]]]);
+        let exact = olr_exact(&[0.5, 0.5], &means, &covs, 1e-12).unwrap();
+        assert_abs_diff_eq!(0.21077243773848037, exact[0], epsilon = 1e-4);
+        assert_abs_diff_eq!(try_olr(vec![0.5, 0.5], means, covs).unwrap()[0], exact[0], epsilon = 1e-4);
+    }
+
+    #[test]
+    fn rejects_invalid_tolerance() {
+        let means = arr2(&[[0.0], [1.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]]]);
+
+        assert!(olr_exact(&[0.5, 0.5], &means, &covs, 0.0).is_err());
+        assert!(olr_exact(&[0.5, 0.5], &means, &covs, f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Detection of the peaks and saddles of a sampled log-density profile.
+//!
+//! The OLR of a pair only depends on the extrema its detector reports: it is the ratio of the
+//! density at the first saddle to the density at the lowest peak. `Detector` selects one of the
+//! built-in detectors through `OlrConfig::detector`, and `olr_with_detector` accepts any other
+//! implementation of `ExtremaDetector`.
+
+use std::str::FromStr;
+
+use ndarray::ArrayView1;
+
+use crate::error::MoebiusError;
+
+/// Log-densities at the local maxima and minima of a profile, in the order along the line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Extrema {
+    pub peaks: Vec<f64>,
+    pub saddles: Vec<f64>,
+}
+
+impl Extrema {
+    fn clear(&mut self) {
+        self.peaks.clear();
+        self.saddles.clear();
+    }
+}
+
+pub trait ExtremaDetector {
+    /// Extrema of `profile`, the log-density at equally spaced points of a line. The end points
+    /// must never be reported.
+    fn detect(&self, profile: &[f64]) -> Extrema;
+
+    /// Same as `detect`, writing into `extrema` so that its buffers can be reused.
+    fn detect_into(&self, profile: &[f64], extrema: &mut Extrema) {
+        *extrema = self.detect(profile);
+    }
+}
+
+/// Reports the sampled values at which the finite differences change sign, so every extremum is
+/// underestimated by up to the change of the density within half a step. Plateaus count as a
+/// single extremum, see `for_each_extremum`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignChange;
+
+impl ExtremaDetector for SignChange {
+    fn detect(&self, profile: &[f64]) -> Extrema {
+        let mut extrema = Extrema::default();
+        self.detect_into(profile, &mut extrema);
+
+        extrema
+    }
+
+    fn detect_into(&self, profile: &[f64], extrema: &mut Extrema) {
+        extrema.clear();
+        for_each_extremum(profile.into(), |k, is_peak| {
+            if is_peak { &mut extrema.peaks } else { &mut extrema.saddles }.push(profile[k]);
+        });
+    }
+}
+
+/// Finds extrema as `SignChange` does, then replaces every value with the vertex of the parabola
+/// through it and its two neighbours. The error shrinks with the cube of the step rather than
+/// its square, and vanishes for profiles that are locally quadratic, like Gaussian log-densities.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Parabolic;
+
+impl ExtremaDetector for Parabolic {
+    fn detect(&self, profile: &[f64]) -> Extrema {
+        let mut extrema = Extrema::default();
+        self.detect_into(profile, &mut extrema);
+
+        extrema
+    }
+
+    fn detect_into(&self, profile: &[f64], extrema: &mut Extrema) {
+        extrema.clear();
+        for_each_extremum(profile.into(), |k, is_peak| {
+            let vertex = parabolic_vertex(profile.into(), k);
+            if is_peak { &mut extrema.peaks } else { &mut extrema.saddles }.push(vertex);
+        });
+    }
+}
+
+/// Value at the vertex of the parabola through `profile[k]` and its two neighbours.
+pub(crate) fn parabolic_vertex(profile: ArrayView1<f64>, k: usize) -> f64 {
+    let (prev, curr, next) = (profile[k - 1], profile[k], profile[k + 1]);
+    // Nonzero unless `curr` is inside a plateau of three or more points, which is flat.
+    let curvature = prev - 2.0 * curr + next;
+
+    if curvature == 0.0 { curr } else { curr - (next - prev) * (next - prev) / (8.0 * curvature) }
+}
+
+/// Calls `f` with the index of every interior extremum of `profile` and whether it is a peak.
+///
+/// Plateaus, runs of equal values, count as a single point: a run bounded by lower values on
+/// both sides is one peak, and a run bounded by higher values on both sides one saddle, reported
+/// at its center index, the lower of the two middle ones for runs of even length. A run bounded
+/// by a lower and a higher value is a shoulder and no extremum, and runs reaching an end of the
+/// profile are never reported, like the end points themselves.
+pub(crate) fn for_each_extremum(profile: ArrayView1<f64>, mut f: impl FnMut(usize, bool)) {
+    let mut start = 1;
+    while start + 1 < profile.len() {
+        let mut end = start;
+        while end + 1 < profile.len() && profile[end + 1] == profile[start] {
+            end += 1;
+        }
+        if end + 1 == profile.len() {
+            break;
+        }
+        let (rise, fall) = (profile[start] - profile[start - 1], profile[end] - profile[end + 1]);
+
+        if rise > 0.0 && fall > 0.0 {
+            f((start + end) / 2, true);
+        }
+        if rise < 0.0 && fall < 0.0 {
+            f((start + end) / 2, false);
+        }
+        start = end + 1;
+    }
+}
+
+/// Built-in detectors selectable through `OlrConfig::detector`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Detector {
+    /// `SignChange`.
+    #[default]
+    SignChange,
+    /// `Parabolic`.
+    Parabolic,
+}
+
+impl ExtremaDetector for Detector {
+    fn detect(&self, profile: &[f64]) -> Extrema {
+        match self {
+            Detector::SignChange => SignChange.detect(profile),
+            Detector::Parabolic => Parabolic.detect(profile),
+        }
+    }
+
+    fn detect_into(&self, profile: &[f64], extrema: &mut Extrema) {
+        match self {
+            Detector::SignChange => SignChange.detect_into(profile, extrema),
+            Detector::Parabolic => Parabolic.detect_into(profile, extrema),
+        }
+    }
+}
+
+impl FromStr for Detector {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sign_change" => Ok(Detector::SignChange),
+            "parabolic" => Ok(Detector::Parabolic),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown extrema detector {:?}, expected \"sign_change\" or \"parabolic\"", s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use crate::extrema::{Detector, Extrema, ExtremaDetector, Parabolic, SignChange};
+
+    #[test]
+    fn detects_extrema() {
+        let profile = [0.0, 2.0, 1.0, 3.0, 3.0, 0.0];
+        let extrema = SignChange.detect(&profile);
+
+        // The plateau of two points is a single peak, and end points are never reported.
+        assert_eq!(Extrema { peaks: vec![2.0, 3.0], saddles: vec![1.0] }, extrema);
+        assert_eq!(Extrema::default(), SignChange.detect(&[1.0]));
+        assert_eq!(Extrema::default(), Parabolic.detect(&[]));
+    }
+
+    #[test]
+    fn plateaus_are_single_extrema() {
+        // A peak plateau of three points, a shoulder, a saddle plateau of two points and a
+        // plateau reaching the end.
+        let profile = [0.0, 1.0, 5.0, 5.0, 5.0, 2.0, 2.0, 1.0, -1.0, -1.0, 4.0, 4.0];
+        let mut centers = Vec::new();
+        super::for_each_extremum((&profile[..]).into(), |k, is_peak| centers.push((k, is_peak)));
+
+        assert_eq!(vec![(3, true), (8, false)], centers);
+        assert_eq!(Extrema { peaks: vec![5.0], saddles: vec![-1.0] }, SignChange.detect(&profile));
+        // The flat top is its own vertex, the saddle lies between its two points.
+        let parabolic = Parabolic.detect(&profile);
+        assert_eq!(vec![5.0], parabolic.peaks);
+        assert_abs_diff_eq!(-1.25, parabolic.saddles[0], epsilon = 1e-12);
+        assert_eq!(Extrema::default(), SignChange.detect(&[1.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn parabolic_is_exact_for_quadratics() {
+        // -(k - 2.3)^2 peaks at 0 between grid points, (k - 4.6)^2 - 5 has its minimum at -5.
+        let peak: Vec<f64> = (0..5).map(|k| -(k as f64 - 2.3).powi(2)).collect();
+        let saddle: Vec<f64> = (0..9).map(|k| (k as f64 - 4.6).powi(2) - 5.0).collect();
+
+        assert_abs_diff_eq!(0.0, Parabolic.detect(&peak).peaks[0], epsilon = 1e-12);
+        assert_abs_diff_eq!(-5.0, Parabolic.detect(&saddle).saddles[0], epsilon = 1e-12);
+        assert_abs_diff_eq!(-0.09, SignChange.detect(&peak).peaks[0], epsilon = 1e-12);
+        assert_eq!(Parabolic.detect(&peak), Detector::Parabolic.detect(&peak));
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(Detector::SignChange, "sign_change".parse().unwrap());
+        assert_eq!(Detector::Parabolic, "parabolic".parse().unwrap());
+        assert!("cubic".parse::<Detector>().is_err());
+    }
+}
@@ -0,0 +1,311 @@
+//! C interface. See `include/moebius.h` for the declarations.
+//!
+//! Every function returns one of the `MoebiusStatus` codes. When a call fails, a description of
+//! the failure is kept per thread and can be read with `moebius_last_error_message`. Panics never
+//! unwind into the caller; they are reported as `MOEBIUS_PANIC`.
+
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_char, c_int};
+use std::panic::{self, UnwindSafe};
+use std::slice;
+
+use crate::error::MoebiusError;
+use crate::olr::{flat_lengths, olr_flat};
+use crate::result::checked_condensed_len;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoebiusStatus {
+    Ok = 0,
+    /// Invalid arguments: null pointers, inconsistent lengths or shapes.
+    Validation = 1,
+    /// A covariance matrix is not symmetric positive definite.
+    SingularCovariance = 2,
+    /// The computation was cancelled before completion.
+    Cancelled = 3,
+    Internal = 4,
+    Panic = 5,
+}
+
+impl MoebiusStatus {
+    fn name(self) -> &'static [u8] {
+        match self {
+            MoebiusStatus::Ok => b"MOEBIUS_OK\0",
+            MoebiusStatus::Validation => b"MOEBIUS_VALIDATION\0",
+            MoebiusStatus::SingularCovariance => b"MOEBIUS_SINGULAR_COVARIANCE\0",
+            MoebiusStatus::Cancelled => b"MOEBIUS_CANCELLED\0",
+            MoebiusStatus::Internal => b"MOEBIUS_INTERNAL\0",
+            MoebiusStatus::Panic => b"MOEBIUS_PANIC\0",
+        }
+    }
+
+    fn from_code(code: c_int) -> Option<Self> {
+        [
+            MoebiusStatus::Ok,
+            MoebiusStatus::Validation,
+            MoebiusStatus::SingularCovariance,
+            MoebiusStatus::Cancelled,
+            MoebiusStatus::Internal,
+            MoebiusStatus::Panic,
+        ].into_iter().find(|status| *status as c_int == code)
+    }
+}
+
+impl From<&MoebiusError> for MoebiusStatus {
+    fn from(e: &MoebiusError) -> Self {
+        match e {
+            MoebiusError::Parse(_) | MoebiusError::Shape(_) | MoebiusError::InvalidConfig(_) | MoebiusError::DimensionMismatch { .. } => {
+                MoebiusStatus::Validation
+            }
+            MoebiusError::Distribution(_) | MoebiusError::NotPositiveDefinite { .. } => MoebiusStatus::SingularCovariance,
+            MoebiusError::Timeout { .. } => MoebiusStatus::Cancelled,
+            MoebiusError::Io(_) | MoebiusError::Invariant { .. } => MoebiusStatus::Internal,
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+    static INJECT_PANIC: Cell<bool> = const { Cell::new(false) };
+}
+
+fn set_last_error(message: String) {
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+/// Makes the next FFI call on the current thread panic inside the boundary. Only available in
+/// debug builds, to test panic handling.
+#[cfg(debug_assertions)]
+#[no_mangle]
+pub extern "C" fn moebius_debug_inject_panic() {
+    INJECT_PANIC.with(|inject| inject.set(true));
+}
+
+fn boundary(f: impl FnOnce() -> Result<(), MoebiusError> + UnwindSafe) -> c_int {
+    let result = panic::catch_unwind(|| {
+        if INJECT_PANIC.with(|inject| inject.replace(false)) {
+            panic!("injected panic");
+        }
+
+        f()
+    });
+
+    let status = match result {
+        Ok(Ok(())) => {
+            set_last_error(String::new());
+            MoebiusStatus::Ok
+        }
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            MoebiusStatus::from(&e)
+        }
+        Err(payload) => {
+            let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_last_error(format!("panic: {}", message));
+            MoebiusStatus::Panic
+        }
+    };
+
+    status as c_int
+}
+
+/// # Safety
+///
+/// `ptr` must be null or point to at least `len` readable values.
+unsafe fn input<'a>(ptr: *const f64, len: usize, name: &str) -> Result<&'a [f64], MoebiusError> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if ptr.is_null() {
+        return Err(MoebiusError::Shape(format!("{} is null", name)));
+    }
+
+    Ok(slice::from_raw_parts(ptr, len))
+}
+
+/// Computes pairwise OLR values of a mixture of `n_comp` components of dimension `dim`.
+///
+/// `w` holds `n_comp` weights, `means` `n_comp * dim` values and `covs` `n_comp * dim * dim`
+/// values, both row-major. `out` must have room for exactly `out_len = n_comp * (n_comp - 1) / 2`
+/// values.
+///
+/// # Safety
+///
+/// All pointers must be valid for the given lengths.
+#[no_mangle]
+pub unsafe extern "C" fn moebius_olr(
+    w: *const f64,
+    n_comp: usize,
+    means: *const f64,
+    dim: usize,
+    covs: *const f64,
+    out: *mut f64,
+    out_len: usize
+) -> c_int {
+    boundary(move || {
+        let (means_len, covs_len) = flat_lengths(n_comp, dim)?;
+        let w = input(w, n_comp, "w")?;
+        let means = input(means, means_len, "means")?;
+        let covs = input(covs, covs_len, "covs")?;
+
+        let expected_len = checked_condensed_len(n_comp)
+            .ok_or_else(|| MoebiusError::Shape(format!("{} components are too many", n_comp)))?;
+        if out_len != expected_len {
+            return Err(MoebiusError::Shape(format!(
+                "out_len is {}, expected {} for {} components", out_len, expected_len, n_comp
+            )));
+        }
+        if out.is_null() && out_len > 0 {
+            return Err(MoebiusError::Shape("out is null".to_string()));
+        }
+
+        let values = olr_flat(w, means, covs, dim)?;
+        if out_len > 0 {
+            slice::from_raw_parts_mut(out, out_len).copy_from_slice(&values);
+        }
+
+        Ok(())
+    })
+}
+
+/// Copies the message of the last failed call on this thread into `buf`, truncating it to
+/// `len - 1` bytes and always NUL-terminating it when `len > 0`. Returns the length of the full
+/// message, excluding the terminator, so that a larger buffer can be allocated if needed.
+///
+/// # Safety
+///
+/// `buf` must be null or valid for writing `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn moebius_last_error_message(buf: *mut c_char, len: usize) -> usize {
+    LAST_ERROR.with(|last| {
+        let message = last.borrow();
+        let bytes = message.as_bytes();
+
+        if !buf.is_null() && len > 0 {
+            let n = bytes.len().min(len - 1);
+            let buf = slice::from_raw_parts_mut(buf as *mut u8, len);
+            buf[..n].copy_from_slice(&bytes[..n]);
+            buf[n] = 0;
+        }
+
+        bytes.len()
+    })
+}
+
+/// Returns the static, NUL-terminated name of a status code, e.g. `"MOEBIUS_VALIDATION"`, or
+/// `"MOEBIUS_UNKNOWN"` for codes not defined by this library.
+#[no_mangle]
+pub extern "C" fn moebius_error_name(code: c_int) -> *const c_char {
+    let name: &'static [u8] = match MoebiusStatus::from_code(code) {
+        Some(status) => status.name(),
+        None => b"MOEBIUS_UNKNOWN\0",
+    };
+
+    name.as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{c_char, c_int, CStr};
+    use std::ptr;
+    use approx::assert_abs_diff_eq;
+    use crate::ffi::{moebius_error_name, moebius_last_error_message, moebius_olr, MoebiusStatus};
+
+    fn last_error() -> String {
+        let mut buf = [0 as c_char; 256];
+        unsafe { moebius_last_error_message(buf.as_mut_ptr(), buf.len()) };
+
+        unsafe { CStr::from_ptr(buf.as_ptr()) }.to_string_lossy().into_owned()
+    }
+
+    fn name(code: c_int) -> String {
+        unsafe { CStr::from_ptr(moebius_error_name(code)) }.to_string_lossy().into_owned()
+    }
+
+    fn call(w: &[f64], means: &[f64], dim: usize, covs: &[f64], out: &mut [f64]) -> c_int {
+        unsafe {
+            moebius_olr(w.as_ptr(), w.len(), means.as_ptr(), dim, covs.as_ptr(), out.as_mut_ptr(), out.len())
+        }
+    }
+
+    #[test]
+    fn computes_olr() {
+        let mut out = [0.0];
+        let code = call(&[0.5, 0.5], &[5.0, 2.0], 1, &[0.5, 0.5], &mut out);
+
+        assert_eq!(MoebiusStatus::Ok as c_int, code);
+        assert_abs_diff_eq!(0.21077243773848037, out[0], epsilon = 1e-4);
+        assert_eq!("", last_error());
+    }
+
+    #[test]
+    fn wrong_output_length() {
+        let mut out = [0.0; 2];
+        let code = call(&[0.5, 0.5], &[5.0, 2.0], 1, &[0.5, 0.5], &mut out);
+
+        assert_eq!(MoebiusStatus::Validation as c_int, code);
+        assert_eq!("MOEBIUS_VALIDATION", name(code));
+        assert!(last_error().contains("out_len is 2, expected 1"));
+    }
+
+    #[test]
+    fn null_input() {
+        let mut out = [0.0];
+        let code = unsafe { moebius_olr(ptr::null(), 2, ptr::null(), 1, ptr::null(), out.as_mut_ptr(), 1) };
+
+        assert_eq!(MoebiusStatus::Validation as c_int, code);
+        assert!(last_error().contains("w is null"));
+    }
+
+    #[test]
+    fn singular_covariance() {
+        let mut out = [0.0];
+        let code = call(&[0.5, 0.5], &[0.0, 0.0, 1.0, 1.0], 2, &[1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0], &mut out);
+
+        assert_eq!(MoebiusStatus::SingularCovariance as c_int, code);
+        assert_eq!("MOEBIUS_SINGULAR_COVARIANCE", name(code));
+        assert!(!last_error().is_empty());
+    }
+
+    #[test]
+    fn message_is_truncated_and_terminated() {
+        let mut out = [0.0; 2];
+        call(&[0.5, 0.5], &[5.0, 2.0], 1, &[0.5, 0.5], &mut out);
+
+        let mut buf = [1 as c_char; 8];
+        let full_len = unsafe { moebius_last_error_message(buf.as_mut_ptr(), buf.len()) };
+
+        assert!(full_len > buf.len());
+        assert_eq!(0, buf[7]);
+        assert_eq!(7, unsafe { CStr::from_ptr(buf.as_ptr()) }.to_bytes().len());
+
+        let mut empty: [c_char; 0] = [];
+        assert_eq!(full_len, unsafe { moebius_last_error_message(empty.as_mut_ptr(), 0) });
+        assert_eq!(full_len, unsafe { moebius_last_error_message(ptr::null_mut(), 16) });
+    }
+
+    #[test]
+    fn unknown_code_name() {
+        assert_eq!("MOEBIUS_OK", name(0));
+        assert_eq!("MOEBIUS_PANIC", name(5));
+        assert_eq!("MOEBIUS_UNKNOWN", name(42));
+        assert_eq!("MOEBIUS_UNKNOWN", name(-1));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn panics_do_not_unwind() {
+        crate::ffi::moebius_debug_inject_panic();
+
+        let mut out = [0.0];
+        let code = call(&[0.5, 0.5], &[5.0, 2.0], 1, &[0.5, 0.5], &mut out);
+
+        assert_eq!(MoebiusStatus::Panic as c_int, code);
+        assert_eq!("MOEBIUS_PANIC", name(code));
+        assert_eq!("panic: injected panic", last_error());
+
+        assert_eq!(MoebiusStatus::Ok as c_int, call(&[0.5, 0.5], &[5.0, 2.0], 1, &[0.5, 0.5], &mut out));
+    }
+}
@@ -0,0 +1,214 @@
+//! Overlap of two weighted components as the fraction of the lighter one under the heavier one.
+//!
+//! Along the line `m1 + t (m2 - m1)`, every weighted density `w_k N_k` is an unnormalized
+//! Gaussian in `t`, so
+//!
+//! ```text
+//! ∫ min(w1 N1, w2 N2) dt / min(∫ w1 N1 dt, ∫ w2 N2 dt)
+//! ```
+//!
+//! is computed in closed form from the points where the two curves cross. Unlike OLR, which
+//! compares the density at the saddle to the lower peak and only tells whether the components
+//! form separate modes, the fraction measures how much mass they share: it is `1` if the lighter
+//! component lies entirely under the heavier one, even when the mixture is bimodal, and stays
+//! positive for pairs with a deep saddle.
+
+use ndarray::prelude::*;
+use statrs::distribution::{ContinuousCDF, Normal};
+
+use crate::error::MoebiusError;
+use crate::gaussian::Gaussian;
+
+/// Fraction of the lighter weighted component `w_k N(m_k, c_k)` that lies under the other one
+/// along the line through both means, in `[0, 1]`. Components with the same mean give `1`.
+pub fn overlap_fraction(
+    w1: f64,
+    m1: ArrayView1<f64>,
+    c1: ArrayView2<f64>,
+    w2: f64,
+    m2: ArrayView1<f64>,
+    c2: ArrayView2<f64>
+) -> Result<f64, MoebiusError> {
+    check_pair(w1, m1, w2, m2)?;
+    let gaussian_1 = Gaussian::new(m1, c1, 0, None)?;
+    let gaussian_2 = Gaussian::new(m2, c2, 1, None)?;
+    if m1 == m2 {
+        return Ok(1.0);
+    }
+
+    let delta = &m2 - &m1;
+    let curves = [LineGaussian::new(&gaussian_1, w1, m1, &delta), LineGaussian::new(&gaussian_2, w2, m1, &delta)];
+    let (shared, ln_max) = shared_mass(&curves);
+    let lighter = (curves[0].ln_mass().min(curves[1].ln_mass()) - ln_max).exp();
+
+    Ok((shared / lighter).clamp(0.0, 1.0))
+}
+
+/// `∫ min(f1, f2) dt` of two curves over the whole line, relative to `exp(ln_max)`, and `ln_max`,
+/// the log-mass of the heavier curve.
+pub(crate) fn shared_mass(curves: &[LineGaussian; 2]) -> (f64, f64) {
+    // Crossings of the log-densities, where `ln f1 - ln f2` vanishes.
+    let (a, b, c) = (
+        (curves[1].a - curves[0].a) / 2.0,
+        (curves[1].b - curves[0].b) / 2.0,
+        curves[0].ln_scale - curves[1].ln_scale + (curves[1].c - curves[0].c) / 2.0,
+    );
+    let mut bounds = vec![f64::NEG_INFINITY];
+    bounds.extend(roots(a, b, c));
+    bounds.push(f64::INFINITY);
+
+    // Masses relative to the heavier component, so that huge dimensions do not underflow.
+    let ln_max = curves[0].ln_mass().max(curves[1].ln_mass());
+    let shared: f64 = bounds.windows(2)
+        .map(|bound| {
+            let (lo, hi) = (bound[0], bound[1]);
+            let probe = match (lo.is_finite(), hi.is_finite()) {
+                (true, true) => (lo + hi) / 2.0,
+                (true, false) => lo + 1.0,
+                (false, true) => hi - 1.0,
+                (false, false) => 0.0,
+            };
+            let lower = if a * probe * probe + b * probe + c < 0.0 { &curves[0] } else { &curves[1] };
+
+            lower.mass_between(lo, hi, ln_max)
+        })
+        .sum();
+
+    (shared, ln_max)
+}
+
+/// Checks the weights and means of a pair of weighted components.
+pub(crate) fn check_pair(w1: f64, m1: ArrayView1<f64>, w2: f64, m2: ArrayView1<f64>) -> Result<(), MoebiusError> {
+    if m1.len() != m2.len() {
+        return Err(MoebiusError::Shape(format!("means have {} and {} entries", m1.len(), m2.len())));
+    }
+    if let Some(w) = [w1, w2].into_iter().find(|w| !(w.is_finite() && *w > 0.0)) {
+        return Err(MoebiusError::Distribution(format!("weights must be positive and finite, got {}", w)));
+    }
+    if m1.iter().chain(m2.iter()).any(|v| !v.is_finite()) {
+        return Err(MoebiusError::Distribution("means must be finite".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Weighted density `exp(ln_scale - (a t^2 + b t + c) / 2)` of a component along the line
+/// `origin + t * delta`.
+pub(crate) struct LineGaussian {
+    ln_scale: f64,
+    a: f64,
+    b: f64,
+    c: f64,
+}
+
+impl LineGaussian {
+    pub(crate) fn new(gaussian: &Gaussian, weight: f64, origin: ArrayView1<f64>, delta: &Array1<f64>) -> Self {
+        // The squared Mahalanobis distance is a quadratic in `t`, recovered from three points.
+        let q = |t: f64| gaussian.quadratic((&origin + &(t * delta)).view());
+        let (q_minus, q_zero, q_plus) = (q(-1.0), q(0.0), q(1.0));
+
+        LineGaussian {
+            ln_scale: weight.ln() + gaussian.ln_pdf(origin) + q_zero / 2.0,
+            a: (q_plus + q_minus) / 2.0 - q_zero,
+            b: (q_plus - q_minus) / 2.0,
+            c: q_zero,
+        }
+    }
+
+    fn center(&self) -> f64 {
+        -self.b / (2.0 * self.a)
+    }
+
+    /// `ln ∫ f dt` over the whole line.
+    fn ln_mass(&self) -> f64 {
+        self.ln_scale - (self.c - self.b * self.b / (4.0 * self.a)) / 2.0 + (2.0 * std::f64::consts::PI / self.a).ln() / 2.0
+    }
+
+    /// `∫ f dt` over `[lo, hi]`, relative to `exp(ln_max)`.
+    fn mass_between(&self, lo: f64, hi: f64, ln_max: f64) -> f64 {
+        let normal = Normal::new(self.center(), 1.0 / self.a.sqrt()).unwrap();
+        // Upper tails are taken from the survival function to keep their precision.
+        let probability = if lo > self.center() {
+            normal.sf(lo) - normal.sf(hi)
+        } else {
+            normal.cdf(hi) - normal.cdf(lo)
+        };
+
+        (self.ln_mass() - ln_max).exp() * probability
+    }
+}
+
+/// Real roots of `a t^2 + b t + c` in increasing order.
+fn roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return if b == 0.0 { Vec::new() } else { vec![-c / b] };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+
+    // Avoids cancellation between `-b` and the square root.
+    let q = -(b + b.signum() * discriminant.sqrt()) / 2.0;
+    let mut roots = if q == 0.0 { vec![0.0] } else { vec![q / a, c / q] };
+    roots.sort_by(f64::total_cmp);
+    roots.dedup();
+
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr1, arr2};
+    use statrs::distribution::{ContinuousCDF, Normal};
+    use crate::fraction::{overlap_fraction, roots};
+
+    #[test]
+    fn equal_gaussians() {
+        let mean = arr1(&[1.0, -1.0]);
+        let cov = arr2(&[[1.0, 0.3], [0.3, 0.8]]);
+
+        assert_eq!(1.0, overlap_fraction(0.5, mean.view(), cov.view(), 0.5, mean.view(), cov.view()).unwrap());
+        // The lighter component lies entirely under the heavier one.
+        assert_eq!(1.0, overlap_fraction(0.2, mean.view(), cov.view(), 0.8, mean.view(), cov.view()).unwrap());
+
+        let shifted = arr1(&[1.0 + 1e-6, -1.0]);
+        let fraction = overlap_fraction(0.5, mean.view(), cov.view(), 0.5, shifted.view(), cov.view()).unwrap();
+        assert_abs_diff_eq!(1.0, fraction, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn matches_closed_form() {
+        // Equal weights and variances: 2 Phi(-delta / 2) with the distance delta in units of the
+        // standard deviation.
+        let cov = arr2(&[[4.0]]);
+        let fraction = overlap_fraction(0.5, arr1(&[0.0]).view(), cov.view(), 0.5, arr1(&[3.0]).view(), cov.view()).unwrap();
+        assert_abs_diff_eq!(2.0 * Normal::new(0.0, 1.0).unwrap().cdf(-0.75), fraction, epsilon = 1e-12);
+
+        let far = overlap_fraction(0.5, arr1(&[0.0]).view(), cov.view(), 0.5, arr1(&[100.0]).view(), cov.view()).unwrap();
+        assert!(far < 1e-100);
+
+        // A narrow light component on top of a wide heavy one.
+        let narrow = overlap_fraction(0.01, arr1(&[0.0]).view(), arr2(&[[0.01]]).view(), 0.99, arr1(&[0.5]).view(), cov.view());
+        assert!(narrow.unwrap() > 0.99);
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let cov = arr2(&[[1.0]]);
+        let (m1, m2) = (arr1(&[0.0]), arr1(&[1.0]));
+
+        assert!(overlap_fraction(0.0, m1.view(), cov.view(), 0.5, m2.view(), cov.view()).is_err());
+        assert!(overlap_fraction(0.5, m1.view(), arr2(&[[-1.0]]).view(), 0.5, m2.view(), cov.view()).is_err());
+        assert!(overlap_fraction(0.5, m1.view(), cov.view(), 0.5, arr1(&[1.0, 0.0]).view(), cov.view()).is_err());
+    }
+
+    #[test]
+    fn quadratic_roots() {
+        assert_eq!(vec![-2.0, 3.0], roots(1.0, -1.0, -6.0));
+        assert_eq!(vec![2.0], roots(0.0, 1.0, -2.0));
+        assert!(roots(1.0, 0.0, 1.0).is_empty());
+        assert!(roots(0.0, 0.0, 1.0).is_empty());
+    }
+}
@@ -0,0 +1,638 @@
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+use nalgebra::{DMatrix, DVector, DVectorSliceMut};
+use ndarray::prelude::*;
+
+use crate::convert::unpack_into;
+use crate::covariance::Covariance;
+use crate::error::MoebiusError;
+use crate::workspace::Scratch;
+
+/// Relative tolerance of the symmetry check of covariance matrices.
+const SYMMETRY_TOLERANCE: f64 = 1e-10;
+
+/// Off-diagonal entries below this fraction of `sqrt(cov_rr * cov_cc)` are treated as zeros when
+/// detecting diagonal covariances. Small enough to keep densities within `1e-12` of the dense
+/// path.
+const DIAGONAL_TOLERANCE: f64 = 1e-14;
+
+/// Number of independent accumulators of the diagonal quadratic form, so that the loop
+/// vectorizes.
+const LANES: usize = 8;
+
+/// Multivariate normal density with a precomputed factorization of its covariance, so that
+/// evaluating it only takes a triangular solve, or a weighted sum of squares for diagonal
+/// covariances.
+#[derive(Debug, Clone)]
+pub(crate) struct Gaussian {
+    mean: Vec<f64>,
+    factor: Factor,
+    /// `-(d ln(2 pi) + ln det cov) / 2`.
+    ln_norm: f64,
+}
+
+#[derive(Debug, Clone)]
+enum Factor {
+    /// Lower triangular factor `L` of `cov = L L^T`.
+    Dense(DMatrix<f64>),
+    /// Inverse variances `1 / cov_kk`.
+    Diagonal(Vec<f64>),
+    /// Independent blocks of dimensions with the means of these dimensions. The density is the
+    /// product of the block densities.
+    Blocks(Vec<Block>),
+    /// Woodbury form of `cov = W W^T + diag(psi)`: the inverses `1 / psi`, the `d x q` matrix
+    /// `diag(psi)^-1 W` and the lower triangular factor of the `q x q` capacitance matrix
+    /// `I + W^T diag(psi)^-1 W`.
+    LowRank { inv_diag: Vec<f64>, scaled_factors: DMatrix<f64>, capacitance_l: DMatrix<f64> },
+}
+
+#[derive(Debug, Clone)]
+struct Block {
+    indices: Vec<usize>,
+    mean: Vec<f64>,
+    factor: Factor,
+}
+
+impl Gaussian {
+    /// Factorizes the covariance of component `component`. `pair` is only used to give context
+    /// to errors.
+    pub(crate) fn new(
+        mean: ArrayView1<f64>,
+        cov: ArrayView2<f64>,
+        component: usize,
+        pair: Option<(usize, usize)>
+    ) -> Result<Self, MoebiusError> {
+        check_finite(mean, component)?;
+        let (factor, ln_det) = factorize(cov, component, pair)?;
+
+        Ok(Gaussian::from_factor(mean, factor, ln_det))
+    }
+
+    /// Same as `new` for a structured covariance, which must have been validated against the
+    /// dimension of `mean`. Every block of a block-diagonal covariance is factorized separately.
+    pub(crate) fn from_covariance(
+        mean: ArrayView1<f64>,
+        cov: &Covariance,
+        component: usize,
+        pair: Option<(usize, usize)>
+    ) -> Result<Self, MoebiusError> {
+        check_finite(mean, component)?;
+        let (factor, ln_det) = match cov {
+            Covariance::Full(cov) => factorize(cov.view(), component, pair)?,
+            Covariance::Diagonal(variances) => factorize_diagonal(variances.view(), component, pair)?,
+            Covariance::BlockDiagonal(blocks) => {
+                let mut factors = Vec::with_capacity(blocks.len());
+                let mut ln_det = 0.0;
+                for (indices, cov) in blocks {
+                    let (factor, block_ln_det) = factorize(cov.view(), component, pair)?;
+                    factors.push(Block { indices: indices.clone(), mean: indices.iter().map(|&k| mean[k]).collect(), factor });
+                    ln_det += block_ln_det;
+                }
+
+                (Factor::Blocks(factors), ln_det)
+            }
+            Covariance::LowRank { factors, diag } => factorize_low_rank(factors.view(), diag.view(), component, pair)?,
+        };
+
+        Ok(Gaussian::from_factor(mean, factor, ln_det))
+    }
+
+    fn from_factor(mean: ArrayView1<f64>, factor: Factor, ln_det: f64) -> Self {
+        Gaussian {
+            mean: mean.to_vec(),
+            factor,
+            ln_norm: -(mean.len() as f64 * (2.0 * PI).ln() + ln_det) / 2.0,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn pdf(&self, x: &Array1<f64>) -> f64 {
+        self.ln_pdf(x.view()).exp()
+    }
+
+    pub(crate) fn ln_pdf(&self, x: ArrayView1<f64>) -> f64 {
+        self.ln_pdf_with(x, &mut Scratch::default())
+    }
+
+    /// Same as `ln_pdf`, using the buffers of `scratch` for temporaries.
+    pub(crate) fn ln_pdf_with(&self, x: ArrayView1<f64>, scratch: &mut Scratch) -> f64 {
+        self.ln_norm - self.quadratic_with(x, scratch) / 2.0
+    }
+
+    /// Appends `ln_pdf` of every column of the `d x n` matrix `points` to `out`. Dense and
+    /// block-diagonal factors solve all columns at once, row by row, instead of one triangular
+    /// solve per point.
+    pub(crate) fn ln_pdf_columns(&self, points: ArrayView2<f64>, out: &mut Vec<f64>, scratch: &mut Scratch) {
+        let start = out.len();
+        out.resize(start + points.ncols(), 0.0);
+        self.factor.quadratic_columns(points, &self.mean, &mut out[start..], scratch);
+        for value in &mut out[start..] {
+            *value = self.ln_norm - *value / 2.0;
+        }
+    }
+
+    /// Squared Mahalanobis distance `(x - mean)^T cov^-1 (x - mean)`.
+    pub(crate) fn quadratic(&self, x: ArrayView1<f64>) -> f64 {
+        self.quadratic_with(x, &mut Scratch::default())
+    }
+
+    pub(crate) fn quadratic_with(&self, x: ArrayView1<f64>, scratch: &mut Scratch) -> f64 {
+        match x.as_slice() {
+            Some(x) => self.factor.quadratic(x, &self.mean, scratch),
+            None => self.factor.quadratic(&x.to_vec(), &self.mean, scratch),
+        }
+    }
+
+    pub(crate) fn dim(&self) -> usize {
+        self.mean.len()
+    }
+
+    /// `ln det cov`.
+    pub(crate) fn ln_det(&self) -> f64 {
+        -2.0 * self.ln_norm - self.mean.len() as f64 * (2.0 * PI).ln()
+    }
+
+    /// `mean + L z`, distributed as the component for standard normal `z`. Only defined for the
+    /// dense and diagonal factors of `new`.
+    pub(crate) fn transform_standard(&self, z: &[f64]) -> Array1<f64> {
+        let offset: Vec<f64> = match &self.factor {
+            Factor::Dense(chol_l) => (chol_l * DVector::from_column_slice(z)).iter().copied().collect(),
+            Factor::Diagonal(inv_var) => z.iter().zip(inv_var).map(|(z, inv_var)| z / inv_var.sqrt()).collect(),
+            Factor::Blocks(_) | Factor::LowRank { .. } => unreachable!("structured covariances are not sampled"),
+        };
+
+        self.mean.iter().zip(offset).map(|(mean, offset)| mean + offset).collect()
+    }
+}
+
+impl Factor {
+    /// `(x - mean)^T cov^-1 (x - mean)`. Temporaries live in `scratch`, so that evaluations
+    /// allocate nothing once its buffers have grown to the dimension.
+    fn quadratic(&self, x: &[f64], mean: &[f64], scratch: &mut Scratch) -> f64 {
+        match self {
+            Factor::Dense(chol_l) => {
+                let mut diff = fill_diff(&mut scratch.diff, x, mean);
+                assert!(chol_l.solve_lower_triangular_mut(&mut diff));
+
+                diff.norm_squared()
+            }
+            Factor::Diagonal(inv_var) => diagonal_quadratic(x, mean, inv_var),
+            Factor::Blocks(blocks) => {
+                let mut gathered = std::mem::take(&mut scratch.gathered);
+                let quadratic = blocks.iter()
+                    .map(|block| {
+                        gathered.clear();
+                        gathered.extend(block.indices.iter().map(|&k| x[k]));
+
+                        block.factor.quadratic(&gathered, &block.mean, scratch)
+                    })
+                    .sum();
+                scratch.gathered = gathered;
+
+                quadratic
+            }
+            Factor::LowRank { inv_diag, scaled_factors, capacitance_l } => {
+                let diff = fill_diff(&mut scratch.diff, x, mean);
+                scratch.projected.resize(scaled_factors.ncols(), 0.0);
+                let mut projected = DVectorSliceMut::from_slice(&mut scratch.projected, scaled_factors.ncols());
+                scaled_factors.tr_mul_to(&diff, &mut projected);
+                assert!(capacitance_l.solve_lower_triangular_mut(&mut projected));
+
+                diagonal_quadratic(x, mean, inv_diag) - projected.norm_squared()
+            }
+        }
+    }
+
+    /// `quadratic` of every column of the `d x n` matrix `points`, written to `out`.
+    fn quadratic_columns(&self, points: ArrayView2<f64>, mean: &[f64], out: &mut [f64], scratch: &mut Scratch) {
+        let (dim, n) = points.dim();
+        if n == 0 {
+            return;
+        }
+        match self {
+            Factor::Dense(chol_l) => {
+                // Forward substitution of `L Z = X - mean` for `LANES` columns at a time, whose
+                // rows stay in the L1 cache, with the sums over the rows above in registers.
+                let solved = &mut scratch.solved;
+                solved.clear();
+                solved.extend(points.indexed_iter().map(|((r, _), x)| x - mean[r]));
+                for start in (0..n).step_by(LANES) {
+                    let width = LANES.min(n - start);
+                    for r in 0..dim {
+                        let mut sums = [0.0; LANES];
+                        for c in 0..r {
+                            let l = chol_l[(r, c)];
+                            let above = &solved[c * n + start..c * n + start + width];
+                            if let Ok(above) = <&[f64; LANES]>::try_from(above) {
+                                for k in 0..LANES {
+                                    sums[k] += l * above[k];
+                                }
+                            } else {
+                                for (sum, z) in sums.iter_mut().zip(above) {
+                                    *sum += l * z;
+                                }
+                            }
+                        }
+                        let diagonal = chol_l[(r, r)];
+                        for (value, sum) in solved[r * n + start..r * n + start + width].iter_mut().zip(sums) {
+                            *value = (*value - sum) / diagonal;
+                        }
+                    }
+                }
+
+                out.fill(0.0);
+                for row in solved.chunks_exact(n) {
+                    out.iter_mut().zip(row).for_each(|(q, z)| *q += z * z);
+                }
+            }
+            Factor::Blocks(blocks) => {
+                let mut gathered = std::mem::take(&mut scratch.gathered);
+                let mut quadratics = std::mem::take(&mut scratch.quadratics);
+                out.fill(0.0);
+                for block in blocks {
+                    gathered.clear();
+                    for &k in &block.indices {
+                        gathered.extend(points.row(k));
+                    }
+                    quadratics.resize(n, 0.0);
+                    let block_points = ArrayView2::from_shape((block.indices.len(), n), &gathered[..]).unwrap();
+                    block.factor.quadratic_columns(block_points, &block.mean, &mut quadratics, scratch);
+                    out.iter_mut().zip(&quadratics).for_each(|(q, block_q)| *q += block_q);
+                }
+                scratch.gathered = gathered;
+                scratch.quadratics = quadratics;
+            }
+            // Their per-point evaluation has no triangular solve to batch.
+            Factor::Diagonal(_) | Factor::LowRank { .. } => {
+                let mut column = std::mem::take(&mut scratch.column);
+                for (q, x) in out.iter_mut().zip(points.columns()) {
+                    column.clear();
+                    column.extend(x);
+                    *q = self.quadratic(&column, mean, scratch);
+                }
+                scratch.column = column;
+            }
+        }
+    }
+}
+
+/// Writes `x - mean` into `buffer` and returns it as a vector.
+fn fill_diff<'b>(buffer: &'b mut Vec<f64>, x: &[f64], mean: &[f64]) -> DVectorSliceMut<'b, f64> {
+    buffer.clear();
+    buffer.extend(x.iter().zip(mean).map(|(x, m)| x - m));
+
+    DVectorSliceMut::from_slice(buffer, mean.len())
+}
+
+fn check_finite(mean: ArrayView1<f64>, component: usize) -> Result<(), MoebiusError> {
+    if mean.iter().any(|v| !v.is_finite()) {
+        return Err(MoebiusError::Distribution(format!("mean of component {} is not finite", component)));
+    }
+
+    Ok(())
+}
+
+/// Factor of a dense covariance matrix and the logarithm of its determinant. Diagonal matrices
+/// are detected and factorized as such.
+fn factorize(cov: ArrayView2<f64>, component: usize, pair: Option<(usize, usize)>) -> Result<(Factor, f64), MoebiusError> {
+    let dim = cov.nrows();
+    if cov.iter().any(|v| !v.is_finite()) {
+        return Err(MoebiusError::Distribution(format!("covariance of component {} is not finite", component)));
+    }
+    for r in 0..dim {
+        for c in 0..r {
+            let scale = cov[[r, c]].abs().max(cov[[c, r]].abs()).max(f64::MIN_POSITIVE);
+            if (cov[[r, c]] - cov[[c, r]]).abs() > SYMMETRY_TOLERANCE * scale {
+                return Err(MoebiusError::Distribution(format!(
+                    "covariance of component {} is not symmetric", component
+                )));
+            }
+        }
+    }
+
+    if is_diagonal(cov) {
+        return factorize_diagonal(cov.diag(), component, pair);
+    }
+
+    let matrix = DMatrix::from_row_slice(dim, dim, &cov.iter().copied().collect::<Vec<_>>());
+    let chol_l = matrix.cholesky()
+        .ok_or(MoebiusError::NotPositiveDefinite { component, pair })?
+        .unpack();
+    let ln_det = 2.0 * chol_l.diagonal().iter().map(|v| v.ln()).sum::<f64>();
+
+    Ok((Factor::Dense(chol_l), ln_det))
+}
+
+fn factorize_diagonal(
+    variances: ArrayView1<f64>,
+    component: usize,
+    pair: Option<(usize, usize)>
+) -> Result<(Factor, f64), MoebiusError> {
+    let inv_var = inverse_variances(variances, component, pair)?;
+
+    Ok((Factor::Diagonal(inv_var), variances.iter().map(|v| v.ln()).sum()))
+}
+
+fn inverse_variances(
+    variances: ArrayView1<f64>,
+    component: usize,
+    pair: Option<(usize, usize)>
+) -> Result<Vec<f64>, MoebiusError> {
+    if variances.iter().any(|v| !v.is_finite()) {
+        return Err(MoebiusError::Distribution(format!("covariance of component {} is not finite", component)));
+    }
+    if variances.iter().any(|&v| v <= 0.0) {
+        return Err(MoebiusError::NotPositiveDefinite { component, pair });
+    }
+
+    Ok(variances.iter().map(|v| 1.0 / v).collect())
+}
+
+/// Factor of `W W^T + diag(psi)`. The log-determinant follows from the matrix determinant lemma,
+/// `ln det cov = ln det (I + W^T diag(psi)^-1 W) + sum ln psi`.
+fn factorize_low_rank(
+    factors: ArrayView2<f64>,
+    diag: ArrayView1<f64>,
+    component: usize,
+    pair: Option<(usize, usize)>
+) -> Result<(Factor, f64), MoebiusError> {
+    if factors.iter().any(|v| !v.is_finite()) {
+        return Err(MoebiusError::Distribution(format!("covariance factors of component {} are not finite", component)));
+    }
+    let inv_diag = inverse_variances(diag, component, pair)?;
+
+    let (dim, rank) = factors.dim();
+    let w = DMatrix::from_row_slice(dim, rank, &factors.iter().copied().collect::<Vec<_>>());
+    let scaled_factors = DMatrix::from_fn(dim, rank, |r, c| w[(r, c)] * inv_diag[r]);
+    let capacitance = DMatrix::identity(rank, rank) + w.tr_mul(&scaled_factors);
+    let capacitance_l = capacitance.cholesky()
+        .ok_or(MoebiusError::NotPositiveDefinite { component, pair })?
+        .unpack();
+
+    let ln_det = 2.0 * capacitance_l.diagonal().iter().map(|v| v.ln()).sum::<f64>()
+        + diag.iter().map(|v| v.ln()).sum::<f64>();
+
+    Ok((Factor::LowRank { inv_diag, scaled_factors, capacitance_l }, ln_det))
+}
+
+fn is_diagonal(cov: ArrayView2<f64>) -> bool {
+    cov.indexed_iter().all(|((r, c), v)| {
+        r == c || v.abs() <= DIAGONAL_TOLERANCE * (cov[[r, r]] * cov[[c, c]]).abs().sqrt()
+    })
+}
+
+/// `sum_k (x_k - m_k)^2 / var_k`, accumulated in `LANES` independent sums.
+fn diagonal_quadratic(x: &[f64], mean: &[f64], inv_var: &[f64]) -> f64 {
+    let mut sums = [0.0; LANES];
+    let chunks = x.chunks_exact(LANES).zip(mean.chunks_exact(LANES)).zip(inv_var.chunks_exact(LANES));
+    for ((x, m), iv) in chunks {
+        for k in 0..LANES {
+            let d = x[k] - m[k];
+            sums[k] += d * d * iv[k];
+        }
+    }
+
+    let tail = x.len() - x.len() % LANES;
+    let rest: f64 = (tail..x.len()).map(|k| (x[k] - mean[k]).powi(2) * inv_var[k]).sum();
+
+    sums.iter().sum::<f64>() + rest
+}
+
+/// Covariances of all components, either dense, structured or packed as by `pack_covariances`.
+#[derive(Clone, Copy)]
+pub(crate) enum Covariances<'a> {
+    Dense(ArrayView3<'a, f64>),
+    Structured(&'a [Covariance]),
+    /// Unpacked one component at a time, when it is factorized.
+    Packed(ArrayView2<'a, f64>),
+}
+
+/// Lazily factorized components of a mixture, shareable between threads. Every component is
+/// factorized on the first pair it takes part in. Failures are not cached, so every pair involving
+/// an invalid component reports an error naming that pair.
+pub(crate) struct ComponentCache<'a> {
+    means: ArrayView2<'a, f64>,
+    covs: Covariances<'a>,
+    components: Vec<OnceLock<Gaussian>>,
+}
+
+impl<'a> ComponentCache<'a> {
+    pub(crate) fn new(means: ArrayView2<'a, f64>, covs: Covariances<'a>) -> Self {
+        Self::with_components(means, covs, Vec::new())
+    }
+
+    /// Cache starting from the `components` factorized by an earlier cache of the same means and
+    /// covariances, see `into_components`. Starts empty if their number does not match.
+    pub(crate) fn with_components(means: ArrayView2<'a, f64>, covs: Covariances<'a>, mut components: Vec<OnceLock<Gaussian>>) -> Self {
+        if components.len() != means.nrows() {
+            components = (0..means.nrows()).map(|_| OnceLock::new()).collect();
+        }
+
+        ComponentCache { means, covs, components }
+    }
+
+    /// Components factorized so far, to be passed to `with_components`.
+    pub(crate) fn into_components(self) -> Vec<OnceLock<Gaussian>> {
+        self.components
+    }
+
+    /// Factorized components `i` and `j` of the pair `(i, j)`.
+    pub(crate) fn pair(&self, i: usize, j: usize) -> Result<(&Gaussian, &Gaussian), MoebiusError> {
+        Ok((self.get(i, Some((i, j)))?, self.get(j, Some((i, j)))?))
+    }
+
+    /// Factorized component `k`, with errors naming no pair.
+    pub(crate) fn component(&self, k: usize) -> Result<&Gaussian, MoebiusError> {
+        self.get(k, None)
+    }
+
+    /// Factorizes every component upfront and reports all invalid ones in a single error.
+    pub(crate) fn check_all(&self) -> Result<(), MoebiusError> {
+        self.check(0..self.components.len())
+    }
+
+    /// Same as `check_all` for the `components` only.
+    pub(crate) fn check(&self, components: impl IntoIterator<Item = usize>) -> Result<(), MoebiusError> {
+        let failures: Vec<(usize, MoebiusError)> = components.into_iter()
+            .filter_map(|k| self.get(k, None).err().map(|e| (k, e)))
+            .collect();
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        let indices: Vec<usize> = failures.iter().map(|(k, _)| *k).collect();
+        let reasons: Vec<String> = failures.iter()
+            .map(|(k, e)| match e {
+                MoebiusError::NotPositiveDefinite { .. } => format!("covariance of component {} is not positive definite", k),
+                MoebiusError::Distribution(msg) => msg.clone(),
+                e => e.to_string(),
+            })
+            .collect();
+
+        Err(MoebiusError::Distribution(format!(
+            "components {:?} are invalid: {}", indices, reasons.join("; ")
+        )))
+    }
+
+    fn get(&self, component: usize, pair: Option<(usize, usize)>) -> Result<&Gaussian, MoebiusError> {
+        let lock = &self.components[component];
+        if let Some(gaussian) = lock.get() {
+            return Ok(gaussian);
+        }
+
+        let mean = self.means.row(component);
+        let gaussian = match self.covs {
+            Covariances::Dense(covs) => Gaussian::new(mean, covs.index_axis(Axis(0), component), component, pair)?,
+            Covariances::Structured(covs) => Gaussian::from_covariance(mean, &covs[component], component, pair)?,
+            Covariances::Packed(packed) => {
+                let mut cov = Array2::zeros((mean.len(), mean.len()));
+                unpack_into(packed.row(component), &mut cov.view_mut());
+                Gaussian::new(mean, cov.view(), component, pair)?
+            }
+        };
+
+        Ok(lock.get_or_init(|| gaussian))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use nalgebra::DVector;
+    use ndarray::{arr1, arr2, arr3, Array1, Array2, Axis};
+    use statrs::distribution::{Continuous, MultivariateNormal};
+    use crate::error::MoebiusError;
+    use crate::covariance::Covariance;
+    use crate::gaussian::{ComponentCache, Covariances, Factor, Gaussian};
+    use crate::workspace::Scratch;
+
+    #[test]
+    fn matches_statrs() {
+        let mean = arr1(&[1.0, -2.0]);
+        let cov = arr2(&[[1.5, 0.3], [0.3, 0.8]]);
+        let gaussian = Gaussian::new(mean.view(), cov.view(), 0, None).unwrap();
+        let mvn = MultivariateNormal::new(mean.to_vec(), cov.iter().copied().collect()).unwrap();
+
+        for x in [[0.0, 0.0], [1.0, -2.0], [3.0, 1.5]] {
+            assert_abs_diff_eq!(mvn.pdf(&DVector::from_vec(x.to_vec())), gaussian.pdf(&arr1(&x)), epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn diagonal_matches_dense() {
+        let dim = 37;
+        let mean = Array1::from_shape_fn(dim, |k| (k as f64 * 0.7).sin());
+        let variances = Array1::from_shape_fn(dim, |k| 0.5 + (k % 5) as f64 * 0.3);
+        let diagonal = Gaussian::new(mean.view(), Array2::from_diag(&variances).view(), 0, None).unwrap();
+        // A negligible off-diagonal entry above the detection tolerance forces the dense path.
+        let mut dense_cov = Array2::from_diag(&variances);
+        dense_cov[[0, 1]] = 1e-13;
+        dense_cov[[1, 0]] = 1e-13;
+        let dense = Gaussian::new(mean.view(), dense_cov.view(), 0, None).unwrap();
+        assert!(matches!(diagonal.factor, Factor::Diagonal(_)));
+        assert!(matches!(dense.factor, Factor::Dense(_)));
+
+        for shift in [0.0, 0.1, 0.4] {
+            let x = Array1::from_shape_fn(dim, |k| mean[k] + shift * (k as f64).cos());
+            assert_abs_diff_eq!(diagonal.pdf(&x).ln(), dense.pdf(&x).ln(), epsilon = 1e-12);
+        }
+
+        let singular = Gaussian::new(arr1(&[0.0, 0.0]).view(), arr2(&[[1.0, 0.0], [0.0, 0.0]]).view(), 3, None);
+        assert!(matches!(singular, Err(MoebiusError::NotPositiveDefinite { component: 3, .. })));
+    }
+
+    #[test]
+    fn blocks_match_dense() {
+        let mean = arr1(&[0.5, -1.0, 2.0, 0.0]);
+        let cov = Covariance::BlockDiagonal(vec![
+            (vec![3, 0], arr2(&[[2.0, 0.7], [0.7, 1.5]])),
+            (vec![1], arr2(&[[0.4]])),
+            (vec![2], arr2(&[[1.2]])),
+        ]);
+        let blocks = Gaussian::from_covariance(mean.view(), &cov, 0, None).unwrap();
+        let dense = Gaussian::new(mean.view(), cov.to_dense().view(), 0, None).unwrap();
+        assert!(matches!(blocks.factor, Factor::Blocks(_)));
+
+        for x in [[0.0, 0.0, 0.0, 0.0], [0.5, -1.0, 2.0, 0.0], [1.0, 0.3, -0.5, 2.0]] {
+            assert_abs_diff_eq!(dense.pdf(&arr1(&x)), blocks.pdf(&arr1(&x)), epsilon = 1e-12);
+        }
+
+        let singular = Covariance::BlockDiagonal(vec![(vec![0], arr2(&[[1.0]])), (vec![1], arr2(&[[-1.0]]))]);
+        let error = Gaussian::from_covariance(arr1(&[0.0, 0.0]).view(), &singular, 5, Some((2, 5))).unwrap_err();
+        assert!(matches!(error, MoebiusError::NotPositiveDefinite { component: 5, pair: Some((2, 5)) }));
+    }
+
+    #[test]
+    fn low_rank_matches_dense() {
+        let dim = 6;
+        let mean = Array1::from_shape_fn(dim, |k| k as f64 * 0.3 - 1.0);
+        let cov = Covariance::LowRank {
+            factors: Array2::from_shape_fn((dim, 2), |(r, c)| ((r + 2 * c) as f64 * 0.9).sin()),
+            diag: Array1::from_shape_fn(dim, |k| 0.2 + 0.1 * k as f64),
+        };
+        let low_rank = Gaussian::from_covariance(mean.view(), &cov, 0, None).unwrap();
+        let dense = Gaussian::new(mean.view(), cov.to_dense().view(), 0, None).unwrap();
+        assert!(matches!(low_rank.factor, Factor::LowRank { .. }));
+
+        for shift in [0.0, 0.3, -0.8] {
+            let x = Array1::from_shape_fn(dim, |k| mean[k] + shift * (k as f64).cos());
+            assert_abs_diff_eq!(dense.pdf(&x).ln(), low_rank.pdf(&x).ln(), epsilon = 1e-12);
+        }
+
+        let negative = Covariance::LowRank { factors: arr2(&[[1.0], [1.0]]), diag: arr1(&[1.0, -0.5]) };
+        assert!(Gaussian::from_covariance(arr1(&[0.0, 0.0]).view(), &negative, 1, None).is_err());
+    }
+
+    #[test]
+    fn columns_match_points() {
+        let dim = 7;
+        let mean = Array1::from_shape_fn(dim, |k| (k as f64 * 0.4).cos());
+        let dense_cov = Array2::from_shape_fn((dim, dim), |(r, c)| if r == c { 1.0 + 0.2 * r as f64 } else { 0.25 / (1.0 + r.abs_diff(c) as f64) });
+        let covs = [
+            Covariance::Full(dense_cov.clone()),
+            Covariance::Diagonal(dense_cov.diag().to_owned()),
+            Covariance::BlockDiagonal(vec![
+                (vec![0, 2, 4, 6], dense_cov.select(Axis(0), &[0, 2, 4, 6]).select(Axis(1), &[0, 2, 4, 6])),
+                (vec![5, 1, 3], dense_cov.select(Axis(0), &[5, 1, 3]).select(Axis(1), &[5, 1, 3])),
+            ]),
+            Covariance::LowRank {
+                factors: Array2::from_shape_fn((dim, 2), |(r, c)| ((r + 2 * c) as f64 * 0.9).sin()),
+                diag: dense_cov.diag().to_owned(),
+            },
+        ];
+        // Not a multiple of the `LANES` columns solved at a time.
+        let points = Array2::from_shape_fn((dim, 21), |(r, k)| mean[r] + (k as f64 - 10.0) * 0.3 * ((r + k) as f64).sin());
+
+        for cov in &covs {
+            let gaussian = Gaussian::from_covariance(mean.view(), cov, 0, None).unwrap();
+            let mut scratch = Scratch::default();
+            let mut batched = vec![f64::NAN];
+            gaussian.ln_pdf_columns(points.view(), &mut batched, &mut scratch);
+
+            assert_eq!(1 + points.ncols(), batched.len());
+            for (x, value) in points.columns().into_iter().zip(&batched[1..]) {
+                assert_abs_diff_eq!(gaussian.ln_pdf(x), *value, epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn errors_name_the_component() {
+        let means = arr2(&[[0.0], [1.0], [2.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]], [[0.0]]]);
+        let cache = ComponentCache::new(means.view(), Covariances::Dense(covs.view()));
+
+        assert!(cache.pair(0, 1).is_ok());
+        match cache.pair(0, 2) {
+            Err(MoebiusError::NotPositiveDefinite { component, pair }) => {
+                assert_eq!(2, component);
+                assert_eq!(Some((0, 2)), pair);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+
+        let asymmetric = Gaussian::new(arr1(&[0.0, 0.0]).view(), arr2(&[[1.0, 0.5], [0.0, 1.0]]).view(), 4, None);
+        assert!(asymmetric.unwrap_err().to_string().contains("component 4"));
+    }
+}
@@ -1,213 +1,160 @@
 use pyo3::prelude::*;
-use ndarray::prelude::*;
-use nalgebra::DVector;
-use ndarray::{OwnedRepr};
-use statrs::distribution::{Continuous, MultivariateNormal};
+
+pub mod batch;
+pub mod binary;
+pub mod boundary;
+pub mod bounded;
+pub mod build_info;
+#[cfg(feature = "serde")]
+pub mod cache;
+pub mod convert;
+pub mod covariance;
+pub mod cross;
+pub mod csv;
+pub mod density;
+pub mod diagnostics;
+pub mod distance;
+pub mod duplicates;
+pub mod ellipse;
+pub mod error;
+pub mod exact;
+pub mod extrema;
+pub mod ffi;
+pub mod fraction;
+mod gaussian;
+mod linalg;
+pub mod merge;
+pub mod mixture;
+pub mod modality;
+pub mod monte_carlo;
+pub mod multi;
+pub mod olr;
+pub mod operator;
+pub mod ordering;
+pub mod ovl;
+pub mod parallel;
+pub mod pdf;
+mod python;
+#[cfg(feature = "serde")]
+pub mod reference;
+pub mod report;
+pub mod resolution;
+pub mod result;
+pub mod rng;
+pub mod separability;
+#[cfg(feature = "serde")]
+pub mod sklearn;
+pub mod sparse;
+pub mod timing;
+pub mod validation;
+pub mod workspace;
+
+pub use batch::olr_batch;
+pub use binary::{BINARY_MAGIC, BINARY_VERSION, FLAG_DETAILS};
+pub use boundary::{decision_boundary, BoundaryParams};
+pub use bounded::olr_bounded;
+pub use build_info::{capabilities, features, Capabilities};
+#[cfg(feature = "serde")]
+pub use cache::olr_matrix_cached;
+pub use convert::{covs_from_nested, covs_from_variances, means_from_nested, means_from_scalars, pack_covariances, unpack_covariances};
+pub use covariance::Covariance;
+pub use cross::{olr_cross, olr_cross_subspace};
+pub use csv::CsvCovariance;
+pub use density::{ComponentDensity, StudentT};
+pub use diagnostics::{diagnose, ComponentDiagnostics, Diagnostics, PairDiagnostics, CONDITION_THRESHOLD};
+pub use distance::{euclidean_pairs, mahalanobis_pairs, MahalanobisMode};
+pub use duplicates::find_duplicates;
+pub use ellipse::ellipse_params;
+pub use error::MoebiusError;
+pub use exact::olr_exact;
+pub use extrema::{Detector, Extrema, ExtremaDetector, Parabolic, SignChange};
+pub use fraction::overlap_fraction;
+pub use merge::{merge_moments, merge_to_gaussian};
+pub use mixture::{ComponentView, GaussianMixture, MixtureParams};
+pub use modality::{two_component_modality, Modality};
+pub use monte_carlo::{overlap_mc, SamplingScheme};
+pub use multi::{olr_multi, MultiOlr, MultiStatus};
+pub use olr::{
+    bimodality, olr, olr_arrays, olr_detailed, olr_flat, olr_flat_covs, olr_labeled, olr_masked, olr_packed, olr_per_axis, olr_scan_scale, olr_t, olr_into, olr_with_budget, olr_with_config, olr_with_covariances, olr_with_densities,
+    olr_streamed, olr_with_detector, olr_with_pdf, olr_with_points, olr_with_stats,
+    self_overlap_check, try_olr, GridStrategy, IndexedOlr, LabeledOlr, NoSaddlePolicy, OlrConfig, OlrDetails, OlrStats, PairDetail, PairOrder, PairStatus,
+    Refinement, Resolution, TimeoutPolicy, ADAPTIVE_PEAK_POINTS, AUTO_START_POINTS, DEFAULT_EXTENSION, DEFAULT_MAX_POINTS, DEFAULT_N_POINTS,
+    DEFAULT_RESOLUTION_TOLERANCE, DEFAULT_VARIANCE_FLOOR, MAX_EXTENSION_RETRIES, TIMEOUT_CHECK_INTERVAL
+};
+pub use operator::{olr_operators, DensePrecision, PrecisionOperator};
+pub use ordering::{apply_permutation, order_by_overlap};
+pub use ovl::{ovl, ovl_pairs, OvlMethod, GRID_BUDGET, MAX_GRID_DIMS};
+pub use parallel::{num_threads, set_num_threads};
+pub use pdf::{gmm_pdf, PdfEvaluator};
+#[cfg(feature = "serde")]
+pub use reference::{dump_reference, reference_profile, ReferenceDump, ReferencePoint};
+pub use report::{ReportFormat, ReportOptions, DEFAULT_TOP_N};
+pub use result::{checked_condensed_len, condensed_len, index_to_pair, pair_to_index, OlrMatrix, OlrSummary};
+pub use rng::Rng;
+pub use separability::{effective_components, separability, separability_with, Aggregation, EffectiveComponents, PairWeight, EFFECTIVE_THRESHOLDS};
+pub use sparse::{olr_sparse, PREFILTER_MARGIN, PREFILTER_POINTS};
+pub use validation::{check_finite, drop_nan_components, normalized_sum, small_variances, validate_mixture, DroppedComponents, WEIGHT_SUM_TOLERANCE};
+pub use workspace::{OlrSession, Scratch, Workspace};
 
 #[pymodule]
 pub fn moebius(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(olr_wrapper, m)?)?;
+    m.add("DEFAULT_N_POINTS", DEFAULT_N_POINTS)?;
+    m.add("DEFAULT_EXTENSION", DEFAULT_EXTENSION)?;
+    m.add_class::<python::OlrResult>()?;
+    m.add_class::<python::PdfEvaluatorWrapper>()?;
+    m.add_class::<python::OlrConfigWrapper>()?;
+    m.add_function(wrap_pyfunction!(python::olr_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_list_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_labeled_wrapper, m)?)?;
+    #[cfg(feature = "serde")]
+    m.add_function(wrap_pyfunction!(python::olr_matrix_cached_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_sparse_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_exact_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::overlap_mc_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::overlap_fraction_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::ovl_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::ovl_pairs_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::condensed_len_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::pair_to_index_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::index_to_pair_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_with_points_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_masked_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_multi_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_bounded_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_per_axis_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_scan_scale_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_block_diagonal_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_t_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_custom_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_cross_subspace_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_timed_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_records_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::set_num_threads_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::get_num_threads_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::order_by_overlap_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::separability_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::bimodality_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_flat_covs_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::olr_packed_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::pack_covariances_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::unpack_covariances_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::diagnose_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::effective_components_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::mahalanobis_pairs_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::euclidean_pairs_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::find_duplicates_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::validate_mixture_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::apply_permutation_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::self_overlap_check_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::ellipse_params_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::decision_boundary_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::bhattacharyya_grad_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::merge_moments_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::merge_to_gaussian_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::mixture_mean_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::mixture_covariance_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::features_wrapper, m)?)?;
+    m.add_function(wrap_pyfunction!(python::capabilities_wrapper, m)?)?;
 
     Ok(())
 }
-
-#[pyfunction()]
-#[pyo3(name = "olr")]
-pub fn olr_wrapper(w: Vec<f64>, means: Vec<Vec<f64>>, covs: Vec<Vec<Vec<f64>>>) -> PyResult<Vec<f64>> {
-    Ok(
-        olr(
-            w,
-            vec_to_array2(means),
-            vec_to_array3(covs)
-        )
-    )
-}
-
-fn vec_to_array2<T: Clone>(v: Vec<Vec<T>>) -> Array2<T> {
-    if v.is_empty() {
-        return Array2::from_shape_vec((0, 0), Vec::new()).unwrap();
-    }
-    let nrows = v.len();
-    let ncols = v[0].len();
-    let mut data = Vec::with_capacity(nrows * ncols);
-    for row in &v {
-        data.extend_from_slice(&row);
-    }
-    Array2::from_shape_vec((nrows, ncols), data).unwrap()
-}
-
-fn vec_to_array3<T: Clone>(v: Vec<Vec<Vec<T>>>) -> Array3<T> {
-    if v.is_empty() {
-        return Array3::from_shape_vec((0, 0, 0), Vec::new()).unwrap();
-    }
-    let nrows = v.len();
-    let ncols = v[0].len();
-    let nitems = v[0][0].len();
-    let mut data = Vec::with_capacity(nrows * ncols * nitems);
-    for row in &v {
-        for col in row {
-            data.extend_from_slice(&col);
-        }
-    }
-
-    Array3::from_shape_vec((nrows, ncols, nitems), data).unwrap()
-}
-
-pub fn olr(w: Vec<f64>, means: Array2<f64>, covs: Array3<f64>) -> Vec<f64> {
-    let n_comp = w.len();
-    let mut olr_values = Vec::new();
-
-    for i in 0..n_comp {
-        for j in (i + 1)..n_comp {
-            let means_slice_i = &means.slice(s![i, ..]).to_owned();
-            let means_slice_j = &means.slice(s![j, ..]).to_owned();
-
-            let delta = (means_slice_j - means_slice_i) * 1.0 / 1000.0;
-            let mut points = vec![means_slice_i - 10.0 * &delta];
-            let mut curr_point: ArrayBase<OwnedRepr<f64>, Ix1> = means_slice_i - 10.0 * &delta;
-
-            for _ in 0..1030 {
-                let new_point: ArrayBase<OwnedRepr<f64>, Ix1> = &curr_point + &delta;
-                curr_point = new_point.clone();
-                points.push(new_point);
-            }
-
-            let w1 = w[i];
-            let w2 = w[j];
-            let w1_new = w1 / (w1 + w2);
-            let w2_new = 1.0 - w1_new;
-
-            let w_new = vec![w1_new, w2_new];
-            let m_new = vec![means_slice_i, means_slice_j];
-
-            let covs_slice_i = &covs.slice(s![i, .., ..]).to_owned();
-            let covs_slice_j = &covs.slice(s![j, .., ..]).to_owned();
-
-            let cov_new = vec![covs_slice_i, covs_slice_j];
-            let mut peaks = Vec::<f64>::new();
-            let mut saddles = Vec::<f64>::new();
-
-            for k in 1..1030 {
-                let pdf_k = pdf_gmm(&points[k], &w_new, &m_new, &cov_new);
-                let pdf_prev_k = pdf_gmm(&points[k - 1], &w_new, &m_new, &cov_new);
-                let pdf_next_k = pdf_gmm(&points[k + 1], &w_new, &m_new, &cov_new);
-
-                if ((pdf_k - pdf_prev_k) > 0.0) & ((pdf_k - pdf_next_k) > 0.0) {
-                    peaks.push(pdf_k);
-                }
-                if ((pdf_k - pdf_prev_k) < 0.0) & ((pdf_k - pdf_next_k) < 0.0) {
-                    saddles.push(pdf_k);
-                }
-            }
-
-            let olr_current;
-            if peaks.len() == 1 {
-                olr_current = 1.0;
-            } else {
-                if saddles.len() == 0 {
-                    olr_current = 1.0;
-                } else {
-                    olr_current = saddles[0] / peaks.into_iter().min_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
-                }
-            }
-
-            olr_values.push(olr_current);
-        }
-    }
-
-    olr_values
-}
-
-fn pdf_gmm(x: &Array1<f64>, w: &Vec<f64>, means: &Vec<&Array1<f64>>, covs: &Vec<&Array2<f64>>) -> f64 {
-    let mut p = 0.0;
-
-    for i in 0..w.len() {
-        p += w[i] * pdf_mvn(x, means[i], covs[i]);
-    }
-
-    p
-}
-
-fn pdf_mvn(x: &Array1<f64>, mean: &Array1<f64>, cov: &Array2<f64>) -> f64 {
-    let cov: Vec<f64> = cov.iter().map(|x| *x).collect();
-    let mvn = MultivariateNormal::new(mean.to_vec(), cov).unwrap();
-
-    mvn.pdf(&DVector::from_vec(x.to_vec()))
-}
-
-#[cfg(test)]
-mod tests {
-    use approx::assert_abs_diff_eq;
-    use ndarray::{arr2, arr3};
-    use crate::olr;
-
-    #[test]
-    fn two_comps_two_dims() {
-        let w = vec![5.2194e-01,  4.7806e-01];
-        let means = arr2(&[
-            [1.1987e+00, 1.1542e+00],
-            [4.1592e+00, 4.1487e+00]
-        ]);
-        let covs = arr3(&[
-            [
-                [1.9455e+00, -9.1612e-04],
-                [-9.1612e-04, 1.9703e+00]
-            ],
-            [
-                [1.5160e+00, 1.1011e+00],
-                [1.1011e+00, 1.5178e+00]
-            ]
-        ]);
-
-        assert_abs_diff_eq!(0.9205257521646449, olr(w, means, covs)[0], epsilon = 1e-4);
-    }
-
-    #[test]
-    fn two_comps_one_dim() {
-        let w = vec![0.5, 0.5];
-        let means = arr2(&[
-            [5.0],
-            [2.0]
-        ]);
-        let covs = arr3(&[
-            [
-                [0.5]
-            ],
-            [
-                [0.5]
-            ]
-        ]);
-
-        assert_abs_diff_eq!(0.21077243773848037, olr(w, means, covs)[0], epsilon = 1e-4)
-    }
-
-    #[test]
-    fn three_comps_two_dims() {
-        let w = vec![5.2194e-01,  4.7806e-01, 5.2194e-01];
-        let means = arr2(&[
-            [1.1987e+00, 1.1542e+00],
-            [4.1592e+00, 4.1487e+00],
-            [4.1592e+00, 4.1487e+00]
-        ]);
-        let covs = arr3(&[
-            [
-                [1.9455e+00, -9.1612e-04],
-                [-9.1612e-04, 1.9703e+00]
-            ],
-            [
-                [1.5160e+00, 1.1011e+00],
-                [1.1011e+00, 1.5178e+00]
-            ],
-            [
-                [1.5160e+00, 1.1009e+00],
-                [1.1009e+00, 1.5178e+00]
-            ]
-        ]);
-
-        let olrs = olr(w, means, covs);
-
-        assert_abs_diff_eq!(0.9205257521646449, olrs[0], epsilon = 1e-4);
-        assert_abs_diff_eq!(0.9464977842655895, olrs[1], epsilon = 1e-4);
-        assert_abs_diff_eq!(1.0, olrs[2], epsilon = 1e-4);
-    }
-}
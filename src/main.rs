@@ -0,0 +1,341 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use moebius::timing::bench;
+use moebius::{
+    diagnose, euclidean_pairs, mahalanobis_pairs, olr_batch, olr_detailed, CsvCovariance, GaussianMixture, MahalanobisMode, MoebiusError, OlrConfig, OlrMatrix,
+    ReportFormat, ReportOptions, DEFAULT_TOP_N
+};
+use serde_json::json;
+
+#[derive(Parser)]
+#[command(name = "moebius", about = "Overlap analysis of Gaussian mixtures")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compute pairwise OLR values of a mixture
+    Compute(ComputeArgs),
+    /// Read one mixture per line from stdin and write one result object per line to stdout
+    Stream(StreamArgs),
+    /// Time repeated computations on a mixture
+    Bench(BenchArgs),
+    /// Report the conditioning of the covariances of a mixture
+    Diagnose(DiagnoseArgs),
+}
+
+#[derive(Args)]
+struct ComputeArgs {
+    /// Path to the mixture file
+    #[arg(long)]
+    input: PathBuf,
+
+    /// Format of the input. Defaults to csv for `.csv` files and json otherwise
+    #[arg(long, value_enum)]
+    input_format: Option<InputFormat>,
+
+    /// Covariance columns of csv input: diag or full
+    #[arg(long, default_value = "full")]
+    covariance: CsvCovariance,
+
+    /// Also report Mahalanobis distances between means: first, second, pooled or symmetric
+    #[arg(long)]
+    mahalanobis: Option<MahalanobisMode>,
+
+    /// Also report Euclidean distances between means
+    #[arg(long)]
+    euclidean: bool,
+
+    /// Output format. `bin` writes only the OLR matrix, in the format of `OlrMatrix::write_to`,
+    /// `report` and `markdown` a report of the pairs as `OlrDetails::render_report`
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+
+    /// Write to this path instead of stdout. The file is only created once the output is
+    /// complete, so a failed computation leaves any existing file untouched
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Number of pairs listed by `--format report` and `--format markdown`
+    #[arg(long, default_value_t = DEFAULT_TOP_N)]
+    top_n: usize,
+}
+
+#[derive(Args)]
+struct StreamArgs {
+    #[arg(long, value_enum, default_value_t = InputFormat::Json)]
+    input_format: InputFormat,
+
+    /// Number of records processed concurrently. Output keeps the input order
+    #[arg(long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Stop at the first invalid record instead of reporting it as `{"error": ...}`
+    #[arg(long)]
+    fail_fast: bool,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// Path to the mixture file
+    #[arg(long)]
+    input: PathBuf,
+
+    /// Format of the input. Defaults to csv for `.csv` files and json otherwise
+    #[arg(long, value_enum)]
+    input_format: Option<InputFormat>,
+
+    /// Covariance columns of csv input: diag or full
+    #[arg(long, default_value = "full")]
+    covariance: CsvCovariance,
+
+    /// Number of timed runs, not counting the warm-up run
+    #[arg(long, default_value_t = 10)]
+    repeat: usize,
+
+    /// OlrConfig as JSON, e.g. '{"n_points": 500}'. Missing fields keep their defaults
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Write the full report as JSON to this path
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct DiagnoseArgs {
+    /// Path to the mixture file
+    #[arg(long)]
+    input: PathBuf,
+
+    /// Format of the input. Defaults to csv for `.csv` files and json otherwise
+    #[arg(long, value_enum)]
+    input_format: Option<InputFormat>,
+
+    /// Covariance columns of csv input: diag or full
+    #[arg(long, default_value = "full")]
+    covariance: CsvCovariance,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InputFormat {
+    /// `{"w": [...], "means": [[...]], "covs": [[[...]]]}`
+    Json,
+    /// Output of `moebius.export_sklearn`
+    Sklearn,
+    /// Header row, then `weight, means..., covariance entries...` per component, see `--covariance`
+    Csv,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Bin,
+    /// Plain-text report
+    Report,
+    /// Markdown report
+    Markdown,
+}
+
+fn parse_mixture(contents: &str, format: InputFormat) -> Result<GaussianMixture, MoebiusError> {
+    match format {
+        InputFormat::Json => GaussianMixture::from_json_str(contents),
+        InputFormat::Sklearn => GaussianMixture::from_sklearn_str(contents),
+        InputFormat::Csv => Err(MoebiusError::InvalidConfig("csv input holds a whole file, not one record per line".to_string())),
+    }
+}
+
+fn read_mixture(path: &Path, format: Option<InputFormat>, covariance: CsvCovariance) -> Result<GaussianMixture, MoebiusError> {
+    let contents = fs::read_to_string(path)?;
+    let is_csv = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    match format {
+        Some(InputFormat::Csv) => GaussianMixture::from_csv_str(&contents, covariance),
+        None if is_csv => GaussianMixture::from_csv_str(&contents, covariance),
+        Some(format) => parse_mixture(&contents, format),
+        None => parse_mixture(&contents, InputFormat::Json),
+    }
+}
+
+fn compute(args: ComputeArgs) -> Result<(), MoebiusError> {
+    let output = compute_output(&args)?;
+    match &args.output {
+        Some(path) => fs::write(path, output)?,
+        None => {
+            let mut out = io::stdout().lock();
+            out.write_all(&output)?;
+            out.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Everything `compute` writes, computed before `--output` is opened.
+fn compute_output(args: &ComputeArgs) -> Result<Vec<u8>, MoebiusError> {
+    let gmm = read_mixture(&args.input, args.input_format, args.covariance)?;
+    if args.format != OutputFormat::Json && (args.mahalanobis.is_some() || args.euclidean) {
+        return Err(MoebiusError::InvalidConfig("distances can only be written with --format json".to_string()));
+    }
+    let report_format = match args.format {
+        OutputFormat::Report => Some(ReportFormat::Text),
+        OutputFormat::Markdown => Some(ReportFormat::Markdown),
+        OutputFormat::Json | OutputFormat::Bin => None,
+    };
+    if let Some(format) = report_format {
+        let details = olr_detailed(&gmm.weights, &gmm.means, &gmm.covs, &OlrConfig::default())?;
+
+        return Ok(details.render_report(&ReportOptions { format, top_n: args.top_n, labels: None }).into_bytes());
+    }
+    let mut out = Vec::new();
+    if args.format == OutputFormat::Bin {
+        OlrMatrix::new(gmm.weights.len(), gmm.olr()?)?.write_to(&mut out)?;
+
+        return Ok(out);
+    }
+
+    let mut report = json!({ "olr": gmm.olr()? });
+    if let Some(mode) = args.mahalanobis {
+        report["mahalanobis"] = json!(mahalanobis_pairs(&gmm.means, &gmm.covs, mode)?);
+    }
+    if args.euclidean {
+        report["euclidean"] = json!(euclidean_pairs(&gmm.means));
+    }
+    writeln!(out, "{}", report)?;
+
+    Ok(out)
+}
+
+fn run_diagnose(args: DiagnoseArgs) -> Result<(), MoebiusError> {
+    let gmm = read_mixture(&args.input, args.input_format, args.covariance)?;
+    let diagnostics = diagnose(&gmm.weights, &gmm.means, &gmm.covs)?;
+
+    // Non-finite values, e.g. the condition number of a singular covariance, are written as null.
+    let components: Vec<_> = diagnostics.components.iter()
+        .map(|c| json!({ "condition_number": c.condition_number, "min_eigenvalue": c.min_eigenvalue, "needs_jitter": c.needs_jitter }))
+        .collect();
+    let pairs: Vec<_> = diagnostics.pairs.iter()
+        .map(|p| json!({ "pair": [p.pair.0, p.pair.1], "scale_ratio": p.scale_ratio }))
+        .collect();
+    println!("{}", json!({ "components": components, "pairs": pairs, "flagged": diagnostics.flagged() }));
+
+    Ok(())
+}
+
+/// Processes one batch of `(line number, record)` pairs. Returns `false` if an error occurred.
+fn stream_batch(
+    batch: &[(usize, String)],
+    args: &StreamArgs,
+    out: &mut impl Write
+) -> Result<bool, MoebiusError> {
+    let parsed: Vec<_> = batch.iter()
+        .map(|(_, line)| parse_mixture(line, args.input_format))
+        .collect();
+    let mixtures: Vec<_> = parsed.iter()
+        .filter_map(|gmm| gmm.as_ref().ok().cloned())
+        .collect();
+    let mut computed = olr_batch(&mixtures, args.jobs).into_iter();
+
+    let mut ok = true;
+    for ((line_no, _), gmm) in batch.iter().zip(parsed) {
+        let result = gmm.and_then(|_| computed.next().unwrap());
+        match result {
+            Ok(olr) => writeln!(out, "{}", json!({ "olr": olr }))?,
+            Err(e) => {
+                ok = false;
+                if args.fail_fast {
+                    eprintln!("error: line {}: {}", line_no, e);
+                    return Ok(false);
+                }
+                writeln!(out, "{}", json!({ "error": e.to_string(), "line": line_no }))?;
+            }
+        }
+        out.flush()?;
+    }
+
+    Ok(ok)
+}
+
+fn stream(args: StreamArgs) -> Result<bool, MoebiusError> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    let mut ok = true;
+    let mut batch = Vec::with_capacity(args.jobs.max(1));
+    for (idx, line) in stdin.lock().lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        batch.push((idx + 1, line));
+
+        if batch.len() >= args.jobs.max(1) {
+            ok &= stream_batch(&batch, &args, &mut out)?;
+            batch.clear();
+            if !ok && args.fail_fast {
+                return Ok(false);
+            }
+        }
+    }
+    if !batch.is_empty() {
+        ok &= stream_batch(&batch, &args, &mut out)?;
+    }
+
+    Ok(ok)
+}
+
+fn run_bench(args: BenchArgs) -> Result<(), MoebiusError> {
+    let gmm = read_mixture(&args.input, args.input_format, args.covariance)?;
+    let config: OlrConfig = match &args.config {
+        Some(config) => serde_json::from_str(config).map_err(|e| MoebiusError::Parse(e.to_string()))?,
+        None => OlrConfig::default(),
+    };
+
+    let report = bench(&gmm, &config, args.repeat)?;
+
+    println!("components:       {} ({} pairs)", report.n_components, report.n_pairs);
+    println!("runs:             {} (+1 warm-up)", report.repeat);
+    println!(
+        "wall time:        min {:.6}s, median {:.6}s, mean {:.6}s",
+        report.wall_time.min, report.wall_time.median, report.wall_time.mean
+    );
+    println!("pdf evaluations:  {} per run", report.pdf_evaluations);
+    println!(
+        "pair time:        p50 {:.6}s, p90 {:.6}s, p99 {:.6}s, max {:.6}s",
+        report.pair_time.p50, report.pair_time.p90, report.pair_time.p99, report.pair_time.max
+    );
+    println!("checksum:         {}", report.checksum);
+
+    if let Some(path) = &args.report {
+        let json = serde_json::to_string_pretty(&report).map_err(|e| MoebiusError::Parse(e.to_string()))?;
+        fs::write(path, json)?;
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Compute(args) => compute(args).map(|_| true),
+        Command::Stream(args) => stream(args),
+        Command::Bench(args) => run_bench(args).map(|_| true),
+        Command::Diagnose(args) => run_diagnose(args).map(|_| true),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
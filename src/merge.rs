@@ -0,0 +1,194 @@
+//! Moment-matched merging of mixture components.
+//!
+//! A set of components `k` with weights `w_k`, means `m_k` and covariances `C_k` is replaced by
+//! the single Gaussian with the same first two moments:
+//!
+//! ```text
+//! W = sum_k w_k,    m = sum_k w_k m_k / W,    C = sum_k w_k (C_k + (m_k - m)(m_k - m)^T) / W
+//! ```
+//!
+//! The between-means term makes the merged component as wide as the components together, which
+//! is what hierarchical merging of overlapping components relies on.
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::mixture::MixtureParams;
+use crate::olr::check_shapes;
+use crate::validation::{check_finite, check_selection};
+
+/// Combined weight, mean and covariance of the components `indices`. Indices must be in range
+/// and distinct; a single index returns that component unchanged. Components of zero total
+/// weight are averaged with equal weights.
+pub fn merge_moments(
+    indices: &[usize],
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>
+) -> Result<(f64, Array1<f64>, Array2<f64>), MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    check_selection(indices, w.len())?;
+
+    match indices {
+        [] => Err(MoebiusError::Shape("no components to merge".to_string())),
+        &[k] => Ok((w[k], means.row(k).to_owned(), covs.index_axis(Axis(0), k).to_owned())),
+        _ => {
+            let weight: f64 = indices.iter().map(|&k| w[k]).sum();
+            let share = |k: usize| if weight == 0.0 { 1.0 / indices.len() as f64 } else { w[k] / weight };
+            let (mean, cov) = moments(indices, share, means, covs);
+
+            Ok((weight, mean, cov))
+        }
+    }
+}
+
+/// Weights, means and covariances of the merged groups of a mixture, one per group, where
+/// component `k` belongs to group `labels[k]` as returned by `OlrMatrix::groups`. Every group
+/// `0..=max(labels)` must hold at least one component and is merged as by `merge_moments`.
+pub fn merge_to_gaussian(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    labels: &[usize]
+) -> Result<MixtureParams, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    if labels.len() != w.len() {
+        return Err(MoebiusError::Shape(format!(
+            "{} labels given for {} components", labels.len(), w.len()
+        )));
+    }
+
+    let n_groups = labels.iter().max().map_or(0, |&max| max + 1);
+    let mut members = vec![Vec::new(); n_groups];
+    for (k, &label) in labels.iter().enumerate() {
+        members[label].push(k);
+    }
+    if let Some(empty) = members.iter().position(Vec::is_empty) {
+        return Err(MoebiusError::Shape(format!("group {} has no components", empty)));
+    }
+
+    let dim = means.ncols();
+    let mut weights = Vec::with_capacity(n_groups);
+    let mut merged_means = Array2::zeros((n_groups, dim));
+    let mut merged_covs = Array3::zeros((n_groups, dim, dim));
+    for (g, indices) in members.iter().enumerate() {
+        let weight: f64 = indices.iter().map(|&k| w[k]).sum();
+        let share = |k: usize| if weight == 0.0 { 1.0 / indices.len() as f64 } else { w[k] / weight };
+        let (mean, cov) = moments(indices, share, means, covs);
+
+        weights.push(weight);
+        merged_means.row_mut(g).assign(&mean);
+        merged_covs.index_axis_mut(Axis(0), g).assign(&cov);
+    }
+
+    Ok((weights, merged_means, merged_covs))
+}
+
+/// Mean and covariance of the components `indices` with normalized weights `share(k)`.
+pub(crate) fn moments(
+    indices: &[usize],
+    share: impl Fn(usize) -> f64,
+    means: &Array2<f64>,
+    covs: &Array3<f64>
+) -> (Array1<f64>, Array2<f64>) {
+    let mut mean = Array1::zeros(means.ncols());
+    for &k in indices {
+        mean.scaled_add(share(k), &means.row(k));
+    }
+    let mut cov = Array2::zeros((means.ncols(), means.ncols()));
+    for &k in indices {
+        let offset = (&means.row(k) - &mean).insert_axis(Axis(1));
+        cov.scaled_add(share(k), &covs.index_axis(Axis(0), k));
+        cov.scaled_add(share(k), &offset.dot(&offset.t()));
+    }
+
+    (mean, cov)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr1, arr2, arr3, Array2, Axis};
+    use crate::merge::{merge_moments, merge_to_gaussian};
+
+    #[test]
+    fn merging_all_components_gives_mixture_moments() {
+        let w = [0.2, 0.5, 0.3];
+        let means = arr2(&[[0.0, 1.0], [2.0, -1.0], [1.0, 3.0]]);
+        let covs = arr3(&[
+            [[1.0, 0.2], [0.2, 0.5]],
+            [[0.7, -0.1], [-0.1, 1.2]],
+            [[2.0, 0.4], [0.4, 0.9]],
+        ]);
+
+        let (weight, mean, cov) = merge_moments(&[0, 1, 2], &w, &means, &covs).unwrap();
+
+        // E[x] and E[x x^T] - E[x] E[x]^T of the mixture.
+        let expected_mean = means.t().dot(&arr1(&w));
+        let mut second_moment = Array2::<f64>::zeros((2, 2));
+        for (k, weight) in w.iter().enumerate() {
+            let m = means.row(k).insert_axis(Axis(1));
+            second_moment = second_moment + *weight * (&covs.index_axis(Axis(0), k) + &m.dot(&m.t()));
+        }
+        let m = expected_mean.clone().insert_axis(Axis(1));
+        let expected_cov = second_moment - m.dot(&m.t());
+
+        assert_abs_diff_eq!(1.0, weight, epsilon = 1e-12);
+        assert_abs_diff_eq!(expected_mean, mean, epsilon = 1e-12);
+        assert_abs_diff_eq!(expected_cov, cov, epsilon = 1e-12);
+        // The order of the indices does not matter.
+        let (_, mean_reversed, cov_reversed) = merge_moments(&[2, 1, 0], &w, &means, &covs).unwrap();
+        assert_abs_diff_eq!(mean, mean_reversed, epsilon = 1e-12);
+        assert_abs_diff_eq!(cov, cov_reversed, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn identical_and_single_components() {
+        let w = [0.25, 0.4, 0.35];
+        let means = arr2(&[[1.5], [1.5], [-2.0]]);
+        let covs = arr3(&[[[0.8]], [[0.8]], [[1.1]]]);
+
+        let (weight, mean, cov) = merge_moments(&[0, 1], &w, &means, &covs).unwrap();
+        assert_abs_diff_eq!(0.65, weight, epsilon = 1e-12);
+        assert_abs_diff_eq!(arr1(&[1.5]), mean, epsilon = 1e-12);
+        assert_abs_diff_eq!(arr2(&[[0.8]]), cov, epsilon = 1e-12);
+
+        assert_eq!((0.35, arr1(&[-2.0]), arr2(&[[1.1]])), merge_moments(&[2], &w, &means, &covs).unwrap());
+        let (_, mean, _) = merge_moments(&[0, 2], &[0.0, 0.4, 0.0], &means, &covs).unwrap();
+        assert_abs_diff_eq!(arr1(&[-0.25]), mean, epsilon = 1e-12);
+
+        for indices in [&[][..], &[3], &[1, 1]] {
+            assert!(merge_moments(indices, &w, &means, &covs).is_err(), "{:?}", indices);
+        }
+    }
+
+    #[test]
+    fn merging_groups() {
+        let w = [0.2, 0.3, 0.5];
+        let means = arr2(&[[1.0, -1.0], [1.0, -1.0], [4.0, 2.0]]);
+        let covs = arr3(&[
+            [[1.0, 0.3], [0.3, 2.0]],
+            [[1.0, 0.3], [0.3, 2.0]],
+            [[0.5, 0.0], [0.0, 0.5]],
+        ]);
+
+        // Two identical components merge into that same component.
+        let (weights, merged_means, merged_covs) = merge_to_gaussian(&w, &means, &covs, &[0, 0, 1]).unwrap();
+        assert_abs_diff_eq!(0.5, weights[0], epsilon = 1e-12);
+        assert_abs_diff_eq!(means.row(0), merged_means.row(0), epsilon = 1e-12);
+        assert_abs_diff_eq!(covs.index_axis(Axis(0), 0), merged_covs.index_axis(Axis(0), 0), epsilon = 1e-12);
+        assert_eq!(0.5, weights[1]);
+        assert_eq!(means.row(2), merged_means.row(1));
+
+        let (_, mean, cov) = merge_moments(&[0, 2], &w, &means, &covs).unwrap();
+        let (_, merged_means, merged_covs) = merge_to_gaussian(&w, &means, &covs, &[0, 1, 0]).unwrap();
+        assert_abs_diff_eq!(mean, merged_means.row(0), epsilon = 1e-12);
+        assert_abs_diff_eq!(cov, merged_covs.index_axis(Axis(0), 0), epsilon = 1e-12);
+
+        for labels in [&[0, 0][..], &[0, 2, 0]] {
+            assert!(merge_to_gaussian(&w, &means, &covs, labels).is_err(), "{:?}", labels);
+        }
+    }
+}
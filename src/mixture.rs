@@ -0,0 +1,221 @@
+use ndarray::prelude::*;
+use rand::Rng as _;
+use statrs::distribution::{ContinuousCDF, Normal};
+
+use crate::convert::{covs_from_variances, means_from_scalars};
+use crate::error::MoebiusError;
+use crate::gaussian::Gaussian;
+use crate::merge::moments;
+use crate::olr::{check_shapes, olr_arrays};
+use crate::rng::Rng;
+
+/// Weights, means and covariances of a mixture, as taken by `olr`.
+pub type MixtureParams = (Vec<f64>, Array2<f64>, Array3<f64>);
+
+/// Gaussian mixture with full covariance matrices: `weights` has `n` entries, `means` is `n x d`
+/// and `covs` is `n x d x d`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaussianMixture {
+    pub weights: Vec<f64>,
+    pub means: Array2<f64>,
+    pub covs: Array3<f64>,
+}
+
+/// Borrowed parameters of a single component of a `GaussianMixture`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComponentView<'a> {
+    pub weight: f64,
+    pub mean: ArrayView1<'a, f64>,
+    pub cov: ArrayView2<'a, f64>,
+}
+
+impl GaussianMixture {
+    pub fn new(weights: Vec<f64>, means: Array2<f64>, covs: Array3<f64>) -> Result<Self, MoebiusError> {
+        check_shapes(weights.len(), &means, &covs)?;
+
+        Ok(GaussianMixture { weights, means, covs })
+    }
+
+    /// One-dimensional mixture with the given means and variances, one per component.
+    pub fn univariate(weights: Vec<f64>, means: Vec<f64>, variances: Vec<f64>) -> Result<Self, MoebiusError> {
+        Self::new(weights, means_from_scalars(means), covs_from_variances(variances))
+    }
+
+    pub fn n_components(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.means.ncols()
+    }
+
+    /// Parameters of component `k`, or `None` if `k` is out of range.
+    pub fn component(&self, k: usize) -> Option<ComponentView<'_>> {
+        Some(ComponentView {
+            weight: *self.weights.get(k)?,
+            mean: self.means.row(k),
+            cov: self.covs.index_axis(Axis(0), k),
+        })
+    }
+
+    /// Parameters of every component in order, borrowed from the mixture without copying.
+    pub fn components(&self) -> impl ExactSizeIterator<Item = ComponentView<'_>> + '_ {
+        self.weights.iter()
+            .zip(self.means.outer_iter().zip(self.covs.outer_iter()))
+            .map(|(&weight, (mean, cov))| ComponentView { weight, mean, cov })
+    }
+
+    pub fn olr(&self) -> Result<Vec<f64>, MoebiusError> {
+        olr_arrays(ArrayView1::from(&self.weights), self.means.view(), self.covs.view()).map(Array1::into_raw_vec)
+    }
+
+    /// Marginal mixture over the dimensions `dims`, in the given order, i.e. the selected columns
+    /// of the means and submatrices of the covariances. Indices must be in range and distinct.
+    pub fn project(&self, dims: &[usize]) -> Result<Self, MoebiusError> {
+        let mut seen = vec![false; self.dim()];
+        for &d in dims {
+            match seen.get_mut(d) {
+                None => return Err(MoebiusError::Shape(format!(
+                    "dimension {} is out of range for a {}-dimensional mixture", d, self.dim()
+                ))),
+                Some(true) => return Err(MoebiusError::Shape(format!("dimension {} is selected twice", d))),
+                Some(selected) => *selected = true,
+            }
+        }
+
+        Ok(GaussianMixture {
+            weights: self.weights.clone(),
+            means: self.means.select(Axis(1), dims),
+            covs: self.covs.select(Axis(1), dims).select(Axis(2), dims),
+        })
+    }
+
+    /// Mean `sum_k w_k m_k` of the mixture, with the weights normalized to sum to `1`.
+    pub fn mixture_mean(&self) -> Result<Array1<f64>, MoebiusError> {
+        Ok(self.moments()?.0)
+    }
+
+    /// Covariance `sum_k w_k (C_k + m_k m_k^T) - m m^T` of the mixture by the law of total
+    /// variance, with the weights normalized to sum to `1`.
+    pub fn mixture_covariance(&self) -> Result<Array2<f64>, MoebiusError> {
+        Ok(self.moments()?.1)
+    }
+
+    fn moments(&self) -> Result<(Array1<f64>, Array2<f64>), MoebiusError> {
+        let total = self.total_weight()?;
+        let indices: Vec<usize> = (0..self.n_components()).collect();
+
+        Ok(moments(&indices, |k| self.weights[k] / total, &self.means, &self.covs))
+    }
+
+    fn total_weight(&self) -> Result<f64, MoebiusError> {
+        let total: f64 = self.weights.iter().sum();
+        if !(total > 0.0 && total.is_finite()) {
+            return Err(MoebiusError::Distribution(format!("weights sum to {}, expected a positive total", total)));
+        }
+
+        Ok(total)
+    }
+
+    /// `n_samples` draws from the mixture as rows, with the weights normalized to sum to `1`.
+    /// The same seed of `rng` gives the same draws.
+    pub fn sample(&self, n_samples: usize, rng: &Rng) -> Result<Array2<f64>, MoebiusError> {
+        let total = self.total_weight()?;
+        let gaussians = self.components()
+            .enumerate()
+            .map(|(k, component)| Gaussian::new(component.mean, component.cov, k, None))
+            .collect::<Result<Vec<_>, _>>()?;
+        let normal = Normal::new(0.0, 1.0).unwrap();
+
+        let mut stream = rng.for_task(0);
+        let mut samples = Array2::zeros((n_samples, self.dim()));
+        for mut row in samples.outer_iter_mut() {
+            let mut u = stream.gen::<f64>() * total;
+            let k = self.weights.iter().position(|&w| {
+                u -= w;
+                u < 0.0
+            });
+            // Rounding may leave `u` just above zero after the last component.
+            let k = k.unwrap_or_else(|| self.weights.iter().rposition(|&w| w > 0.0).unwrap());
+            let z: Vec<f64> = (0..self.dim()).map(|_| normal.inverse_cdf(stream.gen::<f64>())).collect();
+            row.assign(&gaussians[k].transform_standard(&z));
+        }
+
+        Ok(samples)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct MixtureRecord {
+    w: Vec<f64>,
+    means: Vec<Vec<f64>>,
+    covs: Vec<Vec<Vec<f64>>>,
+}
+
+#[cfg(feature = "serde")]
+impl GaussianMixture {
+    /// Parses the native JSON layout `{"w": [...], "means": [[...]], "covs": [[[...]]]}`, which
+    /// mirrors the arguments of `olr`.
+    pub fn from_json_str(s: &str) -> Result<Self, MoebiusError> {
+        let record: MixtureRecord = serde_json::from_str(s)
+            .map_err(|e| MoebiusError::Parse(e.to_string()))?;
+
+        GaussianMixture::new(
+            record.w,
+            crate::means_from_nested(record.means)?,
+            crate::covs_from_nested(record.covs)?
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr1, arr2, arr3, Axis};
+    use crate::mixture::GaussianMixture;
+    use crate::rng::Rng;
+
+    #[test]
+    fn moments_of_two_univariate_components() {
+        // Weights 1 : 3, means 0 and 4, variances 1 and 2.
+        let mixture = GaussianMixture::new(vec![1.0, 3.0], arr2(&[[0.0], [4.0]]), arr3(&[[[1.0]], [[2.0]]])).unwrap();
+
+        assert_abs_diff_eq!(arr1(&[3.0]), mixture.mixture_mean().unwrap(), epsilon = 1e-12);
+        // 0.25 * (1 + 0) + 0.75 * (2 + 16) - 9.
+        assert_abs_diff_eq!(arr2(&[[4.75]]), mixture.mixture_covariance().unwrap(), epsilon = 1e-12);
+
+        let weightless = GaussianMixture { weights: vec![0.0, 0.0], ..mixture };
+        assert!(matches!(weightless.mixture_mean(), Err(crate::MoebiusError::Distribution(_))));
+        assert!(weightless.sample(10, &Rng::new(Some(1))).is_err());
+    }
+
+    #[test]
+    fn univariate_matches_nested() {
+        let nested = GaussianMixture::new(vec![1.0, 3.0], arr2(&[[0.0], [4.0]]), arr3(&[[[1.0]], [[2.0]]])).unwrap();
+        assert_eq!(nested, GaussianMixture::univariate(vec![1.0, 3.0], vec![0.0, 4.0], vec![1.0, 2.0]).unwrap());
+        assert!(GaussianMixture::univariate(vec![1.0, 3.0], vec![0.0, 4.0], vec![1.0]).is_err());
+    }
+
+    #[test]
+    fn moments_match_samples() {
+        let mixture = GaussianMixture::new(
+            vec![0.2, 0.5, 0.3],
+            arr2(&[[0.0, 1.0], [2.0, -1.0], [1.0, 3.0]]),
+            arr3(&[
+                [[1.0, 0.2], [0.2, 0.5]],
+                [[0.7, -0.1], [-0.1, 1.2]],
+                [[2.0, 0.4], [0.4, 0.9]],
+            ])
+        ).unwrap();
+        let rng = Rng::new(Some(7));
+        let samples = mixture.sample(100_000, &rng).unwrap();
+        assert_eq!(samples, mixture.sample(100_000, &rng).unwrap());
+
+        let mean = samples.mean_axis(Axis(0)).unwrap();
+        let centered = &samples - &mean;
+        let cov = centered.t().dot(&centered) / (samples.nrows() - 1) as f64;
+        assert_abs_diff_eq!(mixture.mixture_mean().unwrap(), mean, epsilon = 0.02);
+        assert_abs_diff_eq!(mixture.mixture_covariance().unwrap(), cov, epsilon = 0.05);
+    }
+}
@@ -0,0 +1,99 @@
+//! Analytic modality checks of two-component Gaussian mixtures.
+//!
+//! The density of a pair along the line `m_i + t (m_j - m_i)` is itself a one-dimensional mixture
+//! of two Gaussians in `t`, centered at `0` and `1` with variances `1 / a_i` and `1 / a_j`, where
+//! `a_k = (m_j - m_i)^T C_k^-1 (m_j - m_i)`. Whether such a mixture is unimodal is known in closed
+//! form for equal variances, and sufficient conditions exist for unequal ones:
+//!
+//! * Equal variances `s^2` and separation `d = |mu_1 - mu_2| / s`: unimodal for `d <= 2`, and for
+//!   `d > 2` unimodal if and only if `|ln(p / (1 - p))| >= d sqrt(d^2 - 4) / 2 + 2 ln((d -
+//!   sqrt(d^2 - 4)) / 2)` (Behboodian, 1970).
+//! * Unequal variances: unimodal for every weight if `|mu_1 - mu_2| <= 2 min(s_1, s_2)`
+//!   (Behboodian, 1970) or `(mu_1 - mu_2)^2 < 27 s_1^2 s_2^2 / (4 (s_1^2 + s_2^2))` (Eisenberger,
+//!   1964).
+//!
+//! The modes of a two-component mixture lie between its means (Ray and Lindsay, 2005), so the
+//! modality of the line profile is that of the segment scanned by `olr`.
+
+use ndarray::ArrayView1;
+
+use crate::gaussian::Gaussian;
+
+/// Modality of a two-component mixture certified by the analytic checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Modality {
+    Unimodal,
+    Bimodal,
+}
+
+/// Modality of `p N(means.0, variances.0) + (1 - p) N(means.1, variances.1)`, or `None` if the
+/// checks of the module documentation are inconclusive. Variances must be positive.
+pub fn two_component_modality(p: f64, means: (f64, f64), variances: (f64, f64)) -> Option<Modality> {
+    modality(p.ln() - (1.0 - p).ln(), (means.0 - means.1).abs(), variances)
+}
+
+/// Modality of the line profile of the pair `gaussian_i`, `gaussian_j` with weights `w_i` and
+/// `w_j`, whose means are `mean_i` and `mean_j`. Weights are normalized as in
+/// `olr::pair_log_weights`.
+pub(crate) fn line_modality(
+    (w_i, w_j): (f64, f64),
+    (mean_i, mean_j): (ArrayView1<f64>, ArrayView1<f64>),
+    gaussian_i: &Gaussian,
+    gaussian_j: &Gaussian
+) -> Option<Modality> {
+    let (a_i, a_j) = (gaussian_i.quadratic(mean_j), gaussian_j.quadratic(mean_i));
+    if !(a_i > 0.0 && a_j > 0.0 && a_i.is_finite() && a_j.is_finite()) {
+        return None;
+    }
+    // The weights of the profile in `t` absorb the normalizing constants of both components.
+    let ln_weights = if w_i + w_j == 0.0 { 0.0 } else { w_i.ln() - w_j.ln() };
+    let ln_odds = ln_weights - (gaussian_i.ln_det() - gaussian_j.ln_det()) / 2.0 - (a_i.ln() - a_j.ln()) / 2.0;
+
+    modality(ln_odds, 1.0, (1.0 / a_i, 1.0 / a_j))
+}
+
+/// Checks of the module documentation from the log-odds of the weights and the distance of the
+/// means.
+fn modality(ln_odds: f64, distance: f64, (var_1, var_2): (f64, f64)) -> Option<Modality> {
+    if var_1 == var_2 {
+        let d = distance / var_1.sqrt();
+        if d <= 2.0 {
+            return Some(Modality::Unimodal);
+        }
+        let root = (d * d - 4.0).sqrt();
+        let threshold = d * root / 2.0 + 2.0 * ((d - root) / 2.0).ln();
+
+        return Some(if ln_odds.abs() >= threshold { Modality::Unimodal } else { Modality::Bimodal });
+    }
+
+    let behboodian = distance <= 2.0 * var_1.min(var_2).sqrt();
+    let eisenberger = distance * distance < 27.0 * var_1 * var_2 / (4.0 * (var_1 + var_2));
+
+    (behboodian || eisenberger).then_some(Modality::Unimodal)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::modality::{two_component_modality, Modality};
+
+    #[test]
+    fn equal_variances_are_decided_exactly() {
+        assert_eq!(Some(Modality::Unimodal), two_component_modality(0.5, (0.0, 2.0), (1.0, 1.0)));
+        assert_eq!(Some(Modality::Bimodal), two_component_modality(0.5, (0.0, 2.01), (1.0, 1.0)));
+
+        // At d = 3 the threshold of the log-odds is 1.4291, i.e. p = 0.8067.
+        assert_eq!(Some(Modality::Bimodal), two_component_modality(0.8, (0.0, 3.0), (1.0, 1.0)));
+        assert_eq!(Some(Modality::Unimodal), two_component_modality(0.81, (0.0, 3.0), (1.0, 1.0)));
+        assert_eq!(Some(Modality::Unimodal), two_component_modality(0.19, (0.0, 3.0), (1.0, 1.0)));
+    }
+
+    #[test]
+    fn unequal_variances_are_only_certified_unimodal() {
+        assert_eq!(Some(Modality::Unimodal), two_component_modality(0.5, (0.0, 1.9), (1.0, 4.0)));
+        // Beyond 2 min(s_1, s_2), but within the bound of Eisenberger.
+        assert_eq!(Some(Modality::Unimodal), two_component_modality(0.9, (0.0, 2.5), (1.0, 1e6)));
+        assert_eq!(None, two_component_modality(0.5, (0.0, 2.7), (1.0, 1e6)));
+        assert_eq!(None, two_component_modality(0.5, (0.0, 10.0), (1.0, 2.0)));
+    }
+}
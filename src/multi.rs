@@ -0,0 +1,207 @@
+//! OLR of every pair in the context of the whole mixture.
+//!
+//! `olr` compares two components as if the mixture held only them. `olr_multi` scans the same
+//! line between the means of a pair through the density of the whole mixture instead, so that a
+//! third component lying between the two fills in their saddle. If the other components cannot be
+//! evaluated, e.g. because one of them has a covariance that is not positive definite, the pair
+//! falls back to its pairwise OLR and records why in its `MultiStatus`.
+
+use std::sync::OnceLock;
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::gaussian::{ComponentCache, Covariances, Gaussian};
+use crate::olr::{
+    check_pairs, check_shapes, ln_add, olr_gaussian_pair, plain_value, prepare_covs, profile_extrema_in, refine_resolution,
+    resolve_no_saddle, scan_line_in, scan_pairs, OlrConfig, PairClock, PairDetail, PairStatus, MAX_PREALLOCATED_POINTS
+};
+use crate::result::{condensed_len, index_to_pair, pair_to_index};
+use crate::validation::{check_finite, normalize_weights};
+use crate::workspace::Workspace;
+
+/// How `olr_multi` computed a pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiStatus {
+    /// Through the density of the whole mixture.
+    Mixture,
+    /// Through the density of the pair alone, since the rest of the mixture failed for the given
+    /// reason.
+    Pairwise(String),
+    /// Not at all, since a component of the pair itself is invalid for the given reason.
+    Failed(String),
+}
+
+/// Result of `olr_multi`, in the order of `olr`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiOlr {
+    pub n_components: usize,
+    /// OLR of every pair, `NaN` for pairs that failed or timed out.
+    pub values: Vec<f64>,
+    pub status: Vec<MultiStatus>,
+}
+
+impl MultiOlr {
+    /// Pairs that fell back to their pairwise OLR, with the reason.
+    pub fn fallbacks(&self) -> Vec<((usize, usize), &str)> {
+        self.status.iter().enumerate()
+            .filter_map(|(k, status)| match status {
+                MultiStatus::Pairwise(reason) => Some((index_to_pair(k, self.n_components), reason.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// OLR of every pair `(i, j)` from the log-density `ln sum_k w_k N(x; m_k, C_k)` of the whole
+/// mixture along the line from `means[i]` to `means[j]`, sampled as described in `OlrConfig`.
+/// Pairs fall back to their value in `olr_with_config` if another component cannot be factorized
+/// or the density of the mixture is not finite along their line, and fail with `NaN` if one of
+/// their own components cannot be factorized. Other errors, and all invalid components with
+/// `config.strict`, fail the whole call as in `olr_with_config`.
+pub fn olr_multi(w: &[f64], means: &Array2<f64>, covs: &Array3<f64>, config: &OlrConfig) -> Result<MultiOlr, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    config.validate()?;
+    check_pairs(w.len(), config)?;
+    let w = &*normalize_weights(w, config)?;
+
+    let n_comp = w.len();
+    let covs = prepare_covs(covs.view(), config);
+    let cache = ComponentCache::new(means.view(), Covariances::Dense(covs.view()));
+    if config.strict {
+        cache.check_all()?;
+    }
+    let pre_score = |i: usize, j: usize| match cache.pair(i, j) {
+        Ok((gaussian_i, gaussian_j)) => {
+            -(gaussian_i.quadratic(means.row(j)).sqrt() + gaussian_j.quadratic(means.row(i)).sqrt()) / 2.0
+        }
+        Err(_) => f64::NEG_INFINITY,
+    };
+
+    let n_pairs = condensed_len(n_comp);
+    let status: Vec<OnceLock<MultiStatus>> = (0..n_pairs).map(|_| OnceLock::new()).collect();
+    let points = vec![config.n_points; n_pairs];
+    let details = scan_pairs(n_comp, config, &points, None, pre_score, None, None, |i, j, pair_config, workspace| {
+        let record = |s: MultiStatus| status[pair_to_index(i, j, n_comp)].set(s).unwrap();
+        let (density_i, density_j) = match cache.pair(i, j) {
+            Ok(pair) => pair,
+            Err(e) => {
+                record(MultiStatus::Failed(e.to_string()));
+                let detail = PairDetail::complete(f64::NAN);
+                return Ok((detail, 0));
+            }
+        };
+
+        let context = match cache.check((0..n_comp).filter(|&k| k != i && k != j)) {
+            Ok(()) => mixture_pair(w, means.view(), &cache, (i, j), pair_config, workspace)?,
+            Err(e) => Err(e.to_string()),
+        };
+        match context {
+            Ok(result) => {
+                record(MultiStatus::Mixture);
+                Ok(result)
+            }
+            Err(reason) => {
+                record(MultiStatus::Pairwise(reason));
+                olr_gaussian_pair(w, &means.view(), density_i, density_j, (i, j), pair_config, &config.detector, workspace)
+            }
+        }
+    })?;
+
+    Ok(MultiOlr {
+        n_components: n_comp,
+        values: details.iter().map(plain_value).collect(),
+        status: status.into_iter().map(|s| s.into_inner().unwrap()).collect(),
+    })
+}
+
+/// Profile extrema of pair `(i, j)` through the density of every component of `cache`, which must
+/// all be valid, as in `olr_pair`. The inner error is the reason to fall back to the pairwise OLR.
+fn mixture_pair(
+    w: &[f64],
+    means: ArrayView2<f64>,
+    cache: &ComponentCache,
+    (i, j): (usize, usize),
+    config: &OlrConfig,
+    workspace: &mut Workspace
+) -> Result<Result<(PairDetail, usize), String>, MoebiusError> {
+    let densities: Vec<&Gaussian> = (0..w.len()).map(|k| cache.component(k)).collect::<Result<_, _>>()?;
+    let ln_w: Vec<f64> = w.iter().map(|w| w.ln()).collect();
+    let clock = PairClock::start(config);
+    let mut failure = None;
+
+    let result = refine_resolution(config, |config| resolve_no_saddle(i, j, config, |config| {
+        let Workspace { profile, point, delta, extrema, scratch, .. } = &mut *workspace;
+        profile.clear();
+        profile.reserve(config.grid_len().min(MAX_PREALLOCATED_POINTS));
+        let complete = scan_line_in(means.row(i), means.row(j), config, (point, delta), |point| {
+            if clock.expired(profile.len()) {
+                return false;
+            }
+            let ln_pdf = densities.iter().zip(&ln_w)
+                .fold(f64::NEG_INFINITY, |acc, (density, ln_w)| ln_add(acc, ln_w + density.ln_pdf_with(point.view(), scratch)));
+            if ln_pdf.is_nan() || ln_pdf == f64::INFINITY {
+                failure = Some(format!("the log-density of the mixture at point {} of the line is {}", profile.len(), ln_pdf));
+                return false;
+            }
+            profile.push(ln_pdf);
+            true
+        });
+
+        let mut detail = profile_extrema_in(profile, &config.detector, extrema);
+        // A failed scan stops as a timed-out one, so that it is neither extended nor refined.
+        if !complete {
+            detail.status = PairStatus::TimedOut;
+        }
+
+        detail
+    }))?;
+
+    Ok(match failure {
+        Some(reason) => Err(reason),
+        None => Ok(result),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::multi::{olr_multi, MultiStatus};
+    use crate::olr::{olr_with_config, OlrConfig};
+
+    #[test]
+    fn context_fills_in_the_saddle() {
+        let w = [0.4, 0.4, 0.2];
+        let means = arr2(&[[0.0], [4.0], [2.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]], [[1.0]]]);
+        let config = OlrConfig::default();
+
+        let multi = olr_multi(&w, &means, &covs, &config).unwrap();
+        let pairwise = olr_with_config(&w, &means, &covs, &config).unwrap();
+        assert_eq!(vec![MultiStatus::Mixture; 3], multi.status);
+        assert!(multi.values[0] > pairwise[0], "{:?} {:?}", multi.values, pairwise);
+        assert!(multi.fallbacks().is_empty());
+    }
+
+    #[test]
+    fn invalid_context_falls_back_to_pairwise() {
+        let w = [0.4, 0.4, 0.2];
+        let means = arr2(&[[0.0], [4.0], [2.0]]);
+        let covs = arr3(&[[[1.0]], [[1.5]], [[-1.0]]]);
+        let config = OlrConfig::default();
+
+        let multi = olr_multi(&w, &means, &covs, &config).unwrap();
+        let sliced = olr_with_config(&w[..2], &means.slice(ndarray::s![..2, ..]).to_owned(), &covs.slice(ndarray::s![..2, .., ..]).to_owned(), &config).unwrap();
+        assert_eq!(sliced[0].to_bits(), multi.values[0].to_bits());
+        let fallbacks = multi.fallbacks();
+        assert_eq!(1, fallbacks.len());
+        assert_eq!((0, 1), fallbacks[0].0);
+        assert!(fallbacks[0].1.contains("component 2"), "{}", fallbacks[0].1);
+
+        // Pairs of the invalid component fail on their own.
+        assert!(multi.values[1].is_nan() && multi.values[2].is_nan());
+        assert!(matches!(multi.status[2], MultiStatus::Failed(_)));
+        assert!(olr_multi(&w, &means, &covs, &OlrConfig { strict: true, ..config }).is_err());
+    }
+}
@@ -0,0 +1,3060 @@
+use std::time::{Duration, Instant};
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::thread;
+
+use ndarray::prelude::*;
+use ndarray::Data;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+use crate::error::MoebiusError;
+use crate::convert::packed_len;
+use crate::covariance::Covariance;
+use crate::density::ComponentDensity;
+use crate::gaussian::{ComponentCache, Covariances, Gaussian};
+use crate::density::{Inflated, PdfComponent, StudentT};
+use crate::extrema::{for_each_extremum, parabolic_vertex, Detector, Extrema, ExtremaDetector};
+use crate::modality::{line_modality, Modality};
+use crate::parallel::{build_pool, resolve_threads};
+use crate::resolution::allocate_points;
+use crate::result::{checked_condensed_len, condensed_len, index_to_pair, rank_order, OlrMatrix};
+use crate::validation::{check_finite, check_selection, non_finite, normalize_weights, normalized_sum, small_variances};
+use crate::workspace::{with_thread_workspace, Scratch, SessionBuffers, Workspace};
+
+/// Default number of steps between two component means.
+pub const DEFAULT_N_POINTS: usize = 1000;
+/// Default number of steps sampled before the first mean. The line extends `2 * extension` steps
+/// past the second mean, so with the defaults every pair is scanned in `1030` steps.
+pub const DEFAULT_EXTENSION: usize = 10;
+/// Default relative floor below which variances are reported as suspiciously small.
+pub const DEFAULT_VARIANCE_FLOOR: f64 = 1e-12;
+
+/// Number of times `NoSaddlePolicy::ExtendAndRetry` doubles the extension before giving up, so
+/// that the line extends up to `64` times further than configured.
+pub const MAX_EXTENSION_RETRIES: usize = 6;
+
+/// What to do with a pair whose profile has no saddle between two peaks. A Gaussian pair
+/// without a saddle has merged components, but for other densities, or with a line that does
+/// not pass through the modes, a missing saddle may as well lie outside the sampled line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum NoSaddlePolicy {
+    /// The components are merged and the OLR is `1`.
+    #[default]
+    AssumeMerged,
+    /// Fails with a distribution error naming the pair. Also fails for truly merged pairs, which
+    /// cannot be told apart.
+    Error,
+    /// Rescans the pair with the extension doubled, up to `MAX_EXTENSION_RETRIES` times, and
+    /// falls back to `1` if no saddle is found on the longest line.
+    ExtendAndRetry,
+}
+
+impl FromStr for NoSaddlePolicy {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "assume_merged" => Ok(NoSaddlePolicy::AssumeMerged),
+            "error" => Ok(NoSaddlePolicy::Error),
+            "extend_and_retry" => Ok(NoSaddlePolicy::ExtendAndRetry),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown no-saddle policy {:?}, expected \"assume_merged\", \"error\" or \"extend_and_retry\"", s
+            ))),
+        }
+    }
+}
+
+/// Order in which pairs are computed. Values are always returned in the condensed order of
+/// `olr`, so the order only matters for `olr_streamed`, which reports pairs as they are done, and
+/// for which error is reported if several pairs fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PairOrder {
+    /// Condensed order, `(0, 1), (0, 2), ...`.
+    #[default]
+    Index,
+    /// Pairs with the closest means first, as a cheap proxy for the largest overlap. Closeness is
+    /// the average of the Mahalanobis distances under either covariance, or the Euclidean distance
+    /// for entry points without covariance matrices. Ties keep the condensed order.
+    Overlap,
+}
+
+impl FromStr for PairOrder {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "index" => Ok(PairOrder::Index),
+            "overlap" => Ok(PairOrder::Overlap),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown pair order {:?}, expected \"index\" or \"overlap\"", s
+            ))),
+        }
+    }
+}
+
+/// Sampling of the line of a pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GridStrategy {
+    /// `OlrConfig::n_points` equal steps between the means.
+    #[default]
+    Uniform,
+    /// The uniform grid, plus `ADAPTIVE_PEAK_POINTS` points within three standard deviations of
+    /// every mean around which the uniform grid has fewer, and points at geometrically growing
+    /// distances from there out to the uniform step. Standard deviations are taken along the
+    /// line, as `(u^T C^-1 u)^(-1/2)` for the unit direction `u` between the means, so that the
+    /// saddle next to a component much narrower than the step is still resolved. Applies to
+    /// Gaussian components only; the parabolic detector assumes equal steps and is approximate
+    /// on the added points.
+    Adaptive,
+}
+
+impl FromStr for GridStrategy {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(GridStrategy::Uniform),
+            "adaptive" => Ok(GridStrategy::Adaptive),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown grid strategy {:?}, expected \"uniform\" or \"adaptive\"", s
+            ))),
+        }
+    }
+}
+
+/// What to do when a pair exceeds `OlrConfig::pair_timeout` or the call exceeds
+/// `OlrConfig::deadline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TimeoutPolicy {
+    /// Records the pair as `PairStatus::TimedOut` and goes on with the remaining pairs.
+    #[default]
+    Continue,
+    /// Fails with `MoebiusError::Timeout` naming the pair.
+    Abort,
+}
+
+impl FromStr for TimeoutPolicy {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "continue" => Ok(TimeoutPolicy::Continue),
+            "abort" => Ok(TimeoutPolicy::Abort),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown timeout policy {:?}, expected \"continue\" or \"abort\"", s
+            ))),
+        }
+    }
+}
+
+/// Number of grid points evaluated between two checks of the timeouts.
+pub const TIMEOUT_CHECK_INTERVAL: usize = 256;
+
+/// Steps between the means of the first scan of `Resolution::Auto`.
+pub const AUTO_START_POINTS: usize = 128;
+/// Default `tolerance` of `Resolution::Auto`.
+pub const DEFAULT_RESOLUTION_TOLERANCE: f64 = 1e-6;
+/// Default `max_points` of `Resolution::Auto`, reached after nine refinements.
+pub const DEFAULT_MAX_POINTS: usize = AUTO_START_POINTS << 9;
+
+/// How many steps between the means the line of every pair is sampled with.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Resolution {
+    /// `OlrConfig::n_points` steps.
+    #[default]
+    Fixed,
+    /// Scans the pair with `AUTO_START_POINTS` steps and doubles them until two successive OLR
+    /// estimates differ by less than `tolerance`, or `max_points` steps were scanned. The
+    /// extension covers the same share of the line as `OlrConfig::extension` does of
+    /// `OlrConfig::n_points`. The resolution reached is reported in `PairDetail::refinement`.
+    Auto { tolerance: f64, max_points: usize },
+}
+
+impl Resolution {
+    /// `Resolution::Auto` with `tolerance` and `DEFAULT_MAX_POINTS`.
+    pub fn auto(tolerance: f64) -> Self {
+        Resolution::Auto { tolerance, max_points: DEFAULT_MAX_POINTS }
+    }
+}
+
+/// Sampling parameters of the line connecting two component means.
+///
+/// The line is sampled with step `(m_j - m_i) / n_points`, starting `extension` steps before
+/// `m_i` and ending `2 * extension` steps past `m_j`. `reg_covar` is added to the diagonal of
+/// every covariance matrix, as in scikit-learn, to make nearly singular matrices usable.
+///
+/// `symmetrize` replaces every covariance matrix `C` with `(C + C^T) / 2` before use. This is a
+/// numerical convenience for fitted matrices that are asymmetric by rounding errors only, and
+/// hides genuinely invalid input, so it is off by default.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct OlrConfig {
+    pub n_points: usize,
+    pub extension: usize,
+    /// Whether `n_points` is used as is or the grid is refined until the OLR converges.
+    pub resolution: Resolution,
+    pub reg_covar: f64,
+    pub symmetrize: bool,
+    /// Variances below `variance_floor` times the squared magnitude of the means are reported by
+    /// `olr_detailed`.
+    pub variance_floor: f64,
+    /// Factorizes every covariance before computing any pair and reports all components that
+    /// are not positive definite at once, instead of failing on the first pair that uses one.
+    /// Also rejects weights that do not sum to `1` within `WEIGHT_SUM_TOLERANCE`.
+    pub strict: bool,
+    /// Divides the weights by their sum when it is not `1` within `WEIGHT_SUM_TOLERANCE`, e.g. for
+    /// raw counts, instead of rejecting them with `strict`. `OlrDetails::normalized_sum` records
+    /// the sum. Since every pair normalizes its two weights, values are the same either way.
+    pub auto_normalize: bool,
+    /// Fails with a shape error for mixtures of fewer than two components, which have no pairs and
+    /// otherwise give an empty result. Catches upstream filters that removed all components.
+    pub require_pairs: bool,
+    /// Handling of pairs without a saddle.
+    pub no_saddle: NoSaddlePolicy,
+    /// Checks the modality of every Gaussian pair analytically before scanning it, see
+    /// `modality`. Pairs certified unimodal are `1` without being scanned unless `no_saddle` is
+    /// `NoSaddlePolicy::Error`, and pairs certified bimodal whose saddle is too shallow for the
+    /// grid are `1` without `no_saddle` applying to them. `PairDetail::modality` records the
+    /// outcome.
+    pub analytic_shortcut: bool,
+    /// Sampling of the line of every pair.
+    pub grid: GridStrategy,
+    /// Detector of the peaks and saddles of every profile.
+    pub detector: Detector,
+    /// Returns `PairDetail::extrapolated` as the value of every pair that has one.
+    pub extrapolate: bool,
+    /// Order in which pairs are computed.
+    pub pair_order: PairOrder,
+    /// Time after which the scan of a single pair stops, checked every `TIMEOUT_CHECK_INTERVAL`
+    /// grid points. The pair keeps the extrema found so far and is marked
+    /// `PairStatus::TimedOut`; functions returning plain values report it as `NaN`.
+    pub pair_timeout: Option<Duration>,
+    /// Time after which the whole call stops, measured from its start. Pairs that are running
+    /// stop as with `pair_timeout`, and pairs that have not started time out without being
+    /// scanned.
+    pub deadline: Option<Duration>,
+    /// Handling of pairs that time out.
+    pub on_timeout: TimeoutPolicy,
+    /// Number of threads. `None` uses `parallel::num_threads()`, `0` means all cores and `1` runs
+    /// on the calling thread.
+    pub n_threads: Option<usize>,
+    /// Makes every value a function of the inputs and the configuration alone, bit for bit. Values
+    /// are already the same for any number of threads and pair order, see `scan_pairs`, and sums
+    /// such as the accumulators of diagonal covariances are added in a fixed order whatever the
+    /// SIMD width of the machine. On top of that, deterministic runs
+    ///
+    /// * scan every line point by point instead of with the matrix kernel of `BATCHED_DIMS`,
+    ///   whose sums are associated differently, so values do not depend on the dimension ranges
+    ///   that kernel is tuned for;
+    /// * refine `Resolution::Auto` through every doubling up to `max_points` instead of stopping
+    ///   at the tolerance, so the number of refinements does not hinge on the last bits of a
+    ///   value, at the cost of always scanning as many points as the slowest pair;
+    /// * reject `pair_timeout` and `deadline`, which make values depend on the wall clock.
+    ///
+    /// Transcendental functions come from the platform's math library in either mode, so values
+    /// may still differ between operating systems in their last bits.
+    pub deterministic: bool,
+}
+
+impl Default for OlrConfig {
+    fn default() -> Self {
+        OlrConfig {
+            n_points: DEFAULT_N_POINTS,
+            extension: DEFAULT_EXTENSION,
+            resolution: Resolution::Fixed,
+            reg_covar: 0.0,
+            symmetrize: false,
+            variance_floor: DEFAULT_VARIANCE_FLOOR,
+            strict: false,
+            auto_normalize: false,
+            require_pairs: false,
+            no_saddle: NoSaddlePolicy::AssumeMerged,
+            analytic_shortcut: false,
+            grid: GridStrategy::Uniform,
+            detector: Detector::SignChange,
+            extrapolate: false,
+            pair_order: PairOrder::Index,
+            pair_timeout: None,
+            deadline: None,
+            on_timeout: TimeoutPolicy::Continue,
+            n_threads: None,
+            deterministic: false,
+        }
+    }
+}
+
+impl OlrConfig {
+    pub fn validate(&self) -> Result<(), MoebiusError> {
+        if self.n_points == 0 {
+            return Err(MoebiusError::InvalidConfig("n_points must be positive".to_string()));
+        }
+        if !(self.reg_covar >= 0.0 && self.reg_covar.is_finite()) {
+            return Err(MoebiusError::InvalidConfig(format!(
+                "reg_covar must be non-negative and finite, got {}", self.reg_covar
+            )));
+        }
+        if !(self.variance_floor >= 0.0 && self.variance_floor.is_finite()) {
+            return Err(MoebiusError::InvalidConfig(format!(
+                "variance_floor must be non-negative and finite, got {}", self.variance_floor
+            )));
+        }
+        if let Resolution::Auto { tolerance, max_points } = self.resolution {
+            if !(tolerance > 0.0 && tolerance.is_finite()) {
+                return Err(MoebiusError::InvalidConfig(format!(
+                    "resolution tolerance must be positive and finite, got {}", tolerance
+                )));
+            }
+            if max_points < AUTO_START_POINTS {
+                return Err(MoebiusError::InvalidConfig(format!(
+                    "max_points must be at least {}, got {}", AUTO_START_POINTS, max_points
+                )));
+            }
+        }
+        if self.deterministic && (self.pair_timeout.is_some() || self.deadline.is_some()) {
+            return Err(MoebiusError::InvalidConfig(
+                "pair_timeout and deadline depend on the wall clock and cannot be used with deterministic".to_string()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Number of points at which the mixture density is evaluated for every pair.
+    pub fn grid_len(&self) -> usize {
+        self.n_points + 3 * self.extension + 1
+    }
+}
+
+/// Counters collected while computing OLR values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OlrStats {
+    /// Number of two-component mixture density evaluations.
+    pub pdf_evaluations: usize,
+    /// Wall time spent on every pair, in output order.
+    pub pair_times: Vec<Duration>,
+    /// Names of the threads pairs were computed on. Pool threads are named
+    /// `parallel::THREAD_NAME_PREFIX` followed by their index.
+    pub threads: BTreeSet<String>,
+}
+
+/// Whether the scan of a pair ran to completion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PairStatus {
+    #[default]
+    Complete,
+    /// The scan stopped at `OlrConfig::pair_timeout` or `OlrConfig::deadline`. The extrema are
+    /// those of the part of the line scanned until then.
+    TimedOut,
+}
+
+/// Extrema of the density profile of a single pair.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairDetail {
+    pub value: f64,
+    /// Log-density of the two-component mixture at its lowest peak, `None` if the profile has a
+    /// single peak or no saddle.
+    pub ln_peak: Option<f64>,
+    /// Log-density at the first saddle, `None` together with `ln_peak`.
+    pub ln_saddle: Option<f64>,
+    /// Whether the peak or saddle density lies outside the range of normal `f64` values, so that
+    /// the ratio could only be computed in log space.
+    pub extreme_range: bool,
+    pub status: PairStatus,
+    /// Resolution reached with `Resolution::Auto`, `None` with `Resolution::Fixed`.
+    pub refinement: Option<Refinement>,
+    /// Modality certified by `OlrConfig::analytic_shortcut`, `None` if it was not checked or the
+    /// checks were inconclusive. Unimodal pairs are not scanned and have no refinement.
+    pub modality: Option<Modality>,
+    /// Grid of the last scan, `GridStrategy::Adaptive` only if points were added to the uniform
+    /// grid.
+    pub grid: GridStrategy,
+    /// Richardson extrapolation of the OLR to a continuous profile, see `error_estimate`, clamped
+    /// to `[0, 1]`. `None` for pairs without a saddle and on the adaptive grid.
+    pub extrapolated: Option<f64>,
+    /// Estimated deviation of `value` from the OLR of the continuous profile, `None` together
+    /// with `extrapolated`. It compares the last scan, with `n` steps, to the coarse grids of its
+    /// even and of its odd points, with `n / 2` steps each.
+    ///
+    /// Values on the grid cannot be compared directly: the fine grid contains every point of a
+    /// coarse one, so both often sample an extremum at the same point and have the same value.
+    /// The OLR of `Detector::Parabolic` converges with the cube of the step instead, and with
+    /// `olr_fine` on all points and `olr_coarse` on the even ones, `extrapolated` is
+    /// `olr_fine + (olr_fine - olr_coarse) / 7`. The estimate is the distance of `value` from it,
+    /// plus `|olr_fine - olr_coarse|`, or the same difference for the odd points if that is
+    /// larger, for the error of the extrapolation itself. A coarse grid without a saddle counts
+    /// as merged, with an OLR of `1`. The estimate is asymptotic: it bounds the deviation once
+    /// the coarse grids resolve the extrema, as from the default number of points, and may not
+    /// on coarser grids.
+    pub error_estimate: Option<f64>,
+}
+
+impl PairDetail {
+    /// Completed pair of OLR `value` without extrema, e.g. a merged pair or one that was not
+    /// scanned.
+    pub fn complete(value: f64) -> Self {
+        PairDetail {
+            value,
+            ln_peak: None,
+            ln_saddle: None,
+            extreme_range: false,
+            status: PairStatus::Complete,
+            refinement: None,
+            modality: None,
+            grid: GridStrategy::Uniform,
+            extrapolated: None,
+            error_estimate: None,
+        }
+    }
+
+    /// Pair whose time ran out before its scan started, which counts as merged.
+    pub fn timed_out() -> Self {
+        PairDetail { status: PairStatus::TimedOut, ..PairDetail::complete(1.0) }
+    }
+}
+
+/// Grid on which `Resolution::Auto` stopped refining a pair.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Refinement {
+    /// Steps between the means of the last scan.
+    pub n_points: usize,
+    /// Absolute difference between the OLR of the last and the previous scan, infinite if the
+    /// first scan was not refined. At least `tolerance` if the pair stopped at `max_points`.
+    pub delta: f64,
+}
+
+/// Per-pair extrema together with the warnings raised by the input checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OlrDetails {
+    pub n_components: usize,
+    pub dim: usize,
+    /// Configuration the pairs were computed with.
+    pub config: OlrConfig,
+    /// Pairs in the order of `olr`.
+    pub pairs: Vec<PairDetail>,
+    /// `(component, dimension)` of every variance below `config.variance_floor`, as reported by
+    /// `validation::small_variances`.
+    pub small_variances: Vec<(usize, usize)>,
+    /// Sum of the weights if `config.auto_normalize` divided them by it.
+    pub normalized_sum: Option<f64>,
+}
+
+impl OlrDetails {
+    /// Largest `PairDetail::error_estimate` of all pairs, `None` if no pair has one.
+    pub fn max_error_estimate(&self) -> Option<f64> {
+        self.pairs.iter().filter_map(|detail| detail.error_estimate).reduce(f64::max)
+    }
+}
+
+pub fn olr(w: Vec<f64>, means: Array2<f64>, covs: Array3<f64>) -> Vec<f64> {
+    try_olr(w, means, covs).unwrap()
+}
+
+/// Same as `olr`, but reports inconsistent shapes and invalid covariance matrices as errors
+/// instead of panicking.
+pub fn try_olr(w: Vec<f64>, means: Array2<f64>, covs: Array3<f64>) -> Result<Vec<f64>, MoebiusError> {
+    olr_arrays(ArrayView1::from(&w), means.view(), covs.view()).map(Array1::into_raw_vec)
+}
+
+/// Same as `try_olr` on borrowed arrays, so that views into larger arrays can be passed without
+/// copying.
+pub fn olr_arrays(w: ArrayView1<f64>, means: ArrayView2<f64>, covs: ArrayView3<f64>) -> Result<Array1<f64>, MoebiusError> {
+    let w = w.as_standard_layout();
+
+    olr_impl(w.as_slice().unwrap(), means, covs, &OlrConfig::default(), &Detector::SignChange, None, None).map(|details| Array1::from(values(details)))
+}
+
+/// OLR values paired with the labels of the two components, in the order of `try_olr`.
+pub type LabeledOlr = Vec<((String, String), f64)>;
+
+/// OLR values paired with the indices `(i, j)`, `i < j`, of the two components.
+pub type IndexedOlr = Vec<((usize, usize), f64)>;
+
+/// Callback of `scan_pairs` receiving the output index and detail of every computed pair.
+pub(crate) type EmitPair<'e> = &'e mut dyn FnMut(usize, &PairDetail);
+
+/// Same as `try_olr`, with every value keyed by the labels of its pair. `labels` must have one
+/// entry per component.
+pub fn olr_labeled(
+    w: Vec<f64>,
+    means: Array2<f64>,
+    covs: Array3<f64>,
+    labels: Vec<String>
+) -> Result<LabeledOlr, MoebiusError> {
+    if labels.len() != w.len() {
+        return Err(MoebiusError::Shape(format!(
+            "{} labels given for {} components", labels.len(), w.len()
+        )));
+    }
+
+    let values = OlrMatrix::new(w.len(), try_olr(w, means, covs)?)?;
+    let labeled = values.pairs().into_iter()
+        .zip(values.values())
+        .map(|((i, j), value)| ((labels[i].clone(), labels[j].clone()), *value))
+        .collect();
+
+    Ok(labeled)
+}
+
+/// OLR of every pair computed separately along every axis: `result[k][d]` is the OLR of the
+/// marginals of the `k`-th pair, in the order of `try_olr`, along dimension `d`. Only the
+/// variances are used, so the decomposition describes the full mixture exactly only for diagonal
+/// covariances.
+pub fn olr_per_axis(w: Vec<f64>, means: Array2<f64>, covs: Array3<f64>) -> Result<Vec<Vec<f64>>, MoebiusError> {
+    check_shapes(w.len(), &means, &covs)?;
+
+    let n_pairs = condensed_len(w.len());
+    let mut per_axis = vec![Vec::with_capacity(means.ncols()); n_pairs];
+    for d in 0..means.ncols() {
+        let axis_means = means.slice(s![.., d..d + 1]).to_owned();
+        let axis_covs = covs.slice(s![.., d..d + 1, d..d + 1]).to_owned();
+        let values = olr_with_config(&w, &axis_means, &axis_covs, &OlrConfig::default())?;
+
+        for (pair, value) in per_axis.iter_mut().zip(values) {
+            pair.push(value);
+        }
+    }
+
+    Ok(per_axis)
+}
+
+/// OLR values of the mixture with every covariance multiplied by each of `scales`, one vector in
+/// the order of `olr` per scale. Every covariance is factorized once and rescaled for all scales.
+pub fn olr_scan_scale(
+    w: Vec<f64>,
+    means: Array2<f64>,
+    covs: Array3<f64>,
+    scales: Vec<f64>
+) -> Result<Vec<Vec<f64>>, MoebiusError> {
+    check_shapes(w.len(), &means, &covs)?;
+    check_finite(&w, &means, &covs)?;
+    if let Some(scale) = scales.iter().find(|scale| !(scale.is_finite() && **scale > 0.0)) {
+        return Err(MoebiusError::InvalidConfig(format!("scales must be positive and finite, got {}", scale)));
+    }
+
+    let gaussians = (0..w.len())
+        .map(|k| Gaussian::new(means.row(k), covs.index_axis(Axis(0), k), k, None))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    scales.iter()
+        .map(|&scale| {
+            let inflated: Vec<Inflated> = gaussians.iter().map(|gaussian| Inflated::new(gaussian, scale)).collect();
+
+            olr_with_densities(&w, &means, &inflated, &OlrConfig::default())
+        })
+        .collect()
+}
+
+/// Number of values `(n * dim, n * dim * dim)` of flat means and covariances, or a shape error if
+/// they do not fit in `usize`.
+pub(crate) fn flat_lengths(n_comp: usize, dim: usize) -> Result<(usize, usize), MoebiusError> {
+    n_comp.checked_mul(dim)
+        .and_then(|means_len| Some((means_len, means_len.checked_mul(dim)?)))
+        .ok_or_else(|| MoebiusError::Shape(format!("{} components of dimension {} are too large", n_comp, dim)))
+}
+
+/// Same as `try_olr` for row-major flat inputs: `means` holds `n * dim` values and `covs`
+/// `n * dim * dim` values, where `n = w.len()`.
+pub fn olr_flat(w: &[f64], means: &[f64], covs: &[f64], dim: usize) -> Result<Vec<f64>, MoebiusError> {
+    let n_comp = w.len();
+    let (means_len, covs_len) = flat_lengths(n_comp, dim)?;
+    if means.len() != means_len {
+        return Err(MoebiusError::Shape(format!(
+            "means has {} values, expected {} x {}", means.len(), n_comp, dim
+        )));
+    }
+    if covs.len() != covs_len {
+        return Err(MoebiusError::Shape(format!(
+            "covs has {} values, expected {} x {} x {}", covs.len(), n_comp, dim, dim
+        )));
+    }
+
+    // Shapes with an empty axis still fail if the other axes overflow `isize`.
+    let too_large = |e: ndarray::ShapeError| MoebiusError::Shape(format!("{} components of dimension {}: {}", n_comp, dim, e));
+    let means = Array2::from_shape_vec((n_comp, dim), means.to_vec()).map_err(too_large)?;
+    let covs = Array3::from_shape_vec((n_comp, dim, dim), covs.to_vec()).map_err(too_large)?;
+
+    olr_with_config(w, &means, &covs, &OlrConfig::default())
+}
+
+/// Same as `try_olr` with every covariance flattened row-major into a row of `flat_covs`, which
+/// is `n x (dim * dim)` like `covs.reshape(n, -1)` in NumPy.
+pub fn olr_flat_covs(w: &[f64], means: &Array2<f64>, flat_covs: &Array2<f64>, dim: usize) -> Result<Vec<f64>, MoebiusError> {
+    if means.ncols() != dim {
+        return Err(MoebiusError::Shape(format!("means have {} columns, expected dim = {}", means.ncols(), dim)));
+    }
+    if flat_covs.nrows() != w.len() {
+        return Err(MoebiusError::Shape(format!(
+            "flat_covs has {} rows, but w has {} weights", flat_covs.nrows(), w.len()
+        )));
+    }
+    if Some(flat_covs.ncols()) != dim.checked_mul(dim) {
+        return Err(MoebiusError::Shape(format!(
+            "flat_covs rows have {} values, expected {} x {}", flat_covs.ncols(), dim, dim
+        )));
+    }
+
+    let covs = Array3::from_shape_vec((w.len(), dim, dim), flat_covs.iter().copied().collect()).unwrap();
+
+    olr_with_config(w, means, &covs, &OlrConfig::default())
+}
+
+/// Same as `olr_with_config` with the covariances packed into the rows of `packed_covs` as by
+/// `pack_covariances`, `n x d (d + 1) / 2` instead of `n x d x d`. Every covariance is unpacked
+/// when its component is factorized, so the dense array is never assembled. The packed matrices
+/// are symmetric, so `config.symmetrize` has no effect.
+pub fn olr_packed(w: &[f64], means: &Array2<f64>, packed_covs: &Array2<f64>, config: &OlrConfig) -> Result<Vec<f64>, MoebiusError> {
+    let dim = means.ncols();
+    if means.nrows() != w.len() || packed_covs.nrows() != w.len() {
+        return Err(MoebiusError::Shape(format!(
+            "{} weights, {} means and {} packed covariances given", w.len(), means.nrows(), packed_covs.nrows()
+        )));
+    }
+    if packed_covs.ncols() != packed_len(dim) {
+        return Err(MoebiusError::Shape(format!(
+            "packed covariances have {} values, expected {} for dimension {}", packed_covs.ncols(), packed_len(dim), dim
+        )));
+    }
+    check_finite(w, means, &Array3::zeros((0, 0, 0)))?;
+    if let Some(((k, e), v)) = packed_covs.indexed_iter().find(|(_, v)| !v.is_finite()) {
+        return Err(non_finite(format!("packed_covs[{}][{}]", k, e), *v));
+    }
+    config.validate()?;
+
+    let mut packed_covs = CowArray::from(packed_covs.view());
+    if config.reg_covar != 0.0 {
+        let mut owned = packed_covs.into_owned();
+        // The diagonal entry of row `r` starts the `r`-th packed row, of `dim - r` values.
+        for mut row in owned.outer_iter_mut() {
+            let mut diagonal = 0;
+            for r in 0..dim {
+                row[diagonal] += config.reg_covar;
+                diagonal += dim - r;
+            }
+        }
+        packed_covs = CowArray::from(owned);
+    }
+    let n_pairs = condensed_len(w.len());
+
+    run_pairs(w, means.view(), Covariances::Packed(packed_covs.view()), config, &config.detector, &vec![config.n_points; n_pairs], None, None, None).map(values)
+}
+
+/// OLR of a mixture of two identical copies of the component `(mean, cov)` with equal weights.
+///
+/// The two copies fully overlap, so the result is expected to be `1.0`. This is a cheap sanity
+/// check of a build or platform.
+pub fn self_overlap_check(mean: &Array1<f64>, cov: &Array2<f64>) -> Result<f64, MoebiusError> {
+    let means = ndarray::stack(Axis(0), &[mean.view(), mean.view()])
+        .map_err(|e| MoebiusError::Shape(e.to_string()))?;
+    let covs = ndarray::stack(Axis(0), &[cov.view(), cov.view()])
+        .map_err(|e| MoebiusError::Shape(e.to_string()))?;
+
+    let values = olr_with_config(&[0.5, 0.5], &means, &covs, &OlrConfig::default())?;
+
+    Ok(values[0])
+}
+
+pub fn olr_with_config(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    config: &OlrConfig
+) -> Result<Vec<f64>, MoebiusError> {
+    olr_impl(w, means.view(), covs.view(), config, &config.detector, None, None).map(values)
+}
+
+/// Same as `olr_with_config`, writing the values into `out` instead of a new vector, e.g. to reuse
+/// one buffer across many calls. `out` must hold exactly `condensed_len(w.len())` values and is
+/// left untouched on errors.
+pub fn olr_into(
+    out: &mut [f64],
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    config: &OlrConfig
+) -> Result<(), MoebiusError> {
+    let expected = checked_condensed_len(w.len())
+        .ok_or_else(|| MoebiusError::Shape(format!("{} components are too many", w.len())))?;
+    if out.len() != expected {
+        return Err(MoebiusError::Shape(format!(
+            "out has {} entries, expected {} for {} components", out.len(), expected, w.len()
+        )));
+    }
+
+    let details = olr_impl(w, means.view(), covs.view(), config, &config.detector, None, None)?;
+    out.iter_mut().zip(&details).for_each(|(value, detail)| *value = plain_value(detail));
+
+    Ok(())
+}
+
+/// Same as `olr_with_config`, but instead of sampling every pair with `config.n_points` points,
+/// distributes `budget` points between pairs according to `resolution::allocate_points`. Pairs
+/// with intermediate overlap are sampled more densely than clearly separated or merged ones.
+/// `config.resolution` is ignored, the budget fixing the grid of every pair.
+pub fn olr_with_budget(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    config: &OlrConfig,
+    budget: usize
+) -> Result<Vec<f64>, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    config.validate()?;
+
+    let covs = prepare_covs(covs.view(), config);
+    let points = allocate_points(means, &covs, budget)?;
+    let config = &OlrConfig { resolution: Resolution::Fixed, ..config.clone() };
+
+    run_pairs(w, means.view(), Covariances::Dense(covs.view()), config, &config.detector, &points, None, None, None).map(values)
+}
+
+/// Same as `olr_with_config`, sampling pair `k` of the condensed order with `points[k]` steps
+/// between the means instead of `config.n_points`, e.g. to rescan the difficult pairs of a coarse
+/// first pass more densely. `config.resolution` is ignored.
+pub fn olr_with_points(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    config: &OlrConfig,
+    points: &[usize]
+) -> Result<Vec<f64>, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    config.validate()?;
+    let n_pairs = condensed_len(w.len());
+    if points.len() != n_pairs {
+        return Err(MoebiusError::Shape(format!("{} point counts given for {} pairs", points.len(), n_pairs)));
+    }
+    if let Some(k) = points.iter().position(|&n| n == 0) {
+        return Err(MoebiusError::InvalidConfig(format!("point count of pair {:?} must be positive", index_to_pair(k, w.len()))));
+    }
+
+    let covs = prepare_covs(covs.view(), config);
+    let config = &OlrConfig { resolution: Resolution::Fixed, ..config.clone() };
+
+    run_pairs(w, means.view(), Covariances::Dense(covs.view()), config, &config.detector, points, None, None, None).map(values)
+}
+
+/// Same as `olr_with_config`, additionally accumulating evaluation counts and per-pair timings
+/// into `stats`.
+pub fn olr_with_stats(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    config: &OlrConfig,
+    stats: &mut OlrStats
+) -> Result<Vec<f64>, MoebiusError> {
+    olr_impl(w, means.view(), covs.view(), config, &config.detector, Some(stats), None).map(values)
+}
+
+/// Same as `olr_with_config`, returning the extrema of every pair and flagging variances below
+/// `config.variance_floor`.
+pub fn olr_detailed(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    config: &OlrConfig
+) -> Result<OlrDetails, MoebiusError> {
+    let pairs = olr_impl(w, means.view(), covs.view(), config, &config.detector, None, None)?;
+
+    Ok(OlrDetails {
+        n_components: w.len(),
+        dim: means.ncols(),
+        config: config.clone(),
+        pairs,
+        small_variances: small_variances(means, covs, config.variance_floor),
+        normalized_sum: normalized_sum(w, config),
+    })
+}
+
+/// Whether every pair, in the order of `olr`, is bimodal, i.e. has an OLR below `1` with the
+/// default configuration. The scan of a pair stops as soon as a second peak past a saddle is
+/// found, so separated pairs cost a fraction of their `olr` scan and no ratio is computed.
+pub fn bimodality(w: &[f64], means: &Array2<f64>, covs: &Array3<f64>) -> Result<Vec<bool>, MoebiusError> {
+    bimodality_impl(w, means, covs, None)
+}
+
+fn bimodality_impl(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    stats: Option<&mut OlrStats>
+) -> Result<Vec<bool>, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+
+    let config = OlrConfig::default();
+    let cache = ComponentCache::new(means.view(), Covariances::Dense(covs.view()));
+    let points = vec![config.n_points; condensed_len(w.len())];
+    let details = scan_pairs(w.len(), &config, &points, stats, |_, _| 0.0, None, None, |i, j, config, workspace| {
+        let (density_i, density_j) = cache.pair(i, j)?;
+        let (ln_w1, ln_w2) = pair_log_weights(w[i], w[j]);
+        let Workspace { point, delta, scratch, .. } = &mut *workspace;
+
+        // Extrema of the profile as `SignChange` finds them, from the last three distinct values,
+        // so that a plateau counts once.
+        let (mut previous, mut current) = (f64::NAN, f64::NAN);
+        let (mut n_peaks, mut n_saddles, mut evaluations) = (0, 0, 0);
+        scan_line_in(means.row(i), means.row(j), config, (point, delta), |point| {
+            let next = ln_pdf_pair(point, ln_w1, density_i, ln_w2, density_j, scratch);
+            evaluations += 1;
+            if next == current {
+                return true;
+            }
+            let (rise, fall) = (current - previous, current - next);
+            if rise > 0.0 && fall > 0.0 {
+                n_peaks += 1;
+            }
+            if rise < 0.0 && fall < 0.0 {
+                n_saddles += 1;
+            }
+            (previous, current) = (current, next);
+
+            n_peaks < 2 || n_saddles == 0
+        });
+
+        // Only the classification is kept, as a value below or at `1`.
+        let value = if n_peaks >= 2 && n_saddles > 0 { 0.0 } else { 1.0 };
+        let detail = PairDetail::complete(value);
+
+        Ok((detail, evaluations))
+    })?;
+
+    Ok(details.iter().map(|detail| detail.value < 1.0).collect())
+}
+
+/// Same as `olr_with_config`, calling `emit` with every pair and its value as soon as it is computed.
+/// Pairs are computed and emitted in `config.pair_order`, e.g. the ones most likely to overlap
+/// first with `PairOrder::Overlap`. Returns the labeled values in the order they were emitted, or
+/// sorted with `sort_output` by decreasing value and then by pair, as `OlrMatrix::top_k_overlaps`.
+///
+/// Pairs emitted before an error are not retracted.
+pub fn olr_streamed(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    config: &OlrConfig,
+    sort_output: bool,
+    mut emit: impl FnMut((usize, usize), f64)
+) -> Result<IndexedOlr, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    config.validate()?;
+
+    let n_comp = w.len();
+    let covs = prepare_covs(covs.view(), config);
+    let mut emitted = Vec::with_capacity(condensed_len(n_comp));
+    let mut record = |k: usize, detail: &PairDetail| {
+        let pair = index_to_pair(k, n_comp);
+        emit(pair, plain_value(detail));
+        emitted.push((pair, plain_value(detail)));
+    };
+    let points = vec![config.n_points; condensed_len(n_comp)];
+    run_pairs(w, means.view(), Covariances::Dense(covs.view()), config, &config.detector, &points, None, Some(&mut record), None)?;
+
+    if sort_output {
+        emitted.sort_by(rank_order);
+    }
+
+    Ok(emitted)
+}
+
+/// Same as `olr_with_config` on the sub-mixture of the selected `components`, without copying
+/// the inputs. Pairs follow the order of `components` as if the inputs were sliced with them,
+/// but are keyed by their indices in the full mixture. With `renormalize`, the selected weights
+/// are divided by their sum. `OlrSession::olr_masked` keeps the factorizations between masks.
+pub fn olr_masked(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    components: &[usize],
+    renormalize: bool,
+    config: &OlrConfig
+) -> Result<IndexedOlr, MoebiusError> {
+    let w = masked_weights(w, means, covs, components, renormalize, config)?;
+    let covs = prepare_covs(covs.view(), config);
+    let cache = ComponentCache::new(means.view(), Covariances::Dense(covs.view()));
+
+    masked_pairs(&w, means.view(), &cache, components, config, None)
+}
+
+/// Validates the inputs of `olr_masked` and returns the weights of the full mixture, renormalized
+/// among `components` if requested.
+pub(crate) fn masked_weights(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    components: &[usize],
+    renormalize: bool,
+    config: &OlrConfig
+) -> Result<Vec<f64>, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    config.validate()?;
+    check_selection(components, w.len())?;
+    check_pairs(components.len(), config)?;
+
+    if !renormalize {
+        return Ok(w.to_vec());
+    }
+    let total: f64 = components.iter().map(|&k| w[k]).sum();
+    if total <= 0.0 {
+        return Err(MoebiusError::Shape(format!(
+            "selected components have total weight {}, so their weights cannot be renormalized", total
+        )));
+    }
+
+    Ok(w.iter().map(|weight| weight / total).collect())
+}
+
+/// Pairs of the selected `components` of `cache`, as in `run_pairs` with every index mapped
+/// through `components`.
+pub(crate) fn masked_pairs(
+    w: &[f64],
+    means: ArrayView2<f64>,
+    cache: &ComponentCache,
+    components: &[usize],
+    config: &OlrConfig,
+    session: Option<SessionBuffers<'_>>
+) -> Result<IndexedOlr, MoebiusError> {
+    if config.strict {
+        cache.check(components.iter().copied())?;
+    }
+    let pre_score = |a: usize, b: usize| match cache.pair(components[a], components[b]) {
+        Ok((gaussian_i, gaussian_j)) => {
+            let (i, j) = (components[a], components[b]);
+            -(gaussian_i.quadratic(means.row(j)).sqrt() + gaussian_j.quadratic(means.row(i)).sqrt()) / 2.0
+        }
+        Err(_) => f64::NEG_INFINITY,
+    };
+
+    let n_sel = components.len();
+    let points = vec![config.n_points; condensed_len(n_sel)];
+    let details = scan_pairs(n_sel, config, &points, None, pre_score, None, session, |a, b, pair_config, workspace| {
+        let (i, j) = (components[a], components[b]);
+        let (density_i, density_j) = cache.pair(i, j)?;
+
+        olr_gaussian_pair(w, &means, density_i, density_j, (i, j), pair_config, &config.detector, workspace)
+    })?;
+
+    let pairs = (0..details.len()).map(|k| {
+        let (a, b) = index_to_pair(k, n_sel);
+        (components[a], components[b])
+    });
+
+    Ok(pairs.zip(details.iter().map(plain_value)).collect())
+}
+
+/// Same as `olr_with_config` with the extrema of every profile found by `detector` instead of
+/// `config.detector`.
+pub fn olr_with_detector(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    config: &OlrConfig,
+    detector: &(dyn ExtremaDetector + Sync)
+) -> Result<Vec<f64>, MoebiusError> {
+    olr_impl(w, means.view(), covs.view(), config, detector, None, None).map(values)
+}
+
+pub(crate) fn olr_impl(
+    w: &[f64],
+    means: ArrayView2<f64>,
+    covs: ArrayView3<f64>,
+    config: &OlrConfig,
+    detector: &(dyn ExtremaDetector + Sync),
+    stats: Option<&mut OlrStats>,
+    session: Option<SessionBuffers<'_>>
+) -> Result<Vec<PairDetail>, MoebiusError> {
+    check_shapes(w.len(), &means, &covs)?;
+    check_finite(w, &means, &covs)?;
+    config.validate()?;
+
+    let covs = prepare_covs(covs, config);
+    let n_pairs = condensed_len(w.len());
+
+    run_pairs(w, means.reborrow(), Covariances::Dense(covs.view()), config, detector, &vec![config.n_points; n_pairs], stats, None, session)
+}
+
+/// Same as `olr_with_config` for structured covariances, one per component. Block-diagonal
+/// covariances are evaluated block by block without assembling the dense matrix.
+pub fn olr_with_covariances(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &[Covariance],
+    config: &OlrConfig
+) -> Result<Vec<f64>, MoebiusError> {
+    if means.nrows() != w.len() {
+        return Err(MoebiusError::Shape(format!(
+            "means has {} rows, but w has {} weights", means.nrows(), w.len()
+        )));
+    }
+    if covs.len() != w.len() {
+        return Err(MoebiusError::Shape(format!(
+            "{} covariances given for {} components", covs.len(), w.len()
+        )));
+    }
+    for (k, cov) in covs.iter().enumerate() {
+        if let Covariance::Full(matrix) = cov {
+            if matrix.dim() != (means.ncols(), means.ncols()) {
+                return Err(MoebiusError::DimensionMismatch { component: k, dim: means.ncols(), cov_shape: matrix.dim() });
+            }
+        }
+        cov.validate(means.ncols()).map_err(|e| match e {
+            MoebiusError::Shape(msg) => MoebiusError::Shape(format!("covariance of component {}: {}", k, msg)),
+            e => e,
+        })?;
+    }
+    config.validate()?;
+
+    let covs: Vec<Covariance> = covs.iter().map(|cov| prepare_matrices(cov, config)).collect();
+    let n_pairs = condensed_len(w.len());
+
+    run_pairs(w, means.view(), Covariances::Structured(&covs), config, &config.detector, &vec![config.n_points; n_pairs], None, None, None).map(values)
+}
+
+/// Same as `olr_with_config` for arbitrary component densities, e.g. `StudentT` or boxed
+/// `dyn ComponentDensity` for mixtures of different families. The line of every pair connects
+/// `means[i]` and `means[j]`, which should be the modes of `densities[i]` and `densities[j]`.
+pub fn olr_with_densities<D: ComponentDensity + Sync>(
+    w: &[f64],
+    means: &Array2<f64>,
+    densities: &[D],
+    config: &OlrConfig
+) -> Result<Vec<f64>, MoebiusError> {
+    if means.nrows() != w.len() || densities.len() != w.len() {
+        return Err(MoebiusError::Shape(format!(
+            "{} weights, {} means and {} densities given", w.len(), means.nrows(), densities.len()
+        )));
+    }
+    // Densities validate their own parameters.
+    check_finite(w, means, &Array3::zeros((0, 0, 0)))?;
+    config.validate()?;
+    check_pairs(w.len(), config)?;
+    let w = &*normalize_weights(w, config)?;
+
+    let n_pairs = condensed_len(w.len());
+
+    let pre_score = euclidean_pre_score(means.view());
+    scan_pairs(w.len(), config, &vec![config.n_points; n_pairs], None, pre_score, None, None, |i, j, pair_config, workspace| {
+        olr_pair(w, &means.view(), &densities[i], &densities[j], (i, j), pair_config, &config.detector, workspace)
+    }).map(values)
+}
+
+/// OLR of a mixture whose component densities are given by `pdf(x, k)`, the density of
+/// component `k` at `x`, with the default configuration. The ridgeline scan only evaluates
+/// densities, so any unimodal family works as long as `means[k]` is the mode of component `k`.
+/// `olr` computes the same values for Gaussian densities without copying every point.
+pub fn olr_with_pdf<F>(w: &[f64], means: &Array2<f64>, pdf: F) -> Result<Vec<f64>, MoebiusError>
+where
+    F: Fn(&Array1<f64>, usize) -> f64 + Sync
+{
+    let components: Vec<PdfComponent<'_, F>> = (0..w.len()).map(|index| PdfComponent { pdf: &pdf, index }).collect();
+
+    olr_with_densities(w, means, &components, &OlrConfig::default())
+}
+
+/// OLR of a mixture of multivariate Student-t components with locations `means`, scale matrices
+/// `scales` and degrees of freedom `dofs`. Approaches `olr_with_config` on `covs = scales` as the
+/// degrees of freedom grow.
+pub fn olr_t(
+    w: &[f64],
+    means: &Array2<f64>,
+    scales: &Array3<f64>,
+    dofs: &[f64],
+    config: &OlrConfig
+) -> Result<Vec<f64>, MoebiusError> {
+    check_shapes(w.len(), means, scales)?;
+    if dofs.len() != w.len() {
+        return Err(MoebiusError::Shape(format!("{} degrees of freedom given for {} components", dofs.len(), w.len())));
+    }
+    check_finite(w, means, scales)?;
+
+    let scales = prepare_covs(scales.view(), config);
+    let components = (0..w.len())
+        .map(|k| StudentT::component(means.row(k), scales.index_axis(Axis(0), k), dofs[k], k))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    olr_with_densities(w, means, &components, config)
+}
+
+/// Computes all pairs `i < j` of Gaussian components with `scan_pairs`, after factorizing every
+/// covariance up front if `config.strict` is set.
+#[allow(clippy::too_many_arguments)]
+fn run_pairs<'a>(
+    w: &[f64],
+    means: ArrayView2<'a, f64>,
+    covs: Covariances<'a>,
+    config: &OlrConfig,
+    detector: &(dyn ExtremaDetector + Sync),
+    points: &[usize],
+    stats: Option<&mut OlrStats>,
+    emit: Option<EmitPair<'_>>,
+    session: Option<SessionBuffers<'_>>
+) -> Result<Vec<PairDetail>, MoebiusError> {
+    check_pairs(w.len(), config)?;
+    let w = &*normalize_weights(w, config)?;
+    let cache = ComponentCache::new(means, covs);
+    if config.strict {
+        cache.check_all()?;
+    }
+    // Components that cannot be factorized are scheduled last and fail when their pair is computed.
+    let pre_score = |i: usize, j: usize| match cache.pair(i, j) {
+        Ok((gaussian_i, gaussian_j)) => {
+            -(gaussian_i.quadratic(means.row(j)).sqrt() + gaussian_j.quadratic(means.row(i)).sqrt()) / 2.0
+        }
+        Err(_) => f64::NEG_INFINITY,
+    };
+
+    scan_pairs(w.len(), config, points, stats, pre_score, emit, session, |i, j, pair_config, workspace| {
+        let (density_i, density_j) = cache.pair(i, j)?;
+
+        olr_gaussian_pair(w, &means, density_i, density_j, (i, j), pair_config, detector, workspace)
+    })
+}
+
+/// Negated Euclidean distance between the means of a pair, the pre-score of `PairOrder::Overlap`
+/// for entry points without covariance matrices.
+pub(crate) fn euclidean_pre_score(means: ArrayView2<'_, f64>) -> impl Fn(usize, usize) -> f64 + '_ {
+    move |i, j| -(&means.row(i) - &means.row(j)).mapv(|v| v * v).sum().sqrt()
+}
+
+/// Fails if `config.require_pairs` is set and there are fewer than two components.
+pub(crate) fn check_pairs(n_comp: usize, config: &OlrConfig) -> Result<(), MoebiusError> {
+    if config.require_pairs && n_comp < 2 {
+        return Err(MoebiusError::Shape(format!(
+            "mixture has {} component{}, but require_pairs is set", n_comp, if n_comp == 1 { "" } else { "s" }
+        )));
+    }
+
+    Ok(())
+}
+
+/// Computes all pairs `i < j`, sampling the `k`-th pair with `points[k]` points and evaluating it
+/// with `pair`, which returns the detail of the pair and the number of density evaluations. Pairs
+/// are processed in `config.pair_order`, ranked by `pre_score(i, j)` for `PairOrder::Overlap`, and
+/// returned in output order. Runs serially if `config.n_threads` resolves to a single thread, on a
+/// scoped pool otherwise.
+///
+/// `pair` scans with the buffers of the workspace of `session` if the run is serial, and with
+/// those of the pool thread it runs on otherwise, so that pairs allocate nothing once the buffers
+/// have grown. Parallel runs of a session use its pool, whose threads keep their buffers between
+/// calls, instead of a scoped one.
+///
+/// `emit`, if given, is called with the output index and detail of every pair in processing
+/// order, as soon as the pair and all pairs before it are done. Parallel runs then process pairs
+/// in chunks of a few pairs per thread.
+///
+/// Every entry point ends up here and every value is computed by `pair` alone, with no state
+/// shared between pairs. Results and statistics are combined in pair order, so values are
+/// bitwise identical whatever the number of threads, the pair order or the entry point, which
+/// `tests/consistency.rs` checks. If pairs fail, the error of the first failing pair in
+/// processing order is returned.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn scan_pairs(
+    n_comp: usize,
+    config: &OlrConfig,
+    points: &[usize],
+    stats: Option<&mut OlrStats>,
+    pre_score: impl Fn(usize, usize) -> f64,
+    mut emit: Option<EmitPair<'_>>,
+    session: Option<SessionBuffers<'_>>,
+    pair: impl Fn(usize, usize, &OlrConfig, &mut Workspace) -> Result<(PairDetail, usize), MoebiusError> + Sync
+) -> Result<Vec<PairDetail>, MoebiusError> {
+    let pairs: Vec<(usize, usize)> = (0..n_comp).flat_map(|i| ((i + 1)..n_comp).map(move |j| (i, j))).collect();
+    let order = processing_order(&pairs, config.pair_order, pre_score);
+
+    let call_started = Instant::now();
+    let record_threads = stats.is_some();
+    let evaluate = |k: usize, workspace: &mut Workspace| {
+        let (i, j) = pairs[k];
+        let started = Instant::now();
+        // The deadline is passed on to the pair as the time it has left.
+        let remaining = config.deadline.map(|deadline| deadline.saturating_sub(call_started.elapsed()));
+        let pair_timeout = match (config.pair_timeout, remaining) {
+            (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+            (timeout, remaining) => timeout.or(remaining),
+        };
+        let pair_config = OlrConfig { n_points: points[k], pair_timeout, deadline: None, ..config.clone() };
+
+        let detail = if remaining == Some(Duration::ZERO) {
+            let detail = PairDetail::timed_out();
+            Ok((detail, 0))
+        } else {
+            pair(i, j, &pair_config, workspace)
+        };
+        let detail = match detail {
+            Ok((detail, _)) if detail.status == PairStatus::TimedOut && config.on_timeout == TimeoutPolicy::Abort => {
+                Err(MoebiusError::Timeout { pair: (i, j) })
+            }
+            detail => detail,
+        };
+
+        let thread_name = if record_threads { thread::current().name().map(str::to_string) } else { None };
+
+        (detail, started.elapsed(), thread_name)
+    };
+
+    let (workspace, session_pool) = match session {
+        Some(SessionBuffers { workspace, pool }) => (Some(workspace), pool),
+        None => (None, None),
+    };
+    // More threads than pairs would idle, and a single pair is computed on the calling thread.
+    let n_threads = session_pool
+        .map_or_else(|| resolve_threads(config.n_threads), ThreadPool::current_num_threads)
+        .min(pairs.len())
+        .max(1);
+    let scoped;
+    let pool = match session_pool {
+        _ if n_threads == 1 => None,
+        Some(pool) => Some(pool),
+        None => {
+            scoped = build_pool(n_threads)?;
+            Some(&scoped)
+        }
+    };
+    let chunk_len = if emit.is_some() { 4 * n_threads } else { pairs.len().max(1) };
+
+    type PairResult = (Result<(PairDetail, usize), MoebiusError>, Duration, Option<String>);
+    let mut results: Vec<Option<PairResult>> = (0..pairs.len()).map(|_| None).collect();
+    let mut failed = None;
+    // Returns whether no pair has failed so far.
+    let mut record = |k: usize, result: PairResult| {
+        match (&result.0, emit.as_mut()) {
+            (Err(_), _) if failed.is_none() => failed = Some(k),
+            (Ok((detail, _)), Some(emit)) if failed.is_none() => emit(k, detail),
+            _ => {}
+        }
+        results[k] = Some(result);
+
+        failed.is_none()
+    };
+    match pool {
+        // Serial runs record every pair as soon as it is computed.
+        None => {
+            let mut run = |workspace: &mut Workspace| {
+                for &k in &order {
+                    if !record(k, evaluate(k, workspace)) {
+                        break;
+                    }
+                }
+            };
+            match workspace {
+                Some(workspace) => run(workspace),
+                None => with_thread_workspace(run),
+            }
+        }
+        Some(pool) => {
+            for chunk in order.chunks(chunk_len) {
+                let computed: Vec<_> = pool.install(|| {
+                    chunk.par_iter().map(|&k| with_thread_workspace(|workspace| evaluate(k, workspace))).collect()
+                });
+                let mut ok = true;
+                for (&k, result) in chunk.iter().zip(computed) {
+                    ok = record(k, result);
+                }
+                if !ok {
+                    break;
+                }
+            }
+        }
+    }
+    if let Some(k) = failed {
+        return Err(results[k].take().unwrap().0.unwrap_err());
+    }
+
+    let mut details = Vec::with_capacity(results.len());
+    let mut stats = stats;
+    for (detail, elapsed, thread_name) in results.into_iter().flatten() {
+        let (detail, evaluations) = detail?;
+
+        if let Some(stats) = stats.as_mut() {
+            stats.pdf_evaluations += evaluations;
+            stats.pair_times.push(elapsed);
+            stats.threads.insert(thread_name.unwrap_or_default());
+        }
+
+        details.push(detail);
+    }
+
+    Ok(details)
+}
+
+/// Output indices of `pairs` in the order they are computed.
+fn processing_order(pairs: &[(usize, usize)], order: PairOrder, pre_score: impl Fn(usize, usize) -> f64) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..pairs.len()).collect();
+    if order == PairOrder::Overlap {
+        let scores: Vec<f64> = pairs.iter()
+            .map(|&(i, j)| pre_score(i, j))
+            .map(|score| if score.is_nan() { f64::NEG_INFINITY } else { score })
+            .collect();
+        // Stable, so that ties keep the condensed order.
+        indices.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    }
+
+    indices
+}
+
+/// Values of `details`, with `NaN` for pairs that timed out.
+pub(crate) fn values(details: Vec<PairDetail>) -> Vec<f64> {
+    details.iter().map(plain_value).collect()
+}
+
+pub(crate) fn plain_value(detail: &PairDetail) -> f64 {
+    if detail.status == PairStatus::TimedOut { f64::NAN } else { detail.value }
+}
+
+/// `covs`, symmetrized if `config.symmetrize` is set and with `config.reg_covar` added to the
+/// diagonal of every matrix.
+pub(crate) fn prepare_covs<'a>(covs: ArrayView3<'a, f64>, config: &OlrConfig) -> CowArray<'a, f64, Ix3> {
+    if config.reg_covar == 0.0 && !config.symmetrize {
+        return CowArray::from(covs);
+    }
+
+    let mut covs = covs.to_owned();
+    for mut cov in covs.outer_iter_mut() {
+        let prepared = prepare_matrix(cov.view(), config);
+        cov.assign(&prepared);
+    }
+
+    CowArray::from(covs)
+}
+
+/// `cov` with every matrix prepared as in `prepare_covs`.
+fn prepare_matrices(cov: &Covariance, config: &OlrConfig) -> Covariance {
+    if config.reg_covar == 0.0 && !config.symmetrize {
+        return cov.clone();
+    }
+
+    cov.map_matrices(|matrix| prepare_matrix(matrix, config))
+}
+
+fn prepare_matrix(cov: ArrayView2<f64>, config: &OlrConfig) -> Array2<f64> {
+    let mut cov = if config.symmetrize { (&cov + &cov.t()) / 2.0 } else { cov.to_owned() };
+    cov.diag_mut().map_inplace(|v| *v += config.reg_covar);
+
+    cov
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn olr_pair<D: ComponentDensity>(
+    w: &[f64],
+    means: &ArrayView2<f64>,
+    density_i: &D,
+    density_j: &D,
+    (i, j): (usize, usize),
+    config: &OlrConfig,
+    detector: &(dyn ExtremaDetector + Sync),
+    workspace: &mut Workspace
+) -> Result<(PairDetail, usize), MoebiusError> {
+    olr_pair_on(w, means, density_i, density_j, (i, j), config, detector, workspace, None)
+}
+
+/// `olr_pair` on the grid of `GridStrategy::Adaptive` if `widths` holds the standard deviations
+/// of both components along the line, in units of the distance between the means.
+#[allow(clippy::too_many_arguments)]
+fn olr_pair_on<D: ComponentDensity>(
+    w: &[f64],
+    means: &ArrayView2<f64>,
+    density_i: &D,
+    density_j: &D,
+    (i, j): (usize, usize),
+    config: &OlrConfig,
+    detector: &(dyn ExtremaDetector + Sync),
+    workspace: &mut Workspace,
+    widths: Option<(f64, f64)>
+) -> Result<(PairDetail, usize), MoebiusError> {
+    // The profile is scanned in log space, since densities of components with tiny variances
+    // overflow or underflow long before their ratio does.
+    let (ln_w1, ln_w2) = pair_log_weights(w[i], w[j]);
+    let clock = PairClock::start(config);
+
+    let (detail, evaluations) = refine_resolution(config, |config| resolve_no_saddle(i, j, config, |config| {
+        let Workspace { profile, point, delta, extrema, scratch, columns, ln_pdfs: (ln_pdf_i, ln_pdf_j), grid } = &mut *workspace;
+        let adaptive = widths.is_some_and(|widths| adaptive_grid(config, widths, grid));
+        profile.clear();
+        profile.reserve(config.grid_len().min(MAX_PREALLOCATED_POINTS));
+        let complete = if adaptive {
+            scan_line_at(means.row(i), means.row(j), grid, (point, delta), |point| {
+                if clock.expired(profile.len()) {
+                    return false;
+                }
+                profile.push(ln_pdf_pair(point, ln_w1, density_i, ln_w2, density_j, scratch));
+                true
+            })
+        } else if !config.deterministic && BATCHED_DIMS.contains(&means.ncols()) {
+            scan_line_columns(means.row(i), means.row(j), config, (point, delta, columns), |points| {
+                if clock.expired(profile.len()) {
+                    return false;
+                }
+                ln_pdf_i.clear();
+                ln_pdf_j.clear();
+                density_i.ln_pdf_columns(points, ln_pdf_i, scratch);
+                density_j.ln_pdf_columns(points, ln_pdf_j, scratch);
+                profile.extend(ln_pdf_i.iter().zip(ln_pdf_j.iter()).map(|(a, b)| ln_add(ln_w1 + a, ln_w2 + b)));
+                true
+            })
+        } else {
+            scan_line_in(means.row(i), means.row(j), config, (point, delta), |point| {
+                if clock.expired(profile.len()) {
+                    return false;
+                }
+                profile.push(ln_pdf_pair(point, ln_w1, density_i, ln_w2, density_j, scratch));
+                true
+            })
+        };
+
+        let mut detail = profile_extrema_in(profile, detector, extrema);
+        if !complete {
+            detail.status = PairStatus::TimedOut;
+        }
+        if adaptive {
+            detail.grid = GridStrategy::Adaptive;
+            // The vertices of `vertex_value` assume equal steps.
+            detail.extrapolated = None;
+            detail.error_estimate = None;
+        }
+
+        detail
+    }))?;
+    if CHECK_INVARIANTS {
+        check_invariants((i, j), &detail, workspace)?;
+    }
+
+    Ok((detail, evaluations))
+}
+
+/// `olr_pair` for Gaussian components, whose modality is checked first with
+/// `config.analytic_shortcut` and whose line is sampled according to `config.grid`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn olr_gaussian_pair(
+    w: &[f64],
+    means: &ArrayView2<f64>,
+    density_i: &Gaussian,
+    density_j: &Gaussian,
+    (i, j): (usize, usize),
+    config: &OlrConfig,
+    detector: &(dyn ExtremaDetector + Sync),
+    workspace: &mut Workspace
+) -> Result<(PairDetail, usize), MoebiusError> {
+    let widths = (config.grid == GridStrategy::Adaptive).then(|| {
+        (density_i.quadratic(means.row(j)).sqrt().recip(), density_j.quadratic(means.row(i)).sqrt().recip())
+    });
+    if !config.analytic_shortcut {
+        return olr_pair_on(w, means, density_i, density_j, (i, j), config, detector, workspace, widths);
+    }
+
+    let modality = line_modality((w[i], w[j]), (means.row(i), means.row(j)), density_i, density_j);
+    let (mut detail, evaluations) = match modality {
+        Some(Modality::Unimodal) if config.no_saddle != NoSaddlePolicy::Error => {
+            (PairDetail::complete(1.0), 0)
+        }
+        Some(Modality::Bimodal) => {
+            let config = OlrConfig { no_saddle: NoSaddlePolicy::AssumeMerged, ..config.clone() };
+            olr_pair_on(w, means, density_i, density_j, (i, j), &config, detector, workspace, widths)?
+        }
+        _ => olr_pair_on(w, means, density_i, density_j, (i, j), config, detector, workspace, widths)?,
+    };
+    detail.modality = modality;
+
+    Ok((detail, evaluations))
+}
+
+/// Whether `olr_pair` checks `check_invariants` on every pair: in debug builds, and in release
+/// builds with the `invariants` feature.
+pub(crate) const CHECK_INVARIANTS: bool = cfg!(any(debug_assertions, feature = "invariants"));
+
+/// Consistency of the last scan of a pair left in `workspace`: the line advances by a finite
+/// step, so its parameter is monotone, no log-density is NaN or `+inf`, and the saddle does not
+/// lie above the peak, so that a complete value is in `[0, 1]`.
+fn check_invariants(pair: (usize, usize), detail: &PairDetail, workspace: &Workspace) -> Result<(), MoebiusError> {
+    let violated = |message: String| Err(MoebiusError::Invariant { pair, message });
+
+    if let Some(d) = workspace.delta.iter().position(|step| !step.is_finite()) {
+        return violated(format!("the step of the line in dimension {} is {}", d, workspace.delta[d]));
+    }
+    if let Some(k) = workspace.profile.iter().position(|v| v.is_nan() || *v == f64::INFINITY) {
+        return violated(format!("the log-density at point {} of the line is {}", k, workspace.profile[k]));
+    }
+    if let (Some(ln_peak), Some(ln_saddle)) = (detail.ln_peak, detail.ln_saddle) {
+        if ln_saddle > ln_peak {
+            return violated(format!("the saddle log-density {} is above the peak log-density {}", ln_saddle, ln_peak));
+        }
+    }
+    if detail.status == PairStatus::Complete && !(0.0..=1.0).contains(&detail.value) {
+        return violated(format!("the OLR {} is outside [0, 1]", detail.value));
+    }
+
+    Ok(())
+}
+
+/// Upper bound on the profile buffer reserved up front, so that huge grids that time out early
+/// do not allocate the whole profile.
+pub(crate) const MAX_PREALLOCATED_POINTS: usize = 1 << 20;
+
+/// Expiry of `OlrConfig::pair_timeout`, started when the pair is.
+pub(crate) struct PairClock(Option<Instant>);
+
+impl PairClock {
+    pub(crate) fn start(config: &OlrConfig) -> Self {
+        PairClock(config.pair_timeout.and_then(|timeout| Instant::now().checked_add(timeout)))
+    }
+
+    /// Whether the pair has run out of time, after `evaluated` points of the current scan. The
+    /// clock is only read every `TIMEOUT_CHECK_INTERVAL` points.
+    pub(crate) fn expired(&self, evaluated: usize) -> bool {
+        evaluated.is_multiple_of(TIMEOUT_CHECK_INTERVAL) && self.0.is_some_and(|expiry| Instant::now() >= expiry)
+    }
+}
+
+/// Profile extrema of pair `(i, j)` from `scan`, which samples the line as described by its
+/// config, rescanned according to `config.no_saddle`. Also returns the number of evaluated points.
+pub(crate) fn resolve_no_saddle(
+    i: usize,
+    j: usize,
+    config: &OlrConfig,
+    mut scan: impl FnMut(&OlrConfig) -> PairDetail
+) -> Result<(PairDetail, usize), MoebiusError> {
+    let mut detail = scan(config);
+    let mut evaluations = config.grid_len();
+    // A scan that timed out may have stopped before the saddle.
+    if detail.ln_saddle.is_none() && detail.status == PairStatus::Complete {
+        match config.no_saddle {
+            NoSaddlePolicy::AssumeMerged => {}
+            NoSaddlePolicy::Error => return Err(MoebiusError::Distribution(format!(
+                "no saddle found for pair ({}, {}) on the sampled line", i, j
+            ))),
+            NoSaddlePolicy::ExtendAndRetry => {
+                let mut extended = config.clone();
+                for _ in 0..MAX_EXTENSION_RETRIES {
+                    extended.extension = 2 * extended.extension.max(1);
+                    detail = scan(&extended);
+                    evaluations += extended.grid_len();
+                    if detail.ln_saddle.is_some() || detail.status == PairStatus::TimedOut {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((detail, evaluations))
+}
+
+/// Profile extrema of a pair from `scan`, which samples the line as described by its config like
+/// `resolve_no_saddle`, refined according to `config.resolution` and extrapolated with
+/// `config.extrapolate`. Also returns the number of evaluated points of all scans.
+pub(crate) fn refine_resolution(
+    config: &OlrConfig,
+    scan: impl FnMut(&OlrConfig) -> Result<(PairDetail, usize), MoebiusError>
+) -> Result<(PairDetail, usize), MoebiusError> {
+    let (mut detail, evaluations) = refine_scans(config, scan)?;
+    if config.extrapolate {
+        if let (Some(extrapolated), Some(error)) = (detail.extrapolated, detail.error_estimate) {
+            detail.error_estimate = Some(error - (detail.value - extrapolated).abs());
+            detail.value = extrapolated;
+        }
+    }
+
+    Ok((detail, evaluations))
+}
+
+/// `refine_resolution` without the extrapolation.
+fn refine_scans(
+    config: &OlrConfig,
+    mut scan: impl FnMut(&OlrConfig) -> Result<(PairDetail, usize), MoebiusError>
+) -> Result<(PairDetail, usize), MoebiusError> {
+    let Resolution::Auto { tolerance, max_points } = config.resolution else {
+        return scan(config);
+    };
+
+    let mut current = config.clone();
+    let mut scan_at = |n_points: usize| {
+        current.n_points = n_points;
+        current.extension = (config.extension * n_points).div_ceil(config.n_points);
+        scan(&current)
+    };
+    let (mut detail, mut evaluations) = scan_at(AUTO_START_POINTS)?;
+    let mut refinement = Refinement { n_points: AUTO_START_POINTS, delta: f64::INFINITY };
+    while (refinement.delta >= tolerance || config.deterministic) && refinement.n_points < max_points && detail.status == PairStatus::Complete {
+        let n_points = (2 * refinement.n_points).min(max_points);
+        let (refined, refined_evaluations) = scan_at(n_points)?;
+        refinement = Refinement { n_points, delta: (refined.value - detail.value).abs() };
+        detail = refined;
+        evaluations += refined_evaluations;
+    }
+    detail.refinement = Some(refinement);
+
+    Ok((detail, evaluations))
+}
+
+/// Logarithms of the weights of a pair, normalized to sum to `1`. Pairs of zero-weight components
+/// are compared as if they had equal weights. Scaling both weights only scales the profile, so
+/// the normalization leaves the ratio of saddle to peak unchanged: the OLR of a pair is the same
+/// with its global weights.
+pub(crate) fn pair_log_weights(w1: f64, w2: f64) -> (f64, f64) {
+    let w1_new = if w1 + w2 == 0.0 { 0.5 } else { w1 / (w1 + w2) };
+    let w2_new = 1.0 - w1_new;
+
+    (w1_new.ln(), w2_new.ln())
+}
+
+/// Calls `f` with the `config.grid_len()` points of the line from `mean_i` to `mean_j`, as
+/// described in `OlrConfig`.
+pub(crate) fn scan_line(mean_i: ArrayView1<f64>, mean_j: ArrayView1<f64>, config: &OlrConfig, mut f: impl FnMut(&Array1<f64>)) {
+    scan_line_while(mean_i, mean_j, config, |point| {
+        f(point);
+        true
+    });
+}
+
+/// Same as `scan_line`, stopping as soon as `f` returns `false`. Returns whether every point was
+/// visited.
+pub(crate) fn scan_line_while(
+    mean_i: ArrayView1<f64>,
+    mean_j: ArrayView1<f64>,
+    config: &OlrConfig,
+    f: impl FnMut(&Array1<f64>) -> bool
+) -> bool {
+    scan_line_in(mean_i, mean_j, config, (&mut Vec::new(), &mut Vec::new()), f)
+}
+
+/// Same as `scan_line_while`, keeping the current point and the step in the buffers `point` and
+/// `delta`.
+pub(crate) fn scan_line_in(
+    mean_i: ArrayView1<f64>,
+    mean_j: ArrayView1<f64>,
+    config: &OlrConfig,
+    (point, delta): (&mut Vec<f64>, &mut Vec<f64>),
+    f: impl FnMut(&Array1<f64>) -> bool
+) -> bool {
+    let n_steps = config.grid_len() - 1;
+    let (n_points, extension) = (config.n_points as f64, config.extension as f64);
+    delta.clear();
+    delta.extend(mean_j.iter().zip(&mean_i).map(|(m_j, m_i)| (m_j - m_i) * 1.0 / n_points));
+    point.clear();
+    point.extend(mean_i.iter().zip(delta.iter()).map(|(m_i, d)| m_i - extension * d));
+
+    let step = Array1::from_vec(std::mem::take(delta));
+    let mut current = Array1::from_vec(std::mem::take(point));
+    let complete = for_each_line_point(&mut current, &step, n_steps, f);
+    *delta = step.into_raw_vec();
+    *point = current.into_raw_vec();
+
+    complete
+}
+
+/// Calls `f` with the points `mean_i + t (mean_j - mean_i)` for every `t` of `grid`, keeping the
+/// current point and the difference of the means in the buffers `point` and `delta`.
+fn scan_line_at(
+    mean_i: ArrayView1<f64>,
+    mean_j: ArrayView1<f64>,
+    grid: &[f64],
+    (point, delta): (&mut Vec<f64>, &mut Vec<f64>),
+    mut f: impl FnMut(&Array1<f64>) -> bool
+) -> bool {
+    delta.clear();
+    delta.extend(mean_j.iter().zip(&mean_i).map(|(m_j, m_i)| m_j - m_i));
+    point.clear();
+    point.extend(mean_i.iter());
+
+    let mut current = Array1::from_vec(std::mem::take(point));
+    let complete = grid.iter().all(|&t| {
+        current.iter_mut().zip(mean_i.iter().zip(delta.iter())).for_each(|(x, (m_i, d))| *x = m_i + t * d);
+        f(&current)
+    });
+    *point = current.into_raw_vec();
+
+    complete
+}
+
+/// Points of `GridStrategy::Adaptive` within three standard deviations of a mean around which
+/// the uniform grid has fewer.
+pub const ADAPTIVE_PEAK_POINTS: usize = 32;
+
+/// Ratio of successive distances from the mean of the points `GridStrategy::Adaptive` adds
+/// between three standard deviations and the uniform step.
+const ADAPTIVE_GROWTH: f64 = 1.125;
+
+/// Writes the line parameters of `GridStrategy::Adaptive` for the standard deviations `widths` of
+/// both components along the line into `grid`, in increasing order. `false`, leaving `grid`
+/// unspecified, if the uniform grid already resolves both components.
+fn adaptive_grid(config: &OlrConfig, (width_i, width_j): (f64, f64), grid: &mut Vec<f64>) -> bool {
+    let n_points = config.n_points as f64;
+    let step = 1.0 / n_points;
+    let needs_points = |width: f64| 6.0 * width * n_points < ADAPTIVE_PEAK_POINTS as f64;
+    if !needs_points(width_i) && !needs_points(width_j) {
+        return false;
+    }
+
+    let start = -(config.extension as f64) * step;
+    let end = 1.0 + 2.0 * config.extension as f64 * step;
+    grid.clear();
+    grid.extend((0..config.grid_len()).map(|k| start + k as f64 * step));
+    for (center, width) in [(0.0, width_i), (1.0, width_j)] {
+        if !needs_points(width) {
+            continue;
+        }
+        let patch_step = 6.0 * width / ADAPTIVE_PEAK_POINTS as f64;
+        grid.extend((0..=ADAPTIVE_PEAK_POINTS).map(|k| center - 3.0 * width + k as f64 * patch_step));
+        let mut distance = 3.0 * width * ADAPTIVE_GROWTH;
+        while distance < step {
+            grid.extend([center - distance, center + distance]);
+            distance *= ADAPTIVE_GROWTH;
+        }
+    }
+    grid.retain(|t| (start..=end).contains(t));
+    grid.sort_by(f64::total_cmp);
+    grid.dedup();
+
+    true
+}
+
+/// Dimensions in which `olr_pair` evaluates chunks of the line as matrices with
+/// `scan_line_columns`, which saves the overhead of a triangular solve per point. Pairs in one or
+/// two dimensions have too little work per point to gain from it, and from about a hundred
+/// dimensions on the solves themselves dominate, so both are scanned point by point.
+pub(crate) const BATCHED_DIMS: std::ops::RangeInclusive<usize> = 3..=64;
+
+/// Same as `scan_line_in`, calling `f` with chunks of `TIMEOUT_CHECK_INTERVAL` consecutive points
+/// (fewer for the last one) as the columns of a `d x n` matrix built in `columns`. The points are
+/// accumulated as by `for_each_line_point`, so they are exactly those of `scan_line_in`.
+pub(crate) fn scan_line_columns(
+    mean_i: ArrayView1<f64>,
+    mean_j: ArrayView1<f64>,
+    config: &OlrConfig,
+    (point, delta, columns): (&mut Vec<f64>, &mut Vec<f64>, &mut Vec<f64>),
+    mut f: impl FnMut(ArrayView2<f64>) -> bool
+) -> bool {
+    let (n_points, extension) = (config.n_points as f64, config.extension as f64);
+    delta.clear();
+    delta.extend(mean_j.iter().zip(&mean_i).map(|(m_j, m_i)| (m_j - m_i) * 1.0 / n_points));
+    point.clear();
+    point.extend(mean_i.iter().zip(delta.iter()).map(|(m_i, d)| m_i - extension * d));
+
+    let dim = mean_i.len();
+    let mut remaining = config.grid_len();
+    let mut first = true;
+    while remaining > 0 {
+        let n = remaining.min(TIMEOUT_CHECK_INTERVAL);
+        columns.clear();
+        columns.resize(dim * n, 0.0);
+        for k in 0..n {
+            if !first {
+                point.iter_mut().zip(delta.iter()).for_each(|(p, d)| *p += d);
+            }
+            first = false;
+            for (r, p) in point.iter().enumerate() {
+                columns[r * n + k] = *p;
+            }
+        }
+        remaining -= n;
+
+        if !f(ArrayView2::from_shape((dim, n), &columns[..]).unwrap()) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// OLR of a pair from its log-density profile along the line: the ratio of the density at the
+/// first saddle to the density at the lowest peak found by `detector`, or `1` if the profile has
+/// a single peak or no saddle.
+pub(crate) fn profile_extrema(profile: &[f64], detector: &(impl ExtremaDetector + ?Sized)) -> PairDetail {
+    profile_extrema_in(profile, detector, &mut Extrema::default())
+}
+
+/// Same as `profile_extrema`, detecting into the buffers of `extrema`.
+pub(crate) fn profile_extrema_in(profile: &[f64], detector: &(impl ExtremaDetector + ?Sized), extrema: &mut Extrema) -> PairDetail {
+    detector.detect_into(profile, extrema);
+    let Extrema { peaks, saddles } = extrema;
+
+    if peaks.len() == 1 || saddles.is_empty() {
+        return PairDetail::complete(1.0);
+    }
+
+    let ln_peak = peaks.iter().copied().min_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
+    let ln_saddle = saddles[0];
+    let linear = |v: f64| v.exp().is_normal() && v.exp().is_finite();
+    let value = (ln_saddle - ln_peak).exp();
+    let (extrapolated, error_estimate) = richardson(profile.into(), value).unzip();
+
+    PairDetail {
+        ln_peak: Some(ln_peak),
+        ln_saddle: Some(ln_saddle),
+        extreme_range: !linear(ln_peak) || !linear(ln_saddle),
+        extrapolated,
+        error_estimate,
+        ..PairDetail::complete(value)
+    }
+}
+
+/// `PairDetail::extrapolated` and `PairDetail::error_estimate` of `value`, the OLR of `profile`.
+fn richardson(profile: ArrayView1<f64>, value: f64) -> Option<(f64, f64)> {
+    let olr_fine = vertex_value(profile)?;
+    let olr_coarse = |offset: usize| vertex_value(profile.slice(s![offset..;2])).unwrap_or(1.0);
+    let (olr_even, olr_odd) = (olr_coarse(0), olr_coarse(1));
+
+    let extrapolated = (olr_fine + (olr_fine - olr_even) / 7.0).clamp(0.0, 1.0);
+    let uncertainty = (olr_fine - olr_even).abs().max((olr_fine - olr_odd).abs());
+
+    Some((extrapolated, (value - extrapolated).abs() + uncertainty))
+}
+
+/// OLR of `profile` from the vertices of the parabolas through its lowest peak and first saddle,
+/// as found by `Detector::Parabolic`, or `None` if `profile_extrema` would merge the pair.
+fn vertex_value(profile: ArrayView1<f64>) -> Option<f64> {
+    let (mut n_peaks, mut ln_peak, mut ln_saddle) = (0, None, None);
+    for_each_extremum(profile, |k, is_peak| {
+        let vertex = parabolic_vertex(profile, k);
+        if is_peak {
+            n_peaks += 1;
+            if ln_peak.is_none_or(|ln_peak: f64| vertex < ln_peak) {
+                ln_peak = Some(vertex);
+            }
+        }
+        if !is_peak && ln_saddle.is_none() {
+            ln_saddle = Some(vertex);
+        }
+    });
+    if n_peaks < 2 {
+        return None;
+    }
+
+    Some((ln_saddle? - ln_peak?).exp())
+}
+
+/// Calls `f` with `point` and the `n_steps` points following it at steps of `delta`, updating
+/// `point` in place. Every point is the previous one plus `delta`, as an accumulated chain rather
+/// than `start + k * delta`, so the sampled points and their rounding are the same as those of
+/// the original implementation that allocated every point.
+fn for_each_line_point(point: &mut Array1<f64>, delta: &Array1<f64>, n_steps: usize, mut f: impl FnMut(&Array1<f64>) -> bool) -> bool {
+    if !f(point) {
+        return false;
+    }
+    for _ in 0..n_steps {
+        *point += delta;
+        if !f(point) {
+            return false;
+        }
+    }
+
+    true
+}
+
+pub(crate) fn check_shapes<M, C>(n_comp: usize, means: &ArrayBase<M, Ix2>, covs: &ArrayBase<C, Ix3>) -> Result<(), MoebiusError>
+where
+    M: Data<Elem = f64>,
+    C: Data<Elem = f64>,
+{
+    let dim = means.ncols();
+
+    if means.nrows() != n_comp {
+        return Err(MoebiusError::Shape(format!(
+            "means has {} rows, but w has {} weights", means.nrows(), n_comp
+        )));
+    }
+    let (n_covs, nrows, ncols) = covs.dim();
+    if n_covs == n_comp && n_comp > 0 && (nrows, ncols) != (dim, dim) {
+        // All matrices of a dense array have the same shape, so the first component mismatches.
+        return Err(MoebiusError::DimensionMismatch { component: 0, dim, cov_shape: (nrows, ncols) });
+    }
+    if covs.dim() != (n_comp, dim, dim) {
+        return Err(MoebiusError::Shape(format!(
+            "covs has shape {:?}, expected {:?}", covs.dim(), (n_comp, dim, dim)
+        )));
+    }
+
+    Ok(())
+}
+
+/// `ln(w1 p1(x) + w2 p2(x))` from the log-weights, computed without leaving log space.
+pub(crate) fn ln_pdf_pair<D: ComponentDensity>(
+    x: &Array1<f64>,
+    ln_w1: f64,
+    density_1: &D,
+    ln_w2: f64,
+    density_2: &D,
+    scratch: &mut Scratch
+) -> f64 {
+    ln_add(ln_w1 + density_1.ln_pdf_with(x.view(), scratch), ln_w2 + density_2.ln_pdf_with(x.view(), scratch))
+}
+
+/// `ln(exp(a) + exp(b))` without overflow.
+pub(crate) fn ln_add(a: f64, b: f64) -> f64 {
+    let max = a.max(b);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+
+    max + ((a - max).exp() + (b - max).exp()).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use std::time::{Duration, Instant};
+
+    use ndarray::{arr1, arr2, arr3, s, Array1, Array2, Array3, ArrayView1, Axis};
+    use rayon::prelude::*;
+    use crate::convert::pack_covariances;
+    use crate::covariance::Covariance;
+    use crate::gaussian::Gaussian;
+    use crate::parallel::THREAD_NAME_PREFIX;
+    use crate::density::{ComponentDensity, StudentT};
+    use crate::distance::{mahalanobis_pairs, MahalanobisMode};
+    use crate::error::MoebiusError;
+    use crate::extrema::Detector;
+    use crate::result::{pair_to_index, OlrMatrix};
+    use crate::workspace::OlrSession;
+    use crate::modality::Modality;
+    use crate::olr::{bimodality, bimodality_impl, for_each_line_point, scan_line, scan_line_columns, olr, olr_arrays, olr_detailed, olr_flat_covs, olr_scan_scale, olr_t, olr_with_densities, olr_with_pdf, olr_flat, olr_into, olr_labeled, olr_masked, olr_packed, olr_per_axis, olr_streamed, olr_with_budget, olr_with_config, olr_with_covariances, olr_with_points, olr_with_stats, self_overlap_check, try_olr, GridStrategy, NoSaddlePolicy, OlrConfig, PairDetail, PairOrder, PairStatus, OlrStats, Refinement, Resolution, TimeoutPolicy, AUTO_START_POINTS, CHECK_INVARIANTS, DEFAULT_EXTENSION, DEFAULT_N_POINTS, DEFAULT_VARIANCE_FLOOR, TIMEOUT_CHECK_INTERVAL};
+
+    #[test]
+    fn two_comps_two_dims() {
+        let w = vec![5.2194e-01,  4.7806e-01];
+        let means = arr2(&[
+            [1.1987e+00, 1.1542e+00],
+            [4.1592e+00, 4.1487e+00]
+        ]);
+        let covs = arr3(&[
+            [
+                [1.9455e+00, -9.1612e-04],
+                [-9.1612e-04, 1.9703e+00]
+            ],
+            [
+                [1.5160e+00, 1.1011e+00],
+                [1.1011e+00, 1.5178e+00]
+            ]
+        ]);
+
+        assert_abs_diff_eq!(0.9205257521646449, olr(w, means, covs)[0], epsilon = 1e-4);
+    }
+
+    #[test]
+    fn olr_flat_covs_matches_nested() {
+        let w = vec![5.2194e-01,  4.7806e-01];
+        let means = arr2(&[[1.1987e+00, 1.1542e+00], [4.1592e+00, 4.1487e+00]]);
+        let covs = arr3(&[
+            [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+        ]);
+
+        let flat_covs = covs.clone().into_shape((2, 4)).unwrap();
+        assert_eq!(olr(w.clone(), means.clone(), covs), olr_flat_covs(&w, &means, &flat_covs, 2).unwrap());
+        assert!(olr_flat_covs(&w, &means, &flat_covs.slice(s![.., ..3]).to_owned(), 2).unwrap_err().to_string().contains("3 values"));
+        assert!(olr_flat_covs(&w[..1], &means, &flat_covs, 2).is_err());
+        assert!(olr_flat_covs(&w, &means, &flat_covs, 3).is_err());
+    }
+
+    #[test]
+    fn views_match_owned() {
+        let w = vec![5.2194e-01,  4.7806e-01];
+        let means = arr2(&[[1.1987e+00, 1.1542e+00], [4.1592e+00, 4.1487e+00]]);
+        let covs = arr3(&[
+            [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+        ]);
+        let expected = olr(w.clone(), means.clone(), covs.clone());
+
+        assert_eq!(expected, olr_arrays(arr1(&w).view(), means.view(), covs.view()).unwrap().to_vec());
+
+        // Strided views into larger arrays.
+        let padded_w = arr1(&[w[0], 0.0, w[1]]);
+        let mut padded_means = Array2::zeros((2, 4));
+        padded_means.slice_mut(s![.., ..;2]).assign(&means);
+        let values = olr_arrays(padded_w.slice(s![..;2]), padded_means.slice(s![.., ..;2]), covs.view()).unwrap();
+        assert_eq!(expected, values.to_vec());
+    }
+
+    #[test]
+    fn two_comps_one_dim() {
+        let w = vec![0.5, 0.5];
+        let means = arr2(&[
+            [5.0],
+            [2.0]
+        ]);
+        let covs = arr3(&[
+            [
+                [0.5]
+            ],
+            [
+                [0.5]
+            ]
+        ]);
+
+        assert_abs_diff_eq!(0.21077243773848037, olr(w, means, covs)[0], epsilon = 1e-4)
+    }
+
+    #[test]
+    fn three_comps_two_dims() {
+        let w = vec![5.2194e-01,  4.7806e-01, 5.2194e-01];
+        let means = arr2(&[
+            [1.1987e+00, 1.1542e+00],
+            [4.1592e+00, 4.1487e+00],
+            [4.1592e+00, 4.1487e+00]
+        ]);
+        let covs = arr3(&[
+            [
+                [1.9455e+00, -9.1612e-04],
+                [-9.1612e-04, 1.9703e+00]
+            ],
+            [
+                [1.5160e+00, 1.1011e+00],
+                [1.1011e+00, 1.5178e+00]
+            ],
+            [
+                [1.5160e+00, 1.1009e+00],
+                [1.1009e+00, 1.5178e+00]
+            ]
+        ]);
+
+        let olrs = olr(w, means, covs);
+
+        assert_abs_diff_eq!(0.9205257521646449, olrs[0], epsilon = 1e-4);
+        assert_abs_diff_eq!(0.9464977842655895, olrs[1], epsilon = 1e-4);
+        assert_abs_diff_eq!(1.0, olrs[2], epsilon = 1e-4);
+    }
+
+    #[test]
+    fn packed_covariances_match_dense() {
+        let w = vec![5.2194e-01,  4.7806e-01, 5.2194e-01];
+        let means = arr2(&[[1.1987e+00, 1.1542e+00], [4.1592e+00, 4.1487e+00], [4.1592e+00, 4.1487e+00]]);
+        let covs = arr3(&[
+            [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+            [[1.5160e+00, 1.1009e+00], [1.1009e+00, 1.5178e+00]]
+        ]);
+        let packed = pack_covariances(&covs);
+
+        for config in [OlrConfig::default(), OlrConfig { reg_covar: 1e-3, ..OlrConfig::default() }] {
+            assert_eq!(olr_with_config(&w, &means, &covs, &config).unwrap(), olr_packed(&w, &means, &packed, &config).unwrap());
+        }
+        assert!(olr_packed(&w, &means, &packed.slice(s![.., ..2]).to_owned(), &OlrConfig::default()).is_err());
+    }
+
+    #[test]
+    fn bimodal_pairs() {
+        let w = vec![5.2194e-01, 4.7806e-01, 5.2194e-01, 0.3];
+        let means = arr2(&[[1.1987e+00, 1.1542e+00], [4.1592e+00, 4.1487e+00], [4.1592e+00, 4.1487e+00], [20.0, -3.0]]);
+        let covs = arr3(&[
+            [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+            [[1.5160e+00, 1.1009e+00], [1.1009e+00, 1.5178e+00]],
+            [[1.0, 0.0], [0.0, 1.0]],
+        ]);
+        let values = olr(w.clone(), means.clone(), covs.clone());
+
+        let mut stats = OlrStats::default();
+        let bimodal = bimodality_impl(&w, &means, &covs, Some(&mut stats)).unwrap();
+        assert_eq!(values.iter().map(|&value| value < 1.0).collect::<Vec<_>>(), bimodal);
+        assert_eq!(vec![true, true, true, false, true, true], bimodal);
+        // Bimodal pairs stop after the second peak instead of scanning the whole line.
+        assert!(stats.pdf_evaluations < values.len() * OlrConfig::default().grid_len());
+        assert!(bimodality(&w[..3], &means, &covs).is_err());
+    }
+
+    #[test]
+    fn coarser_grid() {
+        let w = vec![0.5, 0.5];
+        let means = arr2(&[[5.0], [2.0]]);
+        let covs = arr3(&[[[0.5]], [[0.5]]]);
+        let config = OlrConfig { n_points: 500, ..OlrConfig::default() };
+
+        assert_abs_diff_eq!(0.21077243773848037, olr_with_config(&w, &means, &covs, &config).unwrap()[0], epsilon = 1e-3);
+    }
+
+    #[test]
+    fn invalid_config() {
+        let w = vec![0.5, 0.5];
+        let means = arr2(&[[5.0], [2.0]]);
+        let covs = arr3(&[[[0.5]], [[0.5]]]);
+        let config = OlrConfig { n_points: 0, ..OlrConfig::default() };
+
+        assert!(olr_with_config(&w, &means, &covs, &config).is_err());
+    }
+
+    #[test]
+    fn stats_are_collected() {
+        let w = vec![0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0], [2.0], [4.0]]);
+        let covs = arr3(&[[[0.5]], [[0.5]], [[0.5]]]);
+        let config = OlrConfig::default();
+        let mut stats = OlrStats::default();
+
+        olr_with_stats(&w, &means, &covs, &config, &mut stats).unwrap();
+
+        assert_eq!(3, stats.pair_times.len());
+        assert_eq!(3 * config.grid_len(), stats.pdf_evaluations);
+    }
+
+    #[test]
+    fn flat_inputs() {
+        let values = olr_flat(&[0.5, 0.5], &[5.0, 2.0], &[0.5, 0.5], 1).unwrap();
+
+        assert_abs_diff_eq!(0.21077243773848037, values[0], epsilon = 1e-4);
+        assert!(olr_flat(&[0.5, 0.5], &[5.0, 2.0], &[0.5], 1).is_err());
+        assert!(olr_flat(&[0.5, 0.5], &[5.0], &[0.5, 0.5], 1).is_err());
+    }
+
+    #[test]
+    fn self_overlap_is_maximal() {
+        for scale in [1e-3, 1e-1, 1.0, 10.0, 1e3] {
+            let mean = arr1(&[1.0, -2.0]);
+            let cov = arr2(&[
+                [1.0, 0.3],
+                [0.3, 2.0]
+            ]) * scale;
+
+            assert_abs_diff_eq!(1.0, self_overlap_check(&mean, &cov).unwrap(), epsilon = 1e-9);
+        }
+
+        assert_abs_diff_eq!(1.0, self_overlap_check(&arr1(&[5.0]), &arr2(&[[0.5]])).unwrap(), epsilon = 1e-9);
+        assert!(self_overlap_check(&arr1(&[5.0, 1.0]), &arr2(&[[0.5]])).is_err());
+    }
+
+    #[test]
+    fn budget_improves_on_uniform() {
+        // One nearly merged, two intermediate and three clearly separated pairs.
+        let w = vec![0.25; 4];
+        let means = arr2(&[
+            [0.0, 0.0],
+            [2.2, 0.3],
+            [3.1, 4.0],
+            [30.0, 1.0]
+        ]);
+        let covs = arr3(&[
+            [[1.0, 0.2], [0.2, 0.8]],
+            [[0.6, 0.0], [0.0, 1.4]],
+            [[1.2, -0.3], [-0.3, 0.9]],
+            [[1.0, 0.0], [0.0, 1.0]]
+        ]);
+        let reference = olr_with_config(&w, &means, &covs, &OlrConfig { n_points: 20000, ..OlrConfig::default() }).unwrap();
+        let error = |values: Vec<f64>| -> f64 {
+            values.iter().zip(&reference).map(|(a, b)| (a - b).abs()).sum()
+        };
+
+        for budget in [120, 480] {
+            let uniform = olr_with_config(&w, &means, &covs, &OlrConfig { n_points: budget / 6, ..OlrConfig::default() }).unwrap();
+            let budgeted = olr_with_budget(&w, &means, &covs, &OlrConfig::default(), budget).unwrap();
+
+            assert!(error(budgeted) < error(uniform));
+        }
+    }
+
+    #[test]
+    fn zero_weight_pair() {
+        let means = arr2(&[[5.0], [2.0], [0.0]]);
+        let covs = arr3(&[[[0.5]], [[0.5]], [[0.5]]]);
+
+        let values = olr(vec![0.0, 0.0, 1.0], means, covs);
+
+        assert!(values.iter().all(|v| v.is_finite()));
+        assert_abs_diff_eq!(0.21077243773848037, values[0], epsilon = 1e-4);
+    }
+
+    #[test]
+    fn singular_covariance_names_component() {
+        let w = vec![0.25; 4];
+        let means = arr2(&[[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]]);
+        let mut covs = arr3(&[[[1.0, 0.0], [0.0, 1.0]]; 4]);
+        covs[[3, 1, 1]] = 0.0;
+
+        let message = try_olr(w.clone(), means.clone(), covs.clone()).unwrap_err().to_string();
+
+        assert!(message.contains("component 3"));
+        assert!(message.contains("pair (0, 3)"));
+        assert!(message.contains("reg_covar"));
+
+        let config = OlrConfig { reg_covar: 1e-6, ..OlrConfig::default() };
+        assert!(olr_with_config(&w, &means, &covs, &config).is_ok());
+    }
+
+    #[test]
+    fn analytic_shortcut_agrees_with_scan() {
+        let cov = arr2(&[[1.0, 0.3], [0.3, 0.8]]);
+        let shortcut = OlrConfig { analytic_shortcut: true, ..OlrConfig::default() };
+        let mut certified = [0; 2];
+        // Equal covariances straddle both boundaries of the exact criterion, unequal ones the
+        // sufficient conditions for unimodality.
+        for inflation in [1.0, 3.0] {
+            for step in 0..25 {
+                for weight in [0.5, 0.7, 0.8, 0.81, 0.9] {
+                    let w = [weight, 1.0 - weight];
+                    let means = arr2(&[[0.0, 0.0], [1.5 + 0.1 * step as f64, 0.0]]);
+                    let covs = ndarray::stack![ndarray::Axis(0), cov, &cov * inflation];
+
+                    let scanned = olr_detailed(&w, &means, &covs, &OlrConfig::default()).unwrap().pairs[0];
+                    let detail = olr_detailed(&w, &means, &covs, &shortcut).unwrap().pairs[0];
+                    match detail.modality {
+                        Some(Modality::Unimodal) => {
+                            assert_eq!((1.0, None), (scanned.value, scanned.ln_saddle), "{:?} {:?}", w, means);
+                            assert_eq!(1.0, detail.value);
+                            certified[0] += 1;
+                        }
+                        Some(Modality::Bimodal) => {
+                            assert!(scanned.value < 1.0, "{:?} {:?}", w, means);
+                            assert_eq!(scanned, PairDetail { modality: None, ..detail });
+                            certified[1] += 1;
+                        }
+                        None => assert_eq!(scanned, detail),
+                    }
+                }
+            }
+        }
+        assert!(certified[0] > 0 && certified[1] > 0, "{:?}", certified);
+
+        // Pairs certified unimodal still fail with `NoSaddlePolicy::Error`.
+        let config = OlrConfig { no_saddle: NoSaddlePolicy::Error, ..shortcut };
+        assert!(olr_with_config(&[0.5, 0.5], &arr2(&[[0.0, 0.0], [0.5, 0.0]]), &ndarray::stack![ndarray::Axis(0), cov, cov], &config).is_err());
+    }
+
+    #[test]
+    fn olr_into_fills_the_buffer() {
+        let w = [0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0], [1.5], [4.0]]);
+        let covs = arr3(&[[[1.0]], [[0.5]], [[2.0]]]);
+        let config = OlrConfig::default();
+
+        let mut out = [f64::NAN; 3];
+        olr_into(&mut out, &w, &means, &covs, &config).unwrap();
+        assert_eq!(olr_with_config(&w, &means, &covs, &config).unwrap(), out);
+
+        let mut short = [-1.0; 2];
+        let message = olr_into(&mut short, &w, &means, &covs, &config).unwrap_err().to_string();
+        assert!(message.contains("out has 2 entries, expected 3"), "{}", message);
+        assert_eq!([-1.0; 2], short);
+    }
+
+    #[test]
+    fn adaptive_grid_resolves_disparate_scales() {
+        let w = [0.5, 0.5];
+        let means = arr2(&[[0.0], [4000.0]]);
+        let covs = arr3(&[[[1e-6]], [[1e6]]]);
+        let uniform = OlrConfig { n_points: 100, ..OlrConfig::default() };
+        let adaptive = OlrConfig { grid: GridStrategy::Adaptive, ..uniform.clone() };
+        let oracle = crate::exact::olr_exact(&w, &means, &covs, 1e-12).unwrap()[0];
+
+        // The step of the uniform grid is 40, so its saddle is the first point past the narrow peak,
+        // whose width is 1e-3.
+        let coarse = olr_detailed(&w, &means, &covs, &uniform).unwrap().pairs[0];
+        assert!((coarse.value - oracle).abs() > 0.05 * oracle, "{} {}", coarse.value, oracle);
+        assert_eq!(GridStrategy::Uniform, coarse.grid);
+
+        let detail = olr_detailed(&w, &means, &covs, &adaptive).unwrap().pairs[0];
+        assert_abs_diff_eq!(oracle, detail.value, epsilon = 1e-3 * oracle);
+        assert_eq!(GridStrategy::Adaptive, detail.grid);
+
+        // Comparable scales keep the uniform grid.
+        let means = arr2(&[[0.0], [3.0]]);
+        let covs = arr3(&[[[1.0]], [[2.0]]]);
+        let expected = olr_detailed(&w, &means, &covs, &uniform).unwrap().pairs;
+        assert_eq!(expected, olr_detailed(&w, &means, &covs, &adaptive).unwrap().pairs);
+    }
+
+    #[test]
+    fn auto_normalize_divides_weights() {
+        let means = arr2(&[[0.0], [2.0]]);
+        let covs = arr3(&[[[1.0]], [[0.5]]]);
+        let strict = OlrConfig { strict: true, ..OlrConfig::default() };
+        let normalize = OlrConfig { auto_normalize: true, ..strict.clone() };
+
+        let expected = olr_with_config(&[0.5, 0.5], &means, &covs, &strict).unwrap();
+        let message = olr_with_config(&[2.0, 2.0], &means, &covs, &strict).unwrap_err().to_string();
+        assert!(message.contains("weights sum to 4"), "{}", message);
+        assert_eq!(expected, olr_with_config(&[2.0, 2.0], &means, &covs, &normalize).unwrap());
+        assert_eq!(expected, olr_with_config(&[2.0, 2.0], &means, &covs, &OlrConfig::default()).unwrap());
+
+        assert_eq!(Some(4.0), olr_detailed(&[2.0, 2.0], &means, &covs, &normalize).unwrap().normalized_sum);
+        assert_eq!(None, olr_detailed(&[0.5, 0.5], &means, &covs, &normalize).unwrap().normalized_sum);
+        assert!(olr_with_config(&[0.0, 0.0], &means, &covs, &normalize).is_err());
+    }
+
+    #[test]
+    fn strict_reports_all_invalid_components() {
+        let w = vec![0.25; 4];
+        let means = arr2(&[[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]]);
+        let mut covs = arr3(&[[[1.0, 0.0], [0.0, 1.0]]; 4]);
+        covs[[1, 0, 1]] = 2.0;
+        covs[[1, 1, 0]] = 2.0;
+        covs[[3, 1, 1]] = -1.0;
+
+        let lenient = olr_with_config(&w, &means, &covs, &OlrConfig::default()).unwrap_err().to_string();
+        assert!(lenient.contains("component 1") && !lenient.contains("component 3"), "{}", lenient);
+
+        let config = OlrConfig { strict: true, ..OlrConfig::default() };
+        let message = olr_with_config(&w, &means, &covs, &config).unwrap_err().to_string();
+        assert!(message.contains("components [1, 3]"), "{}", message);
+        assert!(message.contains("component 1 is not positive definite"), "{}", message);
+        assert!(message.contains("component 3 is not positive definite"), "{}", message);
+
+        covs[[1, 0, 1]] = 0.0;
+        covs[[1, 1, 0]] = 0.0;
+        covs[[3, 1, 1]] = 1.0;
+        assert_eq!(
+            olr_with_config(&w, &means, &covs, &OlrConfig::default()).unwrap(),
+            olr_with_config(&w, &means, &covs, &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn negative_reg_covar() {
+        let config = OlrConfig { reg_covar: -1.0, ..OlrConfig::default() };
+
+        assert!(olr_with_config(&[0.5, 0.5], &arr2(&[[0.0], [1.0]]), &arr3(&[[[1.0]], [[1.0]]]), &config).is_err());
+    }
+
+    #[test]
+    fn symmetrize_covariances() {
+        let w = vec![5.2194e-01, 4.7806e-01];
+        let means = arr2(&[[1.1987e+00, 1.1542e+00], [4.1592e+00, 4.1487e+00]]);
+        // The two_comps_two_dims mixture with off-diagonal entries perturbed in opposite
+        // directions, so that the symmetric part is unchanged.
+        let covs = arr3(&[
+            [[1.9455e+00, -9.1612e-04 + 1e-7], [-9.1612e-04 - 1e-7, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00 - 1e-7], [1.1011e+00 + 1e-7, 1.5178e+00]],
+        ]);
+
+        assert!(olr_with_config(&w, &means, &covs, &OlrConfig::default()).is_err());
+
+        let config = OlrConfig { symmetrize: true, ..OlrConfig::default() };
+        let values = olr_with_config(&w, &means, &covs, &config).unwrap();
+        assert_abs_diff_eq!(0.9205257521646449, values[0], epsilon = 1e-4);
+    }
+
+    #[test]
+    fn block_diagonal_matches_dense() {
+        let w = vec![0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0, 0.0, 0.0], [1.5, 0.5, -1.0], [0.5, 2.0, 1.0]]);
+        let covs: Vec<Covariance> = (0..3)
+            .map(|k| Covariance::BlockDiagonal(vec![
+                (vec![0, 2], arr2(&[[1.0 + 0.2 * k as f64, 0.3], [0.3, 0.8]])),
+                (vec![1], arr2(&[[0.5 + 0.1 * k as f64]])),
+            ]))
+            .collect();
+        let mut dense = Array3::zeros((3, 3, 3));
+        for (k, cov) in covs.iter().enumerate() {
+            dense.index_axis_mut(Axis(0), k).assign(&cov.to_dense());
+        }
+
+        let config = OlrConfig { n_points: 300, reg_covar: 1e-3, ..OlrConfig::default() };
+        let blocks = olr_with_covariances(&w, &means, &covs, &config).unwrap();
+        let expected = olr_with_config(&w, &means, &dense, &config).unwrap();
+        for (b, e) in blocks.iter().zip(&expected) {
+            assert_abs_diff_eq!(e, b, epsilon = 1e-12);
+        }
+
+        let uncovered = vec![Covariance::BlockDiagonal(vec![(vec![0], arr2(&[[1.0]]))]); 3];
+        let message = olr_with_covariances(&w, &means, &uncovered, &config).unwrap_err().to_string();
+        assert!(message.contains("component 0"));
+    }
+
+    fn low_rank_mixture(dim: usize, rank: usize) -> (Vec<f64>, Array2<f64>, Vec<Covariance>) {
+        let means = Array2::from_shape_fn((2, dim), |(i, k)| i as f64 * 0.1 * (k as f64).cos());
+        let covs = (0..2)
+            .map(|i| Covariance::LowRank {
+                factors: Array2::from_shape_fn((dim, rank), |(r, c)| ((r * (c + 1) + i) as f64 * 0.37).sin() * 0.5),
+                diag: Array1::from_elem(dim, 0.5 + 0.1 * i as f64),
+            })
+            .collect();
+
+        (vec![0.4, 0.6], means, covs)
+    }
+
+    #[test]
+    fn low_rank_matches_dense() {
+        let (w, means, covs) = low_rank_mixture(8, 2);
+        let mut dense = Array3::zeros((2, 8, 8));
+        for (k, cov) in covs.iter().enumerate() {
+            dense.index_axis_mut(Axis(0), k).assign(&cov.to_dense());
+        }
+
+        let low_rank = olr_with_covariances(&w, &means, &covs, &OlrConfig::default()).unwrap();
+        let expected = olr_with_config(&w, &means, &dense, &OlrConfig::default()).unwrap();
+        assert_abs_diff_eq!(expected[0], low_rank[0], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn high_dimensional_low_rank() {
+        let (w, means, covs) = low_rank_mixture(500, 5);
+
+        let started = Instant::now();
+        let values = olr_with_covariances(&w, &means, &covs, &OlrConfig::default()).unwrap();
+
+        assert!(values[0] > 0.0 && values[0] <= 1.0);
+        assert!(started.elapsed() < Duration::from_secs(10), "took {:?}", started.elapsed());
+    }
+
+    #[test]
+    fn non_finite_inputs() {
+        let means = arr2(&[[0.0], [1.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]]]);
+
+        let message = try_olr(vec![0.5, f64::NAN], means.clone(), covs.clone()).unwrap_err().to_string();
+        assert!(message.ends_with("w[1] is NaN"), "{}", message);
+
+        let mut covs = covs;
+        covs[[1, 0, 0]] = f64::INFINITY;
+        let message = try_olr(vec![0.5, 0.5], means, covs).unwrap_err().to_string();
+        assert!(message.ends_with("covs[1][0][0] is inf"), "{}", message);
+    }
+
+    #[test]
+    fn tiny_variances_in_log_space() {
+        // Equal components 200 standard deviations apart: the peaks are at the means and the
+        // saddle at the midpoint, so ln OLR = ln 2 - 100^2 / 2.
+        let sigma = 1e-140;
+        let means = arr2(&[[0.0], [200.0 * sigma]]);
+        let covs = arr3(&[[[sigma * sigma]], [[sigma * sigma]]]);
+
+        let details = olr_detailed(&[0.5, 0.5], &means, &covs, &OlrConfig::default()).unwrap();
+        let pair = details.pairs[0];
+        assert_abs_diff_eq!(2f64.ln() - 5000.0, pair.ln_saddle.unwrap() - pair.ln_peak.unwrap(), epsilon = 1e-6);
+        assert_eq!(0.0, pair.value);
+        assert!(pair.extreme_range);
+        assert!(details.small_variances.is_empty());
+
+        // In linear space the saddle underflows to zero, the valley between the peaks is flat
+        // and the pair would be reported as merged.
+        let gaussian = Gaussian::new(means.row(0), covs.index_axis(Axis(0), 0), 0, None).unwrap();
+        assert_eq!(0.0, gaussian.pdf(&arr1(&[100.0 * sigma])));
+    }
+
+    #[test]
+    fn tiny_variances_match_unit_scale() {
+        // OLR is invariant under scaling, but at this scale the 3D densities overflow.
+        let w = [0.4, 0.6];
+        let unit_means = arr2(&[[0.0, 0.0, 0.0], [2.5, 1.0, -0.5]]);
+        let unit_covs = arr3(&[[[1.0, 0.2, 0.0], [0.2, 1.0, 0.0], [0.0, 0.0, 1.0]]; 2]);
+        let sigma = 1e-140;
+
+        let expected = olr_with_config(&w, &unit_means, &unit_covs, &OlrConfig::default()).unwrap();
+        let scaled = olr_detailed(&w, &(&unit_means * sigma), &(&unit_covs * sigma * sigma), &OlrConfig::default()).unwrap();
+        assert_abs_diff_eq!(expected[0], scaled.pairs[0].value, epsilon = 1e-9);
+        assert!(scaled.pairs[0].extreme_range);
+
+        let cov = &unit_covs.index_axis(Axis(0), 0) * sigma * sigma;
+        let gaussian = Gaussian::new(unit_means.row(0).map(|v| v * sigma).view(), cov.view(), 0, None).unwrap();
+        assert_eq!(f64::INFINITY, gaussian.pdf(&arr1(&[0.0, 0.0, 0.0])));
+    }
+
+    #[test]
+    fn detailed_flags_small_variances() {
+        let means = arr2(&[[0.0], [5.0]]);
+        let covs = arr3(&[[[1.0]], [[1e-13]]]);
+
+        let details = olr_detailed(&[0.5, 0.5], &means, &covs, &OlrConfig::default()).unwrap();
+        assert_eq!(vec![(1, 0)], details.small_variances);
+        assert!(!details.pairs[0].extreme_range);
+    }
+
+    #[test]
+    fn per_axis_decomposition() {
+        let covs = arr3(&[[[1.0, 0.0], [0.0, 0.5]], [[1.5, 0.0], [0.0, 0.5]]]);
+
+        // Separated along x only: the full profile is the x profile scaled by a constant.
+        let means = arr2(&[[0.0, 1.0], [3.0, 1.0]]);
+        let full = olr(vec![0.4, 0.6], means.clone(), covs.clone());
+        let per_axis = olr_per_axis(vec![0.4, 0.6], means, covs.clone()).unwrap();
+        assert_abs_diff_eq!(full[0], per_axis[0][0], epsilon = 1e-12);
+        assert_eq!(1.0, per_axis[0][1]);
+
+        // Separation along both axes overlaps less than along either one.
+        let means = arr2(&[[0.0, 0.0], [2.5, 3.0]]);
+        let full = olr(vec![0.4, 0.6], means.clone(), covs.clone());
+        let per_axis = olr_per_axis(vec![0.4, 0.6], means, covs).unwrap();
+        assert!(per_axis[0].iter().all(|&axis| axis < 1.0 && full[0] < axis), "{:?} {:?}", full, per_axis);
+    }
+
+    #[test]
+    fn line_points_match_allocating_chain() {
+        let mean_i = arr1(&[0.1, -3.7, 1e-3]);
+        let delta = (&arr1(&[2.3, 0.7, -5.9]) - &mean_i) * 1.0 / 997.0;
+        let n_steps = 997 + 3 * 13;
+
+        // Reference: the former implementation, allocating a new array for every step.
+        let mut expected = vec![&mean_i - 13.0 * &delta];
+        let mut curr_point = &mean_i - 13.0 * &delta;
+        for _ in 0..n_steps {
+            let new_point = &curr_point + &delta;
+            curr_point = new_point.clone();
+            expected.push(new_point);
+        }
+
+        let mut points = Vec::new();
+        for_each_line_point(&mut (&mean_i - &(13.0 * &delta)), &delta, n_steps, |point| {
+            points.push(point.clone());
+            true
+        });
+
+        let bits = |points: &[Array1<f64>]| points.iter().flatten().map(|v| v.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&expected), bits(&points));
+    }
+
+    #[test]
+    fn line_columns_match_line_points() {
+        let (mean_i, mean_j) = (arr1(&[0.1, -3.7, 1e-3]), arr1(&[2.3, 0.7, -5.9]));
+        let config = OlrConfig { n_points: 997, extension: 13, ..OlrConfig::default() };
+
+        let mut expected = Vec::new();
+        scan_line(mean_i.view(), mean_j.view(), &config, |point| expected.extend(point.iter().map(|v| v.to_bits())));
+        let mut points = Vec::new();
+        let buffers = (&mut Vec::new(), &mut Vec::new(), &mut Vec::new());
+        assert!(scan_line_columns(mean_i.view(), mean_j.view(), &config, buffers, |columns| {
+            assert!(columns.ncols() <= TIMEOUT_CHECK_INTERVAL);
+            points.extend(columns.columns().into_iter().flatten().map(|v| v.to_bits()));
+            true
+        }));
+
+        assert_eq!(expected, points);
+    }
+
+    #[test]
+    fn batched_scan_matches_point_scan() {
+        // Scans the same pair with matrices of points and, through a density that hides the
+        // Gaussian, point by point.
+        struct PointByPoint(Gaussian);
+        impl ComponentDensity for PointByPoint {
+            fn ln_pdf(&self, x: ArrayView1<f64>) -> f64 {
+                self.0.ln_pdf(x)
+            }
+        }
+
+        let dim = 5;
+        let means = Array2::from_shape_fn((2, dim), |(i, k)| i as f64 * 1.2 + (k as f64 * 0.7).sin());
+        let covs = Array3::from_shape_fn((2, dim, dim), |(i, r, c)| if r == c { 0.6 + 0.2 * i as f64 } else { 0.1 });
+        let gaussians: Vec<Gaussian> = (0..2).map(|k| Gaussian::new(means.row(k), covs.index_axis(Axis(0), k), k, None).unwrap()).collect();
+        let densities: Vec<PointByPoint> = gaussians.iter().cloned().map(PointByPoint).collect();
+        let config = OlrConfig::default();
+
+        let batched = olr_with_densities(&[0.4, 0.6], &means, &gaussians, &config).unwrap();
+        let point_by_point = olr_with_densities(&[0.4, 0.6], &means, &densities, &config).unwrap();
+        assert!(batched[0] < 1.0);
+        assert_abs_diff_eq!(point_by_point[0], batched[0], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn deterministic_mode_is_bitwise_reproducible() {
+        // As in `batched_scan_matches_point_scan`, a density hiding the Gaussian never takes the
+        // matrix kernel.
+        struct PointByPoint(Gaussian);
+        impl ComponentDensity for PointByPoint {
+            fn ln_pdf(&self, x: ArrayView1<f64>) -> f64 {
+                self.0.ln_pdf(x)
+            }
+        }
+
+        let (n_comp, dim) = (4, 5);
+        let w = [0.1, 0.2, 0.3, 0.4];
+        let means = Array2::from_shape_fn((n_comp, dim), |(i, k)| i as f64 * 1.1 + (k as f64 * 0.7 + i as f64).sin());
+        let covs = Array3::from_shape_fn((n_comp, dim, dim), |(i, r, c)| if r == c { 0.5 + 0.2 * i as f64 } else { 0.1 });
+        let densities: Vec<PointByPoint> = (0..n_comp)
+            .map(|k| PointByPoint(Gaussian::new(means.row(k), covs.index_axis(Axis(0), k), k, None).unwrap()))
+            .collect();
+        let config = |n_threads| OlrConfig {
+            resolution: Resolution::Auto { tolerance: 1e-6, max_points: 2048 },
+            n_threads: Some(n_threads),
+            deterministic: true,
+            ..OlrConfig::default()
+        };
+        let bits = |values: Vec<f64>| values.into_iter().map(f64::to_bits).collect::<Vec<_>>();
+
+        let reference = bits(olr_with_densities(&w, &means, &densities, &config(1)).unwrap());
+        for n_threads in [1, 8] {
+            assert_eq!(reference, bits(olr_with_config(&w, &means, &covs, &config(n_threads)).unwrap()));
+            assert_eq!(reference, bits(olr_with_densities(&w, &means, &densities, &config(n_threads)).unwrap()));
+        }
+        assert!(reference.iter().any(|&value| f64::from_bits(value) < 1.0));
+
+        // Every pair is refined up to `max_points`, whatever its convergence.
+        let details = olr_detailed(&w, &means, &covs, &config(1)).unwrap();
+        assert!(details.pairs.iter().all(|detail| detail.refinement.unwrap().n_points == 2048));
+        let timed = OlrConfig { pair_timeout: Some(Duration::from_secs(1)), ..config(1) };
+        assert!(matches!(olr_with_config(&w, &means, &covs, &timed), Err(MoebiusError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn fewer_than_two_components() {
+        let require = OlrConfig { require_pairs: true, ..OlrConfig::default() };
+        let mixtures = [
+            (vec![], Array2::zeros((0, 0)), Array3::zeros((0, 0, 0))),
+            (vec![], Array2::zeros((0, 2)), Array3::zeros((0, 2, 2))),
+            (vec![1.0], arr2(&[[0.0, 1.0]]), arr3(&[[[1.0, 0.0], [0.0, 1.0]]])),
+        ];
+
+        for (w, means, covs) in mixtures {
+            assert!(olr_with_config(&w, &means, &covs, &OlrConfig::default()).unwrap().is_empty());
+            assert!(olr_flat(&w, means.as_slice().unwrap(), covs.as_slice().unwrap(), means.ncols()).unwrap().is_empty());
+            assert!(try_olr(w.clone(), means.clone(), covs.clone()).unwrap().is_empty());
+
+            let message = olr_with_config(&w, &means, &covs, &require).unwrap_err().to_string();
+            assert!(message.contains(&format!("has {} component", w.len())), "{}", message);
+            let structured: Vec<Covariance> = covs.outer_iter().map(|cov| Covariance::Full(cov.to_owned())).collect();
+            assert!(olr_with_covariances(&w, &means, &structured, &require).is_err());
+        }
+    }
+
+    #[test]
+    fn student_t_components() {
+        let w = vec![5.2194e-01, 4.7806e-01];
+        let means = arr2(&[[1.1987e+00, 1.1542e+00], [4.1592e+00, 4.1487e+00]]);
+        let scales = arr3(&[
+            [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+        ]);
+        let gaussian = olr_with_config(&w, &means, &scales, &OlrConfig::default()).unwrap()[0];
+
+        let nearly_normal = olr_t(&w, &means, &scales, &[1e6, 1e6], &OlrConfig::default()).unwrap()[0];
+        assert_abs_diff_eq!(gaussian, nearly_normal, epsilon = 1e-3);
+
+        let heavy = olr_t(&w, &means, &scales, &[3.0, 3.0], &OlrConfig::default()).unwrap()[0];
+        assert!((heavy - gaussian).abs() > (nearly_normal - gaussian).abs());
+
+        assert!(olr_t(&w, &means, &scales, &[3.0], &OlrConfig::default()).is_err());
+        assert!(olr_t(&w, &means, &scales, &[3.0, -1.0], &OlrConfig::default()).unwrap_err().to_string().contains("component 1"));
+    }
+
+    #[test]
+    fn boxed_densities() {
+        let means = arr2(&[[0.0], [3.0]]);
+        let t = StudentT::new(means.row(0), arr2(&[[1.0]]).view(), 4.0).unwrap();
+        let boxed: Vec<Box<dyn ComponentDensity + Sync>> = vec![
+            Box::new(t.clone()),
+            Box::new(StudentT::new(means.row(1), arr2(&[[1.0]]).view(), 4.0).unwrap()),
+        ];
+        let expected = olr_t(&[0.5, 0.5], &means, &arr3(&[[[1.0]], [[1.0]]]), &[4.0, 4.0], &OlrConfig::default()).unwrap();
+
+        assert_eq!(expected, olr_with_densities(&[0.5, 0.5], &means, &boxed, &OlrConfig::default()).unwrap());
+        assert!(olr_with_densities(&[0.5, 0.5], &means, &[t], &OlrConfig::default()).is_err());
+    }
+
+    #[test]
+    fn closure_densities() {
+        let w = vec![5.2194e-01, 4.7806e-01, 0.2];
+        let means = arr2(&[[1.1987e+00, 1.1542e+00], [4.1592e+00, 4.1487e+00], [0.5, 3.0]]);
+        let covs = arr3(&[
+            [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+            [[0.8, 0.0], [0.0, 0.6]],
+        ]);
+        let gaussians: Vec<Gaussian> = (0..3)
+            .map(|k| Gaussian::new(means.row(k), covs.index_axis(Axis(0), k), k, None).unwrap())
+            .collect();
+
+        let values = olr_with_pdf(&w, &means, |x, k| gaussians[k].pdf(x)).unwrap();
+        let expected = olr(w.clone(), means.clone(), covs);
+        for (value, expected) in values.iter().zip(&expected) {
+            assert_abs_diff_eq!(expected, value, epsilon = 1e-12);
+        }
+        assert!(olr_with_pdf(&w[..2], &means, |x, k| gaussians[k].pdf(x)).is_err());
+    }
+
+    #[test]
+    fn streamed_in_pre_score_order() {
+        let w = vec![0.2, 0.2, 0.2, 0.2, 0.2];
+        let means = arr2(&[[0.0, 0.0], [5.0, 0.5], [1.0, 0.2], [9.0, -1.0], [4.0, 1.0]]);
+        let covs = Array3::from_shape_fn((5, 2, 2), |(k, r, c)| if r == c { 0.5 + 0.3 * k as f64 } else { 0.1 });
+        let expected = olr_with_config(&w, &means, &covs, &OlrConfig::default()).unwrap();
+        let distances = mahalanobis_pairs(&means, &covs, MahalanobisMode::Symmetric).unwrap();
+        let mut by_distance: Vec<usize> = (0..distances.len()).collect();
+        by_distance.sort_by(|&a, &b| distances[a].total_cmp(&distances[b]));
+
+        for n_threads in [1, 3] {
+            let config = OlrConfig { pair_order: PairOrder::Overlap, n_threads: Some(n_threads), ..OlrConfig::default() };
+            let mut order = Vec::new();
+            let streamed = olr_streamed(&w, &means, &covs, &config, false, |pair, _| order.push(pair)).unwrap();
+
+            let pairs = OlrMatrix::new(5, expected.clone()).unwrap().pairs();
+            assert_eq!(by_distance.iter().map(|&k| pairs[k]).collect::<Vec<_>>(), order);
+            for (pair, value) in &streamed {
+                assert_eq!(expected[pair_to_index(pair.0, pair.1, 5)], *value);
+            }
+            assert_eq!(expected, olr_with_config(&w, &means, &covs, &config).unwrap());
+        }
+
+        let index_order = olr_streamed(&w, &means, &covs, &OlrConfig::default(), false, |_, _| {}).unwrap();
+        assert_eq!(expected, index_order.iter().map(|(_, value)| *value).collect::<Vec<_>>());
+        let sorted = olr_streamed(&w, &means, &covs, &OlrConfig::default(), true, |_, _| {}).unwrap();
+        assert!(sorted.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert!("closest".parse::<PairOrder>().is_err());
+    }
+
+    #[test]
+    fn covariance_dimension_mismatch() {
+        let w = vec![0.5, 0.5];
+        let means = arr2(&[[0.0, 0.0], [1.0, 1.0]]);
+        let covs = Array3::from_shape_fn((2, 3, 3), |(_, r, c)| if r == c { 1.0 } else { 0.0 });
+
+        let error = olr_with_config(&w, &means, &covs, &OlrConfig::default()).unwrap_err();
+        assert!(matches!(error, MoebiusError::DimensionMismatch { component: 0, dim: 2, cov_shape: (3, 3) }), "{:?}", error);
+        assert_eq!("covariance of component 0 is 3 x 3, but the means have 2 dimensions", error.to_string());
+
+        let structured = vec![Covariance::Full(Array2::eye(2)), Covariance::Full(Array2::eye(3))];
+        let error = olr_with_covariances(&w, &means, &structured, &OlrConfig::default()).unwrap_err();
+        assert!(matches!(error, MoebiusError::DimensionMismatch { component: 1, dim: 2, cov_shape: (3, 3) }), "{:?}", error);
+
+        // A wrong number of matrices is still a plain shape error.
+        let error = olr_with_config(&w, &means, &Array3::zeros((3, 2, 2)), &OlrConfig::default()).unwrap_err();
+        assert!(matches!(error, MoebiusError::Shape(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn timeouts() {
+        let w = vec![0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0], [2.0], [4.5]]);
+        let covs = arr3(&[[[1.0]], [[0.8]], [[1.2]]]);
+        // Scanning a single pair would take minutes.
+        let config = OlrConfig { n_points: 100_000_000, pair_timeout: Some(Duration::from_millis(1)), n_threads: Some(1), ..OlrConfig::default() };
+
+        let started = Instant::now();
+        let details = olr_detailed(&w, &means, &covs, &config).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(10), "took {:?}", started.elapsed());
+        assert!(details.pairs.iter().all(|pair| pair.status == PairStatus::TimedOut));
+        assert!(olr_with_config(&w, &means, &covs, &config).unwrap().iter().all(|v| v.is_nan()));
+
+        let error = olr_with_config(&w, &means, &covs, &OlrConfig { on_timeout: TimeoutPolicy::Abort, ..config.clone() }).unwrap_err();
+        assert!(matches!(error, MoebiusError::Timeout { pair: (0, 1) }), "{:?}", error);
+
+        // Pairs starting after the deadline are not scanned at all.
+        let expired = OlrConfig { pair_timeout: None, deadline: Some(Duration::ZERO), ..config.clone() };
+        let details = olr_detailed(&w, &means, &covs, &expired).unwrap();
+        assert!(details.pairs.iter().all(|pair| pair.status == PairStatus::TimedOut && pair.ln_peak.is_none()));
+
+        let quick = OlrConfig { pair_timeout: Some(Duration::from_secs(60)), deadline: Some(Duration::from_secs(60)), ..OlrConfig::default() };
+        let details = olr_detailed(&w, &means, &covs, &quick).unwrap();
+        assert!(details.pairs.iter().all(|pair| pair.status == PairStatus::Complete));
+        assert_eq!(olr(w.clone(), means.clone(), covs.clone()), olr_with_config(&w, &means, &covs, &quick).unwrap());
+        assert_eq!(TimeoutPolicy::Abort, "abort".parse().unwrap());
+        assert!("retry".parse::<TimeoutPolicy>().is_err());
+    }
+
+    #[test]
+    fn per_pair_points() {
+        let w = vec![0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0], [2.0], [4.5]]);
+        let covs = arr3(&[[[1.0]], [[0.8]], [[1.2]]]);
+        let config = OlrConfig::default();
+        let uniform = olr_with_config(&w, &means, &covs, &config).unwrap();
+        assert_eq!(uniform, olr_with_points(&w, &means, &covs, &config, &[DEFAULT_N_POINTS; 3]).unwrap());
+
+        let refined = olr_with_points(&w, &means, &covs, &config, &[DEFAULT_N_POINTS, 50 * DEFAULT_N_POINTS, DEFAULT_N_POINTS]).unwrap();
+        assert_ne!(uniform[1], refined[1]);
+        assert_eq!((uniform[0].to_bits(), uniform[2].to_bits()), (refined[0].to_bits(), refined[2].to_bits()));
+
+        assert!(matches!(olr_with_points(&w, &means, &covs, &config, &[DEFAULT_N_POINTS; 2]), Err(MoebiusError::Shape(_))));
+        assert!(olr_with_points(&w, &means, &covs, &config, &[DEFAULT_N_POINTS, 0, DEFAULT_N_POINTS]).unwrap_err().to_string().contains("(0, 2)"));
+    }
+
+    #[test]
+    fn auto_resolution() {
+        let w = [0.5, 0.5];
+        let means = arr2(&[[0.0], [2.0]]);
+        let max_points = AUTO_START_POINTS << 7;
+        let config = OlrConfig { resolution: Resolution::Auto { tolerance: 1e-6, max_points }, ..OlrConfig::default() };
+
+        // A component far narrower than any grid step never converges and stops at the cap.
+        let spike = arr3(&[[[1.0]], [[1e-300]]]);
+        let mut stats = OlrStats::default();
+        let details = olr_detailed(&w, &means, &spike, &config).unwrap();
+        let refinement = details.pairs[0].refinement.unwrap();
+        assert_eq!(max_points, refinement.n_points);
+        assert!(refinement.delta >= 1e-6 && details.pairs[0].value.is_finite(), "{:?}", details.pairs[0]);
+        olr_with_stats(&w, &means, &spike, &config, &mut stats).unwrap();
+        let scanned: usize = (0..8)
+            .map(|k| OlrConfig { n_points: AUTO_START_POINTS << k, extension: (DEFAULT_EXTENSION * (AUTO_START_POINTS << k)).div_ceil(DEFAULT_N_POINTS), ..OlrConfig::default() }.grid_len())
+            .sum();
+        assert_eq!(scanned, stats.pdf_evaluations);
+
+        // A flat profile has no saddle at any resolution.
+        let flat = arr3(&[[[1e12]], [[1e12]]]);
+        let details = olr_detailed(&w, &means, &flat, &config).unwrap();
+        assert_eq!(1.0, details.pairs[0].value);
+        assert_eq!(Some(Refinement { n_points: 2 * AUTO_START_POINTS, delta: 0.0 }), details.pairs[0].refinement);
+
+        // Fixed grids report no refinement, and the budget overrides the resolution.
+        let covs = arr3(&[[[1.0]], [[0.5]]]);
+        assert_eq!(None, olr_detailed(&w, &means, &covs, &OlrConfig::default()).unwrap().pairs[0].refinement);
+        assert_eq!(
+            olr_with_budget(&w, &means, &covs, &OlrConfig::default(), 500).unwrap(),
+            olr_with_budget(&w, &means, &covs, &config, 500).unwrap()
+        );
+
+        for resolution in [Resolution::auto(0.0), Resolution::auto(f64::NAN), Resolution::Auto { tolerance: 1e-6, max_points: 100 }] {
+            assert!(OlrConfig { resolution, ..OlrConfig::default() }.validate().is_err(), "{:?}", resolution);
+        }
+    }
+
+    #[test]
+    fn extrapolation() {
+        let w = [0.5, 0.5];
+        let means = arr2(&[[0.0], [2.5]]);
+        let covs = arr3(&[[[1.0]], [[0.5]]]);
+        let config = OlrConfig { extrapolate: true, ..OlrConfig::default() };
+
+        let grid = olr_detailed(&w, &means, &covs, &OlrConfig::default()).unwrap().pairs[0];
+        let extrapolated = olr_detailed(&w, &means, &covs, &config).unwrap().pairs[0];
+        assert_ne!(grid.value, extrapolated.value);
+        assert_eq!(grid.extrapolated, Some(extrapolated.value));
+        assert!(extrapolated.error_estimate.unwrap() < grid.error_estimate.unwrap(), "{:?} {:?}", grid, extrapolated);
+
+        // Pairs without a saddle keep their value.
+        let close = arr2(&[[0.0], [0.5]]);
+        let merged = olr_detailed(&w, &close, &covs, &config).unwrap().pairs[0];
+        assert_eq!((1.0, None, None), (merged.value, merged.extrapolated, merged.error_estimate));
+    }
+
+    #[test]
+    fn saddle_outside_window() {
+        // The second component peaks at 2.1, but the line ends just past 1, before the saddle
+        // at 1.05.
+        let w = [0.5, 0.5];
+        let modes = arr2(&[[0.0], [2.1]]);
+        let covs = arr3(&[[[0.25]], [[0.25]]]);
+        let densities: Vec<Gaussian> = (0..2)
+            .map(|k| Gaussian::new(modes.row(k), covs.index_axis(Axis(0), k), k, None).unwrap())
+            .collect();
+        let line = arr2(&[[0.0], [1.0]]);
+        let expected = olr_with_config(&w, &modes, &covs, &OlrConfig::default()).unwrap()[0];
+        assert!(expected < 0.9, "{}", expected);
+
+        assert_eq!(vec![1.0], olr_with_densities(&w, &line, &densities, &OlrConfig::default()).unwrap());
+
+        let config = OlrConfig { no_saddle: NoSaddlePolicy::Error, ..OlrConfig::default() };
+        assert!(olr_with_densities(&w, &line, &densities, &config).unwrap_err().to_string().contains("pair (0, 1)"));
+
+        let config = OlrConfig { no_saddle: NoSaddlePolicy::ExtendAndRetry, ..OlrConfig::default() };
+        let mut stats = OlrStats::default();
+        let recovered = olr_with_densities(&w, &line, &densities, &config).unwrap()[0];
+        assert_abs_diff_eq!(expected, recovered, epsilon = 1e-4);
+        // Pairs with a saddle are not rescanned.
+        assert_eq!(
+            olr_with_config(&w, &modes, &covs, &OlrConfig::default()).unwrap(),
+            olr_with_stats(&w, &modes, &covs, &config, &mut stats).unwrap()
+        );
+        assert_eq!(config.grid_len(), stats.pdf_evaluations);
+        assert_eq!(Ok(NoSaddlePolicy::ExtendAndRetry), "extend_and_retry".parse().map_err(|_| ()));
+    }
+
+    #[test]
+    fn inflating_covariances() {
+        let w = vec![0.4, 0.6];
+        let means = arr2(&[[0.0, 0.0], [4.0, 1.0]]);
+        let covs = arr3(&[[[1.0, 0.2], [0.2, 0.5]], [[0.7, 0.0], [0.0, 1.2]]]);
+        let scales = vec![0.25, 0.5, 1.0, 1.5, 2.0, 3.0, 6.0];
+
+        let scan = olr_scan_scale(w.clone(), means.clone(), covs.clone(), scales.clone()).unwrap();
+        assert_eq!(scales.len(), scan.len());
+        for pair in scan.windows(2) {
+            assert!(pair[0][0] <= pair[1][0], "{:?}", scan);
+        }
+        assert!(scan[0][0] < 0.01 && scan[6][0] == 1.0, "{:?}", scan);
+
+        for (scale, values) in scales.iter().zip(&scan) {
+            let direct = try_olr(w.clone(), means.clone(), &covs * *scale).unwrap();
+            assert_abs_diff_eq!(direct[0], values[0], epsilon = 1e-9);
+        }
+
+        assert!(olr_scan_scale(w, means, covs, vec![1.0, 0.0]).is_err());
+    }
+
+    #[test]
+    fn explicit_defaults() {
+        let w = vec![0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0], [2.0], [4.5]]);
+        let covs = arr3(&[[[1.0]], [[0.8]], [[1.2]]]);
+        let config = OlrConfig {
+            n_points: DEFAULT_N_POINTS,
+            extension: DEFAULT_EXTENSION,
+            resolution: Resolution::Fixed,
+            reg_covar: 0.0,
+            symmetrize: false,
+            variance_floor: DEFAULT_VARIANCE_FLOOR,
+            strict: false,
+            auto_normalize: false,
+            require_pairs: false,
+            no_saddle: NoSaddlePolicy::AssumeMerged,
+            analytic_shortcut: false,
+            grid: GridStrategy::Uniform,
+            detector: Detector::SignChange,
+            extrapolate: false,
+            pair_order: PairOrder::Index,
+            pair_timeout: None,
+            deadline: None,
+            on_timeout: TimeoutPolicy::Continue,
+            n_threads: None,
+            deterministic: false,
+        };
+
+        assert_eq!(config, OlrConfig::default());
+        assert_eq!(1031, config.grid_len());
+        assert_eq!(olr(w.clone(), means.clone(), covs.clone()), olr_with_config(&w, &means, &covs, &config).unwrap());
+    }
+
+    #[test]
+    fn labeled_pairs() {
+        let w = vec![0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0], [2.0], [4.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]], [[1.0]]]);
+        let labels = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+
+        let values = olr(w.clone(), means.clone(), covs.clone());
+        let labeled = olr_labeled(w.clone(), means.clone(), covs.clone(), labels.clone()).unwrap();
+
+        let expected_pairs = [("a", "b"), ("a", "c"), ("b", "c")];
+        assert_eq!(3, labeled.len());
+        for (((pair, value), expected), expected_value) in labeled.iter().zip(expected_pairs).zip(values) {
+            assert_eq!((expected.0.to_string(), expected.1.to_string()), *pair);
+            assert_eq!(expected_value, *value);
+        }
+
+        assert!(olr_labeled(w, means, covs, labels[..2].to_vec()).is_err());
+    }
+
+    fn many_components() -> (Vec<f64>, Array2<f64>, Array3<f64>) {
+        let n = 8;
+        let w = vec![1.0 / n as f64; n];
+        let means = Array2::from_shape_fn((n, 2), |(i, d)| (i * (d + 1)) as f64 * 0.7);
+        let covs = Array3::from_shape_fn((n, 2, 2), |(i, r, c)| if r == c { 1.0 + 0.1 * i as f64 } else { 0.2 });
+
+        (w, means, covs)
+    }
+
+    #[test]
+    fn identical_across_thread_counts() {
+        let (w, means, covs) = many_components();
+        let serial = olr_with_config(&w, &means, &covs, &OlrConfig { n_threads: Some(1), ..OlrConfig::default() }).unwrap();
+
+        for n_threads in [Some(0), Some(2), Some(3), None] {
+            let config = OlrConfig { n_threads, ..OlrConfig::default() };
+
+            assert_eq!(serial, olr_with_config(&w, &means, &covs, &config).unwrap());
+        }
+    }
+
+    #[test]
+    fn scoped_pool_is_used() {
+        let (w, means, covs) = many_components();
+
+        let mut stats = OlrStats::default();
+        olr_with_stats(&w, &means, &covs, &OlrConfig { n_threads: Some(2), ..OlrConfig::default() }, &mut stats).unwrap();
+        assert!((1..=2).contains(&stats.threads.len()));
+        assert!(stats.threads.iter().all(|name| name.starts_with(THREAD_NAME_PREFIX)));
+
+        let mut stats = OlrStats::default();
+        olr_with_stats(&w, &means, &covs, &OlrConfig { n_threads: Some(1), ..OlrConfig::default() }, &mut stats).unwrap();
+        let current = std::thread::current().name().unwrap_or_default().to_string();
+        assert_eq!(vec![current.clone()], stats.threads.into_iter().collect::<Vec<_>>());
+
+        // A single pair needs no pool.
+        let mut stats = OlrStats::default();
+        let (means, covs) = (means.slice(s![..2, ..]).to_owned(), covs.slice(s![..2, .., ..]).to_owned());
+        olr_with_stats(&[0.5, 0.5], &means, &covs, &OlrConfig { n_threads: Some(4), ..OlrConfig::default() }, &mut stats).unwrap();
+        assert_eq!(vec![current], stats.threads.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn nested_in_rayon_pool() {
+        let (w, means, covs) = many_components();
+        let config = OlrConfig { n_threads: Some(2), ..OlrConfig::default() };
+        let expected = olr_with_config(&w, &means, &covs, &config).unwrap();
+
+        let outer = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let nested: Vec<_> = outer.install(|| {
+            (0..4).into_par_iter().map(|_| olr_with_config(&w, &means, &covs, &config).unwrap()).collect()
+        });
+
+        assert!(nested.iter().all(|values| *values == expected));
+    }
+
+    #[test]
+    fn parallel_errors_match_serial() {
+        let (w, means, mut covs) = many_components();
+        covs[[5, 1, 1]] = 0.0;
+        covs[[6, 1, 1]] = 0.0;
+
+        let serial = olr_with_config(&w, &means, &covs, &OlrConfig { n_threads: Some(1), ..OlrConfig::default() });
+        let parallel = olr_with_config(&w, &means, &covs, &OlrConfig { n_threads: Some(4), ..OlrConfig::default() });
+
+        assert_eq!(serial.unwrap_err().to_string(), parallel.unwrap_err().to_string());
+    }
+
+    #[test]
+    fn masked_matches_sliced() {
+        let w = [0.1, 0.3, 0.2, 0.25, 0.15];
+        let means = arr2(&[[0.0, 0.0], [1.5, 0.5], [3.0, -1.0], [0.5, 2.0], [2.0, 2.5]]);
+        let covs = arr3(&[
+            [[1.0, 0.2], [0.2, 0.6]],
+            [[0.5, 0.0], [0.0, 0.5]],
+            [[0.8, -0.3], [-0.3, 1.2]],
+            [[1.5, 0.4], [0.4, 0.9]],
+            [[0.3, 0.1], [0.1, 0.7]],
+        ]);
+        let config = OlrConfig::default();
+        let mut session = OlrSession::new(config.clone()).unwrap();
+
+        for (components, renormalize) in [(vec![4, 0, 2], false), (vec![0, 1, 3], true), (vec![1, 2, 3, 4], false), (vec![3], true)] {
+            let sliced_w: Vec<f64> = components.iter().map(|&k| w[k]).collect();
+            let total: f64 = sliced_w.iter().sum();
+            let sliced_w: Vec<f64> = sliced_w.iter().map(|&v| if renormalize { v / total } else { v }).collect();
+            let sliced = olr_with_config(&sliced_w, &means.select(Axis(0), &components), &covs.select(Axis(0), &components), &config).unwrap();
+            let sliced = OlrMatrix::new(components.len(), sliced).unwrap();
+            let expected: Vec<_> = sliced.pairs().into_iter()
+                .map(|(a, b)| (components[a], components[b]))
+                .zip(sliced.values().iter().copied())
+                .collect();
+
+            assert_eq!(expected, olr_masked(&w, &means, &covs, &components, renormalize, &config).unwrap(), "{:?}", components);
+            assert_eq!(expected, session.olr_masked(&w, &means, &covs, &components, renormalize).unwrap(), "{:?}", components);
+        }
+
+        // Renormalizing only rescales the weights of a pair, which leaves the OLR unchanged.
+        let masked = olr_masked(&w, &means, &covs, &[0, 1], false, &config).unwrap();
+        assert_abs_diff_eq!(masked[0].1, olr_masked(&w, &means, &covs, &[0, 1], true, &config).unwrap()[0].1, epsilon = 1e-12);
+
+        for components in [&[0, 5][..], &[1, 1]] {
+            assert!(olr_masked(&w, &means, &covs, components, false, &config).is_err(), "{:?}", components);
+        }
+        assert!(olr_masked(&[0.0; 5], &means, &covs, &[0, 1], true, &config).is_err());
+    }
+
+    #[test]
+    fn invariants_reject_overflowing_lines() {
+        // The step between means this far apart overflows, so the line is sampled at NaN.
+        let w = [0.5, 0.5];
+        let means = arr2(&[[-1e308], [1e308]]);
+        let covs = arr3(&[[[1.0]], [[1.0]]]);
+
+        let result = olr_with_config(&w, &means, &covs, &OlrConfig::default());
+        if CHECK_INVARIANTS {
+            let error = result.unwrap_err();
+            assert!(matches!(error, MoebiusError::Invariant { pair: (0, 1), .. }), "{:?}", error);
+            assert!(error.to_string().contains("step of the line in dimension 0 is inf"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn pair_weights_are_scale_free() {
+        // A tiny component next to a large one, alone and within a larger mixture.
+        let means = arr2(&[[0.0], [3.0], [40.0]]);
+        let covs = arr3(&[[[0.5]], [[0.5]], [[0.5]]]);
+        let alone = olr(vec![0.98, 0.02], means.slice(s![..2, ..]).to_owned(), covs.slice(s![..2, .., ..]).to_owned());
+        let global = olr(vec![0.49, 0.01, 0.5], means, covs);
+
+        assert!(alone[0] < 1.0);
+        assert_abs_diff_eq!(alone[0], global[0], epsilon = 1e-12);
+    }
+}
@@ -0,0 +1,223 @@
+//! Matrix-free components for dimensions in which covariances cannot be materialized.
+//!
+//! A component is described by its mean, the action of its precision matrix `cov^-1` and the
+//! log-determinant of its covariance. Along the line `m_i + s * step` of a pair, the squared
+//! Mahalanobis distance from every component is a quadratic in `s`, so each pair takes four
+//! solves and every point of the profile `O(1)` work, whatever the dimension.
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::linalg::SpdFactor;
+use crate::olr::{
+    check_pairs, ln_add, pair_log_weights, profile_extrema, refine_resolution, resolve_no_saddle, scan_pairs, values, OlrConfig,
+    PairClock, PairDetail, PairStatus, MAX_PREALLOCATED_POINTS
+};
+use crate::result::condensed_len;
+use crate::validation::{check_finite, normalize_weights};
+
+/// Gaussian component given by the action of its precision matrix.
+pub trait PrecisionOperator {
+    /// `cov^-1 v`. The covariance must be symmetric positive definite.
+    fn solve(&self, v: ArrayView1<f64>) -> Array1<f64>;
+    /// `ln |cov|`.
+    fn log_det(&self) -> f64;
+    fn mean(&self) -> ArrayView1<'_, f64>;
+}
+
+/// `PrecisionOperator` of a dense covariance, solving with its Cholesky factor. `olr_operators`
+/// on dense operators agrees with `olr_with_config` up to rounding.
+#[derive(Debug, Clone)]
+pub struct DensePrecision {
+    mean: Array1<f64>,
+    factor: SpdFactor,
+}
+
+impl DensePrecision {
+    pub fn new(mean: Array1<f64>, cov: ArrayView2<f64>) -> Result<Self, MoebiusError> {
+        let dim = mean.len();
+        if cov.dim() != (dim, dim) {
+            return Err(MoebiusError::Shape(format!("covariance has shape {:?}, expected {:?}", cov.dim(), (dim, dim))));
+        }
+        let factor = SpdFactor::new(cov, "covariance matrix")?;
+
+        Ok(DensePrecision { mean, factor })
+    }
+}
+
+impl PrecisionOperator for DensePrecision {
+    fn solve(&self, v: ArrayView1<f64>) -> Array1<f64> {
+        self.factor.solve(v)
+    }
+
+    fn log_det(&self) -> f64 {
+        self.factor.ln_det()
+    }
+
+    fn mean(&self) -> ArrayView1<'_, f64> {
+        self.mean.view()
+    }
+}
+
+/// Same as `olr_with_config` for matrix-free components, sampling the same points of the line
+/// between the means of every pair.
+pub fn olr_operators<P: PrecisionOperator + Sync>(
+    w: &[f64],
+    components: &[P],
+    config: &OlrConfig
+) -> Result<Vec<f64>, MoebiusError> {
+    if components.len() != w.len() {
+        return Err(MoebiusError::Shape(format!("{} components given for {} weights", components.len(), w.len())));
+    }
+    let dim = components.first().map_or(0, |component| component.mean().len());
+    if let Some(k) = components.iter().position(|component| component.mean().len() != dim) {
+        return Err(MoebiusError::Shape(format!(
+            "mean of component {} has {} entries, expected {}", k, components[k].mean().len(), dim
+        )));
+    }
+    check_finite(w, &Array2::zeros((0, 0)), &Array3::zeros((0, 0, 0)))?;
+    config.validate()?;
+    check_pairs(w.len(), config)?;
+    let w = &*normalize_weights(w, config)?;
+
+    let n_pairs = condensed_len(w.len());
+    let ln_norm = |component: &P| -(dim as f64 * (2.0 * std::f64::consts::PI).ln() + component.log_det()) / 2.0;
+    let pre_score = |i: usize, j: usize| -(&components[i].mean() - &components[j].mean()).mapv(|v| v * v).sum().sqrt();
+    let details = scan_pairs(w.len(), config, &vec![config.n_points; n_pairs], None, pre_score, None, None, |i, j, pair_config, _| {
+        let (ln_w1, ln_w2) = pair_log_weights(w[i], w[j]);
+        let origin = components[i].mean();
+        let (ln_norm_i, ln_norm_j) = (ln_norm(&components[i]), ln_norm(&components[j]));
+        let clock = PairClock::start(pair_config);
+
+        refine_resolution(pair_config, |config| {
+            let step = (&components[j].mean() - &origin) / config.n_points as f64;
+            let (a_i, b_i, c_i) = project(&components[i], origin, &step);
+            let (a_j, b_j, c_j) = project(&components[j], origin, &step);
+
+            resolve_no_saddle(i, j, config, |config| -> PairDetail {
+                let mut profile = Vec::with_capacity(config.grid_len().min(MAX_PREALLOCATED_POINTS));
+                for k in 0..config.grid_len() {
+                    if clock.expired(k) {
+                        let mut detail = profile_extrema(&profile, &config.detector);
+                        detail.status = PairStatus::TimedOut;
+                        return detail;
+                    }
+                    let s = k as f64 - config.extension as f64;
+                    let ln_p_i = ln_norm_i - (c_i + s * (b_i + s * a_i)) / 2.0;
+                    let ln_p_j = ln_norm_j - (c_j + s * (b_j + s * a_j)) / 2.0;
+
+                    profile.push(ln_add(ln_w1 + ln_p_i, ln_w2 + ln_p_j));
+                }
+
+                profile_extrema(&profile, &config.detector)
+            })
+        })
+    })?;
+
+    Ok(values(details))
+}
+
+/// Coefficients `(a, b, c)` of the squared Mahalanobis distance `a s^2 + b s + c` of
+/// `origin + s * step` from the mean of `component`.
+fn project<P: PrecisionOperator>(component: &P, origin: ArrayView1<f64>, step: &Array1<f64>) -> (f64, f64, f64) {
+    let offset = &origin - &component.mean();
+    let solved_step = component.solve(step.view());
+    let solved_offset = component.solve(offset.view());
+
+    (step.dot(&solved_step), 2.0 * step.dot(&solved_offset), offset.dot(&solved_offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use nalgebra::DMatrix;
+    use ndarray::{arr1, arr2, Array1, Array2, Array3, ArrayView1, Axis};
+    use crate::olr::{olr_with_config, OlrConfig};
+    use crate::operator::{olr_operators, DensePrecision, PrecisionOperator};
+
+    /// Covariance `a ⊗ b` of a `p x q` matrix-valued variable flattened row by row, for which
+    /// `(a ⊗ b) vec(V) = vec(a V b^T)`.
+    struct Kronecker {
+        mean: Array1<f64>,
+        a_inv: Array2<f64>,
+        b_inv: Array2<f64>,
+        log_det: f64,
+    }
+
+    impl Kronecker {
+        fn new(mean: Array1<f64>, a: &Array2<f64>, b: &Array2<f64>) -> Self {
+            let invert = |m: &Array2<f64>| {
+                let inverse = DMatrix::from_fn(m.nrows(), m.ncols(), |r, c| m[[r, c]]).try_inverse().unwrap();
+                Array2::from_shape_fn(m.dim(), |(r, c)| inverse[(r, c)])
+            };
+            let ln_det = |m: &Array2<f64>| DMatrix::from_fn(m.nrows(), m.ncols(), |r, c| m[[r, c]]).determinant().ln();
+            let log_det = b.nrows() as f64 * ln_det(a) + a.nrows() as f64 * ln_det(b);
+
+            Kronecker { mean, a_inv: invert(a), b_inv: invert(b), log_det }
+        }
+    }
+
+    impl PrecisionOperator for Kronecker {
+        fn solve(&self, v: ArrayView1<f64>) -> Array1<f64> {
+            let v = v.to_owned().into_shape((self.a_inv.nrows(), self.b_inv.nrows())).unwrap();
+
+            self.a_inv.dot(&v).dot(&self.b_inv.t()).into_shape(self.mean.len()).unwrap()
+        }
+
+        fn log_det(&self) -> f64 {
+            self.log_det
+        }
+
+        fn mean(&self) -> ArrayView1<'_, f64> {
+            self.mean.view()
+        }
+    }
+
+    fn kron(a: &Array2<f64>, b: &Array2<f64>) -> Array2<f64> {
+        let q = b.nrows();
+        Array2::from_shape_fn((a.nrows() * q, a.ncols() * q), |(r, c)| a[[r / q, c / q]] * b[[r % q, c % q]])
+    }
+
+    #[test]
+    fn kronecker_matches_dense() {
+        let w = [0.3, 0.3, 0.4];
+        let factors = [
+            (arr2(&[[1.0, 0.3], [0.3, 0.8]]), arr2(&[[1.0, 0.2, 0.0], [0.2, 1.5, 0.4], [0.0, 0.4, 0.7]])),
+            (arr2(&[[0.6, -0.1], [-0.1, 1.2]]), arr2(&[[0.9, 0.0, 0.1], [0.0, 1.1, 0.0], [0.1, 0.0, 1.3]])),
+            (arr2(&[[2.0, 0.5], [0.5, 1.0]]), arr2(&[[0.5, 0.1, 0.1], [0.1, 0.5, 0.1], [0.1, 0.1, 0.5]])),
+        ];
+        let means = Array2::from_shape_fn((3, 6), |(k, d)| k as f64 * 1.5 + (d as f64 * 0.9).sin());
+        let covs = Array3::from_shape_fn((3, 6, 6), |(k, r, c)| kron(&factors[k].0, &factors[k].1)[[r, c]]);
+
+        let kronecker: Vec<Kronecker> = factors.iter().enumerate()
+            .map(|(k, (a, b))| Kronecker::new(means.row(k).to_owned(), a, b))
+            .collect();
+        let dense: Vec<DensePrecision> = (0..3)
+            .map(|k| DensePrecision::new(means.row(k).to_owned(), covs.index_axis(Axis(0), k)).unwrap())
+            .collect();
+        let config = OlrConfig::default();
+
+        let expected = olr_with_config(&w, &means, &covs, &config).unwrap();
+        let from_kronecker = olr_operators(&w, &kronecker, &config).unwrap();
+        let from_dense = olr_operators(&w, &dense, &config).unwrap();
+        assert!(expected.iter().any(|v| *v > 0.01 && *v < 0.99), "{:?}", expected);
+        for k in 0..3 {
+            assert_abs_diff_eq!(expected[k], from_dense[k], epsilon = 1e-9);
+            assert_abs_diff_eq!(expected[k], from_kronecker[k], epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn rejects_inconsistent_components() {
+        let cov = arr2(&[[1.0, 0.0], [0.0, 1.0]]);
+        let components = vec![
+            DensePrecision::new(arr1(&[0.0, 0.0]), cov.view()).unwrap(),
+            DensePrecision::new(arr1(&[0.0]), arr2(&[[1.0]]).view()).unwrap(),
+        ];
+
+        assert!(olr_operators(&[0.5, 0.5], &components, &OlrConfig::default()).is_err());
+        assert!(olr_operators(&[1.0], &components, &OlrConfig::default()).is_err());
+        assert!(DensePrecision::new(arr1(&[0.0, 0.0]), arr2(&[[1.0, 2.0], [2.0, 1.0]]).view()).is_err());
+        assert!(DensePrecision::new(arr1(&[0.0]), cov.view()).is_err());
+    }
+}
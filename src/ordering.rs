@@ -0,0 +1,152 @@
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::mixture::MixtureParams;
+use crate::olr::try_olr;
+
+/// Returns a permutation of component indices in which strongly overlapping components are
+/// adjacent.
+///
+/// The order is built greedily from the pairwise OLR matrix: it starts with the most overlapping
+/// pair and then repeatedly attaches the remaining component that overlaps most with either end of
+/// the chain. Ties are broken by the lower index, so the result is deterministic.
+pub fn order_by_overlap(w: Vec<f64>, means: Array2<f64>, covs: Array3<f64>) -> Result<Vec<usize>, MoebiusError> {
+    let n_comp = w.len();
+    let olr_values = try_olr(w, means, covs)?;
+    if n_comp < 2 {
+        return Ok((0..n_comp).collect());
+    }
+
+    let mut matrix = Array2::<f64>::zeros((n_comp, n_comp));
+    let mut values = olr_values.into_iter();
+    for i in 0..n_comp {
+        for j in (i + 1)..n_comp {
+            let value = values.next().unwrap();
+            matrix[[i, j]] = value;
+            matrix[[j, i]] = value;
+        }
+    }
+
+    let (mut first, mut second) = (0, 1);
+    for i in 0..n_comp {
+        for j in (i + 1)..n_comp {
+            if matrix[[i, j]] > matrix[[first, second]] {
+                first = i;
+                second = j;
+            }
+        }
+    }
+
+    let mut order = std::collections::VecDeque::from(vec![first, second]);
+    let mut placed = vec![false; n_comp];
+    placed[first] = true;
+    placed[second] = true;
+
+    while order.len() < n_comp {
+        let head = *order.front().unwrap();
+        let tail = *order.back().unwrap();
+        let mut best: Option<(usize, bool, f64)> = None;
+
+        for k in (0..n_comp).filter(|k| !placed[*k]) {
+            for (at_head, end) in [(true, head), (false, tail)] {
+                let value = matrix[[k, end]];
+                if best.is_none_or(|(_, _, best_value)| value > best_value) {
+                    best = Some((k, at_head, value));
+                }
+            }
+        }
+
+        let (k, at_head, _) = best.unwrap();
+        if at_head {
+            order.push_front(k);
+        } else {
+            order.push_back(k);
+        }
+        placed[k] = true;
+    }
+
+    Ok(order.into_iter().collect())
+}
+
+/// Reorders mixture parameters so that component `k` of the result is component `perm[k]` of the
+/// input.
+pub fn apply_permutation(
+    perm: &[usize],
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>
+) -> Result<MixtureParams, MoebiusError> {
+    let mut seen = vec![false; w.len()];
+    for &idx in perm {
+        if idx >= w.len() || seen[idx] {
+            return Err(MoebiusError::Shape(format!(
+                "{:?} is not a permutation of {} components", perm, w.len()
+            )));
+        }
+        seen[idx] = true;
+    }
+    if perm.len() != w.len() {
+        return Err(MoebiusError::Shape(format!(
+            "{:?} is not a permutation of {} components", perm, w.len()
+        )));
+    }
+
+    Ok((
+        perm.iter().map(|&idx| w[idx]).collect(),
+        means.select(Axis(0), perm),
+        covs.select(Axis(0), perm)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::ordering::{apply_permutation, order_by_overlap};
+
+    #[test]
+    fn overlapping_components_are_adjacent() {
+        let w = vec![0.25, 0.25, 0.25, 0.25];
+        let means = arr2(&[
+            [0.0],
+            [10.0],
+            [20.0],
+            [0.5]
+        ]);
+        let covs = arr3(&[
+            [[1.0]],
+            [[1.0]],
+            [[1.0]],
+            [[1.0]]
+        ]);
+
+        let order = order_by_overlap(w, means, covs).unwrap();
+
+        let pos = |c: usize| order.iter().position(|&k| k == c).unwrap();
+        assert_eq!(4, order.len());
+        assert_eq!(1, pos(0).abs_diff(pos(3)));
+    }
+
+    #[test]
+    fn permutation_is_applied() {
+        let w = vec![0.2, 0.3, 0.5];
+        let means = arr2(&[[0.0], [1.0], [2.0]]);
+        let covs = arr3(&[[[1.0]], [[2.0]], [[3.0]]]);
+
+        let (w, means, covs) = apply_permutation(&[2, 0, 1], &w, &means, &covs).unwrap();
+
+        assert_eq!(vec![0.5, 0.2, 0.3], w);
+        assert_eq!(arr2(&[[2.0], [0.0], [1.0]]), means);
+        assert_eq!(arr3(&[[[3.0]], [[1.0]], [[2.0]]]), covs);
+    }
+
+    #[test]
+    fn invalid_permutation() {
+        let w = vec![0.5, 0.5];
+        let means = arr2(&[[0.0], [1.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]]]);
+
+        assert!(apply_permutation(&[0, 0], &w, &means, &covs).is_err());
+        assert!(apply_permutation(&[0], &w, &means, &covs).is_err());
+        assert!(apply_permutation(&[0, 2], &w, &means, &covs).is_err());
+    }
+}
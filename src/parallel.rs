@@ -0,0 +1,59 @@
+//! Control over the number of threads OLR computations run on.
+//!
+//! Every call with more than one thread runs on its own scoped rayon pool, so concurrent calls
+//! with different settings do not share or resize a global pool. Since the work is submitted with
+//! `ThreadPool::install`, calling from inside another rayon pool blocks the calling worker until
+//! the computation is done instead of deadlocking.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::error::MoebiusError;
+
+/// Default number of threads, `0` meaning all cores.
+static DEFAULT_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Prefix of the names of pool threads.
+pub const THREAD_NAME_PREFIX: &str = "moebius-";
+
+/// Sets the number of threads used by calls that do not specify `n_threads`. `0` means all
+/// cores, which is also the initial default.
+pub fn set_num_threads(n: usize) {
+    DEFAULT_THREADS.store(n, Ordering::Relaxed);
+}
+
+/// Default number of threads as set by `set_num_threads`.
+pub fn num_threads() -> usize {
+    DEFAULT_THREADS.load(Ordering::Relaxed)
+}
+
+/// Actual number of threads for a requested `n_threads`: `None` falls back to the default, `0`
+/// stands for all cores.
+pub(crate) fn resolve_threads(n_threads: Option<usize>) -> usize {
+    match n_threads.unwrap_or_else(num_threads) {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+pub(crate) fn build_pool(n_threads: usize) -> Result<ThreadPool, MoebiusError> {
+    ThreadPoolBuilder::new()
+        .num_threads(n_threads)
+        .thread_name(|i| format!("{}{}", THREAD_NAME_PREFIX, i))
+        .build()
+        .map_err(|e| MoebiusError::InvalidConfig(format!("cannot build a pool of {} threads: {}", n_threads, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parallel::resolve_threads;
+
+    #[test]
+    fn explicit_thread_counts() {
+        assert_eq!(1, resolve_threads(Some(1)));
+        assert_eq!(3, resolve_threads(Some(3)));
+        assert!(resolve_threads(Some(0)) >= 1);
+    }
+}
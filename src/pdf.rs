@@ -0,0 +1,277 @@
+use std::sync::Arc;
+
+use nalgebra::DVector;
+use ndarray::prelude::*;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use statrs::distribution::{Continuous, MultivariateNormal};
+
+use crate::error::MoebiusError;
+use crate::gaussian::Gaussian;
+use crate::olr::check_shapes;
+use crate::parallel::{build_pool, resolve_threads};
+use crate::validation::check_finite;
+
+/// Number of components summed serially before partial sums are combined pairwise. Mixtures with
+/// more components are summed in parallel by `PdfEvaluator::eval` if it has a thread pool.
+pub const SUM_CHUNK: usize = 64;
+
+/// Density of the mixture at `x`, with the weighted densities of the components added by
+/// compensated summation. Factorizes every covariance on each call, so use `PdfEvaluator` to
+/// evaluate the same mixture at many points.
+pub fn gmm_pdf(w: &[f64], means: &Array2<f64>, covs: &Array3<f64>, x: &Array1<f64>) -> Result<f64, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_dim(means.ncols(), x.len())?;
+
+    let mut density = CompensatedSum::default();
+    for (k, &weight) in w.iter().enumerate() {
+        let mvn = MultivariateNormal::new(means.row(k).to_vec(), covs.index_axis(Axis(0), k).iter().copied().collect())
+            .map_err(|e| MoebiusError::Distribution(format!("component {}: {}", k, e)))?;
+        density.add(weight * mvn.pdf(&DVector::from_iterator(x.len(), x.iter().copied())));
+    }
+
+    Ok(density.value())
+}
+
+/// Mixture density with every covariance factorized once, for evaluating the same mixture at
+/// many points.
+///
+/// The weighted densities of the components are summed in chunks of `SUM_CHUNK`, whose sums are
+/// then added pairwise in a fixed order, so results are bitwise identical whatever the number of
+/// threads. All additions are compensated, so that the many small terms of mixtures with
+/// thousands of light components are not lost next to a heavy one.
+#[derive(Debug, Clone)]
+pub struct PdfEvaluator {
+    weights: Vec<f64>,
+    components: Vec<Gaussian>,
+    dim: usize,
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl PdfEvaluator {
+    pub fn new(w: &[f64], means: &Array2<f64>, covs: &Array3<f64>) -> Result<Self, MoebiusError> {
+        check_shapes(w.len(), means, covs)?;
+        check_finite(w, means, covs)?;
+        let components = (0..w.len())
+            .map(|k| Gaussian::new(means.row(k), covs.index_axis(Axis(0), k), k, None))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(PdfEvaluator { weights: w.to_vec(), components, dim: means.ncols(), pool: None })
+    }
+
+    /// Evaluates on `n_threads` threads, resolved as in `OlrConfig`: `eval` splits the components
+    /// of mixtures with more than `SUM_CHUNK` of them between threads, `eval_batch` the points.
+    /// Evaluation is serial on a single thread, which is the default.
+    pub fn with_threads(mut self, n_threads: Option<usize>) -> Result<Self, MoebiusError> {
+        let n_threads = resolve_threads(n_threads);
+        self.pool = if n_threads > 1 { Some(Arc::new(build_pool(n_threads)?)) } else { None };
+
+        Ok(self)
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Density at `x`. Panics if `x` does not have `dim()` entries.
+    pub fn eval(&self, x: &Array1<f64>) -> f64 {
+        match &self.pool {
+            Some(pool) if self.components.len() > SUM_CHUNK => {
+                let x = x.view();
+                self.check_point(x);
+                let chunk_sums: Vec<CompensatedSum> = pool.install(|| {
+                    (0..self.n_chunks()).into_par_iter().map(|chunk| self.chunk_sum(chunk, x)).collect()
+                });
+
+                pairwise_sum(&chunk_sums).value()
+            }
+            _ => self.eval_serial(x.view()),
+        }
+    }
+
+    /// Density at every row of `points`, an `m x dim()` array. Panics on any other number of
+    /// columns.
+    pub fn eval_batch(&self, points: &Array2<f64>) -> Array1<f64> {
+        match &self.pool {
+            Some(pool) => pool.install(|| {
+                let densities: Vec<f64> = (0..points.nrows()).into_par_iter()
+                    .map(|k| self.eval_serial(points.row(k)))
+                    .collect();
+
+                Array1::from(densities)
+            }),
+            None => points.outer_iter().map(|x| self.eval_serial(x)).collect(),
+        }
+    }
+
+    fn eval_serial(&self, x: ArrayView1<f64>) -> f64 {
+        self.check_point(x);
+        let chunk_sums: Vec<CompensatedSum> = (0..self.n_chunks()).map(|chunk| self.chunk_sum(chunk, x)).collect();
+
+        pairwise_sum(&chunk_sums).value()
+    }
+
+    fn n_chunks(&self) -> usize {
+        self.components.len().div_ceil(SUM_CHUNK)
+    }
+
+    fn check_point(&self, x: ArrayView1<f64>) {
+        assert_eq!(self.dim, x.len(), "point has {} entries, expected {}", x.len(), self.dim);
+    }
+
+    /// Weighted density at `x` of the components of the `chunk`-th chunk, summed in order.
+    fn chunk_sum(&self, chunk: usize, x: ArrayView1<f64>) -> CompensatedSum {
+        let range = chunk * SUM_CHUNK..((chunk + 1) * SUM_CHUNK).min(self.components.len());
+
+        let mut sum = CompensatedSum::default();
+        for (weight, component) in self.weights[range.clone()].iter().zip(&self.components[range]) {
+            sum.add(weight * component.ln_pdf(x).exp());
+        }
+
+        sum
+    }
+}
+
+/// Running sum of Neumaier, which keeps the low-order bits every addition rounds off in a separate
+/// compensation, so that its error does not grow with the number of terms.
+#[derive(Debug, Clone, Copy, Default)]
+struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    fn add(&mut self, value: f64) {
+        let sum = self.sum + value;
+        self.compensation += if self.sum.abs() >= value.abs() { (self.sum - sum) + value } else { (value - sum) + self.sum };
+        self.sum = sum;
+    }
+
+    /// Adds the terms of `other`.
+    fn merge(mut self, other: CompensatedSum) -> CompensatedSum {
+        self.add(other.sum);
+        self.compensation += other.compensation;
+
+        self
+    }
+
+    fn value(self) -> f64 {
+        // Infinite sums leave a NaN compensation.
+        if self.sum.is_finite() { self.sum + self.compensation } else { self.sum }
+    }
+}
+
+/// Sum with the halves of `values` added recursively, which fixes the order of additions.
+fn pairwise_sum(values: &[CompensatedSum]) -> CompensatedSum {
+    match values {
+        [] => CompensatedSum::default(),
+        [value] => *value,
+        _ => {
+            let (left, right) = values.split_at(values.len() / 2);
+            pairwise_sum(left).merge(pairwise_sum(right))
+        }
+    }
+}
+
+/// Fails unless a point has `dim` entries.
+pub(crate) fn check_dim(dim: usize, len: usize) -> Result<(), MoebiusError> {
+    if len != dim {
+        return Err(MoebiusError::Shape(format!("point has {} entries, expected {}", len, dim)));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr1, arr2, arr3, Array2, Array3};
+    use crate::pdf::{gmm_pdf, pairwise_sum, CompensatedSum, PdfEvaluator, SUM_CHUNK};
+
+    #[test]
+    fn batch_matches_pointwise() {
+        let w = vec![0.3, 0.7];
+        let means = arr2(&[[0.0, 1.0], [2.0, -1.0]]);
+        let covs = arr3(&[[[1.0, 0.4], [0.4, 0.9]], [[0.5, 0.0], [0.0, 2.0]]]);
+        let evaluator = PdfEvaluator::new(&w, &means, &covs).unwrap();
+        let points = Array2::from_shape_fn((50, 2), |(k, d)| -3.0 + 0.13 * k as f64 + d as f64 * (k as f64 * 0.7).sin());
+
+        let densities = evaluator.eval_batch(&points);
+        assert_eq!(50, densities.len());
+        for (x, density) in points.outer_iter().zip(&densities) {
+            assert_abs_diff_eq!(gmm_pdf(&w, &means, &covs, &x.to_owned()).unwrap(), *density, epsilon = 1e-14);
+        }
+        assert_eq!(densities[7], evaluator.eval(&points.row(7).to_owned()));
+        assert!(gmm_pdf(&w, &means, &covs, &arr1(&[0.0])).is_err());
+    }
+
+    #[test]
+    fn parallel_sum_is_exact() {
+        let n_comp = 10 * SUM_CHUNK + 7;
+        let w = vec![1.0 / n_comp as f64; n_comp];
+        let means = Array2::from_shape_fn((n_comp, 3), |(k, d)| (k as f64 * 0.37 + d as f64).sin() * 4.0);
+        let covs = Array3::from_shape_fn((n_comp, 3, 3), |(k, r, c)| {
+            if r == c { 0.5 + (k % 5) as f64 * 0.2 } else { 0.1 }
+        });
+        let serial = PdfEvaluator::new(&w, &means, &covs).unwrap();
+        let parallel = serial.clone().with_threads(Some(4)).unwrap();
+        let points = Array2::from_shape_fn((20, 3), |(k, d)| (k as f64 * 0.9 - d as f64).cos() * 3.0);
+
+        for x in points.outer_iter() {
+            assert_eq!(serial.eval(&x.to_owned()), parallel.eval(&x.to_owned()));
+        }
+        assert_eq!(serial.eval_batch(&points), parallel.eval_batch(&points));
+        assert_abs_diff_eq!(
+            gmm_pdf(&w, &means, &covs, &points.row(3).to_owned()).unwrap(),
+            parallel.eval(&points.row(3).to_owned()),
+            epsilon = 1e-14
+        );
+    }
+
+    #[test]
+    fn pairwise_sums() {
+        let sum = |values: &[f64]| {
+            let chunks: Vec<CompensatedSum> = values.iter().map(|&sum| CompensatedSum { sum, compensation: 0.0 }).collect();
+            pairwise_sum(&chunks).value()
+        };
+
+        assert_eq!(0.0, sum(&[]));
+        assert_eq!(6.0, sum(&[1.0, 2.0, 3.0]));
+        // `(1e16 + 1) + (-1e16 + 1)`, where the compensation keeps both ones.
+        assert_eq!(2.0, sum(&[1e16, 1.0, -1e16, 1.0]));
+        assert_eq!(f64::INFINITY, sum(&[f64::INFINITY, 1.0]));
+    }
+
+    #[test]
+    fn compensated_sum_keeps_light_components() {
+        // The density of every light component is 1e-17 times that of the heavy one, below half
+        // an ulp of the running sum, so naive summation drops all of them.
+        let n_light = 10_000;
+        let mut w = vec![1e-17; n_light + 1];
+        w[0] = 1.0;
+        let means = Array2::zeros((n_light + 1, 1));
+        let covs = Array3::ones((n_light + 1, 1, 1));
+        let x = arr1(&[0.5]);
+
+        let heavy = gmm_pdf(&w[..1], &means.slice(ndarray::s![..1, ..]).to_owned(), &covs.slice(ndarray::s![..1, .., ..]).to_owned(), &x).unwrap();
+        let light = 1e-17 * heavy;
+        // Up to one rounding of each operation, against about 1e-13 for naive summation.
+        let reference = heavy + n_light as f64 * light;
+        let naive = (0..n_light).fold(heavy, |acc, _| acc + light);
+        assert!((naive - reference).abs() > 1e-14 * reference, "{} {}", naive, reference);
+
+        let evaluator = PdfEvaluator::new(&w, &means, &covs).unwrap();
+        let evaluated = [gmm_pdf(&w, &means, &covs, &x).unwrap(), evaluator.eval(&x), evaluator.with_threads(Some(4)).unwrap().eval(&x)];
+        for density in evaluated {
+            assert!((density - reference).abs() <= 4.0 * f64::EPSILON * reference, "{} {}", density, reference);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_covariances() {
+        let means = arr2(&[[0.0], [1.0]]);
+
+        assert!(PdfEvaluator::new(&[0.5, 0.5], &means, &arr3(&[[[1.0]], [[-1.0]]])).is_err());
+        assert!(PdfEvaluator::new(&[0.5], &means, &arr3(&[[[1.0]], [[1.0]]])).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! Reading of float64 arrays from objects implementing the buffer protocol (numpy arrays,
+//! `array.array`, `memoryview`) or `__dlpack__` (torch, cupy, jax), without going through lists.
+
+use std::ffi::{c_char, c_void};
+
+use pyo3::buffer::{Element, PyBuffer};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyBytes};
+use pyo3::{ffi, AsPyPointer};
+
+/// `DLDeviceType::kDLCPU`.
+const DL_CPU: i32 = 1;
+/// `DLDataTypeCode::kDLInt`, `kDLUInt` and `kDLFloat`.
+const DL_INT: u8 = 0;
+const DL_UINT: u8 = 1;
+const DL_FLOAT: u8 = 2;
+/// Name of unconsumed DLPack capsules.
+const DLTENSOR: &[u8] = b"dltensor\0";
+
+#[repr(C)]
+struct DLDevice {
+    device_type: i32,
+    device_id: i32,
+}
+
+#[repr(C)]
+struct DLDataType {
+    code: u8,
+    bits: u8,
+    lanes: u16,
+}
+
+#[repr(C)]
+struct DLTensor {
+    data: *mut c_void,
+    device: DLDevice,
+    ndim: i32,
+    dtype: DLDataType,
+    shape: *const i64,
+    strides: *const i64,
+    byte_offset: u64,
+}
+
+#[repr(C)]
+struct DLManagedTensor {
+    dl_tensor: DLTensor,
+    manager_ctx: *mut c_void,
+    deleter: Option<unsafe extern "C" fn(*mut DLManagedTensor)>,
+}
+
+/// Shape and row-major values of a dense float64 array.
+pub(super) type DenseArray = (Vec<usize>, Vec<f64>);
+
+/// Numeric element types that are upcast to `f64` on extraction. 64-bit integers beyond `2^53`
+/// lose precision.
+trait Upcast: Copy {
+    fn upcast(self) -> f64;
+}
+
+macro_rules! impl_upcast {
+    ($($t:ty),*) => {
+        $(impl Upcast for $t {
+            fn upcast(self) -> f64 {
+                self as f64
+            }
+        })*
+    };
+}
+
+impl_upcast!(f64, f32, i64, i32, i16, i8, u64, u32, u16, u8);
+
+type BufferReader = fn(&PyAny) -> PyResult<Option<DenseArray>>;
+
+const BUFFER_READERS: [BufferReader; 10] = [
+    read_buffer::<f64>,
+    read_buffer::<f32>,
+    read_buffer::<i64>,
+    read_buffer::<i32>,
+    read_buffer::<i16>,
+    read_buffer::<i8>,
+    read_buffer::<u64>,
+    read_buffer::<u32>,
+    read_buffer::<u16>,
+    read_buffer::<u8>,
+];
+
+/// Reads `obj` as a float64 array if it implements the buffer protocol or `__dlpack__`, upcasting
+/// integer and float32 elements. Returns `None` for other objects so that the caller can fall back
+/// to sequence extraction.
+pub(super) fn extract_dense(obj: &PyAny, name: &str) -> PyResult<Option<DenseArray>> {
+    if obj.is_instance_of::<PyBytes>()? || obj.is_instance_of::<PyByteArray>()? {
+        return Err(PyTypeError::new_err(format!(
+            "{} must be numeric, got {}", name, obj.get_type().name()?
+        )));
+    }
+    if unsafe { ffi::PyObject_CheckBuffer(obj.as_ptr()) } == 1 {
+        return from_buffer(obj, name).map(Some);
+    }
+    if obj.hasattr("__dlpack__")? {
+        return from_dlpack(obj, name).map(Some);
+    }
+
+    Ok(None)
+}
+
+fn read_buffer<T: Element + Upcast>(obj: &PyAny) -> PyResult<Option<DenseArray>> {
+    let Ok(buffer) = PyBuffer::<T>::get(obj) else {
+        return Ok(None);
+    };
+
+    // `to_vec` copies the buffer into C order, honoring its strides.
+    let values = buffer.to_vec(obj.py())?;
+
+    Ok(Some((buffer.shape().to_vec(), values.into_iter().map(Upcast::upcast).collect())))
+}
+
+fn from_buffer(obj: &PyAny, name: &str) -> PyResult<DenseArray> {
+    for read in BUFFER_READERS {
+        if let Some(dense) = read(obj)? {
+            return Ok(dense);
+        }
+    }
+
+    let format = obj.py().import("builtins")
+        .and_then(|builtins| builtins.getattr("memoryview")?.call1((obj,))?.getattr("format")?.extract::<String>())
+        .unwrap_or_default();
+
+    Err(PyTypeError::new_err(format!(
+        "{} must have a numeric dtype, got {}", name, format_name(&format)
+    )))
+}
+
+fn from_dlpack(obj: &PyAny, name: &str) -> PyResult<DenseArray> {
+    if obj.hasattr("__dlpack_device__")? {
+        let (device_type, _): (i32, i32) = obj.call_method0("__dlpack_device__")?.extract()?;
+        check_device(device_type, name)?;
+    }
+
+    let capsule = obj.call_method0("__dlpack__")?;
+    let managed = unsafe {
+        ffi::PyCapsule_GetPointer(capsule.as_ptr(), DLTENSOR.as_ptr() as *const c_char)
+    } as *const DLManagedTensor;
+    if managed.is_null() {
+        return Err(PyErr::take(obj.py()).unwrap_or_else(|| {
+            PyValueError::new_err(format!("{}.__dlpack__() did not return a DLPack capsule", name))
+        }));
+    }
+
+    // The capsule is only borrowed: it is not renamed to "used_dltensor", so the producer's
+    // destructor releases the tensor once the capsule is dropped.
+    let tensor = unsafe { &(*managed).dl_tensor };
+    check_device(tensor.device.device_type, name)?;
+    let dtype = &tensor.dtype;
+    let read = dl_reader(dtype.code, dtype.bits, dtype.lanes).ok_or_else(|| PyTypeError::new_err(format!(
+        "{} must have a numeric dtype, got {}", name, dtype_name(dtype.code, dtype.bits, dtype.lanes)
+    )))?;
+    let item_size = dtype.bits as isize / 8;
+
+    let ndim = tensor.ndim as usize;
+    let shape: Vec<usize> = unsafe { std::slice::from_raw_parts(tensor.shape, ndim) }
+        .iter().map(|&n| n as usize).collect();
+    let strides: Vec<isize> = if tensor.strides.is_null() {
+        contiguous_strides(&shape)
+    } else {
+        unsafe { std::slice::from_raw_parts(tensor.strides, ndim) }.iter().map(|&s| s as isize).collect()
+    };
+
+    let base = unsafe { (tensor.data as *const u8).add(tensor.byte_offset as usize) };
+    let values = gather(&shape, &strides, |offset| unsafe { read(base.offset(offset * item_size)) });
+
+    Ok((shape, values))
+}
+
+fn check_device(device_type: i32, name: &str) -> PyResult<()> {
+    if device_type != DL_CPU {
+        return Err(PyValueError::new_err(format!(
+            "{} is stored on a non-CPU device (DLPack device type {}), call .cpu() first", name, device_type
+        )));
+    }
+
+    Ok(())
+}
+
+type DlReader = unsafe fn(*const u8) -> f64;
+
+unsafe fn read_as<T: Upcast>(ptr: *const u8) -> f64 {
+    (ptr as *const T).read_unaligned().upcast()
+}
+
+/// Reader of a single DLPack element, or `None` for types that are not upcast to `f64`.
+fn dl_reader(code: u8, bits: u8, lanes: u16) -> Option<DlReader> {
+    if lanes != 1 {
+        return None;
+    }
+
+    let read: DlReader = match (code, bits) {
+        (DL_FLOAT, 64) => read_as::<f64>,
+        (DL_FLOAT, 32) => read_as::<f32>,
+        (DL_INT, 64) => read_as::<i64>,
+        (DL_INT, 32) => read_as::<i32>,
+        (DL_INT, 16) => read_as::<i16>,
+        (DL_INT, 8) => read_as::<i8>,
+        (DL_UINT, 64) => read_as::<u64>,
+        (DL_UINT, 32) => read_as::<u32>,
+        (DL_UINT, 16) => read_as::<u16>,
+        (DL_UINT, 8) => read_as::<u8>,
+        _ => return None,
+    };
+
+    Some(read)
+}
+
+fn dtype_name(code: u8, bits: u8, lanes: u16) -> String {
+    if (code, lanes) == (6, 1) {
+        return "bool".to_string();
+    }
+
+    let kind = match code {
+        DL_INT => "int",
+        DL_UINT => "uint",
+        DL_FLOAT => "float",
+        4 => "bfloat",
+        5 => "complex",
+        6 => "bool",
+        _ => "unknown",
+    };
+
+    if lanes == 1 {
+        format!("{}{}", kind, bits)
+    } else {
+        format!("{}{}x{}", kind, bits, lanes)
+    }
+}
+
+/// Human-readable name of a struct-module buffer format that is not upcast to `f64`.
+fn format_name(format: &str) -> String {
+    match format.trim_start_matches(['@', '=', '<', '>', '!']) {
+        "O" => "object".to_string(),
+        "?" => "bool".to_string(),
+        "e" => "float16".to_string(),
+        "Zf" | "Zd" | "Zg" => "complex".to_string(),
+        f if f.ends_with('s') || f.ends_with('w') || f.ends_with('c') => "string".to_string(),
+        "" => "unknown".to_string(),
+        f => format!("buffer format '{}'", f),
+    }
+}
+
+/// Strides, in elements, of a C-contiguous array of the given shape.
+fn contiguous_strides(shape: &[usize]) -> Vec<isize> {
+    let mut strides = vec![1; shape.len()];
+    for k in (0..shape.len().saturating_sub(1)).rev() {
+        strides[k] = strides[k + 1] * shape[k + 1] as isize;
+    }
+
+    strides
+}
+
+/// Reads the elements of a strided array in C order. `read` receives element offsets.
+fn gather(shape: &[usize], strides: &[isize], read: impl Fn(isize) -> f64) -> Vec<f64> {
+    let len: usize = shape.iter().product();
+    let mut values = Vec::with_capacity(len);
+    let mut index = vec![0; shape.len()];
+
+    for _ in 0..len {
+        let offset = index.iter().zip(strides).map(|(&i, &s)| i as isize * s).sum();
+        values.push(read(offset));
+
+        for k in (0..shape.len()).rev() {
+            index[k] += 1;
+            if index[k] < shape[k] {
+                break;
+            }
+            index[k] = 0;
+        }
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::python::buffer::{contiguous_strides, dl_reader, dtype_name, format_name, gather};
+
+    #[test]
+    fn strides_of_contiguous_arrays() {
+        assert_eq!(vec![6, 3, 1], contiguous_strides(&[2, 2, 3]));
+        assert_eq!(vec![1], contiguous_strides(&[4]));
+        assert!(contiguous_strides(&[]).is_empty());
+    }
+
+    #[test]
+    fn gather_honors_strides() {
+        let data: Vec<f64> = (0..12).map(|v| v as f64).collect();
+        let read = |offset: isize| data[offset as usize];
+
+        // 2 x 3 array stored column-major.
+        assert_eq!(vec![0.0, 2.0, 4.0, 1.0, 3.0, 5.0], gather(&[2, 3], &[1, 2], read));
+        // Every other element of the contiguous 2 x 6 array.
+        assert_eq!(vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0], gather(&[2, 3], &[6, 2], read));
+        assert_eq!(data, gather(&[2, 2, 3], &contiguous_strides(&[2, 2, 3]), read));
+        assert!(gather(&[0, 3], &[3, 1], read).is_empty());
+    }
+
+    #[test]
+    fn gather_negative_strides() {
+        let data = [1.0, 2.0, 3.0];
+
+        assert_eq!(vec![3.0, 2.0, 1.0], gather(&[3], &[-1], |offset| data[(2 + offset) as usize]));
+    }
+
+    #[test]
+    fn dtype_names() {
+        assert_eq!("float32", dtype_name(2, 32, 1));
+        assert_eq!("int64", dtype_name(0, 64, 1));
+        assert_eq!("complex128", dtype_name(5, 128, 1));
+        assert_eq!("float32x4", dtype_name(2, 32, 4));
+        assert_eq!("bool", dtype_name(6, 8, 1));
+    }
+
+    #[test]
+    fn dlpack_upcasts() {
+        let float32 = 1.5f32.to_ne_bytes();
+        let int64 = (-3i64).to_ne_bytes();
+        let uint8 = [200u8];
+
+        assert_eq!(1.5, unsafe { dl_reader(2, 32, 1).unwrap()(float32.as_ptr()) });
+        assert_eq!(-3.0, unsafe { dl_reader(0, 64, 1).unwrap()(int64.as_ptr()) });
+        assert_eq!(200.0, unsafe { dl_reader(1, 8, 1).unwrap()(uint8.as_ptr()) });
+
+        assert!(dl_reader(2, 16, 1).is_none());
+        assert!(dl_reader(6, 8, 1).is_none());
+        assert!(dl_reader(5, 128, 1).is_none());
+        assert!(dl_reader(2, 32, 4).is_none());
+    }
+
+    #[test]
+    fn format_names() {
+        assert_eq!("object", format_name("O"));
+        assert_eq!("bool", format_name("?"));
+        assert_eq!("float16", format_name("<e"));
+        assert_eq!("string", format_name("<10w"));
+        assert_eq!("string", format_name("3s"));
+        assert_eq!("buffer format 'T{d:x:}'", format_name("T{d:x:}"));
+    }
+}
@@ -0,0 +1,1787 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use ndarray::prelude::*;
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyArray3, PyArrayDyn, ToPyArray};
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyTypeError, PyUserWarning, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyIterator, PyList, PySequence, PyString};
+
+use crate::boundary::decision_boundary;
+use crate::bounded::olr_bounded;
+use crate::build_info::{capabilities, features};
+#[cfg(feature = "serde")]
+use crate::cache::olr_matrix_cached;
+use crate::convert::{covs_from_nested, covs_from_variances, means_from_nested, means_from_scalars, pack_covariances, unpack_covariances, vec_to_array2};
+use crate::covariance::Covariance;
+use crate::cross::olr_cross_subspace;
+use crate::diagnostics::diagnose;
+use crate::distance::{euclidean_pairs, mahalanobis_pairs};
+use crate::duplicates::find_duplicates;
+use crate::ellipse::ellipse_params;
+use crate::error::MoebiusError;
+use crate::exact::olr_exact;
+use crate::fraction::overlap_fraction;
+use crate::merge::{merge_moments, merge_to_gaussian};
+use crate::mixture::{GaussianMixture, MixtureParams};
+use crate::monte_carlo::overlap_mc;
+use crate::multi::{olr_multi, MultiStatus};
+use crate::olr::{
+    bimodality, check_shapes, ln_add, olr_detailed, olr_flat_covs, olr_into, olr_with_config, plain_value, olr_labeled, olr_masked, olr_packed, olr_per_axis, olr_scan_scale, olr_t, olr_with_points, pair_log_weights, profile_extrema, scan_line, olr_with_covariances, self_overlap_check, IndexedOlr, LabeledOlr, OlrConfig, OlrDetails, PairDetail, PairStatus, Resolution, DEFAULT_EXTENSION, DEFAULT_N_POINTS,
+    DEFAULT_RESOLUTION_TOLERANCE
+};
+use crate::ordering::{apply_permutation, order_by_overlap};
+use crate::ovl::{ovl, ovl_pairs};
+use crate::pdf::{check_dim, PdfEvaluator};
+use crate::parallel::{num_threads, set_num_threads};
+use crate::timing::olr_timed;
+use crate::report::{ReportFormat, ReportOptions, DEFAULT_TOP_N};
+use crate::resolution::bhattacharyya_grad;
+use crate::result::{checked_condensed_len, condensed_len, index_to_pair, pair_to_index, OlrMatrix};
+use crate::rng::Rng;
+use crate::sparse::olr_sparse;
+use crate::separability::{effective_components, separability_with, EFFECTIVE_THRESHOLDS};
+use crate::validation::{check_finite, drop_nan_components, normalized_sum, small_variances, validate_mixture};
+
+mod buffer;
+
+/// Describes the first element of a nested sequence that is not a number, e.g.
+/// `"means[1][0] must be a number, got str"`.
+fn invalid_element(obj: &PyAny, name: &str) -> Option<String> {
+    match obj.downcast::<PySequence>() {
+        Ok(seq) if !obj.is_instance_of::<PyString>().unwrap_or(false) => invalid_item(seq, name),
+        _ => Some(format!("{} must be a sequence, got {}", name, type_name(obj))),
+    }
+}
+
+fn invalid_item(seq: &PySequence, path: &str) -> Option<String> {
+    (0..seq.len().unwrap_or(0))
+        .filter_map(|i| seq.get_item(i).ok().map(|item| (format!("{}[{}]", path, i), item)))
+        .find_map(|(path, item)| {
+            if item.is_instance_of::<PyString>().unwrap_or(false) {
+                return Some(format!("{} must be a number, got {}", path, type_name(item)));
+            }
+            if item.extract::<f64>().is_ok() {
+                return None;
+            }
+
+            match item.downcast::<PySequence>() {
+                Ok(seq) => invalid_item(seq, &path),
+                Err(_) => Some(format!("{} must be a number, got {}", path, type_name(item))),
+            }
+        })
+}
+
+fn type_name(obj: &PyAny) -> &str {
+    obj.get_type().name().unwrap_or("unknown")
+}
+
+/// Prefixes the message of a shape or config error with the Python argument `name` it is about.
+fn named(name: &str, error: MoebiusError) -> MoebiusError {
+    match error {
+        MoebiusError::Shape(message) => MoebiusError::Shape(format!("{}: {}", name, message)),
+        MoebiusError::InvalidConfig(message) => MoebiusError::InvalidConfig(format!("{}: {}", name, message)),
+        error => error,
+    }
+}
+
+/// Parses the string option `name`, e.g. `no_saddle="error"`.
+fn option<T: FromStr<Err = MoebiusError>>(name: &str, value: &str) -> Result<T, MoebiusError> {
+    value.parse().map_err(|e| named(name, e))
+}
+
+/// Extracts `n x d` means. With `promote_1d`, a flat sequence of `n` numbers is accepted as well
+/// and read as the means of `n` one-dimensional components.
+///
+/// Objects implementing the buffer protocol or `__dlpack__` are read directly if they hold
+/// float64 values, sequences are read element by element otherwise.
+fn extract_means(means: &PyAny, promote_1d: bool) -> PyResult<Array2<f64>> {
+    if let Some((shape, values)) = buffer::extract_dense(means, "means")? {
+        return Ok(match shape[..] {
+            [n_comp, dim] => Array2::from_shape_vec((n_comp, dim), values).unwrap(),
+            [_] if promote_1d => means_from_scalars(values),
+            _ => return Err(MoebiusError::Shape(format!(
+                "means must have {} dimensions, got shape {:?}", if promote_1d { "1 or 2" } else { "2" }, shape
+            )).into()),
+        });
+    }
+    if let Ok(nested) = means.extract::<Vec<Vec<f64>>>() {
+        return Ok(means_from_nested(nested).map_err(|e| named("means", e))?);
+    }
+    if promote_1d {
+        if let Ok(flat) = means.extract::<Vec<f64>>() {
+            return Ok(means_from_scalars(flat));
+        }
+    }
+
+    if let Some(message) = invalid_element(means, "means") {
+        return Err(PyTypeError::new_err(message));
+    }
+
+    Err(PyTypeError::new_err(if promote_1d {
+        "means must be a sequence of n sequences of d floats, or of n floats for 1D mixtures"
+    } else {
+        "means must be a sequence of n sequences of d floats"
+    }))
+}
+
+/// Extracts `n x d x d` covariances. With `promote_1d`, a flat sequence of `n` numbers is accepted
+/// as well and read as the variances of `n` one-dimensional components.
+///
+/// Objects implementing the buffer protocol or `__dlpack__` are read as in `extract_means`.
+fn extract_covs(covs: &PyAny, promote_1d: bool) -> PyResult<Array3<f64>> {
+    if let Some((shape, values)) = buffer::extract_dense(covs, "covs")? {
+        return Ok(match shape[..] {
+            [n_comp, nrows, ncols] if nrows == ncols => Array3::from_shape_vec((n_comp, nrows, ncols), values).unwrap(),
+            [_, nrows, ncols] => return Err(MoebiusError::Shape(format!(
+                "covariance matrices must be square, got {} x {}", nrows, ncols
+            )).into()),
+            [_] if promote_1d => covs_from_variances(values),
+            _ => return Err(MoebiusError::Shape(format!(
+                "covs must have {} dimensions, got shape {:?}", if promote_1d { "1 or 3" } else { "3" }, shape
+            )).into()),
+        });
+    }
+    if let Ok(nested) = covs.extract::<Vec<Vec<Vec<f64>>>>() {
+        return Ok(covs_from_nested(nested).map_err(|e| named("covs", e))?);
+    }
+    if promote_1d {
+        if let Ok(flat) = covs.extract::<Vec<f64>>() {
+            return Ok(covs_from_variances(flat));
+        }
+    }
+
+    if let Some(message) = invalid_element(covs, "covs") {
+        return Err(PyTypeError::new_err(message));
+    }
+
+    Err(PyTypeError::new_err(if promote_1d {
+        "covs must be a sequence of n d x d matrices, or of n variances for 1D mixtures"
+    } else {
+        "covs must be a sequence of n d x d matrices"
+    }))
+}
+
+/// Extracts means and covariances as `extract_means` and `extract_covs`, see `check_promoted`.
+fn extract_params(means: &PyAny, covs: &PyAny, promote_1d: bool) -> PyResult<(Array2<f64>, Array3<f64>)> {
+    let (means_array, covs_array) = (extract_means(means, promote_1d)?, extract_covs(covs, promote_1d)?);
+    if promote_1d {
+        check_promoted(means, covs, &means_array, covs_array.dim().1)?;
+    }
+
+    Ok((means_array, covs_array))
+}
+
+/// Flat means and flat covariances only describe one-dimensional mixtures, so either one given
+/// flat while the other holds more than one dimension is an error rather than a broadcast.
+fn check_promoted(means: &PyAny, covs: &PyAny, means_array: &Array2<f64>, cov_dim: usize) -> PyResult<()> {
+    if means_array.nrows() == 0 {
+        return Ok(());
+    }
+    if is_flat(means) && cov_dim != 1 {
+        return Err(MoebiusError::Shape(format!(
+            "flat means describe a 1D mixture, but covs are {} x {} matrices; pass means as n sequences of {} floats",
+            cov_dim, cov_dim, cov_dim
+        )).into());
+    }
+    if is_flat(covs) && means_array.ncols() != 1 {
+        return Err(MoebiusError::Shape(format!(
+            "flat covs describe a 1D mixture, but means have {} dimensions; pass covs as n {} x {} matrices",
+            means_array.ncols(), means_array.ncols(), means_array.ncols()
+        )).into());
+    }
+
+    Ok(())
+}
+
+/// Whether `obj` is an array with a single dimension or a sequence of numbers.
+fn is_flat(obj: &PyAny) -> bool {
+    if let Ok(ndim) = obj.getattr("ndim").and_then(|ndim| ndim.extract::<usize>()) {
+        return ndim == 1;
+    }
+
+    obj.downcast::<PySequence>().ok()
+        .and_then(|seq| seq.get_item(0).ok())
+        .is_some_and(|item| item.extract::<f64>().is_ok())
+}
+
+/// Reads `covs` given as a sequence of `{"factors": W, "diag": psi}` dicts, one low-rank
+/// covariance `W W^T + diag(psi)` per component. `None` if `covs` is not a sequence of dicts.
+fn extract_low_rank(covs: &PyAny) -> PyResult<Option<Vec<Covariance>>> {
+    let Ok(seq) = covs.downcast::<PySequence>() else {
+        return Ok(None);
+    };
+    // Multi-dimensional memoryviews cannot be indexed, but hold no dicts either.
+    if seq.len()? == 0 || !seq.get_item(0).is_ok_and(|item| item.is_instance_of::<PyDict>().unwrap_or(false)) {
+        return Ok(None);
+    }
+
+    let mut structured = Vec::with_capacity(seq.len()?);
+    for k in 0..seq.len()? {
+        let item = seq.get_item(k)?;
+        let dict = item.downcast::<PyDict>()
+            .map_err(|_| PyTypeError::new_err(format!("covs[{}] must be a dict, got {}", k, type_name(item))))?;
+        let field = |key: &str| dict.get_item(key).ok_or_else(|| {
+            PyTypeError::new_err(format!("covs[{}] must have \"factors\" and \"diag\" entries", k))
+        });
+
+        let factors = extract_array(field("factors")?, &format!("covs[{}][\"factors\"]", k), 2)?;
+        let diag = extract_array(field("diag")?, &format!("covs[{}][\"diag\"]", k), 1)?;
+        structured.push(Covariance::LowRank {
+            factors: factors.into_dimensionality().unwrap(),
+            diag: diag.into_dimensionality().unwrap(),
+        });
+    }
+
+    Ok(Some(structured))
+}
+
+/// Reads an `ndim`-dimensional array of numbers, `ndim` being 1 or 2.
+fn extract_array(obj: &PyAny, name: &str, ndim: usize) -> PyResult<ArrayD<f64>> {
+    if let Some((shape, values)) = buffer::extract_dense(obj, name)? {
+        if shape.len() != ndim {
+            return Err(MoebiusError::Shape(format!(
+                "{} must have {} dimensions, got shape {:?}", name, ndim, shape
+            )).into());
+        }
+
+        return Ok(ArrayD::from_shape_vec(shape, values).unwrap());
+    }
+
+    let array = if ndim == 1 {
+        obj.extract::<Vec<f64>>().ok().map(|v| Array1::from(v).into_dyn())
+    } else {
+        obj.extract::<Vec<Vec<f64>>>().ok().map(vec_to_array2).transpose()?.map(|a| a.into_dyn())
+    };
+
+    array.ok_or_else(|| match invalid_element(obj, name) {
+        Some(message) => PyTypeError::new_err(message),
+        None => PyTypeError::new_err(format!("{} must be a {}-dimensional sequence of numbers", name, ndim)),
+    })
+}
+
+/// Result of `olr`.
+///
+/// `values` is the condensed vector of pairwise OLR values in the order expected by
+/// `scipy.spatial.distance.squareform`, `matrix` the corresponding square matrix with ones on the
+/// diagonal.
+#[pyclass(name = "OlrResult")]
+pub struct OlrResult {
+    inner: OlrMatrix,
+    matrix: Option<Py<PyArray2<f64>>>,
+    components: Vec<usize>,
+    /// Per-pair details of results computed by `olr` with dense covariances, for `report`.
+    details: Option<OlrDetails>,
+}
+
+#[pymethods]
+impl OlrResult {
+    #[getter]
+    fn values<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        PyArray1::from_slice(py, self.inner.values())
+    }
+
+    #[getter]
+    fn matrix(&mut self, py: Python<'_>) -> Py<PyArray2<f64>> {
+        self.matrix
+            .get_or_insert_with(|| self.inner.to_square().into_pyarray(py).into())
+            .clone_ref(py)
+    }
+
+    #[getter]
+    fn pairs(&self) -> Vec<(usize, usize)> {
+        self.inner.pairs()
+    }
+
+    /// Original index of every component, which differs from its position only if components
+    /// were dropped.
+    #[getter]
+    fn components(&self) -> Vec<usize> {
+        self.components.clone()
+    }
+
+    /// Dict with `max`, `min`, `mean` and `argmax` (a pair of indices), or `None` for mixtures
+    /// with fewer than two components. Results with details also have `max_error_estimate`, the
+    /// largest bound on the sampling error of a value, `None` if no pair has a saddle.
+    fn summary<'py>(&self, py: Python<'py>) -> PyResult<Option<&'py PyDict>> {
+        let Some(summary) = self.inner.summary() else {
+            return Ok(None);
+        };
+
+        let dict = PyDict::new(py);
+        dict.set_item("max", summary.max)?;
+        dict.set_item("min", summary.min)?;
+        dict.set_item("mean", summary.mean)?;
+        dict.set_item("argmax", summary.argmax)?;
+        if let Some(details) = &self.details {
+            dict.set_item("max_error_estimate", details.max_error_estimate())?;
+        }
+
+        Ok(Some(dict))
+    }
+
+    /// The `k` pairs with the largest values as `((i, j), value)`, by decreasing value and then
+    /// increasing `(i, j)`, so that ties are selected and ordered reproducibly.
+    fn top_k_overlaps(&self, k: usize) -> Vec<((usize, usize), f64)> {
+        self.inner.top_k_overlaps(k)
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.values().len()
+    }
+
+    fn __getitem__(&self, idx: isize) -> PyResult<f64> {
+        let values = self.inner.values();
+        let resolved = if idx < 0 { idx + values.len() as isize } else { idx };
+
+        usize::try_from(resolved).ok()
+            .and_then(|i| values.get(i).copied())
+            .ok_or_else(|| PyIndexError::new_err("OlrResult index out of range"))
+    }
+
+    /// Yields `((i, j), value)` for every pair.
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<&'py PyIterator> {
+        let items: Vec<_> = self.inner.pairs().into_iter()
+            .zip(self.inner.values().iter().copied())
+            .collect();
+
+        PyIterator::from_object(py, PyList::new(py, items))
+    }
+
+    fn __repr__(&self) -> String {
+        self.inner.to_string()
+    }
+
+    /// Condensed values in the binary format of `moebius compute --format bin`.
+    fn tobytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let mut bytes = Vec::new();
+        self.inner.write_to(&mut bytes)?;
+
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    /// Deterministic plain-text report of the result, or Markdown with `markdown=True`: the shape
+    /// of the mixture, the configuration, summary statistics, the `top_n` most overlapping pairs
+    /// and any warnings. Components are named by `labels`, or by their index in the input.
+    /// Only available for results of `olr` with dense covariances.
+    #[pyo3(signature = (top_n = DEFAULT_TOP_N, markdown = false, labels = None))]
+    fn report(&self, top_n: usize, markdown: bool, labels: Option<Vec<String>>) -> PyResult<String> {
+        let Some(details) = &self.details else {
+            return Err(PyValueError::new_err("report is only available for results of olr with dense covariances"));
+        };
+        let options = ReportOptions {
+            format: if markdown { ReportFormat::Markdown } else { ReportFormat::Text },
+            top_n,
+            labels: Some(labels.unwrap_or_else(|| self.components.iter().map(usize::to_string).collect())),
+        };
+
+        Ok(details.render_report(&options))
+    }
+
+    /// Reads the output of `tobytes`. Component indices are not stored, so `components` of the
+    /// result is `0..n` even if components were dropped before `tobytes`.
+    #[staticmethod]
+    fn frombytes(data: &[u8]) -> PyResult<Self> {
+        let inner = OlrMatrix::read_from(&mut &data[..]).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let components = (0..inner.n_components()).collect();
+
+        Ok(OlrResult { inner, matrix: None, components, details: None })
+    }
+}
+
+/// Sampling options of `olr` and `olr_list`, for passing the same ones to many calls as
+/// `config=cfg`. `n_points` and `tolerance` select the resolution as the keywords of `olr` do,
+/// `extension` is the number of steps scanned past the means, `detector` and `no_saddle` take
+/// the values of the keywords of the same name and `grid` is `"uniform"` or `"adaptive"`, which
+/// adds points around Gaussian components much narrower than the step. Attributes are checked
+/// when set, so an invalid config cannot be built.
+#[pyclass(name = "OlrConfig")]
+#[derive(Clone)]
+pub struct OlrConfigWrapper {
+    #[pyo3(get)]
+    n_points: Option<usize>,
+    #[pyo3(get)]
+    tolerance: f64,
+    #[pyo3(get)]
+    extension: usize,
+    #[pyo3(get)]
+    detector: String,
+    #[pyo3(get)]
+    grid: String,
+    #[pyo3(get)]
+    reg_covar: f64,
+    #[pyo3(get)]
+    no_saddle: String,
+}
+
+#[pymethods]
+impl OlrConfigWrapper {
+    #[new]
+    #[pyo3(signature = (
+        *, n_points = None, tolerance = DEFAULT_RESOLUTION_TOLERANCE, extension = DEFAULT_EXTENSION, detector = "sign_change",
+        grid = "uniform", reg_covar = 0.0, no_saddle = "assume_merged"
+    ))]
+    fn new(
+        n_points: Option<usize>,
+        tolerance: f64,
+        extension: usize,
+        detector: &str,
+        grid: &str,
+        reg_covar: f64,
+        no_saddle: &str
+    ) -> PyResult<Self> {
+        let (detector, grid, no_saddle) = (detector.to_string(), grid.to_string(), no_saddle.to_string());
+
+        OlrConfigWrapper { n_points, tolerance, extension, detector, grid, reg_covar, no_saddle }.checked()
+    }
+
+    #[setter]
+    fn set_n_points(&mut self, n_points: Option<usize>) -> PyResult<()> {
+        *self = OlrConfigWrapper { n_points, ..self.clone() }.checked()?;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_tolerance(&mut self, tolerance: f64) -> PyResult<()> {
+        *self = OlrConfigWrapper { tolerance, ..self.clone() }.checked()?;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_extension(&mut self, extension: usize) -> PyResult<()> {
+        *self = OlrConfigWrapper { extension, ..self.clone() }.checked()?;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_detector(&mut self, detector: String) -> PyResult<()> {
+        *self = OlrConfigWrapper { detector, ..self.clone() }.checked()?;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_grid(&mut self, grid: String) -> PyResult<()> {
+        *self = OlrConfigWrapper { grid, ..self.clone() }.checked()?;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_reg_covar(&mut self, reg_covar: f64) -> PyResult<()> {
+        *self = OlrConfigWrapper { reg_covar, ..self.clone() }.checked()?;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_no_saddle(&mut self, no_saddle: String) -> PyResult<()> {
+        *self = OlrConfigWrapper { no_saddle, ..self.clone() }.checked()?;
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "OlrConfig(n_points={}, tolerance={:?}, extension={}, detector='{}', grid='{}', reg_covar={:?}, no_saddle='{}')",
+            self.n_points.map_or("None".to_string(), |n| n.to_string()), self.tolerance, self.extension, self.detector,
+            self.grid, self.reg_covar, self.no_saddle
+        )
+    }
+}
+
+impl OlrConfigWrapper {
+    /// `self` if it gives a valid `OlrConfig`.
+    fn checked(self) -> PyResult<Self> {
+        self.applied_to(OlrConfig::default())?.validate()?;
+        Ok(self)
+    }
+
+    /// `config` with the options of `self` in place of its own.
+    fn applied_to(&self, config: OlrConfig) -> Result<OlrConfig, MoebiusError> {
+        let resolution = resolution(self.n_points, self.tolerance)?;
+
+        Ok(OlrConfig {
+            n_points: resolution.n_points,
+            resolution: resolution.resolution,
+            extension: self.extension,
+            detector: option("detector", &self.detector)?,
+            grid: option("grid", &self.grid)?,
+            reg_covar: self.reg_covar,
+            no_saddle: option("no_saddle", &self.no_saddle)?,
+            ..config
+        })
+    }
+}
+
+/// Mixture density with every covariance factorized once, for evaluating the same mixture at
+/// many points, e.g. for plotting. Takes `w`, `means` and `covs` as `olr`.
+///
+/// `n_threads` is resolved as in `olr`, `None` using the default of `set_num_threads`. Results do
+/// not depend on it.
+#[pyclass(name = "PdfEvaluator")]
+pub struct PdfEvaluatorWrapper {
+    inner: PdfEvaluator,
+}
+
+#[pymethods]
+impl PdfEvaluatorWrapper {
+    #[new]
+    #[pyo3(signature = (w, means, covs, *, n_threads = 1))]
+    fn new(w: Vec<f64>, means: &PyAny, covs: &PyAny, n_threads: Option<usize>) -> PyResult<Self> {
+        let (means, covs) = extract_params(means, covs, true)?;
+        let inner = PdfEvaluator::new(&w, &means, &covs)?
+            .with_threads(n_threads)?;
+
+        Ok(PdfEvaluatorWrapper { inner })
+    }
+
+    /// Density at the point `x`, a sequence of `d` floats.
+    fn eval(&self, x: Vec<f64>) -> PyResult<f64> {
+        check_dim(self.inner.dim(), x.len())?;
+
+        Ok(self.inner.eval(&Array1::from(x)))
+    }
+
+    /// Densities at every row of `points`, an `m x d` array, or a flat sequence of `m` points
+    /// for one-dimensional mixtures.
+    fn eval_batch<'py>(&self, py: Python<'py>, points: &PyAny) -> PyResult<&'py PyArray1<f64>> {
+        let points = extract_points(points, self.inner.dim())?;
+
+        Ok(py.allow_threads(|| self.inner.eval_batch(&points)).into_pyarray(py))
+    }
+}
+
+/// `m x dim` points given as a dense array or nested sequences, or as a flat sequence if `dim`
+/// is `1`.
+fn extract_points(points: &PyAny, dim: usize) -> PyResult<Array2<f64>> {
+    let points = if let Some((shape, values)) = buffer::extract_dense(points, "points")? {
+        match shape[..] {
+            [m, d] => Array2::from_shape_vec((m, d), values).unwrap(),
+            [m] if dim == 1 => Array2::from_shape_vec((m, 1), values).unwrap(),
+            _ => return Err(MoebiusError::Shape(format!("points must have 2 dimensions, got shape {:?}", shape)).into()),
+        }
+    } else if let Ok(nested) = points.extract::<Vec<Vec<f64>>>() {
+        vec_to_array2(nested)?
+    } else if let (1, Ok(flat)) = (dim, points.extract::<Vec<f64>>()) {
+        means_from_scalars(flat)
+    } else {
+        return Err(PyTypeError::new_err("points must be a sequence of sequences of floats"));
+    };
+    check_dim(dim, points.ncols())?;
+
+    Ok(points)
+}
+
+/// Pairwise OLR values. For one-dimensional mixtures `means` and `covs` may be flat lists of means
+/// and variances, i.e. `olr([0.5, 0.5], [5, 2], [0.5, 0.5])` is the same as
+/// `olr([0.5, 0.5], [[5], [2]], [[[0.5]], [[0.5]]])`. Either one may be flat on its own, but a
+/// flat one next to the other of `d > 1` dimensions raises a `ValueError` instead of being
+/// broadcast. Pass `promote_1d=False` to require the nested form. Low-rank covariances `W W^T + diag(psi)` of mixtures of factor analyzers are
+/// passed as `covs=[{"factors": W, "diag": psi}, ...]` with `d x q` factors `W`, `q < d`.
+///
+/// `reg_covar` is added to the diagonal of every covariance matrix and
+/// `symmetrize=True` replaces every covariance `C` with `(C + C.T) / 2`, which is only meant for
+/// matrices that are asymmetric by rounding errors.
+///
+/// Non-finite inputs raise a `ValueError` naming the entry, e.g. `covs[12][3][7] is NaN`. With
+/// `drop_nan=True`, components with NaN parameters are removed instead with a warning, and
+/// `components` of the result holds the original index of every remaining component. Infinite
+/// values are always rejected.
+///
+/// `strict=True` checks every covariance before computing any pair and raises a single
+/// `ValueError` listing all components that are not positive definite, and raises a `ValueError`
+/// for weights that do not sum to `1`. `auto_normalize=True` divides such weights, e.g. raw
+/// counts, by their sum instead and warns about it.
+///
+/// Mixtures of fewer than two components give an empty result, or raise a `ValueError` with
+/// `require_pairs=True`, e.g. when `drop_nan` removed all but one component.
+///
+/// `no_saddle` selects what happens to pairs without a saddle on the sampled line:
+/// `"assume_merged"` gives `1.0`, `"error"` raises a `ValueError` and `"extend_and_retry"`
+/// rescans the pair on longer lines before falling back to `1.0`.
+///
+/// `detector` finds the peaks and saddles of every profile: `"sign_change"` takes the sampled
+/// values at sign changes of the differences, `"parabolic"` refines them by interpolating a
+/// parabola through three points, which makes values less sensitive to the grid resolution.
+/// `extrapolate=True` returns the Richardson extrapolation of every pair from its grid of
+/// parabolic vertices and the coarser grids within it, see `PairDetail::extrapolated` in the Rust
+/// documentation, which is closer to the OLR of the continuous profile.
+///
+/// By default every pair is scanned with 128 steps between the means, doubled until two
+/// successive values differ by less than `tolerance`. `n_points=int` scans every pair with that
+/// many steps instead, like the Rust API does by default.
+///
+/// `pair_timeout` and `deadline` bound the time in seconds spent on a single pair and on the
+/// whole call. Pairs that run out of time give `NaN` with `on_timeout="continue"`, while
+/// `on_timeout="abort"` raises a `TimeoutError` naming the pair.
+///
+/// `n_threads` is the number of threads pairs are computed on: `None` uses the default set by
+/// `set_num_threads`, `0` all cores and `1` the calling thread only. The GIL is released during
+/// the computation.
+///
+/// `deterministic=True` makes every value bitwise reproducible from the inputs alone, see
+/// `OlrConfig::deterministic` in the Rust documentation: lines are scanned point by point, the
+/// tolerance refinement always runs up to its maximum of points, and `pair_timeout` and
+/// `deadline` raise a `ValueError`.
+///
+/// `config` is an `OlrConfig` whose options replace the keywords of the same name, e.g. to pass
+/// the same sampling to many calls.
+///
+/// `out` is a preallocated, writable and contiguous float64 array the values are written into
+/// instead of a new result, either condensed with `n * (n - 1) / 2` entries or an `n x n` matrix
+/// with ones on the diagonal, and is returned itself. It requires dense covariances and cannot be
+/// combined with `drop_nan=True`.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(
+    name = "olr",
+    signature = (
+        w, means, covs, *,
+        promote_1d = true, reg_covar = 0.0, symmetrize = false, drop_nan = false, strict = false,
+        auto_normalize = false, require_pairs = false, no_saddle = "assume_merged", detector = "sign_change", extrapolate = false,
+        n_points = None,
+        tolerance = DEFAULT_RESOLUTION_TOLERANCE, pair_timeout = None, deadline = None, on_timeout = "continue",
+        n_threads = None, deterministic = false, config = None, out = None
+    ),
+    text_signature = "(w, means, covs, *, promote_1d=True, reg_covar=0.0, symmetrize=False, drop_nan=False, strict=False, \
+        auto_normalize=False, require_pairs=False, no_saddle='assume_merged', detector='sign_change', extrapolate=False, \
+        n_points=None, tolerance=1e-06, pair_timeout=None, deadline=None, on_timeout='continue', n_threads=None, deterministic=False, config=None, out=None)"
+)]
+pub fn olr_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    promote_1d: bool,
+    reg_covar: f64,
+    symmetrize: bool,
+    drop_nan: bool,
+    strict: bool,
+    auto_normalize: bool,
+    require_pairs: bool,
+    no_saddle: &str,
+    detector: &str,
+    extrapolate: bool,
+    n_points: Option<usize>,
+    tolerance: f64,
+    pair_timeout: Option<f64>,
+    deadline: Option<f64>,
+    on_timeout: &str,
+    n_threads: Option<usize>,
+    deterministic: bool,
+    config: Option<OlrConfigWrapper>,
+    out: Option<&PyAny>
+) -> PyResult<PyObject> {
+    let keywords = OlrConfig {
+        reg_covar, symmetrize, strict, auto_normalize, require_pairs, no_saddle: option("no_saddle", no_saddle)?, detector: option("detector", detector)?,
+        pair_timeout: seconds("pair_timeout", pair_timeout)?, deadline: seconds("deadline", deadline)?,
+        on_timeout: option("on_timeout", on_timeout)?, n_threads, deterministic, extrapolate,
+        ..resolution(n_points, tolerance)?
+    };
+    let config = match config {
+        Some(config) => config.applied_to(keywords)?,
+        None => keywords,
+    };
+    if let Some(out) = out {
+        if drop_nan {
+            return Err(PyValueError::new_err("out cannot be combined with drop_nan, which changes the number of components"));
+        }
+        return olr_out(py, &w, means, covs, promote_1d, &config, out);
+    }
+    let (values, components, details) = compute_olr(py, w, means, covs, promote_1d, &config, drop_nan)?;
+
+    Ok(OlrResult { inner: OlrMatrix::new(components.len(), values)?, matrix: None, components, details }.into_py(py))
+}
+
+/// Writes the values of `olr` into `out`, checked as described there, and returns it.
+fn olr_out(py: Python<'_>, w: &[f64], means: &PyAny, covs: &PyAny, promote_1d: bool, config: &OlrConfig, out: &PyAny) -> PyResult<PyObject> {
+    let array = match out.downcast::<PyArrayDyn<f64>>() {
+        Ok(array) => array,
+        Err(_) => return Err(PyValueError::new_err(match out.getattr("dtype") {
+            Ok(dtype) => format!("out must have dtype float64, got {}", dtype),
+            Err(_) => format!("out must be a numpy array, got {}", type_name(out)),
+        })),
+    };
+    let n_comp = w.len();
+    let n_pairs = condensed_len_wrapper(n_comp)?;
+    let square = match array.shape() {
+        [len] if *len == n_pairs => false,
+        [rows, cols] if (*rows, *cols) == (n_comp, n_comp) => true,
+        shape => return Err(PyValueError::new_err(format!(
+            "out must have shape ({},) or ({}, {}) for {} components, got {:?}", n_pairs, n_comp, n_comp, n_comp, shape
+        ))),
+    };
+
+    // The inputs are copied before `out` is borrowed, so they may share its memory.
+    let (means, covs) = extract_params(means, covs, promote_1d)?;
+    check_shapes(n_comp, &means, &covs)?;
+    warn_small_variances(py, &means, &covs, config)?;
+    warn_normalized(py, w, config)?;
+
+    let mut guard = array.try_readwrite().map_err(|e| PyValueError::new_err(format!("out must be writable: {}", e)))?;
+    let slice = guard.as_slice_mut().map_err(|_| PyValueError::new_err("out must be contiguous"))?;
+    if square {
+        let values = py.allow_threads(|| olr_with_config(w, &means, &covs, config))?;
+        for (k, value) in values.into_iter().enumerate() {
+            let (i, j) = index_to_pair(k, n_comp);
+            slice[i * n_comp + j] = value;
+            slice[j * n_comp + i] = value;
+        }
+        (0..n_comp).for_each(|i| slice[i * n_comp + i] = 1.0);
+    } else {
+        py.allow_threads(|| olr_into(slice, w, &means, &covs, config))?;
+    }
+    drop(guard);
+
+    Ok(out.into_py(py))
+}
+
+/// Default config with a fixed grid of `n_points` steps, or refined to `tolerance` if `None`.
+fn resolution(n_points: Option<usize>, tolerance: f64) -> Result<OlrConfig, MoebiusError> {
+    if !(tolerance > 0.0 && tolerance.is_finite()) {
+        return Err(MoebiusError::InvalidConfig(format!("tolerance must be positive and finite, got {}", tolerance)));
+    }
+
+    Ok(match n_points {
+        Some(n_points) => OlrConfig { n_points, ..OlrConfig::default() },
+        None => OlrConfig { resolution: Resolution::auto(tolerance), ..OlrConfig::default() },
+    })
+}
+
+/// Converts a duration in seconds given as `name`.
+fn seconds(name: &str, value: Option<f64>) -> Result<Option<Duration>, MoebiusError> {
+    value
+        .map(|secs| Duration::try_from_secs_f64(secs)
+            .map_err(|_| MoebiusError::InvalidConfig(format!("{} must be a non-negative number of seconds, got {}", name, secs))))
+        .transpose()
+}
+
+/// Same as `olr`, returning the condensed values as a plain list.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(
+    name = "olr_list",
+    signature = (
+        w, means, covs, *,
+        promote_1d = true, reg_covar = 0.0, symmetrize = false, drop_nan = false, strict = false,
+        auto_normalize = false, require_pairs = false, no_saddle = "assume_merged", detector = "sign_change", extrapolate = false,
+        n_points = None,
+        tolerance = DEFAULT_RESOLUTION_TOLERANCE, pair_timeout = None, deadline = None, on_timeout = "continue",
+        n_threads = None, deterministic = false, config = None
+    ),
+    text_signature = "(w, means, covs, *, promote_1d=True, reg_covar=0.0, symmetrize=False, drop_nan=False, strict=False, \
+        auto_normalize=False, require_pairs=False, no_saddle='assume_merged', detector='sign_change', extrapolate=False, \
+        n_points=None, tolerance=1e-06, pair_timeout=None, deadline=None, on_timeout='continue', n_threads=None, deterministic=False, config=None)"
+)]
+pub fn olr_list_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    promote_1d: bool,
+    reg_covar: f64,
+    symmetrize: bool,
+    drop_nan: bool,
+    strict: bool,
+    auto_normalize: bool,
+    require_pairs: bool,
+    no_saddle: &str,
+    detector: &str,
+    extrapolate: bool,
+    n_points: Option<usize>,
+    tolerance: f64,
+    pair_timeout: Option<f64>,
+    deadline: Option<f64>,
+    on_timeout: &str,
+    n_threads: Option<usize>,
+    deterministic: bool,
+    config: Option<OlrConfigWrapper>
+) -> PyResult<Vec<f64>> {
+    let keywords = OlrConfig {
+        reg_covar, symmetrize, strict, auto_normalize, require_pairs, no_saddle: option("no_saddle", no_saddle)?, detector: option("detector", detector)?,
+        pair_timeout: seconds("pair_timeout", pair_timeout)?, deadline: seconds("deadline", deadline)?,
+        on_timeout: option("on_timeout", on_timeout)?, n_threads, deterministic, extrapolate,
+        ..resolution(n_points, tolerance)?
+    };
+    let config = match config {
+        Some(config) => config.applied_to(keywords)?,
+        None => keywords,
+    };
+
+    Ok(compute_olr(py, w, means, covs, promote_1d, &config, drop_nan)?.0)
+}
+
+/// Same as `olr` with default options, reusing the matrix stored at `path` if it was computed
+/// from bitwise identical inputs and writing it there otherwise.
+#[cfg(feature = "serde")]
+#[pyfunction]
+#[pyo3(name = "olr_matrix_cached", signature = (w, means, covs, path, *, promote_1d = true))]
+pub fn olr_matrix_cached_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    path: std::path::PathBuf,
+    promote_1d: bool
+) -> PyResult<OlrResult> {
+    let (means, covs) = extract_params(means, covs, promote_1d)?;
+    let inner = py.allow_threads(|| olr_matrix_cached(&w, &means, &covs, &path))?;
+
+    Ok(OlrResult { inner, matrix: None, components: (0..w.len()).collect(), details: None })
+}
+
+/// Pairs `(i, j, olr)` with `i < j` and an OLR above `cutoff`, computed without the dense
+/// matrix. Values are those of `olr` with default options.
+#[pyfunction]
+#[pyo3(name = "olr_sparse", signature = (w, means, covs, cutoff, *, promote_1d = true))]
+pub fn olr_sparse_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    cutoff: f64,
+    promote_1d: bool
+) -> PyResult<Vec<(usize, usize, f64)>> {
+    let (means, covs) = extract_params(means, covs, promote_1d)?;
+
+    Ok(py.allow_threads(|| olr_sparse(&w, &means, &covs, cutoff))?)
+}
+
+/// OLR values of `olr` and `olr_list` with the original indices of the components they refer to.
+fn compute_olr(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    promote_1d: bool,
+    config: &OlrConfig,
+    drop_nan: bool
+) -> PyResult<(Vec<f64>, Vec<usize>, Option<OlrDetails>)> {
+    let means_array = extract_means(means, promote_1d)?;
+    if let Some(covs) = extract_low_rank(covs)? {
+        warn_normalized(py, &w, config)?;
+        let values = py.allow_threads(|| olr_with_covariances(&w, &means_array, &covs, config))?;
+
+        return Ok((values, (0..w.len()).collect(), None));
+    }
+    let covs_array = extract_covs(covs, promote_1d)?;
+    if promote_1d {
+        check_promoted(means, covs, &means_array, covs_array.dim().1)?;
+    }
+    let (means, covs) = (means_array, covs_array);
+    check_shapes(w.len(), &means, &covs)?;
+    warn_small_variances(py, &means, &covs, config)?;
+
+    if !drop_nan {
+        warn_normalized(py, &w, config)?;
+        let details = py.allow_threads(|| olr_detailed(&w, &means, &covs, config))?;
+
+        return Ok((details.pairs.iter().map(plain_value).collect(), (0..w.len()).collect(), Some(details)));
+    }
+
+    let cleaned = drop_nan_components(&w, &means, &covs)?;
+    if !cleaned.dropped.is_empty() {
+        PyErr::warn(
+            py,
+            py.get_type::<PyUserWarning>(),
+            &format!(
+                "dropped components {:?} with NaN parameters, remaining components are {:?} of the input",
+                cleaned.dropped, cleaned.kept
+            ),
+            1
+        )?;
+    }
+    warn_normalized(py, &cleaned.w, config)?;
+    let details = py.allow_threads(|| olr_detailed(&cleaned.w, &cleaned.means, &cleaned.covs, config))?;
+
+    Ok((details.pairs.iter().map(plain_value).collect(), cleaned.kept, Some(details)))
+}
+
+/// Warns about the variances below `config.variance_floor`, see `small_variances`.
+fn warn_small_variances(py: Python<'_>, means: &Array2<f64>, covs: &Array3<f64>, config: &OlrConfig) -> PyResult<()> {
+    let small = small_variances(means, covs, config.variance_floor);
+    if !small.is_empty() {
+        PyErr::warn(
+            py,
+            py.get_type::<PyUserWarning>(),
+            &format!(
+                "variances {:?} (component, dimension) are below {:e} relative to the squared magnitude of the means",
+                small, config.variance_floor
+            ),
+            1
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Warns if `config.auto_normalize` divides the weights `w` by their sum.
+fn warn_normalized(py: Python<'_>, w: &[f64], config: &OlrConfig) -> PyResult<()> {
+    if let Some(total) = normalized_sum(w, config) {
+        PyErr::warn(
+            py,
+            py.get_type::<PyUserWarning>(),
+            &format!("weights sum to {}, they were divided by their sum", total),
+            1
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Block-diagonal covariance of a single component: `(indices, matrix)` for every block.
+type PyBlocks = Vec<(Vec<usize>, Vec<Vec<f64>>)>;
+
+/// Same as `olr` for block-diagonal covariances. `covs[k]` lists the blocks of component `k` as
+/// `(indices, matrix)` pairs, where the index groups partition the dimensions and `matrix` is the
+/// covariance of the dimensions in `indices`. Every block is factorized separately, so high
+/// dimensional mixtures of small blocks stay cheap.
+#[pyfunction]
+#[pyo3(
+    name = "olr_block_diagonal",
+    signature = (w, means, covs, *, reg_covar = 0.0, symmetrize = false, n_threads = None)
+)]
+pub fn olr_block_diagonal_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: Vec<PyBlocks>,
+    reg_covar: f64,
+    symmetrize: bool,
+    n_threads: Option<usize>
+) -> PyResult<OlrResult> {
+    let config = OlrConfig { reg_covar, symmetrize, n_threads, ..OlrConfig::default() };
+    let means = extract_means(means, false)?;
+    let covs = covs.into_iter()
+        .map(|blocks| {
+            let blocks = blocks.into_iter()
+                .map(|(indices, matrix)| Ok((indices, vec_to_array2(matrix)?)))
+                .collect::<Result<_, MoebiusError>>()?;
+
+            Ok(Covariance::BlockDiagonal(blocks))
+        })
+        .collect::<Result<Vec<_>, MoebiusError>>()?;
+
+    let n_comp = w.len();
+    let values = py.allow_threads(|| olr_with_covariances(&w, &means, &covs, &config))?;
+
+    Ok(OlrResult { inner: OlrMatrix::new(n_comp, values)?, matrix: None, components: (0..n_comp).collect(), details: None })
+}
+
+type PyModel<'py> = (Vec<f64>, &'py PyAny, &'py PyAny);
+
+fn extract_mixture((w, means, covs): PyModel<'_>) -> PyResult<GaussianMixture> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    Ok(GaussianMixture::new(w, means, covs)?)
+}
+
+/// OLR between the components of two mixtures given as `(w, means, covs)`, computed on the shared
+/// dimensions only: `dims_a[k]` of `model_a` and `dims_b[k]` of `model_b` are the same feature.
+/// The result has one row per component of `model_a` and one column per component of `model_b`.
+#[pyfunction]
+#[pyo3(name = "olr_cross_subspace")]
+pub fn olr_cross_subspace_wrapper(
+    model_a: PyModel<'_>,
+    model_b: PyModel<'_>,
+    dims_a: Vec<usize>,
+    dims_b: Vec<usize>
+) -> PyResult<Vec<Vec<f64>>> {
+    let values = olr_cross_subspace(&extract_mixture(model_a)?, &extract_mixture(model_b)?, &dims_a, &dims_b)?;
+
+    Ok(values.outer_iter().map(|row| row.to_vec()).collect())
+}
+
+/// Same as `olr` for a mixture of multivariate Student-t components with locations `means`,
+/// scale matrices `scales` and degrees of freedom `dofs`.
+#[pyfunction]
+#[pyo3(name = "olr_t")]
+pub fn olr_t_wrapper(py: Python<'_>, w: Vec<f64>, means: &PyAny, scales: &PyAny, dofs: Vec<f64>) -> PyResult<OlrResult> {
+    let means = extract_means(means, true)?;
+    let scales = extract_covs(scales, true)?;
+    let n_comp = w.len();
+    let values = py.allow_threads(|| olr_t(&w, &means, &scales, &dofs, &OlrConfig::default()))?;
+
+    Ok(OlrResult { inner: OlrMatrix::new(n_comp, values)?, matrix: None, components: (0..n_comp).collect(), details: None })
+}
+
+/// Same as `olr_list` for arbitrary component families: `logpdf_callbacks[k]` takes a point as a
+/// 1D float64 numpy array and returns the log-density of component `k` there. `means` only
+/// defines the lines that are scanned and should hold the modes of the components.
+///
+/// Every pair calls both callbacks at all `n_points + 3 * extension + 1` points of its line while
+/// holding the GIL, so this is orders of magnitude slower than the native densities. Exceptions
+/// raised by a callback are re-raised with the component, pair and grid index attached and the
+/// original exception as `__cause__`.
+#[pyfunction]
+#[pyo3(
+    name = "olr_custom",
+    signature = (w, means, logpdf_callbacks, n_points = DEFAULT_N_POINTS, extension = DEFAULT_EXTENSION)
+)]
+pub fn olr_custom_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    logpdf_callbacks: Vec<&PyAny>,
+    n_points: usize,
+    extension: usize
+) -> PyResult<Vec<f64>> {
+    let means = extract_means(means, true)?;
+    let config = OlrConfig { n_points, extension, ..OlrConfig::default() };
+    config.validate()?;
+    if means.nrows() != w.len() || logpdf_callbacks.len() != w.len() {
+        return Err(MoebiusError::Shape(format!(
+            "{} weights, {} means and {} callbacks given", w.len(), means.nrows(), logpdf_callbacks.len()
+        )).into());
+    }
+    check_finite(&w, &means, &Array3::zeros((0, 0, 0)))?;
+
+    let n_comp = w.len();
+    let mut values = Vec::with_capacity(condensed_len(n_comp));
+    for i in 0..n_comp {
+        for j in (i + 1)..n_comp {
+            let (ln_w1, ln_w2) = pair_log_weights(w[i], w[j]);
+            let mut profile = Vec::with_capacity(config.grid_len());
+            let mut error = None;
+            scan_line(means.row(i), means.row(j), &config, |point| {
+                if error.is_some() {
+                    return;
+                }
+                let index = profile.len();
+                let ln_pdf = |k: usize| call_logpdf(py, logpdf_callbacks[k], point)
+                    .map_err(|e| at_grid_point(py, e, k, (i, j), index));
+                match ln_pdf(i).and_then(|a| Ok((a, ln_pdf(j)?))) {
+                    Ok((a, b)) => profile.push(ln_add(ln_w1 + a, ln_w2 + b)),
+                    Err(e) => error = Some(e),
+                }
+            });
+            if let Some(e) = error {
+                return Err(e);
+            }
+
+            values.push(profile_extrema(&profile, &config.detector).value);
+        }
+    }
+
+    Ok(values)
+}
+
+fn call_logpdf(py: Python<'_>, callback: &PyAny, point: &Array1<f64>) -> PyResult<f64> {
+    let value: f64 = callback.call1((point.to_pyarray(py),))?.extract()?;
+    if value.is_nan() {
+        return Err(PyValueError::new_err("log-density is NaN"));
+    }
+
+    Ok(value)
+}
+
+/// `e` re-raised as the same exception type, or as `RuntimeError` if that type cannot be built
+/// from a message, with the location of the failed call prepended and `e` as the cause.
+fn at_grid_point(py: Python<'_>, e: PyErr, component: usize, pair: (usize, usize), index: usize) -> PyErr {
+    let message = format!(
+        "logpdf_callbacks[{}] failed at grid index {} of pair {:?}: {}", component, index, pair, e.value(py)
+    );
+    let wrapped = e.get_type(py).call1((message.clone(),))
+        .map(PyErr::from_value)
+        .unwrap_or_else(|_| PyRuntimeError::new_err(message));
+    wrapped.set_cause(py, Some(e));
+
+    wrapped
+}
+
+/// Same as `olr_list`, additionally returning a dict with `total_time` and `pair_time_mean` in
+/// seconds, `n_pairs` and `pdf_evaluations`.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(
+    name = "olr_timed",
+    signature = (
+        w, means, covs, *,
+        n_points = DEFAULT_N_POINTS, extension = DEFAULT_EXTENSION, reg_covar = 0.0, symmetrize = false, n_threads = None
+    )
+)]
+pub fn olr_timed_wrapper<'py>(
+    py: Python<'py>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    n_points: usize,
+    extension: usize,
+    reg_covar: f64,
+    symmetrize: bool,
+    n_threads: Option<usize>
+) -> PyResult<(Vec<f64>, &'py PyDict)> {
+    let config = OlrConfig { n_points, extension, reg_covar, symmetrize, n_threads, ..OlrConfig::default() };
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    let (values, timing) = py.allow_threads(|| olr_timed(&w, &means, &covs, &config))?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("total_time", timing.total.as_secs_f64())?;
+    dict.set_item("pair_time_mean", timing.pair_mean.as_secs_f64())?;
+    dict.set_item("n_pairs", timing.n_pairs)?;
+    dict.set_item("pdf_evaluations", timing.pdf_evaluations)?;
+
+    Ok((values, dict))
+}
+
+/// Per-pair results as a dict of numpy columns, e.g. for `pandas.DataFrame(olr_records(...))`:
+/// `i` and `j` (int64) and `olr` (float64), in the order of `olr_list`. Unless `minimal` is set,
+/// also `ln_peak`, `ln_saddle` and `error_estimate` (float64, NaN for pairs without a saddle),
+/// and `extreme_range` and `timed_out` (bool).
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(
+    name = "olr_records",
+    signature = (
+        w, means, covs, *,
+        minimal = false, n_points = DEFAULT_N_POINTS, extension = DEFAULT_EXTENSION, reg_covar = 0.0, symmetrize = false,
+        n_threads = None
+    )
+)]
+pub fn olr_records_wrapper<'py>(
+    py: Python<'py>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    minimal: bool,
+    n_points: usize,
+    extension: usize,
+    reg_covar: f64,
+    symmetrize: bool,
+    n_threads: Option<usize>
+) -> PyResult<&'py PyDict> {
+    let config = OlrConfig { n_points, extension, reg_covar, symmetrize, n_threads, ..OlrConfig::default() };
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    let details = py.allow_threads(|| olr_detailed(&w, &means, &covs, &config))?;
+    let pairs = &details.pairs;
+    let (i, j): (Vec<i64>, Vec<i64>) = (0..pairs.len())
+        .map(|idx| {
+            let (i, j) = index_to_pair(idx, w.len());
+            (i as i64, j as i64)
+        })
+        .unzip();
+
+    let dict = PyDict::new(py);
+    dict.set_item("i", i.into_pyarray(py))?;
+    dict.set_item("j", j.into_pyarray(py))?;
+    dict.set_item("olr", pairs.iter().map(|p| p.value).collect::<Vec<_>>().into_pyarray(py))?;
+    if !minimal {
+        let column = |f: fn(&PairDetail) -> Option<f64>| pairs.iter().map(|p| f(p).unwrap_or(f64::NAN)).collect::<Vec<_>>();
+        dict.set_item("ln_peak", column(|p| p.ln_peak).into_pyarray(py))?;
+        dict.set_item("ln_saddle", column(|p| p.ln_saddle).into_pyarray(py))?;
+        dict.set_item("error_estimate", column(|p| p.error_estimate).into_pyarray(py))?;
+        dict.set_item("extreme_range", pairs.iter().map(|p| p.extreme_range).collect::<Vec<_>>().into_pyarray(py))?;
+        dict.set_item("timed_out", pairs.iter().map(|p| p.status == PairStatus::TimedOut).collect::<Vec<_>>().into_pyarray(py))?;
+    }
+
+    Ok(dict)
+}
+
+/// Sets the number of threads used by calls that do not pass `n_threads`. `0` means all cores.
+#[pyfunction]
+#[pyo3(name = "set_num_threads")]
+pub fn set_num_threads_wrapper(n: usize) {
+    set_num_threads(n);
+}
+
+/// Default number of threads as set by `set_num_threads`, `0` meaning all cores.
+#[pyfunction]
+#[pyo3(name = "get_num_threads")]
+pub fn get_num_threads_wrapper() -> usize {
+    num_threads()
+}
+
+/// Cargo features the extension was compiled with, e.g. `["cli", "serde"]`.
+#[pyfunction]
+#[pyo3(name = "features")]
+pub fn features_wrapper() -> Vec<&'static str> {
+    features()
+}
+
+/// What the extension supports, as a dict with the keys `version`, `features`, `parallel`,
+/// `methods`, `detectors`, `covariance_inputs` and `simd`, see `Capabilities`. The keys are
+/// the same in every build; `simd` is `None` for builds without SIMD.
+#[pyfunction]
+#[pyo3(name = "capabilities")]
+pub fn capabilities_wrapper(py: Python<'_>) -> PyResult<&PyDict> {
+    let capabilities = capabilities();
+    let dict = PyDict::new(py);
+    dict.set_item("version", capabilities.version)?;
+    dict.set_item("features", capabilities.features)?;
+    dict.set_item("parallel", capabilities.parallel)?;
+    dict.set_item("methods", capabilities.methods)?;
+    dict.set_item("detectors", capabilities.detectors)?;
+    dict.set_item("covariance_inputs", capabilities.covariance_inputs)?;
+    dict.set_item("simd", capabilities.simd)?;
+
+    Ok(dict)
+}
+
+/// Same as `olr_list` with the extrema of every pair refined to `tol` instead of read off the
+/// sampling grid. Roughly ten times slower than `olr_list`.
+#[pyfunction]
+#[pyo3(name = "olr_exact", signature = (w, means, covs, tol = 1e-12))]
+pub fn olr_exact_wrapper(py: Python<'_>, w: Vec<f64>, means: &PyAny, covs: &PyAny, tol: f64) -> PyResult<Vec<f64>> {
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    Ok(py.allow_threads(|| olr_exact(&w, &means, &covs, tol))?)
+}
+
+/// Monte Carlo estimate of the overlapping coefficient `∫ min(p_i, p_j) dx` of every pair, in
+/// the order of `olr_list`. `scheme` is `"pseudo_random"`, `"antithetic"` or `"halton"`, the
+/// latter two giving estimates of lower variance for the same `n_samples`.
+///
+/// The same `seed` gives identical estimates for any `n_threads`; `None` seeds from the
+/// operating system's entropy source.
+#[pyfunction]
+#[pyo3(name = "overlap_mc", signature = (means, covs, n_samples = 10000, scheme = "halton", seed = None, n_threads = None))]
+pub fn overlap_mc_wrapper(
+    py: Python<'_>,
+    means: &PyAny,
+    covs: &PyAny,
+    n_samples: usize,
+    scheme: &str,
+    seed: Option<u64>,
+    n_threads: Option<usize>
+) -> PyResult<Vec<f64>> {
+    let scheme = option("scheme", scheme)?;
+    let (means, covs) = extract_params(means, covs, true)?;
+    let rng = Rng::new(seed);
+
+    Ok(py.allow_threads(|| overlap_mc(&means, &covs, n_samples, scheme, &rng, n_threads))?)
+}
+
+/// Same as `olr_list`, with every value keyed by the labels of its pair: `[((a, b), value), ...]`.
+#[pyfunction]
+#[pyo3(name = "olr_labeled")]
+pub fn olr_labeled_wrapper(w: Vec<f64>, means: &PyAny, covs: &PyAny, labels: Vec<String>) -> PyResult<LabeledOlr> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    Ok(
+        olr_labeled(
+            w,
+            means,
+            covs,
+            labels
+        )?
+    )
+}
+
+/// Same as `olr_list` with `points[k]` steps between the means for pair `k` in the order of
+/// `olr_list`, e.g. to rescan the pairs a coarse first pass found difficult.
+#[pyfunction]
+#[pyo3(name = "olr_with_points", signature = (w, means, covs, points, *, detector = "sign_change", n_threads = None))]
+pub fn olr_with_points_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    points: Vec<usize>,
+    detector: &str,
+    n_threads: Option<usize>
+) -> PyResult<Vec<f64>> {
+    let config = OlrConfig { detector: option("detector", detector)?, n_threads, ..OlrConfig::default() };
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    Ok(py.allow_threads(|| olr_with_points(&w, &means, &covs, &config, &points))?)
+}
+
+/// OLR of the sub-mixture of the selected `components`, as `[((i, j), value), ...]` with `i`
+/// and `j` indices of the full mixture, in the order of slicing the inputs with `components`.
+/// With `renormalize`, the selected weights are divided by their sum.
+#[pyfunction]
+#[pyo3(name = "olr_masked", signature = (w, means, covs, components, *, renormalize = false, n_threads = None))]
+pub fn olr_masked_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    components: Vec<usize>,
+    renormalize: bool,
+    n_threads: Option<usize>
+) -> PyResult<IndexedOlr> {
+    let config = OlrConfig { n_threads, ..OlrConfig::default() };
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    Ok(py.allow_threads(|| olr_masked(&w, &means, &covs, &components, renormalize, &config))?)
+}
+
+/// OLR of every pair through the density of the whole mixture, in the order of `olr_list`. Pairs
+/// fall back to their pairwise OLR with a `UserWarning` if the other components cannot be
+/// evaluated, and are `nan` if one of their own components is invalid.
+#[pyfunction]
+#[pyo3(name = "olr_multi", signature = (w, means, covs, *, n_threads = None))]
+pub fn olr_multi_wrapper(py: Python<'_>, w: Vec<f64>, means: &PyAny, covs: &PyAny, n_threads: Option<usize>) -> PyResult<Vec<f64>> {
+    let config = OlrConfig { n_threads, ..OlrConfig::default() };
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    let multi = py.allow_threads(|| olr_multi(&w, &means, &covs, &config))?;
+    for (k, status) in multi.status.iter().enumerate() {
+        let (pair, message) = match status {
+            MultiStatus::Mixture => continue,
+            MultiStatus::Pairwise(reason) => (index_to_pair(k, multi.n_components), format!("computed pairwise: {}", reason)),
+            MultiStatus::Failed(reason) => (index_to_pair(k, multi.n_components), format!("failed: {}", reason)),
+        };
+        PyErr::warn(py, py.get_type::<PyUserWarning>(), &format!("pair {:?} {}", pair, message), 1)?;
+    }
+
+    Ok(multi.values)
+}
+
+/// OLR of every pair in the order of `olr_list`, scanning only the part of the line between the
+/// means inside the box `[lower, upper]`, with one bound per dimension. A component whose mode
+/// lies outside the box forms no peak, so such pairs are merged within it.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(
+    name = "olr_bounded",
+    signature = (
+        w, means, covs, lower, upper, *,
+        n_points = DEFAULT_N_POINTS, extension = DEFAULT_EXTENSION, reg_covar = 0.0, symmetrize = false, n_threads = None
+    )
+)]
+pub fn olr_bounded_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    lower: Vec<f64>,
+    upper: Vec<f64>,
+    n_points: usize,
+    extension: usize,
+    reg_covar: f64,
+    symmetrize: bool,
+    n_threads: Option<usize>
+) -> PyResult<Vec<f64>> {
+    let config = OlrConfig { n_points, extension, reg_covar, symmetrize, n_threads, ..OlrConfig::default() };
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    Ok(py.allow_threads(|| olr_bounded(&w, &means, &covs, &lower, &upper, &config))?)
+}
+
+/// Per-dimension OLR of every pair, `[[olr_axis_0, olr_axis_1, ...], ...]` in the order of
+/// `olr_list`. Exact only for diagonal covariances, since off-diagonal entries are ignored.
+#[pyfunction]
+#[pyo3(name = "olr_per_axis")]
+pub fn olr_per_axis_wrapper(w: Vec<f64>, means: &PyAny, covs: &PyAny) -> PyResult<Vec<Vec<f64>>> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    Ok(
+        olr_per_axis(
+            w,
+            means,
+            covs
+        )?
+    )
+}
+
+/// `olr_list` of the mixture with all covariances multiplied by each factor in `scales`, one list
+/// per factor.
+#[pyfunction]
+#[pyo3(name = "olr_scan_scale")]
+pub fn olr_scan_scale_wrapper(py: Python<'_>, w: Vec<f64>, means: &PyAny, covs: &PyAny, scales: Vec<f64>) -> PyResult<Vec<Vec<f64>>> {
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    Ok(py.allow_threads(|| olr_scan_scale(w, means, covs, scales))?)
+}
+
+#[pyfunction()]
+#[pyo3(name = "order_by_overlap")]
+pub fn order_by_overlap_wrapper(w: Vec<f64>, means: &PyAny, covs: &PyAny) -> PyResult<Vec<usize>> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    Ok(
+        order_by_overlap(
+            w,
+            means,
+            covs
+        )?
+    )
+}
+
+/// Same as `olr_list` with the covariances flattened row-major to `n x (dim * dim)`, e.g.
+/// `covs.reshape(n, -1)`.
+#[pyfunction]
+#[pyo3(name = "olr_flat_covs")]
+pub fn olr_flat_covs_wrapper(py: Python<'_>, w: Vec<f64>, means: &PyAny, flat_covs: &PyAny, dim: usize) -> PyResult<Vec<f64>> {
+    let means = extract_means(means, false)?;
+    let flat_covs = extract_array(flat_covs, "flat_covs", 2)?.into_dimensionality::<Ix2>().unwrap();
+
+    Ok(py.allow_threads(|| olr_flat_covs(&w, &means, &flat_covs, dim))?)
+}
+
+/// Same as `olr_list` with `n_points=DEFAULT_N_POINTS` and the upper triangle of every
+/// covariance packed into a row of `packed_covs`, `n x d (d + 1) / 2` as by `pack_covariances`.
+/// Covariances are unpacked one component at a time.
+#[pyfunction]
+#[pyo3(name = "olr_packed", signature = (w, means, packed_covs, *, reg_covar = 0.0, n_threads = None))]
+pub fn olr_packed_wrapper(
+    py: Python<'_>,
+    w: Vec<f64>,
+    means: &PyAny,
+    packed_covs: &PyAny,
+    reg_covar: f64,
+    n_threads: Option<usize>
+) -> PyResult<Vec<f64>> {
+    let config = OlrConfig { reg_covar, n_threads, ..OlrConfig::default() };
+    let means = extract_means(means, false)?;
+    let packed_covs = extract_array(packed_covs, "packed_covs", 2)?.into_dimensionality::<Ix2>().unwrap();
+
+    Ok(py.allow_threads(|| olr_packed(&w, &means, &packed_covs, &config))?)
+}
+
+/// Upper triangles of the `n x d x d` covariances `covs` as an `n x d (d + 1) / 2` numpy array,
+/// row by row as `cov[np.triu_indices(d)]`.
+#[pyfunction]
+#[pyo3(name = "pack_covariances")]
+pub fn pack_covariances_wrapper<'py>(py: Python<'py>, covs: &PyAny) -> PyResult<&'py PyArray2<f64>> {
+    Ok(pack_covariances(&extract_covs(covs, false)?).into_pyarray(py))
+}
+
+/// Symmetric `n x d x d` covariances of packed upper triangles, inverse of `pack_covariances`.
+#[pyfunction]
+#[pyo3(name = "unpack_covariances")]
+pub fn unpack_covariances_wrapper<'py>(py: Python<'py>, packed_covs: &PyAny) -> PyResult<&'py PyArray3<f64>> {
+    let packed_covs = extract_array(packed_covs, "packed_covs", 2)?.into_dimensionality::<Ix2>().unwrap();
+
+    Ok(unpack_covariances(&packed_covs)?.into_pyarray(py))
+}
+
+/// Conditioning of the covariances as a dict with two lists of dicts: `components`, with the
+/// `condition_number`, `min_eigenvalue` and `needs_jitter` of every covariance, and `pairs`, with
+/// the `pair` and the `scale_ratio` of the widths of its components along the line between the
+/// means (`None` for coincident means).
+#[pyfunction]
+#[pyo3(name = "diagnose")]
+pub fn diagnose_wrapper<'py>(py: Python<'py>, w: Vec<f64>, means: &PyAny, covs: &PyAny) -> PyResult<&'py PyDict> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    let diagnostics = diagnose(&w, &means, &covs)?;
+
+    let components = PyList::empty(py);
+    for component in &diagnostics.components {
+        let dict = PyDict::new(py);
+        dict.set_item("condition_number", component.condition_number)?;
+        dict.set_item("min_eigenvalue", component.min_eigenvalue)?;
+        dict.set_item("needs_jitter", component.needs_jitter)?;
+        components.append(dict)?;
+    }
+    let pairs = PyList::empty(py);
+    for pair in &diagnostics.pairs {
+        let dict = PyDict::new(py);
+        dict.set_item("pair", pair.pair)?;
+        dict.set_item("scale_ratio", pair.scale_ratio)?;
+        pairs.append(dict)?;
+    }
+
+    let dict = PyDict::new(py);
+    dict.set_item("components", components)?;
+    dict.set_item("pairs", pairs)?;
+
+    Ok(dict)
+}
+
+/// Whether every pair, in the order of `olr_list`, is bimodal, i.e. has an OLR below `1`. Faster
+/// than `olr`, since the scan of a pair stops at its second peak.
+#[pyfunction]
+#[pyo3(name = "bimodality")]
+pub fn bimodality_wrapper(py: Python<'_>, w: Vec<f64>, means: &PyAny, covs: &PyAny) -> PyResult<Vec<bool>> {
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    Ok(py.allow_threads(|| bimodality(&w, &means, &covs))?)
+}
+
+/// Overlap of the whole mixture as a single number in `[0, 1]`. `agg` is `"max"`, `"mean"` or
+/// `"weighted_mean"`, which weights pair `(i, j)` by `w[i] * w[j]`. `pair_weight` weights the
+/// pairs of `"max"` and `"mean"` by `1` (`"uniform"`), `w[i] * w[j]` (`"weight_product"`) or
+/// `min(w[i], w[j])` (`"min_weight"`).
+#[pyfunction]
+#[pyo3(name = "separability", signature = (w, means, covs, agg = "weighted_mean", pair_weight = "uniform"))]
+pub fn separability_wrapper(w: Vec<f64>, means: &PyAny, covs: &PyAny, agg: &str, pair_weight: &str) -> PyResult<f64> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    Ok(
+        separability_with(
+            w,
+            means,
+            covs,
+            option("agg", agg)?,
+            option("pair_weight", pair_weight)?
+        )?
+    )
+}
+
+/// `(threshold, count, groups)` for every threshold, where `count` is the number of groups left
+/// after merging all pairs with OLR above the threshold and `groups` the group of every
+/// component. `thresholds` defaults to `0.1, 0.2, ..., 0.9`.
+#[pyfunction]
+#[pyo3(name = "effective_components", signature = (w, means, covs, thresholds = None))]
+pub fn effective_components_wrapper(
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    thresholds: Option<Vec<f64>>
+) -> PyResult<Vec<(f64, usize, Vec<usize>)>> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    let thresholds = thresholds.unwrap_or_else(|| EFFECTIVE_THRESHOLDS.to_vec());
+    let effective = effective_components(w, means, covs, &thresholds)?;
+
+    Ok(effective.into_iter().map(|e| (e.threshold, e.count, e.groups)).collect())
+}
+
+/// Mahalanobis distance between the means of every pair, in the order of `olr_list`. `mode`
+/// selects the covariance: `"first"`, `"second"`, `"pooled"` for their average or `"symmetric"`
+/// for the average of both directions.
+#[pyfunction]
+#[pyo3(name = "mahalanobis_pairs", signature = (means, covs, mode = "pooled"))]
+pub fn mahalanobis_pairs_wrapper(means: &PyAny, covs: &PyAny, mode: &str) -> PyResult<Vec<f64>> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    Ok(mahalanobis_pairs(&means, &covs, option("mode", mode)?)?)
+}
+
+/// Euclidean distance between the means of every pair, in the order of `olr_list`.
+#[pyfunction]
+#[pyo3(name = "euclidean_pairs")]
+pub fn euclidean_pairs_wrapper(means: &PyAny) -> PyResult<Vec<f64>> {
+    Ok(euclidean_pairs(&extract_means(means, true)?))
+}
+
+/// Checks shapes, finiteness, weight normalization, symmetry and positive definiteness at once
+/// and raises a single `ValueError` listing every problem found.
+#[pyfunction]
+#[pyo3(name = "validate_mixture")]
+pub fn validate_mixture_wrapper(w: Vec<f64>, means: &PyAny, covs: &PyAny) -> PyResult<()> {
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    validate_mixture(&w, &means, &covs).map_err(|errors| {
+        let lines: Vec<String> = errors.iter().map(|e| format!("  - {}", e)).collect();
+
+        PyValueError::new_err(format!("mixture has {} problem(s):\n{}", errors.len(), lines.join("\n")))
+    })
+}
+
+/// Pairs `(i, j)` of components whose means and covariances differ by at most `tol` in every
+/// entry.
+#[pyfunction]
+#[pyo3(name = "find_duplicates", signature = (means, covs, tol = 1e-6))]
+pub fn find_duplicates_wrapper(means: &PyAny, covs: &PyAny, tol: f64) -> PyResult<Vec<(usize, usize)>> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    Ok(find_duplicates(&means, &covs, tol)?)
+}
+
+type NestedParams = (Vec<f64>, Vec<Vec<f64>>, Vec<Vec<Vec<f64>>>);
+
+fn params_to_nested((w, means, covs): MixtureParams) -> NestedParams {
+    (
+        w,
+        means.outer_iter().map(|row| row.to_vec()).collect(),
+        covs.outer_iter().map(|cov| cov.outer_iter().map(|row| row.to_vec()).collect()).collect()
+    )
+}
+
+#[pyfunction()]
+#[pyo3(name = "apply_permutation")]
+pub fn apply_permutation_wrapper(
+    perm: Vec<usize>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny
+) -> PyResult<NestedParams> {
+    let (means, covs) = extract_params(means, covs, true)?;
+    let params = apply_permutation(
+        &perm,
+        &w,
+        &means,
+        &covs
+    )?;
+
+    Ok(params_to_nested(params))
+}
+
+/// OLR of two identical copies of the component `(mean, cov)`, expected to be `1.0`.
+#[pyfunction()]
+#[pyo3(name = "self_overlap_check")]
+pub fn self_overlap_check_wrapper(mean: Vec<f64>, cov: Vec<Vec<f64>>) -> PyResult<f64> {
+    let cov = covs_from_nested(vec![cov])?;
+
+    Ok(self_overlap_check(&Array1::from(mean), &cov.index_axis(Axis(0), 0).to_owned())?)
+}
+
+/// Number of pairs of `n` components, i.e. the length of the condensed values of `olr`.
+#[pyfunction]
+#[pyo3(name = "condensed_len")]
+pub fn condensed_len_wrapper(n: usize) -> PyResult<usize> {
+    checked_condensed_len(n).ok_or_else(|| PyValueError::new_err(format!("{} components are too many", n)))
+}
+
+/// Position of the pair `(i, j)` of `n` components in the condensed values of `olr`, which are
+/// ordered as `for i in range(n): for j in range(i + 1, n)`. The order of `i` and `j` does not
+/// matter. Raises `IndexError` if `i == j` or either index is not below `n`.
+#[pyfunction]
+#[pyo3(name = "pair_to_index")]
+pub fn pair_to_index_wrapper(i: usize, j: usize, n: usize) -> PyResult<usize> {
+    if i == j {
+        return Err(PyIndexError::new_err(format!("a pair needs two different components, got ({}, {})", i, j)));
+    }
+    if i >= n || j >= n {
+        return Err(PyIndexError::new_err(format!("pair ({}, {}) is out of range for {} components", i, j, n)));
+    }
+
+    Ok(pair_to_index(i, j, n))
+}
+
+/// Pair `(i, j)`, `i < j`, at position `k` of the condensed values of `olr` for `n` components.
+/// Raises `IndexError` if `k` is not below `condensed_len(n)`.
+#[pyfunction]
+#[pyo3(name = "index_to_pair")]
+pub fn index_to_pair_wrapper(k: usize, n: usize) -> PyResult<(usize, usize)> {
+    let len = condensed_len_wrapper(n)?;
+    if k >= len {
+        return Err(PyIndexError::new_err(format!("index {} is out of range for {} pairs", k, len)));
+    }
+
+    Ok(index_to_pair(k, n))
+}
+
+/// Fraction of the lighter of the weighted components `w1 N(m1, c1)` and `w2 N(m2, c2)` that
+/// lies under the other one along the line through their means, in `[0, 1]`. Unlike OLR, it
+/// measures shared mass rather than whether the components form separate modes.
+#[pyfunction]
+#[pyo3(name = "overlap_fraction")]
+pub fn overlap_fraction_wrapper(
+    w1: f64,
+    m1: Vec<f64>,
+    c1: Vec<Vec<f64>>,
+    w2: f64,
+    m2: Vec<f64>,
+    c2: Vec<Vec<f64>>
+) -> PyResult<f64> {
+    let (c1, c2) = (vec_to_array2(c1)?, vec_to_array2(c2)?);
+
+    Ok(overlap_fraction(w1, ArrayView1::from(&m1), c1.view(), w2, ArrayView1::from(&m2), c2.view())?)
+}
+
+/// Overlapping coefficient `∫ min(w1 N(m1, c1), w2 N(m2, c2)) dx`, the statistical OVL for unit
+/// weights. `method="auto"` is exact in one dimension and integrates on a grid in two or three;
+/// `"analytic"` and `"grid"` force either. Use `overlap_mc` in more dimensions.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(name = "ovl", signature = (w1, m1, c1, w2, m2, c2, method = "auto"))]
+pub fn ovl_wrapper(
+    w1: f64,
+    m1: Vec<f64>,
+    c1: Vec<Vec<f64>>,
+    w2: f64,
+    m2: Vec<f64>,
+    c2: Vec<Vec<f64>>,
+    method: &str
+) -> PyResult<f64> {
+    let (c1, c2) = (vec_to_array2(c1)?, vec_to_array2(c2)?);
+
+    Ok(ovl(w1, ArrayView1::from(&m1), c1.view(), w2, ArrayView1::from(&m2), c2.view(), option("method", method)?)?)
+}
+
+/// OVL of every pair of unit-weight components, in the order of `olr`.
+#[pyfunction]
+#[pyo3(name = "ovl_pairs", signature = (means, covs, method = "auto"))]
+pub fn ovl_pairs_wrapper(py: Python<'_>, means: &PyAny, covs: &PyAny, method: &str) -> PyResult<Vec<f64>> {
+    let method = option("method", method)?;
+    let (means, covs) = extract_params(means, covs, true)?;
+
+    Ok(py.allow_threads(|| ovl_pairs(&means, &covs, method))?)
+}
+
+/// Semi-axis lengths and rotation matrix (axes as columns) of the `n_sigma` confidence ellipse of
+/// a covariance matrix.
+#[pyfunction()]
+#[pyo3(name = "ellipse_params", signature = (cov, n_sigma = 1.0))]
+pub fn ellipse_params_wrapper(cov: Vec<Vec<f64>>, n_sigma: f64) -> PyResult<(Vec<f64>, Vec<Vec<f64>>)> {
+    let (axes, rotation) = ellipse_params(&vec_to_array2(cov)?, n_sigma)?;
+
+    Ok((axes, rotation.outer_iter().map(|row| row.to_vec()).collect()))
+}
+
+/// Quadratic discriminant `x^T A x + b^T x + c` of two weighted components, as a dict of `A` and
+/// `b` as numpy arrays and `c`. It is zero on the boundary `w1 N1 = w2 N2` and positive where the
+/// first component dominates; `A` vanishes for equal covariances.
+#[pyfunction]
+#[pyo3(name = "decision_boundary")]
+pub fn decision_boundary_wrapper<'py>(
+    py: Python<'py>,
+    w1: f64,
+    m1: Vec<f64>,
+    c1: Vec<Vec<f64>>,
+    w2: f64,
+    m2: Vec<f64>,
+    c2: Vec<Vec<f64>>
+) -> PyResult<&'py PyDict> {
+    let params = decision_boundary(w1, &Array1::from(m1), &vec_to_array2(c1)?, w2, &Array1::from(m2), &vec_to_array2(c2)?)?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("A", params.a.into_pyarray(py))?;
+    dict.set_item("b", params.b.into_pyarray(py))?;
+    dict.set_item("c", params.c)?;
+
+    Ok(dict)
+}
+
+/// Gradients of the Bhattacharyya distance between `N(m1, c1)` and `N(m2, c2)` with respect to
+/// `m1` and `m2`, as a tuple of numpy arrays. The distance grows as the components separate, so
+/// ascending along the gradients reduces their overlap.
+#[pyfunction]
+#[pyo3(name = "bhattacharyya_grad")]
+pub fn bhattacharyya_grad_wrapper<'py>(
+    py: Python<'py>,
+    m1: Vec<f64>,
+    c1: Vec<Vec<f64>>,
+    m2: Vec<f64>,
+    c2: Vec<Vec<f64>>
+) -> PyResult<(&'py PyArray1<f64>, &'py PyArray1<f64>)> {
+    let (m1, m2) = (Array1::from(m1), Array1::from(m2));
+    let (grad_1, grad_2) = bhattacharyya_grad(m1.view(), vec_to_array2(c1)?.view(), m2.view(), vec_to_array2(c2)?.view())?;
+
+    Ok((grad_1.into_pyarray(py), grad_2.into_pyarray(py)))
+}
+
+/// Mean of the whole mixture, with the weights normalized to sum to `1`, as a numpy array.
+#[pyfunction]
+#[pyo3(name = "mixture_mean")]
+pub fn mixture_mean_wrapper<'py>(py: Python<'py>, w: Vec<f64>, means: &PyAny, covs: &PyAny) -> PyResult<&'py PyArray1<f64>> {
+    Ok(extract_mixture((w, means, covs))?.mixture_mean()?.into_pyarray(py))
+}
+
+/// Covariance of the whole mixture by the law of total variance, as a numpy array.
+#[pyfunction]
+#[pyo3(name = "mixture_covariance")]
+pub fn mixture_covariance_wrapper<'py>(py: Python<'py>, w: Vec<f64>, means: &PyAny, covs: &PyAny) -> PyResult<&'py PyArray2<f64>> {
+    Ok(extract_mixture((w, means, covs))?.mixture_covariance()?.into_pyarray(py))
+}
+
+/// Weight, mean and covariance of the single Gaussian matching the first two moments of the
+/// components `indices`, the mean and covariance as numpy arrays.
+#[pyfunction]
+#[pyo3(name = "merge_moments", signature = (indices, w, means, covs, *, promote_1d = true))]
+pub fn merge_moments_wrapper<'py>(
+    py: Python<'py>,
+    indices: Vec<usize>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    promote_1d: bool
+) -> PyResult<(f64, &'py PyArray1<f64>, &'py PyArray2<f64>)> {
+    let (means, covs) = extract_params(means, covs, promote_1d)?;
+    let (weight, mean, cov) = merge_moments(&indices, &w, &means, &covs)?;
+
+    Ok((weight, mean.into_pyarray(py), cov.into_pyarray(py)))
+}
+
+/// Weights, means and covariances of the groups `labels`, e.g. from `effective_components`, each
+/// merged as by `merge_moments`. The means and covariances are numpy arrays.
+#[pyfunction]
+#[pyo3(name = "merge_to_gaussian", signature = (w, means, covs, labels, *, promote_1d = true))]
+pub fn merge_to_gaussian_wrapper<'py>(
+    py: Python<'py>,
+    w: Vec<f64>,
+    means: &PyAny,
+    covs: &PyAny,
+    labels: Vec<usize>,
+    promote_1d: bool
+) -> PyResult<(Vec<f64>, &'py PyArray2<f64>, &'py PyArray3<f64>)> {
+    let (means, covs) = extract_params(means, covs, promote_1d)?;
+    let (weights, merged_means, merged_covs) = merge_to_gaussian(&w, &means, &covs, &labels)?;
+
+    Ok((weights, merged_means.into_pyarray(py), merged_covs.into_pyarray(py)))
+}
@@ -0,0 +1,131 @@
+//! Reference profiles for validating other implementations of the OLR scan.
+//!
+//! `dump_reference` writes every point the scan samples for one pair, with the log-density and
+//! density of the pair's two-component mixture there, as JSON:
+//!
+//! ```json
+//! {"pair": [0, 1], "n_points": 1000, "extension": 10, "olr": 0.87,
+//!  "points": [{"t": -0.01, "x": [...], "ln_pdf": -3.2, "pdf": 0.04}, ...]}
+//! ```
+//!
+//! `t` is the position of the point in units of the distance between the means, `0` at
+//! `means[i]` and `1` at `means[j]`. `x` holds the coordinates as computed by the scan, including
+//! the rounding of its accumulated steps, so that a port can be compared point by point.
+
+use std::io::Write;
+
+use ndarray::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::MoebiusError;
+use crate::gaussian::Gaussian;
+use crate::olr::{check_shapes, ln_pdf_pair, pair_log_weights, profile_extrema, scan_line, OlrConfig};
+use crate::validation::check_finite;
+use crate::workspace::Scratch;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferencePoint {
+    pub t: f64,
+    pub x: Vec<f64>,
+    pub ln_pdf: f64,
+    pub pdf: f64,
+}
+
+/// Sampled profile of a pair, as written by `dump_reference`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceDump {
+    pub pair: (usize, usize),
+    pub n_points: usize,
+    pub extension: usize,
+    /// OLR of the pair computed from `points`.
+    pub olr: f64,
+    pub points: Vec<ReferencePoint>,
+}
+
+/// Profile of the pair `(i, j)` sampled as by `olr_with_config` with `config`. The line runs
+/// from `means[i]` to `means[j]`, so swapping `i` and `j` reverses it.
+pub fn reference_profile(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    (i, j): (usize, usize),
+    config: &OlrConfig
+) -> Result<ReferenceDump, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    config.validate()?;
+    if let Some(k) = [i, j].into_iter().find(|&k| k >= w.len()) {
+        return Err(MoebiusError::Shape(format!("component {} is out of range for {} components", k, w.len())));
+    }
+    if i == j {
+        return Err(MoebiusError::Shape(format!("pair ({}, {}) needs two different components", i, j)));
+    }
+
+    let density_i = Gaussian::new(means.row(i), covs.index_axis(Axis(0), i), i, None)?;
+    let density_j = Gaussian::new(means.row(j), covs.index_axis(Axis(0), j), j, None)?;
+    let (ln_w1, ln_w2) = pair_log_weights(w[i], w[j]);
+
+    let mut points = Vec::with_capacity(config.grid_len());
+    let mut scratch = Scratch::default();
+    scan_line(means.row(i), means.row(j), config, |x| {
+        let ln_pdf = ln_pdf_pair(x, ln_w1, &density_i, ln_w2, &density_j, &mut scratch);
+        points.push(ReferencePoint {
+            t: (points.len() as f64 - config.extension as f64) / config.n_points as f64,
+            x: x.to_vec(),
+            ln_pdf,
+            pdf: ln_pdf.exp(),
+        });
+    });
+    let profile: Vec<f64> = points.iter().map(|point| point.ln_pdf).collect();
+
+    Ok(ReferenceDump {
+        pair: (i, j),
+        n_points: config.n_points,
+        extension: config.extension,
+        olr: profile_extrema(&profile, &config.detector).value,
+        points,
+    })
+}
+
+/// Writes the profile of the pair `(i, j)` with the default config to `writer` as JSON.
+pub fn dump_reference(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    i: usize,
+    j: usize,
+    writer: impl Write
+) -> Result<(), MoebiusError> {
+    let dump = reference_profile(w, means, covs, (i, j), &OlrConfig::default())?;
+
+    serde_json::to_writer(writer, &dump).map_err(|e| MoebiusError::Io(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::olr::{olr_with_config, OlrConfig};
+    use crate::reference::{dump_reference, reference_profile, ReferenceDump};
+
+    #[test]
+    fn dump_round_trips() {
+        let w = vec![0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0, 0.0], [1.5, 0.5], [4.0, -1.0]]);
+        let covs = arr3(&[[[1.0, 0.2], [0.2, 0.8]], [[0.7, 0.0], [0.0, 1.1]], [[1.2, -0.3], [-0.3, 0.9]]]);
+        let config = OlrConfig::default();
+
+        let mut bytes = Vec::new();
+        dump_reference(&w, &means, &covs, 0, 2, &mut bytes).unwrap();
+        let dump: ReferenceDump = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(reference_profile(&w, &means, &covs, (0, 2), &config).unwrap(), dump);
+        assert_eq!(config.grid_len(), dump.points.len());
+        assert_eq!(olr_with_config(&w, &means, &covs, &config).unwrap()[1], dump.olr);
+        assert_eq!(-0.01, dump.points[0].t);
+        assert_eq!(vec![0.0, 0.0], dump.points[10].x);
+        assert_eq!(dump.points[10].ln_pdf.exp(), dump.points[10].pdf);
+
+        assert!(dump_reference(&w, &means, &covs, 1, 1, &mut Vec::new()).is_err());
+        assert!(dump_reference(&w, &means, &covs, 0, 3, &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,287 @@
+//! Human-readable reports of `OlrDetails` for audit trails.
+//!
+//! A report lists the shape of the mixture, the configuration, summary statistics, the most
+//! overlapping pairs and the warnings raised while computing them. It holds no timings, and every
+//! value is printed with a fixed number of decimals, so the report of the same mixture and
+//! configuration is the same on every run and platform and diffs between releases only show
+//! actual changes.
+
+use std::str::FromStr;
+
+use crate::error::MoebiusError;
+use crate::extrema::Detector;
+use crate::olr::{plain_value, GridStrategy, NoSaddlePolicy, OlrDetails, PairStatus, Resolution};
+use crate::result::OlrMatrix;
+
+/// Default number of pairs listed by a report.
+pub const DEFAULT_TOP_N: usize = 10;
+
+/// Decimals of the OLR values in a report.
+const DECIMALS: usize = 6;
+
+/// Markup of a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    /// Plain text with indented sections.
+    #[default]
+    Text,
+    /// Markdown with headings, lists and a table.
+    Markdown,
+}
+
+impl FromStr for ReportFormat {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "markdown" => Ok(ReportFormat::Markdown),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown report format {:?}, expected \"text\" or \"markdown\"", s
+            ))),
+        }
+    }
+}
+
+/// What `OlrDetails::render_report` prints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportOptions {
+    pub format: ReportFormat,
+    /// Number of pairs listed, in the order of `OlrMatrix::top_k_overlaps`.
+    pub top_n: usize,
+    /// Name of every component. Components without a label are named by their index.
+    pub labels: Option<Vec<String>>,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        ReportOptions { format: ReportFormat::Text, top_n: DEFAULT_TOP_N, labels: None }
+    }
+}
+
+impl OlrDetails {
+    /// Deterministic report of the pairs, see the module documentation. Panics if `pairs` does
+    /// not hold one entry per pair of `n_components` components.
+    pub fn render_report(&self, options: &ReportOptions) -> String {
+        let values = OlrMatrix::new(self.n_components, self.pairs.iter().map(plain_value).collect())
+            .expect("one detail per pair of components");
+        let label = |k: usize| {
+            options.labels.as_ref()
+                .and_then(|labels| labels.get(k).cloned())
+                .unwrap_or_else(|| k.to_string())
+        };
+        let pair = |(i, j): (usize, usize)| format!("({}, {})", label(i), label(j));
+        let mut report = Report { format: options.format, out: String::new() };
+
+        report.title("OLR report");
+
+        report.section("Mixture");
+        report.item("components", self.n_components);
+        report.item("dimensions", self.dim);
+        report.item("pairs", self.pairs.len());
+
+        let config = &self.config;
+        report.section("Configuration");
+        report.item("n_points", config.n_points);
+        report.item("extension", config.extension);
+        report.item("resolution", match config.resolution {
+            Resolution::Fixed => "fixed".to_string(),
+            Resolution::Auto { tolerance, max_points } => {
+                format!("auto (tolerance {:e}, max_points {})", tolerance, max_points)
+            }
+        });
+        report.item("grid", match config.grid {
+            GridStrategy::Uniform => "uniform",
+            GridStrategy::Adaptive => "adaptive",
+        });
+        report.item("reg_covar", config.reg_covar);
+        report.item("symmetrize", config.symmetrize);
+        report.item("detector", match config.detector {
+            Detector::SignChange => "sign_change",
+            Detector::Parabolic => "parabolic",
+        });
+        report.item("extrapolate", config.extrapolate);
+        report.item("no_saddle", match config.no_saddle {
+            NoSaddlePolicy::AssumeMerged => "assume_merged",
+            NoSaddlePolicy::Error => "error",
+            NoSaddlePolicy::ExtendAndRetry => "extend_and_retry",
+        });
+        report.item("deterministic", config.deterministic);
+
+        report.section("Summary");
+        match values.summary() {
+            Some(summary) => {
+                report.item("max", format!("{:.*} {}", DECIMALS, summary.max, pair(summary.argmax)));
+                report.item("min", format!("{:.*}", DECIMALS, summary.min));
+                report.item("mean", format!("{:.*}", DECIMALS, summary.mean));
+                let bimodal = values.values().iter().filter(|&&value| value < 1.0).count();
+                report.item("bimodal pairs", format!("{} of {}", bimodal, self.pairs.len()));
+                if let Some(error) = self.max_error_estimate() {
+                    report.item("max error estimate", format!("{:.2e}", error));
+                }
+            }
+            None => report.line("no pairs"),
+        }
+
+        let top = values.top_k_overlaps(options.top_n);
+        report.section(&format!("Top {} overlapping pairs", top.len()));
+        let rows: Vec<[String; 4]> = top.iter().enumerate()
+            .map(|(rank, &((i, j), value))| [(rank + 1).to_string(), label(i), label(j), format!("{:.*}", DECIMALS, value)])
+            .collect();
+        report.table(["rank", "i", "j", "olr"], &rows);
+
+        let mut warnings = Vec::new();
+        if config.reg_covar > 0.0 {
+            warnings.push(format!("reg_covar {} was added to the diagonal of every covariance", config.reg_covar));
+        }
+        if config.symmetrize {
+            warnings.push("covariances were symmetrized as (C + C^T) / 2".to_string());
+        }
+        if let Some(total) = self.normalized_sum {
+            warnings.push(format!("weights summing to {} were divided by their sum", total));
+        }
+        for &(k, d) in &self.small_variances {
+            warnings.push(format!(
+                "variance of component {} in dimension {} is below {:e} relative to the means", label(k), d, config.variance_floor
+            ));
+        }
+        for (pair_idx, detail) in values.pairs().into_iter().zip(&self.pairs) {
+            if detail.status == PairStatus::TimedOut {
+                warnings.push(format!("pair {} timed out", pair(pair_idx)));
+            }
+            if detail.extreme_range {
+                warnings.push(format!("pair {} has densities outside the f64 range, its ratio was computed in log space", pair(pair_idx)));
+            }
+            match (config.resolution, detail.refinement) {
+                (Resolution::Auto { tolerance, .. }, Some(refinement)) if refinement.delta >= tolerance => {
+                    warnings.push(format!("pair {} did not converge within {} steps", pair(pair_idx), refinement.n_points));
+                }
+                _ => {}
+            }
+        }
+        report.section("Warnings");
+        if warnings.is_empty() {
+            report.line("none");
+        }
+        for warning in &warnings {
+            report.line(warning);
+        }
+
+        report.out
+    }
+}
+
+/// Report under construction.
+struct Report {
+    format: ReportFormat,
+    out: String,
+}
+
+impl Report {
+    fn title(&mut self, title: &str) {
+        match self.format {
+            ReportFormat::Text => self.out += &format!("{}\n{}\n", title, "=".repeat(title.len())),
+            ReportFormat::Markdown => self.out += &format!("# {}\n", title),
+        }
+    }
+
+    fn section(&mut self, title: &str) {
+        match self.format {
+            ReportFormat::Text => self.out += &format!("\n{}\n", title),
+            ReportFormat::Markdown => self.out += &format!("\n## {}\n\n", title),
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        match self.format {
+            ReportFormat::Text => self.out += &format!("  {}\n", line),
+            ReportFormat::Markdown => self.out += &format!("- {}\n", line),
+        }
+    }
+
+    fn item(&mut self, name: &str, value: impl ToString) {
+        self.line(&format!("{}: {}", name, value.to_string()));
+    }
+
+    /// Columns are left-aligned in text, with the last one right-aligned.
+    fn table(&mut self, header: [&str; 4], rows: &[[String; 4]]) {
+        match self.format {
+            ReportFormat::Text => {
+                let widths: Vec<usize> = (0..4)
+                    .map(|c| rows.iter().map(|row| row[c].len()).chain([header[c].len()]).max().unwrap())
+                    .collect();
+                let render = |cells: [&str; 4]| {
+                    let cells: Vec<String> = cells.iter().enumerate()
+                        .map(|(c, cell)| if c == 3 { format!("{:>1$}", cell, widths[c]) } else { format!("{:<1$}", cell, widths[c]) })
+                        .collect();
+
+                    format!("  {}\n", cells.join("  "))
+                };
+                self.out += &render(header);
+                for row in rows {
+                    self.out += &render([&row[0], &row[1], &row[2], &row[3]]);
+                }
+            }
+            ReportFormat::Markdown => {
+                self.out += &format!("| {} |\n| --- | --- | --- | ---: |\n", header.join(" | "));
+                for row in rows {
+                    self.out += &format!("| {} |\n", row.join(" | "));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::olr::{olr_detailed, GridStrategy, OlrConfig};
+    use crate::report::{ReportFormat, ReportOptions};
+
+    #[test]
+    fn labels_and_warnings() {
+        let w = [0.3, 0.3, 0.4];
+        let means = arr2(&[[0.0], [0.5], [1e7]]);
+        let covs = arr3(&[[[1.0]], [[1.0]], [[1.0]]]);
+        let config = OlrConfig { reg_covar: 1e-6, ..OlrConfig::default() };
+        let details = olr_detailed(&w, &means, &covs, &config).unwrap();
+        let labels = Some(vec!["a".to_string(), "b".to_string()]);
+
+        let report = details.render_report(&ReportOptions { top_n: 1, labels: labels.clone(), ..ReportOptions::default() });
+        assert!(report.contains("Top 1 overlapping pairs"), "{}", report);
+        assert!(report.contains("  rank  i  j       olr\n  1     a  b  1.000000\n"), "{}", report);
+        assert!(report.contains("reg_covar 0.000001 was added"), "{}", report);
+        // The variances are tiny next to the means, and component 2 has no label.
+        assert!(report.contains("variance of component a in dimension 0"), "{}", report);
+        assert!(report.contains("variance of component 2 in dimension 0"), "{}", report);
+        assert_eq!(report, details.render_report(&ReportOptions { top_n: 1, labels, ..ReportOptions::default() }));
+
+        let markdown = details.render_report(&ReportOptions { format: ReportFormat::Markdown, ..ReportOptions::default() });
+        assert!(markdown.starts_with("# OLR report\n"));
+        assert!(markdown.contains("| 1 | 0 | 1 | 1.000000 |\n"), "{}", markdown);
+        assert!("html".parse::<ReportFormat>().is_err());
+    }
+
+    #[test]
+    fn configuration_tells_runs_apart() {
+        let w = [0.5, 0.5];
+        let means = arr2(&[[0.0], [3.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]]]);
+        let configuration = |config: OlrConfig| {
+            let report = olr_detailed(&w, &means, &covs, &config).unwrap().render_report(&ReportOptions::default());
+            let start = report.find("Configuration").unwrap();
+            report[start..report.find("Summary").unwrap()].to_string()
+        };
+        let default = configuration(OlrConfig::default());
+
+        for (config, line) in [
+            (OlrConfig { extrapolate: true, ..OlrConfig::default() }, "extrapolate: true"),
+            (OlrConfig { grid: GridStrategy::Adaptive, ..OlrConfig::default() }, "grid: adaptive"),
+            (OlrConfig { deterministic: true, ..OlrConfig::default() }, "deterministic: true"),
+        ] {
+            let changed = configuration(config);
+            assert_ne!(default, changed);
+            assert!(changed.contains(line), "{}", changed);
+        }
+    }
+}
@@ -0,0 +1,359 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+
+/// Pairwise OLR values of an `n`-component mixture in condensed form.
+///
+/// `values[k]` is the OLR of the `k`-th pair `(i, j)`, `i < j`, in row-major order of the upper
+/// triangle, i.e. `(0, 1), (0, 2), ..., (0, n - 1), (1, 2), ...`. This is the order used by
+/// `scipy.spatial.distance.squareform`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OlrMatrix {
+    n_components: usize,
+    values: Vec<f64>,
+}
+
+/// Extremes and mean of the pairwise OLR values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OlrSummary {
+    pub max: f64,
+    pub min: f64,
+    pub mean: f64,
+    /// Pair with the largest OLR value.
+    pub argmax: (usize, usize),
+}
+
+/// Number of pairs `i < j` of `n` components, i.e. of values in a condensed matrix. Panics if
+/// it does not fit in `usize`.
+pub const fn condensed_len(n: usize) -> usize {
+    match checked_condensed_len(n) {
+        Some(len) => len,
+        None => panic!("too many components for a condensed matrix"),
+    }
+}
+
+/// Same as `condensed_len`, or `None` if the number of pairs does not fit in `usize`.
+pub const fn checked_condensed_len(n: usize) -> Option<usize> {
+    if n.is_multiple_of(2) {
+        (n / 2).checked_mul(n.saturating_sub(1))
+    } else {
+        n.checked_mul((n - 1) / 2)
+    }
+}
+
+/// Position of the pair `(i, j)` of `n` components in a condensed matrix, in the order in which
+/// `olr` computes pairs, `for i in 0..n { for j in (i + 1)..n { .. } }`. The order of `i` and `j`
+/// does not matter. Panics if `i == j` or either index is not below `n`.
+pub const fn pair_to_index(i: usize, j: usize, n: usize) -> usize {
+    assert!(i != j, "a pair needs two different components");
+    assert!(i < n && j < n, "component index out of range");
+    let (i, j) = if i < j { (i, j) } else { (j, i) };
+
+    i * (2 * n - i - 1) / 2 + (j - i - 1)
+}
+
+/// Pair `(i, j)`, `i < j`, at position `k` of a condensed matrix of `n` components, the inverse
+/// of `pair_to_index`. Panics if `k` is not below `condensed_len(n)`.
+pub const fn index_to_pair(k: usize, n: usize) -> (usize, usize) {
+    assert!(k < condensed_len(n), "condensed index out of range");
+    let (mut i, mut k) = (0, k);
+    while k >= n - i - 1 {
+        k -= n - i - 1;
+        i += 1;
+    }
+
+    (i, i + 1 + k)
+}
+
+/// Total order of ranked pairs: decreasing OLR, ties broken by increasing `(i, j)`, and NaN values
+/// last. Every ranking output uses it, so the selection and order of equal values do not depend
+/// on the platform or on the order in which pairs were computed.
+pub(crate) fn rank_order(a: &((usize, usize), f64), b: &((usize, usize), f64)) -> Ordering {
+    a.1.is_nan().cmp(&b.1.is_nan())
+        .then_with(|| b.1.total_cmp(&a.1))
+        .then_with(|| a.0.cmp(&b.0))
+}
+
+/// Largest number of components for which `Display` prints the full matrix.
+const MAX_TABLE_COMPONENTS: usize = 10;
+
+impl OlrMatrix {
+    pub fn new(n_components: usize, values: Vec<f64>) -> Result<Self, MoebiusError> {
+        let expected = condensed_len(n_components);
+        if values.len() != expected {
+            return Err(MoebiusError::Shape(format!(
+                "{} values given for {} components, expected {}", values.len(), n_components, expected
+            )));
+        }
+
+        Ok(OlrMatrix { n_components, values })
+    }
+
+    pub fn n_components(&self) -> usize {
+        self.n_components
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn into_values(self) -> Vec<f64> {
+        self.values
+    }
+
+    /// Component index pairs in the order of `values`.
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        let n = self.n_components;
+
+        (0..n).flat_map(|i| ((i + 1)..n).map(move |j| (i, j))).collect()
+    }
+
+    /// Symmetric `n x n` matrix of OLR values with ones on the diagonal, since every component
+    /// fully overlaps with itself.
+    pub fn to_square(&self) -> Array2<f64> {
+        let mut square = Array2::eye(self.n_components);
+        for ((i, j), value) in self.pairs().into_iter().zip(&self.values) {
+            square[[i, j]] = *value;
+            square[[j, i]] = *value;
+        }
+
+        square
+    }
+
+    /// Group label of every component after merging all pairs with OLR above `threshold`, i.e.
+    /// the connected components of the graph with an edge for every such pair. Groups are
+    /// numbered in the order of their lowest component.
+    pub fn groups(&self, threshold: f64) -> Vec<usize> {
+        // Union-find in which the root of every set is its lowest component.
+        let mut parent: Vec<usize> = (0..self.n_components).collect();
+        for ((i, j), value) in self.pairs().into_iter().zip(&self.values) {
+            if *value > threshold {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+
+        let mut labels = vec![0; self.n_components];
+        let mut n_groups = 0;
+        for k in 0..self.n_components {
+            let r = root(&mut parent, k);
+            if r == k {
+                labels[k] = n_groups;
+                n_groups += 1;
+            } else {
+                labels[k] = labels[r];
+            }
+        }
+
+        labels
+    }
+
+    /// The `k` pairs with the largest OLR, or all pairs if there are fewer, ordered by decreasing
+    /// value. Pairs of equal value are selected and ordered by increasing `(i, j)`.
+    pub fn top_k_overlaps(&self, k: usize) -> Vec<((usize, usize), f64)> {
+        let mut ranked: Vec<_> = self.pairs().into_iter().zip(self.values.iter().copied()).collect();
+        if k < ranked.len() {
+            if k > 0 {
+                ranked.select_nth_unstable_by(k - 1, rank_order);
+            }
+            ranked.truncate(k);
+        }
+        ranked.sort_unstable_by(rank_order);
+
+        ranked
+    }
+
+    /// `None` for mixtures with fewer than two components.
+    pub fn summary(&self) -> Option<OlrSummary> {
+        let (argmax, max) = self.pairs().into_iter()
+            .zip(self.values.iter().copied())
+            .reduce(|best, curr| if curr.1 > best.1 { curr } else { best })?;
+        let min = self.values.iter().copied().fold(f64::INFINITY, f64::min);
+        let mean = self.values.iter().sum::<f64>() / self.values.len() as f64;
+
+        Some(OlrSummary { max, min, mean, argmax })
+    }
+}
+
+fn root(parent: &mut [usize], mut k: usize) -> usize {
+    while parent[k] != k {
+        parent[k] = parent[parent[k]];
+        k = parent[k];
+    }
+
+    k
+}
+
+impl fmt::Display for OlrMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = self.n_components;
+        if n > MAX_TABLE_COMPONENTS {
+            return match self.summary() {
+                Some(s) => write!(
+                    f,
+                    "OlrResult({} components, {} pairs, max {:.4} at {:?}, min {:.4}, mean {:.4})",
+                    n, self.values.len(), s.max, s.argmax, s.min, s.mean
+                ),
+                None => write!(f, "OlrResult({} components)", n),
+            };
+        }
+
+        write!(f, "OlrResult({} components)\n   ", n)?;
+        for j in 0..n {
+            write!(f, " {:>6}", j)?;
+        }
+
+        let square = self.to_square();
+        for i in 0..n {
+            let mut row = format!("{:>3}", i);
+            for j in 0..n {
+                if j <= i {
+                    row.push_str(&format!(" {:>6}", ""));
+                } else {
+                    row.push_str(&format!(" {:>6.4}", square[[i, j]]));
+                }
+            }
+            write!(f, "\n{}", row.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::arr2;
+    use crate::result::{checked_condensed_len, condensed_len, index_to_pair, pair_to_index, OlrMatrix};
+
+    /// Position of pair `(i, j)`, `i < j`, in a condensed matrix as computed by scipy's
+    /// `squareform`.
+    fn scipy_condensed_index(n: usize, i: usize, j: usize) -> usize {
+        n * i - i * (i + 1) / 2 + (j - i - 1)
+    }
+
+    #[test]
+    fn ordering_matches_squareform() {
+        let n = 6;
+        let values: Vec<f64> = (0..n * (n - 1) / 2).map(|k| k as f64).collect();
+        let matrix = OlrMatrix::new(n, values).unwrap();
+
+        for (k, (i, j)) in matrix.pairs().into_iter().enumerate() {
+            assert_eq!(k, scipy_condensed_index(n, i, j));
+        }
+
+        let square = matrix.to_square();
+        for i in 0..n {
+            for j in (i + 1)..n {
+                assert_eq!(scipy_condensed_index(n, i, j) as f64, square[[i, j]]);
+                assert_eq!(square[[i, j]], square[[j, i]]);
+            }
+            assert_eq!(1.0, square[[i, i]]);
+        }
+    }
+
+    #[test]
+    fn index_round_trip() {
+        for n in 0..=100usize {
+            assert_eq!(n * n.saturating_sub(1) / 2, condensed_len(n));
+            let mut k = 0;
+            for i in 0..n {
+                for j in (i + 1)..n {
+                    assert_eq!(k, pair_to_index(i, j, n));
+                    assert_eq!(k, pair_to_index(j, i, n));
+                    assert_eq!(scipy_condensed_index(n, i, j), k);
+                    assert_eq!((i, j), index_to_pair(k, n));
+                    k += 1;
+                }
+            }
+            assert_eq!(condensed_len(n), k);
+        }
+
+        const PAIR: (usize, usize) = index_to_pair(pair_to_index(3, 7, 10), 10);
+        assert_eq!((3, 7), PAIR);
+        assert_eq!(None, checked_condensed_len(usize::MAX));
+        assert_eq!(Some((1 << 19) * ((1 << 20) - 1)), checked_condensed_len(1 << 20));
+    }
+
+    #[test]
+    #[should_panic(expected = "two different components")]
+    fn same_component() {
+        pair_to_index(2, 2, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "component index out of range")]
+    fn component_out_of_range() {
+        pair_to_index(1, 5, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "condensed index out of range")]
+    fn index_out_of_range() {
+        index_to_pair(10, 5);
+    }
+
+    #[test]
+    fn top_k_ties() {
+        // Pairs (0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3).
+        let matrix = OlrMatrix::new(4, vec![0.5, 0.9, 0.5, 0.9, f64::NAN, 0.5]).unwrap();
+
+        assert_eq!(vec![((0, 2), 0.9), ((1, 2), 0.9), ((0, 1), 0.5)], matrix.top_k_overlaps(3));
+        let ranked = |k: usize| matrix.top_k_overlaps(k).into_iter().map(|(pair, _)| pair).collect::<Vec<_>>();
+        let all = ranked(10);
+        assert_eq!(vec![(0, 2), (1, 2), (0, 1), (0, 3), (2, 3), (1, 3)], all);
+        for k in 0..=6 {
+            assert_eq!(all[..k], ranked(k)[..]);
+        }
+    }
+
+    #[test]
+    fn square_matrix() {
+        let matrix = OlrMatrix::new(3, vec![0.1, 0.2, 0.3]).unwrap();
+
+        assert_eq!(arr2(&[[1.0, 0.1, 0.2], [0.1, 1.0, 0.3], [0.2, 0.3, 1.0]]), matrix.to_square());
+    }
+
+    #[test]
+    fn summary() {
+        let summary = OlrMatrix::new(3, vec![0.1, 0.5, 0.3]).unwrap().summary().unwrap();
+
+        assert_eq!(0.5, summary.max);
+        assert_eq!(0.1, summary.min);
+        assert_abs_diff_eq!(0.3, summary.mean, epsilon = 1e-12);
+        assert_eq!((0, 2), summary.argmax);
+        assert_eq!(None, OlrMatrix::new(1, vec![]).unwrap().summary());
+    }
+
+    #[test]
+    fn groups() {
+        // (0, 2) and (2, 3) overlap, 1 is separated.
+        let matrix = OlrMatrix::new(4, vec![0.1, 0.8, 0.2, 0.1, 0.3, 0.6]).unwrap();
+
+        assert_eq!(vec![0, 1, 0, 0], matrix.groups(0.5));
+        assert_eq!(vec![0, 1, 0, 2], matrix.groups(0.7));
+        assert_eq!(vec![0, 1, 2, 3], matrix.groups(0.8));
+        assert_eq!(vec![0, 0, 0, 0], matrix.groups(0.0));
+    }
+
+    #[test]
+    fn wrong_number_of_values() {
+        assert!(OlrMatrix::new(3, vec![0.1, 0.2]).is_err());
+    }
+
+    #[test]
+    fn display() {
+        let small = OlrMatrix::new(3, vec![0.1, 0.2, 0.3]).unwrap().to_string();
+        assert_eq!(
+            "OlrResult(3 components)\n         0      1      2\n  0        0.1000 0.2000\n  1               0.3000\n  2",
+            small
+        );
+
+        let n = 11;
+        let large = OlrMatrix::new(n, vec![0.5; n * (n - 1) / 2]).unwrap().to_string();
+        assert_eq!("OlrResult(11 components, 55 pairs, max 0.5000 at (0, 1), min 0.5000, mean 0.5000)", large);
+    }
+}
@@ -0,0 +1,58 @@
+//! Seeding of stochastic computations.
+//!
+//! Every stochastic function takes an `Rng` and never shares a random stream between tasks:
+//! each task, e.g. a component pair, draws from its own `StdRng` derived from the seed and
+//! a key identifying the task. Results are therefore bitwise identical for the same seed
+//! whatever the number of threads or the order tasks run in.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Source of the random streams of a computation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    seed: u64,
+}
+
+impl Rng {
+    /// `None` draws the seed from the operating system's entropy source, so that results differ
+    /// between calls. The drawn seed is available from `seed` to reproduce them.
+    pub fn new(seed: Option<u64>) -> Self {
+        Rng { seed: seed.unwrap_or_else(rand::random) }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Stream of the task identified by `key`, independent of the streams of other keys.
+    pub(crate) fn for_task(&self, key: u64) -> StdRng {
+        StdRng::seed_from_u64(mix(self.seed ^ mix(key)))
+    }
+}
+
+/// SplitMix64 finalizer, so that consecutive keys and seeds give unrelated streams.
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng as _;
+    use crate::rng::Rng;
+
+    #[test]
+    fn task_streams() {
+        let rng = Rng::new(Some(5));
+        let draw = |rng: &Rng, key| rng.for_task(key).gen::<u64>();
+
+        assert_eq!(draw(&rng, 3), draw(&Rng::new(Some(5)), 3));
+        assert_ne!(draw(&rng, 3), draw(&rng, 4));
+        assert_ne!(draw(&rng, 3), draw(&Rng::new(Some(6)), 3));
+        assert_ne!(Rng::new(None).seed(), Rng::new(None).seed());
+    }
+}
@@ -0,0 +1,266 @@
+use std::str::FromStr;
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::olr::try_olr;
+use crate::result::OlrMatrix;
+
+/// How pairwise OLR values are combined into a single score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Largest pairwise value, i.e. the overlap of the least separated pair.
+    Max,
+    /// Plain mean over all pairs.
+    Mean,
+    /// Mean over all pairs `(i, j)` weighted by `w_i * w_j`, so that overlaps between small
+    /// components count less.
+    WeightedMean,
+}
+
+impl FromStr for Aggregation {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "max" => Ok(Aggregation::Max),
+            "mean" => Ok(Aggregation::Mean),
+            "weighted_mean" => Ok(Aggregation::WeightedMean),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown aggregation {:?}, expected \"max\", \"mean\" or \"weighted_mean\"", s
+            ))),
+        }
+    }
+}
+
+/// How much every pair `(i, j)` counts in `separability_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PairWeight {
+    /// `p_ij = 1`, every pair counts the same.
+    #[default]
+    Uniform,
+    /// `p_ij = w_i * w_j`, the probability that two draws from the mixture come from `i` and `j`.
+    WeightProduct,
+    /// `p_ij = min(w_i, w_j)`, so that a pair counts as much as its lighter component.
+    MinWeight,
+}
+
+impl PairWeight {
+    fn weight(self, w_i: f64, w_j: f64) -> f64 {
+        match self {
+            PairWeight::Uniform => 1.0,
+            PairWeight::WeightProduct => w_i * w_j,
+            PairWeight::MinWeight => w_i.min(w_j),
+        }
+    }
+}
+
+impl FromStr for PairWeight {
+    type Err = MoebiusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(PairWeight::Uniform),
+            "weight_product" => Ok(PairWeight::WeightProduct),
+            "min_weight" => Ok(PairWeight::MinWeight),
+            _ => Err(MoebiusError::InvalidConfig(format!(
+                "unknown pair weight {:?}, expected \"uniform\", \"weight_product\" or \"min_weight\"", s
+            ))),
+        }
+    }
+}
+
+/// Thresholds swept by `effective_components` by default.
+pub const EFFECTIVE_THRESHOLDS: [f64; 9] = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+
+/// Number of distinguishable clusters at one overlap threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectiveComponents {
+    pub threshold: f64,
+    /// Number of groups left after merging every pair with OLR above `threshold`.
+    pub count: usize,
+    /// Group of every component, as returned by `OlrMatrix::groups`.
+    pub groups: Vec<usize>,
+}
+
+/// Single score summarizing how much the components of a mixture overlap: close to `0` for a
+/// well-separated mixture and `1` if some or all components merge. Mixtures with fewer than two
+/// components score `0`.
+///
+/// `WeightedMean` falls back to `Mean` if all products of weights vanish.
+pub fn separability(
+    w: Vec<f64>,
+    means: Array2<f64>,
+    covs: Array3<f64>,
+    agg: Aggregation
+) -> Result<f64, MoebiusError> {
+    separability_with(w, means, covs, agg, PairWeight::Uniform)
+}
+
+/// `separability` with the OLR `v_ij` of every pair weighted by `p_ij` from `pair_weight`:
+///
+/// ```text
+/// Mean: sum_ij p_ij v_ij / sum_ij p_ij,    Max: max_ij p_ij v_ij / max_ij p_ij
+/// ```
+///
+/// Both are the unweighted scores if all `p_ij` vanish. `WeightedMean` is `Mean` with
+/// `WeightProduct` and cannot be combined with `MinWeight`.
+pub fn separability_with(
+    w: Vec<f64>,
+    means: Array2<f64>,
+    covs: Array3<f64>,
+    agg: Aggregation,
+    pair_weight: PairWeight
+) -> Result<f64, MoebiusError> {
+    let (agg, pair_weight) = match (agg, pair_weight) {
+        (Aggregation::WeightedMean, PairWeight::Uniform | PairWeight::WeightProduct) => {
+            (Aggregation::Mean, PairWeight::WeightProduct)
+        },
+        (Aggregation::WeightedMean, _) => return Err(MoebiusError::InvalidConfig(
+            "weighted_mean already weights pairs by w_i * w_j, combine mean with the pair weight instead".to_string()
+        )),
+        other => other,
+    };
+    let weights = w.clone();
+    let values = OlrMatrix::new(w.len(), try_olr(w, means, covs)?)?;
+    let Some(summary) = values.summary() else {
+        return Ok(0.0);
+    };
+    let pair_weights: Vec<f64> = values.pairs().into_iter()
+        .map(|(i, j)| pair_weight.weight(weights[i], weights[j]))
+        .collect();
+    let weighted = pair_weights.iter().zip(values.values()).map(|(p, v)| p * v);
+
+    let score = match agg {
+        Aggregation::Max => {
+            let largest = pair_weights.iter().copied().fold(0.0, f64::max);
+            if largest == 0.0 {
+                summary.max
+            } else {
+                weighted.fold(0.0, f64::max) / largest
+            }
+        },
+        _ => {
+            let total: f64 = pair_weights.iter().sum();
+            if total == 0.0 {
+                summary.mean
+            } else {
+                weighted.sum::<f64>() / total
+            }
+        }
+    };
+
+    Ok(score)
+}
+
+/// Effective number of components of the mixture at every threshold in `thresholds`: pairs with
+/// OLR above the threshold are merged, transitively, and the remaining groups counted. Sweeping the
+/// thresholds, e.g. over `EFFECTIVE_THRESHOLDS`, shows how the number of clusters collapses as the
+/// tolerated overlap decreases.
+pub fn effective_components(
+    w: Vec<f64>,
+    means: Array2<f64>,
+    covs: Array3<f64>,
+    thresholds: &[f64]
+) -> Result<Vec<EffectiveComponents>, MoebiusError> {
+    if let Some(threshold) = thresholds.iter().find(|t| t.is_nan()) {
+        return Err(MoebiusError::InvalidConfig(format!("thresholds must not be NaN, got {}", threshold)));
+    }
+
+    let values = OlrMatrix::new(w.len(), try_olr(w, means, covs)?)?;
+
+    Ok(thresholds.iter()
+        .map(|&threshold| {
+            let groups = values.groups(threshold);
+            let count = groups.iter().max().map_or(0, |last| last + 1);
+
+            EffectiveComponents { threshold, count, groups }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::separability::{effective_components, separability, separability_with, Aggregation, PairWeight, EFFECTIVE_THRESHOLDS};
+
+    #[test]
+    fn separated_and_overlapping() {
+        let covs = arr3(&[[[1.0]], [[1.0]], [[1.0]]]);
+        let separated = arr2(&[[0.0], [20.0], [40.0]]);
+        let overlapping = arr2(&[[0.0], [0.5], [1.0]]);
+
+        for agg in [Aggregation::Max, Aggregation::Mean, Aggregation::WeightedMean] {
+            let low = separability(vec![0.3, 0.3, 0.4], separated.clone(), covs.clone(), agg).unwrap();
+            let high = separability(vec![0.3, 0.3, 0.4], overlapping.clone(), covs.clone(), agg).unwrap();
+
+            assert!(low < 1e-6, "{:?}: {}", agg, low);
+            assert!(high > 0.99, "{:?}: {}", agg, high);
+        }
+    }
+
+    #[test]
+    fn weighting() {
+        // (0, 1) overlaps, (0, 2) and (1, 2) are separated.
+        let means = arr2(&[[0.0], [2.0], [40.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]], [[1.0]]]);
+
+        let mean = separability(vec![0.01, 0.01, 0.98], means.clone(), covs.clone(), Aggregation::Mean).unwrap();
+        let weighted = separability(vec![0.01, 0.01, 0.98], means.clone(), covs.clone(), Aggregation::WeightedMean).unwrap();
+        let max = separability(vec![0.01, 0.01, 0.98], means, covs, Aggregation::Max).unwrap();
+
+        assert!(weighted < mean);
+        assert!(mean < max);
+        assert_eq!(Ok(Aggregation::WeightedMean), "weighted_mean".parse().map_err(|_| ()));
+        assert!("median".parse::<Aggregation>().is_err());
+    }
+
+    #[test]
+    fn pair_weights() {
+        // (0, 1) overlaps, (0, 2) and (1, 2) are separated.
+        let means = arr2(&[[0.0], [2.0], [40.0]]);
+        let covs = arr3(&[[[1.0]], [[1.0]], [[1.0]]]);
+        let score = |w: Vec<f64>, agg: Aggregation, pair_weight: PairWeight| {
+            separability_with(w, means.clone(), covs.clone(), agg, pair_weight).unwrap()
+        };
+
+        // Heavier components 0 and 1 make their overlapping pair count more.
+        let light = score(vec![0.1, 0.1, 0.8], Aggregation::Mean, PairWeight::WeightProduct);
+        let heavy = score(vec![0.3, 0.3, 0.4], Aggregation::Mean, PairWeight::WeightProduct);
+        assert!(light < heavy, "{} {}", light, heavy);
+        assert_eq!(light, score(vec![0.1, 0.1, 0.8], Aggregation::WeightedMean, PairWeight::Uniform));
+
+        let w = vec![0.1, 0.1, 0.8];
+        let uniform = score(w.clone(), Aggregation::Mean, PairWeight::Uniform);
+        assert_eq!(separability(w.clone(), means.clone(), covs.clone(), Aggregation::Mean).unwrap(), uniform);
+        // min(w_i, w_j) is 0.1 for every pair.
+        assert!((uniform - score(w.clone(), Aggregation::Mean, PairWeight::MinWeight)).abs() < 1e-12);
+        assert!(score(vec![0.1, 0.6, 0.3], Aggregation::Max, PairWeight::WeightProduct) < score(w.clone(), Aggregation::Max, PairWeight::Uniform));
+        assert!(separability_with(w, means, covs, Aggregation::WeightedMean, PairWeight::MinWeight).is_err());
+        assert_eq!(Ok(PairWeight::MinWeight), "min_weight".parse().map_err(|_| ()));
+        assert!("max_weight".parse::<PairWeight>().is_err());
+    }
+
+    #[test]
+    fn duplicated_component() {
+        let means = arr2(&[
+            [1.1987e+00, 1.1542e+00],
+            [4.1592e+00, 4.1487e+00],
+            [4.1592e+00, 4.1487e+00]
+        ]);
+        let covs = arr3(&[
+            [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+            [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+            [[1.5160e+00, 1.1009e+00], [1.1009e+00, 1.5178e+00]]
+        ]);
+        let w = vec![0.4, 0.3, 0.3];
+
+        let effective = effective_components(w.clone(), means.clone(), covs.clone(), &[0.95, 0.99, 0.999]).unwrap();
+        assert!(effective.iter().all(|e| e.count == 2 && e.groups == vec![0, 1, 1]), "{:?}", effective);
+
+        let sweep = effective_components(w.clone(), means.clone(), covs.clone(), &EFFECTIVE_THRESHOLDS).unwrap();
+        assert_eq!(vec![1, 1, 1, 1, 1, 1, 1, 1, 2], sweep.iter().map(|e| e.count).collect::<Vec<_>>());
+        assert_eq!(3, effective_components(w.clone(), means.clone(), covs.clone(), &[1.0]).unwrap()[0].count);
+        assert!(effective_components(w, means, covs, &[f64::NAN]).is_err());
+    }
+}
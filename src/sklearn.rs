@@ -0,0 +1,254 @@
+//! Reading of scikit-learn `GaussianMixture` models exported to JSON.
+//!
+//! Pickled (joblib) models are not read directly. Instead, `moebius.export_sklearn(gmm, path)`
+//! writes the fitted parameters as
+//!
+//! ```json
+//! {"weights": [...], "means": [[...]], "covariances": ..., "covariance_type": "full"}
+//! ```
+//!
+//! where `covariances` keeps the layout of `gmm.covariances_` for the given `covariance_type`:
+//!
+//! * `full` — `n x d x d`, one matrix per component;
+//! * `tied` — `d x d`, a single matrix shared by all components;
+//! * `diag` — `n x d`, the diagonal of each component's matrix;
+//! * `spherical` — `n`, a single variance per component.
+//!
+//! `covariance_type` may be omitted, in which case `full` is assumed.
+
+use std::fs;
+use std::path::Path;
+
+use ndarray::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::MoebiusError;
+use crate::mixture::GaussianMixture;
+use crate::convert::{vec_to_array2, vec_to_array3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CovarianceType {
+    #[default]
+    Full,
+    Tied,
+    Diag,
+    Spherical,
+}
+
+#[derive(Debug, Deserialize)]
+struct SklearnExport {
+    weights: Vec<f64>,
+    means: Vec<Vec<f64>>,
+    covariances: Value,
+    #[serde(default)]
+    covariance_type: CovarianceType,
+}
+
+impl GaussianMixture {
+    pub fn from_sklearn_json(path: impl AsRef<Path>) -> Result<Self, MoebiusError> {
+        let contents = fs::read_to_string(path)?;
+
+        Self::from_sklearn_str(&contents)
+    }
+
+    pub fn from_sklearn_str(s: &str) -> Result<Self, MoebiusError> {
+        let export: SklearnExport = serde_json::from_str(s)
+            .map_err(|e| MoebiusError::Parse(e.to_string()))?;
+
+        let n_comp = export.weights.len();
+        let means = vec_to_array2(export.means)?;
+        let dim = means.ncols();
+        let covs = expand_covariances(export.covariances, export.covariance_type, n_comp, dim)?;
+
+        GaussianMixture::new(export.weights, means, covs)
+    }
+}
+
+fn expand_covariances(
+    covariances: Value,
+    covariance_type: CovarianceType,
+    n_comp: usize,
+    dim: usize
+) -> Result<Array3<f64>, MoebiusError> {
+    let parse_err = |e: serde_json::Error| MoebiusError::Parse(
+        format!("covariances do not match covariance_type {:?}: {}", covariance_type, e)
+    );
+
+    let covs = match covariance_type {
+        CovarianceType::Full => {
+            let full: Vec<Vec<Vec<f64>>> = serde_json::from_value(covariances).map_err(parse_err)?;
+
+            vec_to_array3(full)?
+        }
+        CovarianceType::Tied => {
+            let tied: Vec<Vec<f64>> = serde_json::from_value(covariances).map_err(parse_err)?;
+            let tied = vec_to_array2(tied)?;
+            if tied.dim() != (dim, dim) {
+                return Err(MoebiusError::Shape(format!(
+                    "tied covariance has shape {:?}, expected {:?}", tied.dim(), (dim, dim)
+                )));
+            }
+
+            let mut covs = Array3::zeros((n_comp, dim, dim));
+            for mut cov in covs.outer_iter_mut() {
+                cov.assign(&tied);
+            }
+
+            covs
+        }
+        CovarianceType::Diag => {
+            let diag: Vec<Vec<f64>> = serde_json::from_value(covariances).map_err(parse_err)?;
+            let diag = vec_to_array2(diag)?;
+            if diag.dim() != (n_comp, dim) {
+                return Err(MoebiusError::Shape(format!(
+                    "diag covariances have shape {:?}, expected {:?}", diag.dim(), (n_comp, dim)
+                )));
+            }
+
+            let mut covs = Array3::zeros((n_comp, dim, dim));
+            for (mut cov, variances) in covs.outer_iter_mut().zip(diag.outer_iter()) {
+                cov.diag_mut().assign(&variances);
+            }
+
+            covs
+        }
+        CovarianceType::Spherical => {
+            let spherical: Vec<f64> = serde_json::from_value(covariances).map_err(parse_err)?;
+            if spherical.len() != n_comp {
+                return Err(MoebiusError::Shape(format!(
+                    "spherical covariances have {} entries, expected {}", spherical.len(), n_comp
+                )));
+            }
+
+            let mut covs = Array3::zeros((n_comp, dim, dim));
+            for (mut cov, variance) in covs.outer_iter_mut().zip(spherical) {
+                cov.diag_mut().fill(variance);
+            }
+
+            covs
+        }
+    };
+
+    Ok(covs)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use ndarray::{arr2, arr3};
+    use crate::mixture::GaussianMixture;
+    use crate::olr;
+
+    fn fixture(name: &str) -> GaussianMixture {
+        let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+
+        GaussianMixture::from_sklearn_json(path).unwrap()
+    }
+
+    #[test]
+    fn full_round_trip() {
+        let gmm = fixture("sklearn_full.json");
+
+        let w = vec![5.2194e-01, 4.7806e-01];
+        let means = arr2(&[
+            [1.1987e+00, 1.1542e+00],
+            [4.1592e+00, 4.1487e+00]
+        ]);
+        let covs = arr3(&[
+            [
+                [1.9455e+00, -9.1612e-04],
+                [-9.1612e-04, 1.9703e+00]
+            ],
+            [
+                [1.5160e+00, 1.1011e+00],
+                [1.1011e+00, 1.5178e+00]
+            ]
+        ]);
+
+        assert_eq!(gmm.covs, covs);
+        assert_abs_diff_eq!(olr(w, means, covs)[0], gmm.olr().unwrap()[0], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn tied_round_trip() {
+        let gmm = fixture("sklearn_tied.json");
+
+        let covs = arr3(&[
+            [
+                [1.5, 0.3],
+                [0.3, 1.2]
+            ],
+            [
+                [1.5, 0.3],
+                [0.3, 1.2]
+            ]
+        ]);
+
+        assert_eq!(gmm.covs, covs);
+        assert_abs_diff_eq!(
+            olr(gmm.weights.clone(), gmm.means.clone(), covs)[0],
+            gmm.olr().unwrap()[0],
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn diag_round_trip() {
+        let gmm = fixture("sklearn_diag.json");
+
+        let covs = arr3(&[
+            [
+                [0.5]
+            ],
+            [
+                [0.5]
+            ]
+        ]);
+
+        assert_eq!(gmm.covs, covs);
+        assert_abs_diff_eq!(0.21077243773848037, gmm.olr().unwrap()[0], epsilon = 1e-4);
+    }
+
+    #[test]
+    fn spherical_round_trip() {
+        let gmm = fixture("sklearn_spherical.json");
+
+        let covs = arr3(&[
+            [
+                [2.0, 0.0],
+                [0.0, 2.0]
+            ],
+            [
+                [1.5, 0.0],
+                [0.0, 1.5]
+            ]
+        ]);
+
+        assert_eq!(gmm.covs, covs);
+        assert_abs_diff_eq!(
+            olr(gmm.weights.clone(), gmm.means.clone(), covs)[0],
+            gmm.olr().unwrap()[0],
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn covariance_type_defaults_to_full() {
+        let gmm = GaussianMixture::from_sklearn_str(
+            r#"{"weights": [0.5, 0.5], "means": [[5.0], [2.0]], "covariances": [[[0.5]], [[0.5]]]}"#
+        ).unwrap();
+
+        assert_abs_diff_eq!(0.21077243773848037, gmm.olr().unwrap()[0], epsilon = 1e-4);
+    }
+
+    #[test]
+    fn mismatched_layout_is_rejected() {
+        let result = GaussianMixture::from_sklearn_str(
+            r#"{"weights": [0.5, 0.5], "means": [[5.0], [2.0]], "covariances": [0.5, 0.5], "covariance_type": "diag"}"#
+        );
+
+        assert!(result.is_err());
+    }
+}
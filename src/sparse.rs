@@ -0,0 +1,138 @@
+//! Pairs with a non-negligible OLR, for mixtures too large for the dense matrix.
+//!
+//! `olr_sparse` scans the pairs one row `i` at a time and only keeps those above the cutoff. Every
+//! pair is first scanned on a coarse grid of `PREFILTER_POINTS` points. Sampling the profile can
+//! only lower the peaks and raise the saddles, so the coarse OLR overestimates the exact one, and
+//! pairs whose coarse OLR is well below the cutoff are rejected without the full scan.
+
+use ndarray::prelude::*;
+use rayon::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::gaussian::{ComponentCache, Covariances};
+use crate::olr::{check_shapes, olr_pair, prepare_covs, NoSaddlePolicy, OlrConfig, PairStatus};
+use crate::parallel::{build_pool, resolve_threads};
+use crate::validation::check_finite;
+use crate::workspace::with_thread_workspace;
+
+/// Points of the coarse scan of every pair.
+pub const PREFILTER_POINTS: usize = 64;
+/// Pairs whose coarse OLR is below `PREFILTER_MARGIN * cutoff` are rejected. The margin absorbs
+/// the sampling error of the full scan, which can exceed the exact OLR slightly.
+pub const PREFILTER_MARGIN: f64 = 0.5;
+
+/// Pairs `(i, j, olr)` with `i < j` whose OLR exceeds `cutoff`, in the order of the condensed
+/// matrix. Values are those of `olr_with_config` with the default config, but only the selected
+/// pairs are stored.
+pub fn olr_sparse(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    cutoff: f64
+) -> Result<Vec<(usize, usize, f64)>, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    check_finite(w, means, covs)?;
+    if cutoff.is_nan() {
+        return Err(MoebiusError::InvalidConfig("cutoff is NaN".to_string()));
+    }
+
+    let config = OlrConfig::default();
+    config.validate()?;
+    let covs = prepare_covs(covs.view(), &config);
+    let cache = ComponentCache::new(means.view(), Covariances::Dense(covs.view()));
+    let coarse = OlrConfig {
+        n_points: PREFILTER_POINTS,
+        // At least the same share of the line beyond the means as the full scan.
+        extension: (config.extension * PREFILTER_POINTS).div_ceil(config.n_points),
+        no_saddle: NoSaddlePolicy::AssumeMerged,
+        ..config.clone()
+    };
+
+    let row = |i: usize| -> Result<Vec<(usize, usize, f64)>, MoebiusError> {
+        with_thread_workspace(|workspace| {
+            let mut selected = Vec::new();
+            for j in (i + 1)..w.len() {
+                let (density_i, density_j) = cache.pair(i, j)?;
+                let mut scan = |config: &OlrConfig| -> Result<f64, MoebiusError> {
+                    let (detail, _) = olr_pair(w, &means.view(), density_i, density_j, (i, j), config, &config.detector, workspace)?;
+                    debug_assert_eq!(PairStatus::Complete, detail.status);
+
+                    Ok(detail.value)
+                };
+
+                if config.n_points > PREFILTER_POINTS && scan(&coarse)? < PREFILTER_MARGIN * cutoff {
+                    continue;
+                }
+                let value = scan(&config)?;
+                if value > cutoff {
+                    selected.push((i, j, value));
+                }
+            }
+
+            Ok(selected)
+        })
+    };
+
+    let n_threads = resolve_threads(config.n_threads);
+    let rows: Vec<_> = if n_threads == 1 {
+        (0..w.len()).map(row).collect()
+    } else {
+        build_pool(n_threads)?.install(|| (0..w.len()).into_par_iter().map(row).collect())
+    };
+
+    let mut pairs = Vec::new();
+    for row in rows {
+        pairs.extend(row?);
+    }
+
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array2, Array3};
+    use crate::olr::try_olr;
+    use crate::result::index_to_pair;
+    use crate::sparse::olr_sparse;
+
+    fn mixture() -> (Vec<f64>, Array2<f64>, Array3<f64>) {
+        // Clusters of three close components, far apart from each other.
+        let n = 12;
+        let w = vec![1.0 / n as f64; n];
+        let means = Array2::from_shape_fn((n, 2), |(k, d)| {
+            let (cluster, member) = ((k / 3) as f64, (k % 3) as f64);
+            if d == 0 { 100.0 * cluster + 1.3 * member } else { 0.7 * member * member }
+        });
+        let covs = Array3::from_shape_fn((n, 2, 2), |(k, r, c)| match (r, c) {
+            (0, 0) => 0.5 + 0.1 * (k % 4) as f64,
+            (1, 1) => 0.8,
+            _ => 0.1,
+        });
+
+        (w, means, covs)
+    }
+
+    #[test]
+    fn matches_nonzero_dense_entries() {
+        let (w, means, covs) = mixture();
+        let dense = try_olr(w.clone(), means.clone(), covs.clone()).unwrap();
+        let above = |cutoff: f64| -> Vec<(usize, usize, f64)> {
+            dense.iter().enumerate()
+                .filter(|(_, &v)| v > cutoff)
+                .map(|(k, &v)| {
+                    let (i, j) = index_to_pair(k, w.len());
+                    (i, j, v)
+                })
+                .collect()
+        };
+
+        let sparse = olr_sparse(&w, &means, &covs, 0.0).unwrap();
+        assert_eq!(above(0.0), sparse);
+        assert!(sparse.len() < dense.len());
+
+        for cutoff in [1e-12, 1e-3, 0.1] {
+            assert_eq!(above(cutoff), olr_sparse(&w, &means, &covs, cutoff).unwrap(), "cutoff {}", cutoff);
+        }
+        assert!(olr_sparse(&w, &means, &covs, f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use ndarray::prelude::*;
+
+use crate::error::MoebiusError;
+use crate::mixture::GaussianMixture;
+use crate::olr::{olr_with_stats, OlrConfig, OlrStats};
+
+/// Wall time statistics in seconds.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TimeSummary {
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+}
+
+/// Percentiles of the time spent on a single pair, in seconds.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PairPercentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BenchReport {
+    pub config: OlrConfig,
+    pub n_components: usize,
+    pub n_pairs: usize,
+    pub repeat: usize,
+    /// Wall time of every timed run, in seconds.
+    pub runs: Vec<f64>,
+    pub wall_time: TimeSummary,
+    /// Density evaluations performed by a single run.
+    pub pdf_evaluations: usize,
+    pub pair_time: PairPercentiles,
+    /// Sum of all OLR values of a single run.
+    pub checksum: f64,
+}
+
+/// Cost of a single OLR computation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OlrTiming {
+    /// Wall time of the whole computation.
+    pub total: Duration,
+    /// Mean wall time of a pair. With several threads, pairs overlap, so this may exceed
+    /// `total / n_pairs`.
+    pub pair_mean: Duration,
+    pub n_pairs: usize,
+    /// Density evaluations, `config.grid_len()` for every pair and more for pairs rescanned by
+    /// `NoSaddlePolicy::ExtendAndRetry`.
+    pub pdf_evaluations: usize,
+}
+
+/// Same as `olr_with_config`, additionally returning how long the computation took and how many
+/// density evaluations it performed.
+pub fn olr_timed(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>,
+    config: &OlrConfig
+) -> Result<(Vec<f64>, OlrTiming), MoebiusError> {
+    let mut stats = OlrStats::default();
+    let started = Instant::now();
+    let values = olr_with_stats(w, means, covs, config, &mut stats)?;
+    let total = started.elapsed();
+
+    let n_pairs = stats.pair_times.len();
+    let pair_mean = if n_pairs == 0 {
+        Duration::ZERO
+    } else {
+        stats.pair_times.iter().sum::<Duration>() / n_pairs as u32
+    };
+
+    Ok((values, OlrTiming { total, pair_mean, n_pairs, pdf_evaluations: stats.pdf_evaluations }))
+}
+
+/// Computes OLR values of `gmm` once to warm up and then `repeat` more times, timing every run.
+pub fn bench(gmm: &GaussianMixture, config: &OlrConfig, repeat: usize) -> Result<BenchReport, MoebiusError> {
+    if repeat == 0 {
+        return Err(MoebiusError::InvalidConfig("repeat must be positive".to_string()));
+    }
+
+    let run = |stats: &mut OlrStats| -> Result<f64, MoebiusError> {
+        let values = olr_with_stats(
+            black_box(&gmm.weights),
+            black_box(&gmm.means),
+            black_box(&gmm.covs),
+            config,
+            stats
+        )?;
+
+        Ok(black_box(values.iter().sum()))
+    };
+
+    let checksum = run(&mut OlrStats::default())?;
+
+    let mut runs = Vec::with_capacity(repeat);
+    let mut stats = OlrStats::default();
+    for _ in 0..repeat {
+        let started = Instant::now();
+        black_box(run(&mut stats)?);
+        runs.push(started.elapsed().as_secs_f64());
+    }
+
+    let mut sorted_runs = runs.clone();
+    sorted_runs.sort_by(f64::total_cmp);
+    let mut pair_times = stats.pair_times.clone();
+    pair_times.sort();
+
+    Ok(BenchReport {
+        config: config.clone(),
+        n_components: gmm.n_components(),
+        n_pairs: pair_times.len() / repeat,
+        repeat,
+        wall_time: TimeSummary {
+            min: sorted_runs[0],
+            median: percentile(&sorted_runs, 0.5),
+            mean: runs.iter().sum::<f64>() / repeat as f64,
+        },
+        runs,
+        pdf_evaluations: stats.pdf_evaluations / repeat,
+        pair_time: PairPercentiles {
+            p50: duration_percentile(&pair_times, 0.5),
+            p90: duration_percentile(&pair_times, 0.9),
+            p99: duration_percentile(&pair_times, 0.99),
+            max: pair_times.last().map_or(0.0, Duration::as_secs_f64),
+        },
+        checksum,
+    })
+}
+
+/// Nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn duration_percentile(sorted: &[Duration], q: f64) -> f64 {
+    let secs: Vec<f64> = sorted.iter().map(Duration::as_secs_f64).collect();
+
+    percentile(&secs, q)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3};
+    use crate::mixture::GaussianMixture;
+    use crate::olr::{olr_with_config, OlrConfig};
+    use crate::timing::{bench, olr_timed, percentile};
+
+    fn mixture() -> GaussianMixture {
+        GaussianMixture::new(
+            vec![0.3, 0.3, 0.4],
+            arr2(&[[0.0], [2.0], [4.0]]),
+            arr3(&[[[0.5]], [[0.5]], [[0.5]]])
+        ).unwrap()
+    }
+
+    #[test]
+    fn repeat_count_is_respected() {
+        let config = OlrConfig { n_points: 100, ..OlrConfig::default() };
+        let report = bench(&mixture(), &config, 4).unwrap();
+
+        assert_eq!(4, report.repeat);
+        assert_eq!(4, report.runs.len());
+        assert_eq!(3, report.n_pairs);
+        assert_eq!(3 * config.grid_len(), report.pdf_evaluations);
+        assert!(report.wall_time.min <= report.wall_time.median);
+        assert!(report.pair_time.p50 <= report.pair_time.p90);
+        assert!(report.pair_time.p90 <= report.pair_time.p99);
+        assert!(report.pair_time.p99 <= report.pair_time.max);
+    }
+
+    #[test]
+    fn timed_evaluation_count() {
+        let gmm = mixture();
+        let config = OlrConfig { n_points: 200, n_threads: Some(1), ..OlrConfig::default() };
+
+        let (values, timing) = olr_timed(&gmm.weights, &gmm.means, &gmm.covs, &config).unwrap();
+
+        assert_eq!(olr_with_config(&gmm.weights, &gmm.means, &gmm.covs, &config).unwrap(), values);
+        assert_eq!(3, timing.n_pairs);
+        assert_eq!(config.grid_len() * 3, timing.pdf_evaluations);
+        assert!(timing.pair_mean <= timing.total);
+    }
+
+    #[test]
+    fn zero_repeat_is_rejected() {
+        assert!(bench(&mixture(), &OlrConfig::default(), 0).is_err());
+    }
+
+    #[test]
+    fn nearest_rank() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+
+        assert_eq!(2.0, percentile(&sorted, 0.5));
+        assert_eq!(4.0, percentile(&sorted, 0.99));
+        assert_eq!(1.0, percentile(&sorted, 0.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn report_serializes() {
+        let report = bench(&mixture(), &OlrConfig { n_points: 100, ..OlrConfig::default() }, 2).unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+
+        for key in ["config", "n_pairs", "repeat", "runs", "wall_time", "pdf_evaluations", "pair_time", "checksum"] {
+            assert!(json.get(key).is_some(), "missing {}", key);
+        }
+        assert_eq!(100, json["config"]["n_points"]);
+        assert_eq!(2, json["runs"].as_array().unwrap().len());
+        assert!(json["wall_time"]["median"].is_f64());
+    }
+}
@@ -0,0 +1,297 @@
+use std::borrow::Cow;
+
+use ndarray::prelude::*;
+use ndarray::Data;
+
+use crate::error::MoebiusError;
+use crate::gaussian::Gaussian;
+use crate::olr::{check_shapes, OlrConfig};
+
+/// Largest deviation of the sum of the weights from `1` accepted by `validate_mixture` and by
+/// `OlrConfig::strict`.
+pub const WEIGHT_SUM_TOLERANCE: f64 = 1e-6;
+
+/// Mixture left after dropping components with NaN parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroppedComponents {
+    pub w: Vec<f64>,
+    pub means: Array2<f64>,
+    pub covs: Array3<f64>,
+    /// Original index of every remaining component, i.e. component `k` of the result is
+    /// component `kept[k]` of the input.
+    pub kept: Vec<usize>,
+    /// Original indices of the dropped components.
+    pub dropped: Vec<usize>,
+}
+
+/// Checks that all weights, means and covariances are finite and reports the first offending
+/// entry with its coordinates, e.g. `covs[12][3][7] is NaN`.
+pub fn check_finite<M, C>(w: &[f64], means: &ArrayBase<M, Ix2>, covs: &ArrayBase<C, Ix3>) -> Result<(), MoebiusError>
+where
+    M: Data<Elem = f64>,
+    C: Data<Elem = f64>,
+{
+    reject(w, means, covs, |v| !v.is_finite())
+}
+
+/// Checks that the selected `components` of a mixture of `n_comp` components are in range and
+/// distinct.
+pub(crate) fn check_selection(components: &[usize], n_comp: usize) -> Result<(), MoebiusError> {
+    let mut seen = vec![false; n_comp];
+    for &k in components {
+        match seen.get_mut(k) {
+            None => return Err(MoebiusError::Shape(format!(
+                "component {} is out of range for a mixture of {} components", k, n_comp
+            ))),
+            Some(true) => return Err(MoebiusError::Shape(format!("component {} is selected twice", k))),
+            Some(selected) => *selected = true,
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes every component whose weight, mean or covariance contains NaN. Infinite values are
+/// still rejected as in `check_finite`, since they do not come from missing data.
+pub fn drop_nan_components(
+    w: &[f64],
+    means: &Array2<f64>,
+    covs: &Array3<f64>
+) -> Result<DroppedComponents, MoebiusError> {
+    check_shapes(w.len(), means, covs)?;
+    reject(w, means, covs, f64::is_infinite)?;
+
+    let (kept, dropped): (Vec<usize>, Vec<usize>) = (0..w.len()).partition(|&k| {
+        !w[k].is_nan()
+            && !means.row(k).iter().any(|v| v.is_nan())
+            && !covs.index_axis(Axis(0), k).iter().any(|v| v.is_nan())
+    });
+
+    Ok(DroppedComponents {
+        w: kept.iter().map(|&k| w[k]).collect(),
+        means: means.select(Axis(0), &kept),
+        covs: covs.select(Axis(0), &kept),
+        kept,
+        dropped,
+    })
+}
+
+/// Runs every check on a mixture and reports all problems found instead of the first one: shapes,
+/// square covariances, finite parameters, non-negative weights summing to `1` within
+/// `WEIGHT_SUM_TOLERANCE`, and symmetric positive definite covariances.
+///
+/// Non-finite entries are reported once per weight, mean and covariance matrix. Covariances with
+/// non-finite entries are not factorized.
+pub fn validate_mixture(w: &[f64], means: &Array2<f64>, covs: &Array3<f64>) -> Result<(), Vec<MoebiusError>> {
+    let (n_covs, nrows, ncols) = covs.dim();
+    let mut errors = Vec::new();
+    if nrows != ncols {
+        errors.push(MoebiusError::Shape(format!("covariance matrices must be square, got {} x {}", nrows, ncols)));
+    } else if let Err(e) = check_shapes(w.len(), means, covs) {
+        errors.push(e);
+    }
+
+    for (k, &v) in w.iter().enumerate() {
+        if !v.is_finite() {
+            errors.push(non_finite(format!("w[{}]", k), v));
+        } else if v < 0.0 {
+            errors.push(MoebiusError::Distribution(format!("w[{}] is negative: {}", k, v)));
+        }
+    }
+    let total: f64 = w.iter().sum();
+    if total.is_finite() && (total - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+        errors.push(MoebiusError::Distribution(format!("weights sum to {}, expected 1", total)));
+    }
+
+    for (k, mean) in means.outer_iter().enumerate() {
+        if let Some((d, &v)) = mean.indexed_iter().find(|(_, v)| !v.is_finite()) {
+            errors.push(non_finite(format!("means[{}][{}]", k, d), v));
+        }
+    }
+    let mut finite = vec![true; n_covs];
+    for (k, cov) in covs.outer_iter().enumerate() {
+        if let Some(((r, c), &v)) = cov.indexed_iter().find(|(_, v)| !v.is_finite()) {
+            errors.push(non_finite(format!("covs[{}][{}][{}]", k, r, c), v));
+            finite[k] = false;
+        }
+    }
+
+    if errors.iter().all(|e| !matches!(e, MoebiusError::Shape(_))) {
+        // Centered, so that covariances are checked regardless of the means.
+        let origin = Array1::zeros(ncols);
+        for (k, cov) in covs.outer_iter().enumerate().filter(|&(k, _)| finite[k]) {
+            if let Err(e) = Gaussian::new(origin.view(), cov, k, None) {
+                errors.push(e);
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Sum of the weights if `config.auto_normalize` divides them by it, i.e. if they do not sum to
+/// `1` within `WEIGHT_SUM_TOLERANCE`.
+pub fn normalized_sum(w: &[f64], config: &OlrConfig) -> Option<f64> {
+    let total: f64 = w.iter().sum();
+
+    (config.auto_normalize && !w.is_empty() && (total - 1.0).abs() > WEIGHT_SUM_TOLERANCE).then_some(total)
+}
+
+/// Weights as used by the pairs: divided by their sum as described in `normalized_sum`, which
+/// must then be positive, and otherwise rejected with `config.strict` unless they sum to `1`.
+pub(crate) fn normalize_weights<'a>(w: &'a [f64], config: &OlrConfig) -> Result<Cow<'a, [f64]>, MoebiusError> {
+    if let Some(total) = normalized_sum(w, config) {
+        if !(total > 0.0 && total.is_finite()) {
+            return Err(MoebiusError::Distribution(format!("weights sum to {}, so they cannot be normalized", total)));
+        }
+        return Ok(Cow::Owned(w.iter().map(|v| v / total).collect()));
+    }
+    let total: f64 = w.iter().sum();
+    if config.strict && !w.is_empty() && (total - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+        return Err(MoebiusError::Distribution(format!(
+            "weights sum to {}, expected 1; set auto_normalize to divide them by their sum", total
+        )));
+    }
+
+    Ok(Cow::Borrowed(w))
+}
+
+/// `(component, dimension)` of every variance below `floor * scale^2`, where `scale` is the
+/// largest absolute value of the means, or `1` if all means are zero. Such variances are likely
+/// fitting artifacts and push densities towards the limits of the floating-point range.
+pub fn small_variances(means: &Array2<f64>, covs: &Array3<f64>, floor: f64) -> Vec<(usize, usize)> {
+    let scale = means.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+    let threshold = floor * if scale > 0.0 { scale * scale } else { 1.0 };
+
+    covs.outer_iter()
+        .enumerate()
+        .flat_map(|(k, cov)| {
+            cov.diag().iter().enumerate()
+                .filter(|(_, &v)| v < threshold)
+                .map(|(d, _)| (k, d))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Reports the first entry, in the order weights, means, covariances, for which `rejected` holds.
+fn reject<M, C>(
+    w: &[f64],
+    means: &ArrayBase<M, Ix2>,
+    covs: &ArrayBase<C, Ix3>,
+    rejected: impl Fn(f64) -> bool
+) -> Result<(), MoebiusError>
+where
+    M: Data<Elem = f64>,
+    C: Data<Elem = f64>,
+{
+    if let Some((k, v)) = w.iter().enumerate().find(|(_, v)| rejected(**v)) {
+        return Err(non_finite(format!("w[{}]", k), *v));
+    }
+    if let Some(((i, j), v)) = means.indexed_iter().find(|(_, v)| rejected(**v)) {
+        return Err(non_finite(format!("means[{}][{}]", i, j), *v));
+    }
+    if let Some(((i, r, c), v)) = covs.indexed_iter().find(|(_, v)| rejected(**v)) {
+        return Err(non_finite(format!("covs[{}][{}][{}]", i, r, c), *v));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn non_finite(location: String, value: f64) -> MoebiusError {
+    let kind = if value.is_nan() { "NaN" } else if value > 0.0 { "inf" } else { "-inf" };
+
+    MoebiusError::Distribution(format!("{} is {}", location, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, arr3, Array2, Array3};
+    use crate::error::MoebiusError;
+    use crate::validation::{check_finite, drop_nan_components, small_variances, validate_mixture};
+
+    fn mixture() -> (Vec<f64>, Array2<f64>, Array3<f64>) {
+        (
+            vec![0.2, 0.3, 0.5],
+            arr2(&[[0.0, 1.0], [1.0, 2.0], [2.0, 3.0]]),
+            arr3(&[[[1.0, 0.0], [0.0, 1.0]]; 3]),
+        )
+    }
+
+    #[test]
+    fn reports_coordinates() {
+        let (mut w, mut means, mut covs) = mixture();
+        assert!(check_finite(&w, &means, &covs).is_ok());
+
+        covs[[2, 1, 0]] = f64::NAN;
+        assert!(check_finite(&w, &means, &covs).unwrap_err().to_string().ends_with("covs[2][1][0] is NaN"));
+        means[[1, 1]] = f64::NEG_INFINITY;
+        assert!(check_finite(&w, &means, &covs).unwrap_err().to_string().ends_with("means[1][1] is -inf"));
+        w[0] = f64::NAN;
+        assert!(check_finite(&w, &means, &covs).unwrap_err().to_string().ends_with("w[0] is NaN"));
+    }
+
+    #[test]
+    fn reports_every_defect() {
+        let (w, means, covs) = mixture();
+        assert!(validate_mixture(&w, &means, &covs).is_ok());
+
+        let w = vec![0.5, -0.1, 0.5];
+        let mut means = means;
+        means[[0, 1]] = f64::NAN;
+        let mut covs = covs;
+        covs[[1, 0, 1]] = 0.5;
+        covs[[0, 0, 0]] = -1.0;
+
+        let errors = validate_mixture(&w, &means, &covs).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(5, errors.len(), "{:?}", messages);
+        assert!(messages[0].ends_with("w[1] is negative: -0.1"));
+        assert!(messages[1].contains("weights sum to 0.9"));
+        assert!(messages[2].ends_with("means[0][1] is NaN"));
+        assert!(matches!(errors[3], MoebiusError::NotPositiveDefinite { component: 0, pair: None }));
+        assert!(messages[4].contains("component 1 is not symmetric"));
+    }
+
+    #[test]
+    fn reports_shapes() {
+        let (w, means, _) = mixture();
+        let errors = validate_mixture(&w, &means, &Array3::zeros((3, 2, 3))).unwrap_err();
+
+        assert_eq!(1, errors.len());
+        assert!(errors[0].to_string().contains("must be square"));
+        assert!(validate_mixture(&w[..2], &means, &arr3(&[[[1.0, 0.0], [0.0, 1.0]]; 3])).is_err());
+    }
+
+    #[test]
+    fn flags_small_variances() {
+        let means = arr2(&[[100.0, 0.0], [0.0, 0.0]]);
+        let covs = arr3(&[[[1e-9, 0.0], [0.0, 1.0]], [[1.0, 0.0], [0.0, 1e-12]]]);
+
+        // Relative to the squared mean magnitude 1e4.
+        assert_eq!(vec![(0, 0), (1, 1)], small_variances(&means, &covs, 1e-12));
+        assert_eq!(vec![(1, 1)], small_variances(&means, &covs, 1e-14));
+        assert!(small_variances(&means, &covs, 1e-17).is_empty());
+    }
+
+    #[test]
+    fn drops_and_remaps() {
+        let (w, mut means, covs) = mixture();
+        means[[1, 0]] = f64::NAN;
+
+        let cleaned = drop_nan_components(&w, &means, &covs).unwrap();
+        assert_eq!(vec![0, 2], cleaned.kept);
+        assert_eq!(vec![1], cleaned.dropped);
+        assert_eq!(vec![0.2, 0.5], cleaned.w);
+        assert_eq!(arr2(&[[0.0, 1.0], [2.0, 3.0]]), cleaned.means);
+        assert_eq!(2, cleaned.covs.dim().0);
+
+        let mut covs = covs;
+        covs[[0, 0, 0]] = f64::INFINITY;
+        assert!(drop_nan_components(&w, &means, &covs).unwrap_err().to_string().ends_with("covs[0][0][0] is inf"));
+    }
+}
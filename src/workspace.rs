@@ -0,0 +1,145 @@
+//! Buffers reused across the pairs of a scan and across calls.
+//!
+//! Scanning a pair needs the sampled profile, the current point of the line and scratch space
+//! for the triangular solves of the densities. A `Workspace` holds all of them, so that after
+//! the first pair has grown the buffers to the size of the mixture, further pairs allocate
+//! nothing. Every thread running pairs uses its own workspace, and `OlrSession` keeps one across
+//! calls, together with a pool whose threads keep theirs.
+
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+use ndarray::prelude::*;
+use rayon::ThreadPool;
+
+use crate::error::MoebiusError;
+use crate::extrema::Extrema;
+use crate::gaussian::{ComponentCache, Covariances, Gaussian};
+use crate::olr::{masked_pairs, masked_weights, olr_impl, prepare_covs, values, IndexedOlr, OlrConfig};
+use crate::parallel::{build_pool, resolve_threads};
+
+/// Scratch space of density evaluations, passed to `ComponentDensity::ln_pdf_with`. The buffers
+/// are private; implementations outside the crate ignore them.
+#[derive(Debug, Default)]
+pub struct Scratch {
+    /// `x - mean`, solved in place.
+    pub(crate) diff: Vec<f64>,
+    /// Projection of `diff` onto the factors of a low-rank covariance.
+    pub(crate) projected: Vec<f64>,
+    /// Coordinates of `x` in the dimensions of a block, or rows of the points in them.
+    pub(crate) gathered: Vec<f64>,
+    /// `L^-1 (X - mean)` of a matrix of points `X`, row by row.
+    pub(crate) solved: Vec<f64>,
+    /// Quadratic forms of a matrix of points in a single block.
+    pub(crate) quadratics: Vec<f64>,
+    /// A column of a matrix of points, copied to be contiguous.
+    pub(crate) column: Vec<f64>,
+}
+
+/// Reusable buffers of the pair scan.
+#[derive(Debug, Default)]
+pub struct Workspace {
+    pub(crate) profile: Vec<f64>,
+    pub(crate) point: Vec<f64>,
+    pub(crate) delta: Vec<f64>,
+    pub(crate) extrema: Extrema,
+    pub(crate) scratch: Scratch,
+    /// Chunk of points of the line, as the columns of a `d x n` matrix stored row by row.
+    pub(crate) columns: Vec<f64>,
+    /// Log-densities of the two components of the pair at the points of `columns`.
+    pub(crate) ln_pdfs: (Vec<f64>, Vec<f64>),
+    /// Line parameters of the points of `GridStrategy::Adaptive`.
+    pub(crate) grid: Vec<f64>,
+}
+
+thread_local! {
+    static THREAD_WORKSPACE: RefCell<Workspace> = RefCell::new(Workspace::default());
+}
+
+/// Calls `f` with the workspace of the current thread, or with a new one if it is already in use
+/// further up the stack, e.g. by a callback that computes another OLR.
+pub(crate) fn with_thread_workspace<R>(f: impl FnOnce(&mut Workspace) -> R) -> R {
+    THREAD_WORKSPACE.with(|workspace| match workspace.try_borrow_mut() {
+        Ok(mut workspace) => f(&mut workspace),
+        Err(_) => f(&mut Workspace::default()),
+    })
+}
+
+/// Buffers an `OlrSession` lends to `scan_pairs`: its workspace for serial runs, and its pool,
+/// `None` for a single thread, for parallel ones.
+pub(crate) struct SessionBuffers<'a> {
+    pub(crate) workspace: &'a mut Workspace,
+    pub(crate) pool: Option<&'a ThreadPool>,
+}
+
+/// `olr_with_config` with a fixed config and buffers kept between calls, for computing the OLR of
+/// many mixtures of similar size, e.g. in every iteration of an EM fit. Serial runs reuse the
+/// workspace of the session. For parallel runs the session builds a pool when it is created,
+/// with the number of threads `config.n_threads` resolves to then, and its threads keep their
+/// workspaces between calls.
+#[derive(Debug)]
+pub struct OlrSession {
+    config: OlrConfig,
+    workspace: Workspace,
+    pool: Option<ThreadPool>,
+    factors: Option<Factors>,
+}
+
+/// Components factorized by `OlrSession::olr_masked`, with the means and covariances they were
+/// factorized from.
+#[derive(Debug)]
+struct Factors {
+    means: Array2<f64>,
+    covs: Array3<f64>,
+    components: Vec<OnceLock<Gaussian>>,
+}
+
+impl OlrSession {
+    pub fn new(config: OlrConfig) -> Result<Self, MoebiusError> {
+        config.validate()?;
+        let n_threads = resolve_threads(config.n_threads);
+        let pool = if n_threads == 1 { None } else { Some(build_pool(n_threads)?) };
+
+        Ok(OlrSession { config, workspace: Workspace::default(), pool, factors: None })
+    }
+
+    pub fn config(&self) -> &OlrConfig {
+        &self.config
+    }
+
+    /// Same as `olr_with_config` with the config of the session.
+    pub fn olr(&mut self, w: &[f64], means: &Array2<f64>, covs: &Array3<f64>) -> Result<Vec<f64>, MoebiusError> {
+        let config = &self.config;
+        let session = SessionBuffers { workspace: &mut self.workspace, pool: self.pool.as_ref() };
+        let details = olr_impl(w, means.view(), covs.view(), config, &config.detector, None, Some(session))?;
+
+        Ok(values(details))
+    }
+
+    /// Same as `olr_masked` with the config of the session. Components are factorized once and
+    /// reused by later calls with the same means and covariances, e.g. while sweeping over
+    /// masks; other inputs replace them.
+    pub fn olr_masked(
+        &mut self,
+        w: &[f64],
+        means: &Array2<f64>,
+        covs: &Array3<f64>,
+        components: &[usize],
+        renormalize: bool
+    ) -> Result<IndexedOlr, MoebiusError> {
+        let config = &self.config;
+        let w = masked_weights(w, means, covs, components, renormalize, config)?;
+        let (inputs, cached) = match self.factors.take() {
+            Some(factors) if factors.means == means && factors.covs == covs => ((factors.means, factors.covs), factors.components),
+            _ => ((means.clone(), covs.clone()), Vec::new()),
+        };
+
+        let prepared = prepare_covs(covs.view(), config);
+        let cache = ComponentCache::with_components(means.view(), Covariances::Dense(prepared.view()), cached);
+        let session = SessionBuffers { workspace: &mut self.workspace, pool: self.pool.as_ref() };
+        let result = masked_pairs(&w, means.view(), &cache, components, config, Some(session));
+        self.factors = Some(Factors { means: inputs.0, covs: inputs.1, components: cache.into_components() });
+
+        result
+    }
+}
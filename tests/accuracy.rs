@@ -0,0 +1,157 @@
+//! The default fast path must stay within the documented `1e-4` of the grid-independent
+//! reference values of `olr_exact`.
+
+mod common;
+
+use moebius::{olr_detailed, olr_exact, olr_with_config, Detector, OlrConfig, OlrDetails, Resolution, DEFAULT_RESOLUTION_TOLERANCE};
+#[cfg(feature = "serde")]
+use moebius::DEFAULT_MAX_POINTS;
+
+use common::{random_mixture, Rng};
+
+const SEED: u64 = 0x6578616374;
+const N_MIXTURES: usize = 15;
+const EPSILON: f64 = 1e-4;
+
+#[test]
+fn default_scan_matches_reference() {
+    let mut rng = Rng::new(SEED);
+
+    for _ in 0..N_MIXTURES {
+        let (w, means, covs) = random_mixture(&mut rng);
+        let fast = olr_with_config(&w, &means, &covs, &OlrConfig::default()).unwrap();
+        let exact = olr_exact(&w, &means, &covs, 1e-12).unwrap();
+
+        for (k, (fast, exact)) in fast.iter().zip(&exact).enumerate() {
+            assert!((fast - exact).abs() <= EPSILON, "pair {}: fast {} vs exact {}", k, fast, exact);
+        }
+    }
+}
+
+#[test]
+fn parabolic_detector_reduces_grid_sensitivity() {
+    let mut rng = Rng::new(SEED);
+    let mixtures: Vec<_> = (0..N_MIXTURES).map(|_| random_mixture(&mut rng)).collect();
+    let exact: Vec<_> = mixtures.iter().map(|(w, means, covs)| olr_exact(w, means, covs, 1e-12).unwrap()).collect();
+
+    for n_points in [20, 50, 200] {
+        let max_error = |detector| {
+            mixtures.iter().zip(&exact)
+                .flat_map(|((w, means, covs), exact)| {
+                    let config = OlrConfig { n_points, detector, ..OlrConfig::default() };
+                    let values = olr_with_config(w, means, covs, &config).unwrap();
+                    values.into_iter().zip(exact).map(|(value, exact)| (value - exact).abs()).collect::<Vec<_>>()
+                })
+                .fold(0.0f64, f64::max)
+        };
+        let (sign_change, parabolic) = (max_error(Detector::SignChange), max_error(Detector::Parabolic));
+
+        assert!(parabolic < sign_change / 3.0, "{} points: {} vs {}", n_points, parabolic, sign_change);
+    }
+}
+
+/// Configurations whose error estimates must bound the deviation from the reference: the grid
+/// values, the extrapolated ones, and the refinements of `Resolution::Auto`.
+fn estimated_configs() -> [OlrConfig; 3] {
+    [
+        OlrConfig::default(),
+        OlrConfig { extrapolate: true, ..OlrConfig::default() },
+        OlrConfig { resolution: Resolution::auto(DEFAULT_RESOLUTION_TOLERANCE), ..OlrConfig::default() },
+    ]
+}
+
+/// Asserts that every error estimate of `details` bounds the deviation from `exact`, and that
+/// every pair with a saddle has one.
+fn assert_error_bounded(details: &OlrDetails, exact: &[f64], label: &str) {
+    for (k, (detail, exact)) in details.pairs.iter().zip(exact).enumerate() {
+        assert_eq!(detail.ln_saddle.is_some(), detail.error_estimate.is_some(), "{} pair {}: {:?}", label, k, detail);
+        if let Some(error) = detail.error_estimate {
+            let deviation = (detail.value - exact).abs();
+            assert!(deviation <= error, "{} pair {}: deviation {:e} exceeds estimate {:e}", label, k, deviation, error);
+        }
+    }
+}
+
+#[test]
+fn error_estimate_bounds_deviation_from_reference() {
+    let mut rng = Rng::new(SEED);
+
+    for _ in 0..N_MIXTURES {
+        let (w, means, covs) = random_mixture(&mut rng);
+        let exact = olr_exact(&w, &means, &covs, 1e-12).unwrap();
+
+        for config in estimated_configs() {
+            let details = olr_detailed(&w, &means, &covs, &config).unwrap();
+            assert_error_bounded(&details, &exact, &format!("{:?}", config.resolution));
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn error_estimate_bounds_deviation_on_fixtures() {
+    for name in ["full", "diag", "spherical", "tied"] {
+        let contents = std::fs::read_to_string(format!("tests/fixtures/sklearn_{}.json", name)).unwrap();
+        let gmm = moebius::GaussianMixture::from_sklearn_str(&contents).unwrap();
+        let exact = olr_exact(&gmm.weights, &gmm.means, &gmm.covs, 1e-12).unwrap();
+
+        for config in estimated_configs() {
+            let details = olr_detailed(&gmm.weights, &gmm.means, &gmm.covs, &config).unwrap();
+            assert_error_bounded(&details, &exact, name);
+        }
+    }
+}
+
+#[test]
+fn extrapolation_approaches_reference() {
+    let mut rng = Rng::new(SEED);
+
+    for _ in 0..N_MIXTURES {
+        let (w, means, covs) = random_mixture(&mut rng);
+        let exact = olr_exact(&w, &means, &covs, 1e-12).unwrap();
+        let grid = olr_with_config(&w, &means, &covs, &OlrConfig::default()).unwrap();
+        let extrapolated = olr_with_config(&w, &means, &covs, &OlrConfig { extrapolate: true, ..OlrConfig::default() }).unwrap();
+
+        // Extrapolating turns the grid error into that of the Richardson step, of higher order. A
+        // grid point may hit an extremum of single pairs, so only the worst pairs are compared.
+        let max_error = |values: &[f64]| values.iter().zip(&exact).map(|(value, exact)| (value - exact).abs()).fold(0.0, f64::max);
+        let (grid_error, extrapolated_error) = (max_error(&grid), max_error(&extrapolated));
+        assert!(extrapolated_error * 10.0 <= grid_error, "{:e} vs {:e}", extrapolated_error, grid_error);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn detectors_agree_on_fixtures() {
+    for name in ["full", "diag", "spherical", "tied"] {
+        let contents = std::fs::read_to_string(format!("tests/fixtures/sklearn_{}.json", name)).unwrap();
+        let gmm = moebius::GaussianMixture::from_sklearn_str(&contents).unwrap();
+        let olr = |detector| {
+            let config = OlrConfig { detector, ..OlrConfig::default() };
+            olr_with_config(&gmm.weights, &gmm.means, &gmm.covs, &config).unwrap()
+        };
+
+        for (sign_change, parabolic) in olr(Detector::SignChange).iter().zip(olr(Detector::Parabolic)) {
+            assert!((sign_change - parabolic).abs() <= EPSILON, "{}: {} vs {}", name, sign_change, parabolic);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn auto_resolution_converges_on_fixtures() {
+    for name in ["full", "diag", "spherical", "tied"] {
+        let contents = std::fs::read_to_string(format!("tests/fixtures/sklearn_{}.json", name)).unwrap();
+        let gmm = moebius::GaussianMixture::from_sklearn_str(&contents).unwrap();
+        let config = OlrConfig { resolution: Resolution::auto(DEFAULT_RESOLUTION_TOLERANCE), ..OlrConfig::default() };
+        let auto = olr_detailed(&gmm.weights, &gmm.means, &gmm.covs, &config).unwrap();
+        let fine = OlrConfig { n_points: DEFAULT_MAX_POINTS, ..OlrConfig::default() };
+        let fine = olr_with_config(&gmm.weights, &gmm.means, &gmm.covs, &fine).unwrap();
+
+        for (detail, fine) in auto.pairs.iter().zip(fine) {
+            let refinement = detail.refinement.unwrap();
+            assert!(refinement.delta < DEFAULT_RESOLUTION_TOLERANCE, "{}: {:?}", name, refinement);
+            assert!((detail.value - fine).abs() <= 10.0 * DEFAULT_RESOLUTION_TOLERANCE, "{}: {} vs {}", name, detail.value, fine);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Steady-state calls of `OlrSession` must not allocate per pair. The only allocations left are
+//! per call, like the results vector, and per component, like the factorizations, so the number
+//! of allocations grows linearly with the number of components while the number of pairs grows
+//! quadratically. Allocations of rayon pool threads count towards the call that runs them, so
+//! tests starting pools hold `POOLS`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use ndarray::{arr1, arr2, Array2, Array3};
+use moebius::{olr_arrays, olr_with_config, olr_with_covariances, Covariance, GaussianMixture, OlrConfig, OlrSession};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Allocations of all rayon pool threads.
+static POOL_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static POOLS: Mutex<()> = Mutex::new(());
+
+fn count() {
+    if rayon::current_thread_index().is_some() {
+        POOL_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    } else {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Allocations made by `f` on the current thread and on pool threads.
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get) + POOL_ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+
+    ALLOCATIONS.with(Cell::get) + POOL_ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// `n_comp` overlapping components in 3 dimensions with dense covariances.
+fn mixture(n_comp: usize) -> (Vec<f64>, Array2<f64>, Array3<f64>) {
+    let w = vec![1.0 / n_comp as f64; n_comp];
+    let means = Array2::from_shape_fn((n_comp, 3), |(k, d)| ((k * 7 + d * 3) % 5) as f64 * 0.8);
+    let covs = Array3::from_shape_fn((n_comp, 3, 3), |(k, r, c)| {
+        if r == c { 1.0 + 0.1 * (k % 3) as f64 } else { 0.3 }
+    });
+
+    (w, means, covs)
+}
+
+/// Allocations of the calls of `session.olr` on the mixtures, after a warm-up that grows the
+/// buffers of the session, and those of every thread of its pool, to the largest mixture.
+fn session_allocations(config: &OlrConfig, mixtures: &[(Vec<f64>, Array2<f64>, Array3<f64>)]) -> Vec<usize> {
+    let mut session = OlrSession::new(config.clone()).unwrap();
+    for (w, means, covs) in mixtures.iter().rev().chain(mixtures) {
+        assert_eq!(olr_with_config(w, means, covs, config).unwrap(), session.olr(w, means, covs).unwrap());
+    }
+
+    mixtures.iter()
+        .map(|(w, means, covs)| allocations(|| {
+            session.olr(w, means, covs).unwrap();
+        }))
+        .collect()
+}
+
+#[test]
+fn no_allocations_per_pair() {
+    let _pools = POOLS.lock().unwrap();
+    let mixtures: Vec<_> = [4, 8, 12].into_iter().map(mixture).collect();
+
+    let serial = session_allocations(&OlrConfig { n_threads: Some(1), ..OlrConfig::default() }, &mixtures);
+    // 6, 28 and 66 pairs for 4, 8 and 12 components.
+    assert_eq!(serial[1] - serial[0], serial[2] - serial[1], "allocations {:?}", serial);
+
+    // The default config runs on a pool if the machine has several cores. Handing the calls to
+    // the pool of the session costs a few allocations of rayon but none per pair, while a pool
+    // per call would cost dozens.
+    for config in [OlrConfig::default(), OlrConfig { n_threads: Some(2), ..OlrConfig::default() }] {
+        let parallel = session_allocations(&config, &mixtures);
+        assert!(
+            parallel.iter().zip(&serial).all(|(parallel, serial)| *parallel <= serial + 16),
+            "allocations {:?} with {:?} threads, {:?} serially", parallel, config.n_threads, serial
+        );
+    }
+}
+
+#[test]
+fn structured_covariances_reuse_scratch() {
+    // Block-diagonal and low-rank covariances need scratch space for every point.
+    let covs = [
+        Covariance::BlockDiagonal(vec![(vec![0, 2], Array2::from_shape_vec((2, 2), vec![1.0, 0.2, 0.2, 1.5]).unwrap()), (vec![1], Array2::eye(1))]),
+        Covariance::LowRank {
+            factors: Array2::from_shape_vec((3, 1), vec![0.5, -0.3, 0.8]).unwrap(),
+            diag: arr1(&[0.6, 0.9, 0.7]),
+        },
+    ];
+    let means = arr2(&[[0.0, 0.0, 0.0], [1.0, 0.5, -0.5]]);
+    let config = OlrConfig { n_threads: Some(1), ..OlrConfig::default() };
+    let compute = |n_points: usize| {
+        let config = OlrConfig { n_points, ..config.clone() };
+        allocations(|| {
+            olr_with_covariances(&[0.5, 0.5], &means, &covs, &config).unwrap();
+        })
+    };
+
+    compute(10_000);
+    // The number of points sampled does not change the number of allocations.
+    assert_eq!(compute(100), compute(10_000));
+}
+
+#[test]
+fn mixture_borrows_components() {
+    let _pools = POOLS.lock().unwrap();
+    let (w, means, covs) = mixture(6);
+    let mixture = GaussianMixture::new(w, means, covs).unwrap();
+
+    let mut trace = 0.0;
+    assert_eq!(0, allocations(|| {
+        for component in mixture.components() {
+            trace += component.weight * component.cov.diag().sum() + component.mean.sum();
+        }
+    }));
+    assert!(trace > 0.0);
+    assert_eq!(mixture.component(5).unwrap().mean, mixture.means.row(5));
+    assert!(mixture.component(6).is_none());
+
+    let expected = olr_arrays(mixture.weights.as_slice().into(), mixture.means.view(), mixture.covs.view()).unwrap();
+    assert_eq!(expected.to_vec(), mixture.olr().unwrap());
+}
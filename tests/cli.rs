@@ -0,0 +1,302 @@
+mod common;
+
+use assert_cmd::Command;
+use serde_json::{json, Value};
+
+use common::{random_mixture, Rng};
+
+const VALID: &str = r#"{"w": [0.5, 0.5], "means": [[5.0], [2.0]], "covs": [[[0.5]], [[0.5]]]}"#;
+const RAGGED: &str = r#"{"w": [0.5, 0.5], "means": [[5.0], [2.0, 1.0]], "covs": [[[0.5]], [[0.5]]]}"#;
+const FAR: &str = r#"{"w": [0.5, 0.5], "means": [[0.0], [2.0]], "covs": [[[0.5]], [[0.5]]]}"#;
+
+fn lines(output: &[u8]) -> Vec<Value> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn compute_sklearn() {
+    let output = Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/sklearn_full.json", "--input-format", "sklearn"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let olr = lines(&output.stdout)[0]["olr"][0].as_f64().unwrap();
+    assert!((olr - 0.9205257521646449).abs() < 1e-4);
+}
+
+#[test]
+fn compute_reports_distances() {
+    let output = Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/sklearn_full.json", "--input-format", "sklearn"])
+        .args(["--mahalanobis", "pooled", "--euclidean"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let report = &lines(&output.stdout)[0];
+    assert_eq!(1, report["mahalanobis"].as_array().unwrap().len());
+    let euclidean = report["euclidean"][0].as_f64().unwrap();
+    assert!((euclidean - (2.9605f64.powi(2) + 2.9945f64.powi(2)).sqrt()).abs() < 1e-12);
+
+    let output = Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/sklearn_full.json", "--mahalanobis", "median"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn stream_reports_errors_inline() {
+    let input = [VALID, RAGGED, FAR, VALID].join("\n");
+
+    for jobs in ["1", "3"] {
+        let output = Command::cargo_bin("moebius").unwrap()
+            .args(["stream", "--jobs", jobs])
+            .write_stdin(input.clone())
+            .output()
+            .unwrap();
+
+        assert!(!output.status.success());
+
+        let records = lines(&output.stdout);
+        assert_eq!(4, records.len());
+        assert!((records[0]["olr"][0].as_f64().unwrap() - 0.21077243773848037).abs() < 1e-4);
+        assert_eq!(2, records[1]["line"]);
+        assert!(records[1]["error"].is_string());
+        assert!(records[2]["olr"][0].as_f64().unwrap() > 0.5);
+        assert_eq!(records[0], records[3]);
+    }
+}
+
+#[test]
+fn stream_fail_fast() {
+    let output = Command::cargo_bin("moebius").unwrap()
+        .args(["stream", "--fail-fast"])
+        .write_stdin([VALID, RAGGED, VALID].join("\n"))
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(1, lines(&output.stdout).len());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2"));
+}
+
+#[test]
+fn stream_success() {
+    Command::cargo_bin("moebius").unwrap()
+        .arg("stream")
+        .write_stdin(format!("{}\n\n{}\n", VALID, FAR))
+        .assert()
+        .success();
+}
+
+#[test]
+fn bench_writes_report() {
+    let report = std::env::temp_dir().join(format!("moebius-bench-{}.json", std::process::id()));
+
+    Command::cargo_bin("moebius").unwrap()
+        .args(["bench", "--input", "tests/fixtures/sklearn_full.json", "--input-format", "sklearn"])
+        .args(["--repeat", "3", "--config", r#"{"n_points": 200}"#])
+        .arg("--report").arg(&report)
+        .assert()
+        .success();
+
+    let json: Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    std::fs::remove_file(&report).unwrap();
+
+    assert_eq!(3, json["repeat"]);
+    assert_eq!(3, json["runs"].as_array().unwrap().len());
+    assert_eq!(200, json["config"]["n_points"]);
+}
+
+#[test]
+fn bench_rejects_unknown_config() {
+    Command::cargo_bin("moebius").unwrap()
+        .args(["bench", "--input", "tests/fixtures/sklearn_full.json", "--input-format", "sklearn"])
+        .args(["--config", r#"{"n_pionts": 200}"#])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn singular_covariance_names_component() {
+    let output = Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/singular.json"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("component 2"));
+    assert!(stderr.contains("pair (0, 2)"));
+}
+
+#[test]
+fn stream_matches_library_bitwise() {
+    let mut rng = Rng::new(7);
+    let mixtures: Vec<_> = (0..6).map(|_| random_mixture(&mut rng)).collect();
+    let input: Vec<String> = mixtures.iter()
+        .map(|(w, means, covs)| {
+            let means: Vec<Vec<f64>> = means.outer_iter().map(|row| row.to_vec()).collect();
+            let covs: Vec<Vec<Vec<f64>>> = covs.outer_iter()
+                .map(|cov| cov.outer_iter().map(|row| row.to_vec()).collect())
+                .collect();
+
+            json!({ "w": w, "means": means, "covs": covs }).to_string()
+        })
+        .collect();
+    // Shortest round-trip formatting maps distinct values to distinct strings, so comparing the
+    // lines compares the values bit by bit.
+    let expected: Vec<String> = mixtures.into_iter()
+        .map(|(w, means, covs)| json!({ "olr": moebius::try_olr(w, means, covs).unwrap() }).to_string())
+        .collect();
+
+    for jobs in ["1", "4"] {
+        let output = Command::cargo_bin("moebius").unwrap()
+            .args(["stream", "--jobs", jobs])
+            .write_stdin(input.join("\n"))
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(expected, String::from_utf8_lossy(&output.stdout).lines().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn compute_binary() {
+    let output = Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/sklearn_full.json", "--input-format", "sklearn", "--format", "bin"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let matrix = moebius::OlrMatrix::read_from(&mut output.stdout.as_slice()).unwrap();
+    assert_eq!(2, matrix.n_components());
+    assert!((matrix.values()[0] - 0.9205257521646449).abs() < 1e-4);
+
+    let output = Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/sklearn_full.json", "--input-format", "sklearn"])
+        .args(["--format", "bin", "--euclidean"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn compute_writes_output_file() {
+    let path = std::env::temp_dir().join(format!("moebius-compute-{}.json", std::process::id()));
+    let stdout = Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/mixture_full.json"])
+        .output()
+        .unwrap();
+
+    Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/mixture_full.json"])
+        .arg("--output").arg(&path)
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(stdout.stdout, std::fs::read(&path).unwrap());
+
+    // A failed computation keeps the previous output.
+    Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/mixture_full.json", "--format", "bin", "--euclidean"])
+        .arg("--output").arg(&path)
+        .assert()
+        .failure();
+    Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/malformed.csv", "--covariance", "diag"])
+        .arg("--output").arg(&path)
+        .assert()
+        .failure();
+    let kept = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(stdout.stdout, kept);
+}
+
+#[test]
+fn compute_csv_matches_json() {
+    let compute = |args: &[&str]| {
+        let output = Command::cargo_bin("moebius").unwrap().arg("compute").args(args).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    for covariance in ["full", "diag"] {
+        let csv = format!("tests/fixtures/mixture_{}.csv", covariance);
+        let json = format!("tests/fixtures/mixture_{}.json", covariance);
+        let expected = compute(&["--input", &json, "--euclidean"]);
+        assert_eq!(expected, compute(&["--input", &csv, "--covariance", covariance, "--euclidean"]));
+        assert_eq!(expected, compute(&["--input", &csv, "--input-format", "csv", "--covariance", covariance, "--euclidean"]));
+    }
+}
+
+#[test]
+fn malformed_csv_names_line() {
+    let output = Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/malformed.csv", "--covariance", "diag"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 3"));
+
+    // The full layout needs `1 + d + d * d` columns.
+    Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", "tests/fixtures/mixture_diag.csv"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn report_matches_golden_files() {
+    let report = |input: &str, args: &[&str]| {
+        let output = Command::cargo_bin("moebius").unwrap()
+            .args(["compute", "--input", input])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let json = "tests/fixtures/mixture_full.json";
+
+    assert_eq!(std::fs::read_to_string("tests/fixtures/report_full.txt").unwrap(), report(json, &["--format", "report"]));
+    assert_eq!(
+        std::fs::read_to_string("tests/fixtures/report_full.md").unwrap(),
+        report(json, &["--format", "markdown", "--top-n", "2"])
+    );
+    // The CSV table of the same mixture gives the same report.
+    assert_eq!(report(json, &["--format", "report"]), report("tests/fixtures/mixture_full.csv", &["--format", "report"]));
+
+    Command::cargo_bin("moebius").unwrap()
+        .args(["compute", "--input", json, "--format", "report", "--euclidean"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn diagnose_flags_singular_covariances() {
+    let diagnose = |args: &[&str]| {
+        let output = Command::cargo_bin("moebius").unwrap().arg("diagnose").args(args).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        serde_json::from_slice::<Value>(&output.stdout).unwrap()
+    };
+
+    let singular = diagnose(&["--input", "tests/fixtures/singular.json"]);
+    assert_eq!(json!([2]), singular["flagged"]);
+    assert_eq!(Value::Null, singular["components"][2]["condition_number"]);
+    assert_eq!(json!(3), json!(singular["pairs"].as_array().unwrap().len()));
+
+    for args in [&["--input", "tests/fixtures/mixture_full.json"][..], &["--input", "tests/fixtures/mixture_diag.csv", "--covariance", "diag"]] {
+        assert_eq!(json!([]), diagnose(args)["flagged"], "{:?}", args);
+    }
+}
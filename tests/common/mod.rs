@@ -0,0 +1,62 @@
+//! Seeded random mixtures shared by the integration tests.
+
+// Every test crate uses a different subset of the helpers.
+#![allow(dead_code)]
+
+use ndarray::{Array2, Array3};
+
+/// SplitMix64, so that the mixtures are the same on every platform without extra dependencies.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[low, high)`.
+    pub fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `low..=high`.
+    pub fn range(&mut self, low: usize, high: usize) -> usize {
+        low + (self.next_u64() % (high - low + 1) as u64) as usize
+    }
+}
+
+/// Mixture of 2 to 6 components in 1 to 4 dimensions with covariances `A A^T + 0.1 I`.
+pub fn random_mixture(rng: &mut Rng) -> (Vec<f64>, Array2<f64>, Array3<f64>) {
+    let n_comp = rng.range(2, 6);
+    let dim = rng.range(1, 4);
+
+    let raw: Vec<f64> = (0..n_comp).map(|_| rng.uniform(0.1, 1.0)).collect();
+    let total: f64 = raw.iter().sum();
+    let w = raw.iter().map(|v| v / total).collect();
+    let means = Array2::from_shape_fn((n_comp, dim), |_| rng.uniform(-3.0, 3.0));
+
+    let mut covs = Array3::zeros((n_comp, dim, dim));
+    for k in 0..n_comp {
+        let a = Array2::from_shape_fn((dim, dim), |_| rng.uniform(-1.0, 1.0));
+        let mut cov = a.dot(&a.t());
+        cov.diag_mut().map_inplace(|v| *v += 0.1);
+        covs.index_axis_mut(ndarray::Axis(0), k).assign(&cov);
+    }
+
+    (w, means, covs)
+}
+
+/// Same bits, so that `NaN` and signed zeros are compared exactly as well.
+pub fn assert_bitwise_eq(expected: &[f64], actual: &[f64], mode: &str) {
+    let bits = |values: &[f64]| values.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+
+    assert_eq!(bits(expected), bits(actual), "{}: {:?} != {:?}", mode, expected, actual);
+}
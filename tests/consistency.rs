@@ -0,0 +1,119 @@
+//! Every execution mode must produce bitwise identical values for the same input. All modes end
+//! up in the same per-pair routine and collect pairs in output order, so neither the number of
+//! threads nor the scheduling order can change a single bit.
+
+mod common;
+
+use ndarray::{Array2, Array3};
+use moebius::ffi::{moebius_olr, MoebiusStatus};
+use moebius::timing::olr_timed;
+use moebius::{
+    olr_batch, olr_detailed, olr_flat, olr_with_config, olr_with_covariances, olr_with_stats, try_olr, Covariance,
+    GaussianMixture, OlrConfig, OlrStats
+};
+
+use common::{assert_bitwise_eq, random_mixture, Rng};
+
+const SEED: u64 = 0x6d6f6562697573;
+const N_MIXTURES: usize = 10;
+
+fn mixtures() -> Vec<(Vec<f64>, Array2<f64>, Array3<f64>)> {
+    let mut rng = Rng::new(SEED);
+
+    (0..N_MIXTURES).map(|_| random_mixture(&mut rng)).collect()
+}
+
+fn serial(w: &[f64], means: &Array2<f64>, covs: &Array3<f64>) -> Vec<f64> {
+    olr_with_config(w, means, covs, &OlrConfig { n_threads: Some(1), ..OlrConfig::default() }).unwrap()
+}
+
+#[test]
+fn thread_counts() {
+    for (w, means, covs) in mixtures() {
+        let expected = serial(&w, &means, &covs);
+
+        for n_threads in [Some(2), Some(3), Some(0), None] {
+            let config = OlrConfig { n_threads, ..OlrConfig::default() };
+            let mode = format!("n_threads = {:?}", n_threads);
+            assert_bitwise_eq(&expected, &olr_with_config(&w, &means, &covs, &config).unwrap(), &mode);
+        }
+    }
+}
+
+#[test]
+fn entry_points() {
+    for (w, means, covs) in mixtures() {
+        let expected = serial(&w, &means, &covs);
+        let config = OlrConfig::default();
+
+        assert_bitwise_eq(&expected, &try_olr(w.clone(), means.clone(), covs.clone()).unwrap(), "try_olr");
+
+        let gmm = GaussianMixture::new(w.clone(), means.clone(), covs.clone()).unwrap();
+        assert_bitwise_eq(&expected, &gmm.olr().unwrap(), "GaussianMixture::olr");
+
+        let flat = olr_flat(&w, means.as_slice().unwrap(), covs.as_slice().unwrap(), means.ncols()).unwrap();
+        assert_bitwise_eq(&expected, &flat, "olr_flat");
+
+        let mut stats = OlrStats::default();
+        assert_bitwise_eq(&expected, &olr_with_stats(&w, &means, &covs, &config, &mut stats).unwrap(), "olr_with_stats");
+        assert_bitwise_eq(&expected, &olr_timed(&w, &means, &covs, &config).unwrap().0, "olr_timed");
+
+        let detailed: Vec<f64> = olr_detailed(&w, &means, &covs, &config).unwrap().pairs.iter().map(|p| p.value).collect();
+        assert_bitwise_eq(&expected, &detailed, "olr_detailed");
+    }
+}
+
+#[test]
+fn structured_covariances() {
+    for (w, means, covs) in mixtures() {
+        let expected = serial(&w, &means, &covs);
+        let dim = means.ncols();
+
+        let full: Vec<Covariance> = covs.outer_iter().map(|cov| Covariance::Full(cov.to_owned())).collect();
+        let values = olr_with_covariances(&w, &means, &full, &OlrConfig::default()).unwrap();
+        assert_bitwise_eq(&expected, &values, "Covariance::Full");
+
+        let single_block: Vec<Covariance> = covs.outer_iter()
+            .map(|cov| Covariance::BlockDiagonal(vec![((0..dim).collect(), cov.to_owned())]))
+            .collect();
+        let values = olr_with_covariances(&w, &means, &single_block, &OlrConfig::default()).unwrap();
+        assert_bitwise_eq(&expected, &values, "Covariance::BlockDiagonal");
+    }
+}
+
+#[test]
+fn batch_jobs() {
+    let mixtures = mixtures();
+    let gmms: Vec<GaussianMixture> = mixtures.iter()
+        .map(|(w, means, covs)| GaussianMixture::new(w.clone(), means.clone(), covs.clone()).unwrap())
+        .collect();
+
+    for jobs in [1, 3, 8] {
+        for ((w, means, covs), result) in mixtures.iter().zip(olr_batch(&gmms, jobs)) {
+            assert_bitwise_eq(&serial(w, means, covs), &result.unwrap(), &format!("olr_batch, jobs = {}", jobs));
+        }
+    }
+}
+
+#[test]
+fn c_interface() {
+    for (w, means, covs) in mixtures() {
+        let expected = serial(&w, &means, &covs);
+        let mut out = vec![0.0; expected.len()];
+
+        let status = unsafe {
+            moebius_olr(
+                w.as_ptr(),
+                w.len(),
+                means.as_ptr(),
+                means.ncols(),
+                covs.as_ptr(),
+                out.as_mut_ptr(),
+                out.len()
+            )
+        };
+
+        assert_eq!(MoebiusStatus::Ok as i32, status);
+        assert_bitwise_eq(&expected, &out, "moebius_olr");
+    }
+}
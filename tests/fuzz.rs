@@ -0,0 +1,122 @@
+//! Property-based tests of the input conversion and validation layers: malformed inputs must
+//! produce typed errors and never panic, well-formed inputs must convert losslessly.
+
+use ndarray::{Array2, Array3};
+use proptest::prelude::*;
+use moebius::{covs_from_nested, means_from_nested, olr_flat, try_olr, MoebiusError};
+
+/// Any `f64`, including NaN, infinities and subnormals.
+fn any_value() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        8 => -10.0..10.0f64,
+        1 => any::<f64>(),
+        1 => Just(f64::NAN),
+        1 => Just(f64::INFINITY),
+        1 => Just(0.0),
+    ]
+}
+
+/// Possibly ragged nested rows.
+fn nested2() -> impl Strategy<Value = Vec<Vec<f64>>> {
+    prop::collection::vec(prop::collection::vec(any_value(), 0..5), 0..5)
+}
+
+fn nested3() -> impl Strategy<Value = Vec<Vec<Vec<f64>>>> {
+    prop::collection::vec(nested2(), 0..5)
+}
+
+/// Rectangular `rows x cols` rows.
+fn rectangular(rows: usize, cols: usize) -> impl Strategy<Value = Vec<Vec<f64>>> {
+    prop::collection::vec(prop::collection::vec(any_value(), cols), rows)
+}
+
+/// A mixture with `n` components in `d` dimensions and valid covariances `A A^T + I`.
+fn well_formed() -> impl Strategy<Value = (Vec<f64>, Array2<f64>, Array3<f64>)> {
+    (1..4usize, 1..4usize).prop_flat_map(|(n, d)| {
+        (
+            prop::collection::vec(0.01..1.0f64, n),
+            prop::collection::vec(-5.0..5.0f64, n * d),
+            prop::collection::vec(-1.0..1.0f64, n * d * d),
+        ).prop_map(move |(w, means, factors)| {
+            let means = Array2::from_shape_vec((n, d), means).unwrap();
+            let mut covs = Array3::zeros((n, d, d));
+            for (k, mut cov) in covs.outer_iter_mut().enumerate() {
+                let a = Array2::from_shape_vec((d, d), factors[k * d * d..(k + 1) * d * d].to_vec()).unwrap();
+                cov.assign(&(a.dot(&a.t()) + Array2::<f64>::eye(d)));
+            }
+
+            (w, means, covs)
+        })
+    })
+}
+
+fn is_typed(result: &Result<Vec<f64>, MoebiusError>) -> bool {
+    match result {
+        Ok(values) => values.iter().all(|v| (0.0..=1.0).contains(v)),
+        Err(e) => !e.to_string().is_empty(),
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig { cases: 64, ..ProptestConfig::default() })]
+
+    #[test]
+    fn nested_means_never_panic(v in nested2()) {
+        let result = means_from_nested(v.clone());
+        let ragged = v.iter().any(|row| row.len() != v[0].len());
+
+        prop_assert_eq!(ragged, result.is_err());
+    }
+
+    #[test]
+    fn nested_covs_never_panic(v in nested3()) {
+        let _ = covs_from_nested(v);
+    }
+
+    #[test]
+    fn means_round_trip(v in (0..6usize, 0..6usize).prop_flat_map(|(rows, cols)| rectangular(rows, cols))) {
+        let array = means_from_nested(v.clone()).unwrap();
+        let back: Vec<Vec<f64>> = array.outer_iter().map(|row| row.to_vec()).collect();
+
+        let bits = |v: &Vec<Vec<f64>>| v.iter().flatten().map(|x| x.to_bits()).collect::<Vec<_>>();
+        prop_assert_eq!(bits(&v), bits(&back));
+        prop_assert_eq!(v.len(), array.nrows());
+    }
+
+    #[test]
+    fn covs_round_trip(v in (0..4usize, 0..4usize).prop_flat_map(|(n, d)| prop::collection::vec(rectangular(d, d), n))) {
+        let array = covs_from_nested(v.clone()).unwrap();
+        let back: Vec<Vec<Vec<f64>>> = array.outer_iter()
+            .map(|cov| cov.outer_iter().map(|row| row.to_vec()).collect())
+            .collect();
+
+        let bits = |v: &Vec<Vec<Vec<f64>>>| v.iter().flatten().flatten().map(|x| x.to_bits()).collect::<Vec<_>>();
+        prop_assert_eq!(bits(&v), bits(&back));
+        prop_assert_eq!(v.len(), array.dim().0);
+    }
+
+    #[test]
+    fn malformed_mixtures_yield_errors(w in prop::collection::vec(any_value(), 0..4), means in nested2(), covs in nested3()) {
+        let Ok(means) = means_from_nested(means) else { return Ok(()) };
+        let Ok(covs) = covs_from_nested(covs) else { return Ok(()) };
+
+        prop_assert!(is_typed(&try_olr(w, means, covs)));
+    }
+
+    #[test]
+    fn flat_inputs_yield_errors(
+        w in prop::collection::vec(any_value(), 0..4),
+        means in prop::collection::vec(any_value(), 0..10),
+        covs in prop::collection::vec(any_value(), 0..20),
+        dim in prop_oneof![0..4usize, Just(usize::MAX), Just(usize::MAX / 2)]
+    ) {
+        prop_assert!(is_typed(&olr_flat(&w, &means, &covs, dim)));
+    }
+
+    #[test]
+    fn well_formed_mixtures_succeed((w, means, covs) in well_formed()) {
+        let flat = olr_flat(&w, means.as_slice().unwrap(), covs.as_slice().unwrap(), means.ncols()).unwrap();
+
+        prop_assert_eq!(try_olr(w, means, covs).unwrap(), flat);
+    }
+}
@@ -0,0 +1,77 @@
+"""Tests of means and covs read through the buffer protocol and `__dlpack__`. Run with `pytest tests/python` after `maturin develop`."""
+
+import array
+
+import numpy as np
+import pytest
+
+import moebius
+
+W = [0.5, 0.5]
+MEANS = [[0.0, 0.0], [4.0, 1.0]]
+COVS = [[[1.0, 0.0], [0.0, 1.0]], [[2.0, 0.5], [0.5, 1.0]]]
+EXPECTED = moebius.olr_list(W, MEANS, COVS)
+
+
+def buffer(typecode, values, shape):
+    """`values` as a memoryview of the given shape, without numpy."""
+    return memoryview(array.array(typecode, values)).cast("B").cast(typecode, shape)
+
+
+class DLPackOnly:
+    """Exposes an array through `__dlpack__` only, as torch and cupy tensors do."""
+
+    def __init__(self, array, device=(1, 0)):
+        self.array, self.device = array, device
+
+    def __dlpack__(self, stream=None):
+        return self.array.__dlpack__()
+
+    def __dlpack_device__(self):
+        return self.device
+
+
+def test_array_array_of_variances():
+    assert moebius.olr_list(W, array.array("d", [0.0, 5.0]), array.array("d", [1.0, 2.0])) == moebius.olr_list(W, [0.0, 5.0], [1.0, 2.0])
+
+
+def test_multi_dimensional_memoryviews():
+    means = buffer("d", [0.0, 0.0, 4.0, 1.0], [2, 2])
+    covs = buffer("d", [1.0, 0.0, 0.0, 1.0, 2.0, 0.5, 0.5, 1.0], [2, 2, 2])
+
+    assert moebius.olr_list(W, means, covs) == EXPECTED
+
+
+def test_non_contiguous_views_are_honored():
+    # Every other column of arrays padded with values that would change the result if read.
+    means = np.full((2, 4), -9.0)
+    means[:, ::2] = MEANS
+    covs = np.full((2, 2, 4), -9.0)
+    covs[:, :, ::2] = COVS
+    means, covs = means[:, ::2], covs[:, :, ::2]
+    assert not means.flags.c_contiguous and not covs.flags.c_contiguous
+
+    assert moebius.olr_list(W, means, covs) == EXPECTED
+    assert moebius.olr_list(W, memoryview(means), memoryview(covs)) == EXPECTED
+    assert moebius.olr_list(W, DLPackOnly(means), DLPackOnly(covs)) == EXPECTED
+    assert moebius.olr_list(W, means[::-1], covs[::-1]) == moebius.olr_list(W, MEANS[::-1], COVS[::-1])
+
+
+def test_dlpack():
+    assert moebius.olr_list(W, DLPackOnly(np.array(MEANS)), DLPackOnly(np.array(COVS))) == EXPECTED
+    assert moebius.olr_list(W, DLPackOnly(np.array(MEANS, dtype=np.float32)), COVS) == EXPECTED
+
+
+def test_device_tensors_are_rejected():
+    cuda = DLPackOnly(np.array(MEANS), device=(2, 0))
+
+    with pytest.raises(ValueError, match=r"^means is stored on a non-CPU device \(DLPack device type 2\), call \.cpu\(\) first$"):
+        moebius.olr_list(W, cuda, COVS)
+
+
+@pytest.mark.parametrize("wrap", [lambda a: a, memoryview, DLPackOnly])
+def test_dtype_mismatch_names_the_expected_dtype(wrap):
+    with pytest.raises(TypeError, match=r"^means must have a numeric dtype, got complex"):
+        moebius.olr_list(W, wrap(np.array(MEANS, dtype=np.complex128)), COVS)
+    with pytest.raises(TypeError, match=r"^covs must have a numeric dtype, got bool$"):
+        moebius.olr_list(W, MEANS, wrap(np.ones((2, 2, 2), dtype=bool)))
@@ -0,0 +1,21 @@
+"""Tests of the build introspection. Run with `pytest tests/python` after `maturin develop`."""
+
+import moebius
+
+
+def test_keys_are_stable():
+    capabilities = moebius.capabilities()
+
+    assert list(capabilities) == ["version", "features", "parallel", "methods", "detectors", "covariance_inputs", "simd"]
+    assert capabilities["features"] == moebius.features()
+    assert capabilities["parallel"] is True
+    assert {"line", "exact", "multi"} <= set(capabilities["methods"])
+    assert capabilities["simd"] is None or isinstance(capabilities["simd"], str)
+
+
+def test_reflects_compiled_features():
+    capabilities = moebius.capabilities()
+
+    assert ("spherical" in capabilities["covariance_inputs"]) == ("serde" in capabilities["features"])
+    for detector in capabilities["detectors"]:
+        moebius.olr_list([0.5, 0.5], [[0.0], [2.0]], [1.0, 1.0], detector=detector)
@@ -0,0 +1,67 @@
+"""Tests of the element types accepted for `w`, `means` and `covs`. Run with `pytest tests/python` after `maturin develop`."""
+
+import numpy as np
+import pytest
+
+import moebius
+
+W = [0.5, 0.5]
+MEANS = [[0.0, 0.0], [4.0, 1.0]]
+COVS = [[[1.0, 0.0], [0.0, 1.0]], [[2.0, 0.5], [0.5, 1.0]]]
+EXPECTED = moebius.olr_list(W, MEANS, COVS)
+
+
+def test_int_lists_are_upcast():
+    assert moebius.olr_list([1, 1], [[0, 0], [4, 1]], COVS, auto_normalize=True) == pytest.approx(EXPECTED)
+    assert moebius.olr_list(W, [[0, 0], [4, 1]], [[[1, 0], [0, 1]], [[2, 0.5], [0.5, 1]]]) == EXPECTED
+    assert moebius.olr_list(W, [0, 5], [1, 2]) == moebius.olr_list(W, [[0.0], [5.0]], [[[1.0]], [[2.0]]])
+
+
+def test_int64_arrays_are_upcast():
+    means = np.array([[0, 0], [4, 1]], dtype=np.int64)
+    covs = np.array([[[1, 0], [0, 1]], [[4, 1], [1, 2]]], dtype=np.int64)
+
+    assert moebius.olr_list(W, means, covs) == moebius.olr_list(W, means.astype(np.float64), covs.astype(np.float64))
+    assert moebius.olr_list(np.array([1, 1], dtype=np.int64), means, covs, auto_normalize=True) == pytest.approx(
+        moebius.olr_list(W, means.astype(np.float64), covs.astype(np.float64))
+    )
+
+
+def test_float32_arrays_are_upcast():
+    # Every value is exact in float32, so the results must be identical.
+    means = np.array(MEANS, dtype=np.float32)
+    covs = np.array(COVS, dtype=np.float32)
+
+    assert moebius.olr_list(np.array(W, dtype=np.float32), means, covs) == EXPECTED
+    assert moebius.olr_list(W, np.asfortranarray(means), covs) == EXPECTED
+
+
+@pytest.mark.parametrize("means, covs, message", [
+    ([[0.0, 0.0], [4, "1"]], COVS, r"^means\[1\]\[1\] must be a number, got str$"),
+    ([[0.0, 0.0], ["4", 1.0]], COVS, r"^means\[1\]\[0\] must be a number, got str$"),
+    (MEANS, [[[1.0, 0.0], [0.0, 1.0]], [[2.0, None], [0.5, 1.0]]], r"^covs\[1\]\[0\]\[1\] must be a number, got NoneType$"),
+    ([0.0, "4"], [1.0, 2.0], r"^means\[1\] must be a number, got str$"),
+    ("04", COVS, r"^means must be a sequence, got str$"),
+])
+def test_mixed_lists_name_the_element(means, covs, message):
+    with pytest.raises(TypeError, match=message):
+        moebius.olr_list(W, means, covs)
+
+
+def test_mixed_weights_name_the_argument():
+    with pytest.raises(TypeError, match="argument 'w'"):
+        moebius.olr_list([0.5, "0.5"], MEANS, COVS)
+
+
+@pytest.mark.parametrize("array, dtype", [
+    (np.array(MEANS, dtype=object), "object"),
+    (np.array([["0", "0"], ["4", "1"]]), "string"),
+    (np.array([[True, False], [False, True]]), "bool"),
+])
+def test_non_numeric_dtypes_name_the_argument(array, dtype):
+    with pytest.raises(TypeError, match=f"^means must have a numeric dtype, got {dtype}$"):
+        moebius.olr_list(W, array, COVS)
+
+    covs = np.broadcast_to(array[:, :, None], (2, 2, 2))
+    with pytest.raises(TypeError, match=f"^covs must have a numeric dtype, got {dtype}$"):
+        moebius.olr_list(W, MEANS, covs)
@@ -0,0 +1,31 @@
+"""Tests of the OLR within a bounding box. Run with `pytest tests/python` after `maturin develop`."""
+
+import math
+
+import pytest
+
+import moebius
+
+W = [0.5, 0.5]
+MEANS = [[0.0, 0.0], [4.0, 0.0]]
+COVS = [[[1.0, 0.0], [0.0, 1.0]], [[1.0, 0.0], [0.0, 1.0]]]
+
+
+def test_unbounded_box_matches_olr_list():
+    values = moebius.olr_bounded(W, MEANS, COVS, [-math.inf] * 2, [math.inf] * 2, n_points=1000)
+
+    assert values == pytest.approx(moebius.olr_list(W, MEANS, COVS, n_points=1000), abs=1e-12)
+
+
+def test_excluding_a_mode_merges_the_pair():
+    inside = moebius.olr_bounded(W, MEANS, COVS, [-1.0, -1.0], [5.0, 1.0])
+    clipped = moebius.olr_bounded(W, MEANS, COVS, [-1.0, -1.0], [3.0, 1.0])
+
+    assert inside[0] < 0.5
+    assert clipped == [1.0]
+
+
+@pytest.mark.parametrize("lower, upper", [([0.0, 0.0], [1.0, 0.0]), ([0.0], [1.0])])
+def test_invalid_bounds(lower, upper):
+    with pytest.raises(ValueError):
+        moebius.olr_bounded(W, MEANS, COVS, lower, upper)
@@ -0,0 +1,59 @@
+"""Tests of `moebius.OlrConfig`. Run with `pytest tests/python` after `maturin develop`."""
+
+import pytest
+
+import moebius
+
+W = [0.45, 0.55]
+MEANS = [[0.0], [2.6]]
+COVS = [[[1.0]], [[0.7]]]
+
+
+def test_defaults_match_keywords():
+    config = moebius.OlrConfig()
+
+    assert config.n_points is None
+    assert config.extension == moebius.DEFAULT_EXTENSION
+    assert moebius.olr_list(W, MEANS, COVS, config=config) == moebius.olr_list(W, MEANS, COVS)
+
+
+def test_mutated_attributes_change_the_values():
+    config = moebius.OlrConfig(n_points=8)
+    coarse = moebius.olr_list(W, MEANS, COVS, config=config)
+    assert coarse == moebius.olr_list(W, MEANS, COVS, n_points=8)
+
+    config.n_points = 2000
+    fine = moebius.olr_list(W, MEANS, COVS, config=config)
+    assert fine != coarse
+
+    config.n_points = 8
+    config.detector = "parabolic"
+    assert moebius.olr_list(W, MEANS, COVS, config=config) != coarse
+    assert moebius.olr(W, MEANS, COVS, config=config)[0] == moebius.olr_list(W, MEANS, COVS, config=config)[0]
+
+
+def test_config_replaces_keywords():
+    config = moebius.OlrConfig(n_points=8)
+
+    assert moebius.olr_list(W, MEANS, COVS, n_points=2000, config=config) == moebius.olr_list(W, MEANS, COVS, n_points=8)
+
+
+@pytest.mark.parametrize("options, match", [
+    ({"n_points": 0}, "n_points"),
+    ({"tolerance": 0.0}, "tolerance"),
+    ({"detector": "peaks"}, "detector"),
+    ({"grid": "random"}, "grid"),
+    ({"no_saddle": "never"}, "no_saddle"),
+    ({"reg_covar": -1.0}, "reg_covar"),
+])
+def test_rejects_invalid_options(options, match):
+    with pytest.raises(ValueError, match=match):
+        moebius.OlrConfig(**options)
+
+
+def test_rejected_attributes_keep_the_config():
+    config = moebius.OlrConfig(tolerance=1e-4)
+
+    with pytest.raises(ValueError, match="tolerance"):
+        config.tolerance = float("nan")
+    assert config.tolerance == 1e-4
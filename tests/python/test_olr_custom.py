@@ -0,0 +1,49 @@
+"""Tests of the Python callback path. Run with `pytest tests/python` after `maturin develop`."""
+
+import numpy as np
+import pytest
+
+import moebius
+
+W = [5.2194e-01, 4.7806e-01]
+MEANS = np.array([[1.1987e+00, 1.1542e+00], [4.1592e+00, 4.1487e+00]])
+COVS = np.array([
+    [[1.9455e+00, -9.1612e-04], [-9.1612e-04, 1.9703e+00]],
+    [[1.5160e+00, 1.1011e+00], [1.1011e+00, 1.5178e+00]],
+])
+
+
+def gaussian_logpdf(mean, cov):
+    inv = np.linalg.inv(cov)
+    _, ln_det = np.linalg.slogdet(cov)
+    ln_norm = -0.5 * (len(mean) * np.log(2 * np.pi) + ln_det)
+
+    def logpdf(x):
+        assert isinstance(x, np.ndarray) and x.shape == mean.shape
+        diff = x - mean
+        return ln_norm - 0.5 * diff @ inv @ diff
+
+    return logpdf
+
+
+def test_gaussian_callbacks_match_native():
+    callbacks = [gaussian_logpdf(m, c) for m, c in zip(MEANS, COVS)]
+
+    custom = moebius.olr_custom(W, MEANS, callbacks)
+
+    assert custom == pytest.approx(moebius.olr_list(W, MEANS, COVS, n_points=1000), abs=1e-12)
+
+
+def test_callback_errors_carry_grid_index():
+    def failing(x):
+        raise KeyError("boom")
+
+    with pytest.raises(KeyError, match="grid index 0 of pair \\(0, 1\\)") as info:
+        moebius.olr_custom(W, MEANS, [failing, gaussian_logpdf(MEANS[1], COVS[1])])
+
+    assert isinstance(info.value.__cause__, KeyError)
+
+
+def test_callback_count_checked():
+    with pytest.raises(ValueError):
+        moebius.olr_custom(W, MEANS, [gaussian_logpdf(MEANS[0], COVS[0])])
@@ -0,0 +1,70 @@
+"""Tests of the keyword options of `olr`. Run with `pytest tests/python` after `maturin develop`."""
+
+import inspect
+import random
+
+import pytest
+
+import moebius
+
+W = [0.3, 0.3, 0.4]
+MEANS = [[0.0], [1.5], [4.0]]
+COVS = [[[1.0]], [[0.5]], [[2.0]]]
+
+
+@pytest.mark.parametrize("function", [moebius.olr, moebius.olr_list])
+def test_signature_lists_defaults(function):
+    parameters = inspect.signature(function).parameters
+
+    assert list(parameters)[:3] == ["w", "means", "covs"]
+    assert all(p.kind is inspect.Parameter.KEYWORD_ONLY for p in list(parameters.values())[3:])
+    assert parameters["no_saddle"].default == "assume_merged"
+    assert parameters["n_points"].default is None
+
+
+def test_explicit_defaults_in_any_order():
+    defaults = {
+        name: p.default for name, p in inspect.signature(moebius.olr_list).parameters.items()
+        if p.kind is inspect.Parameter.KEYWORD_ONLY
+    }
+    expected = moebius.olr_list(W, MEANS, COVS)
+
+    names = list(defaults)
+    for seed in range(3):
+        random.Random(seed).shuffle(names)
+        assert moebius.olr_list(covs=COVS, w=W, means=MEANS, **{name: defaults[name] for name in names}) == expected
+
+
+def test_options_are_keyword_only():
+    with pytest.raises(TypeError):
+        moebius.olr_list(W, MEANS, COVS, True)
+
+
+@pytest.mark.parametrize("options, name", [
+    ({"no_saddle": "never"}, "no_saddle"),
+    ({"detector": "peaks"}, "detector"),
+    ({"on_timeout": "retry"}, "on_timeout"),
+    ({"tolerance": -1.0}, "tolerance"),
+    ({"n_points": 0}, "n_points"),
+    ({"reg_covar": -1.0}, "reg_covar"),
+    ({"deadline": -1.0}, "deadline"),
+])
+def test_errors_name_the_argument(options, name):
+    with pytest.raises(ValueError, match=name):
+        moebius.olr_list(W, MEANS, COVS, **options)
+
+
+def test_shape_errors_name_the_argument():
+    with pytest.raises(ValueError, match="w has 2"):
+        moebius.olr_list(W[:2], MEANS, COVS)
+    with pytest.raises(ValueError, match="covs: "):
+        moebius.olr_list(W, MEANS, [[[1.0]], [[0.5, 0.1]], [[2.0]]])
+
+
+def test_extrapolate_moves_values_towards_the_exact_olr():
+    exact = moebius.olr_exact(W, MEANS, COVS)
+    grid = moebius.olr_list(W, MEANS, COVS, n_points=1000)
+    extrapolated = moebius.olr_list(W, MEANS, COVS, n_points=1000, extrapolate=True)
+
+    assert extrapolated != grid
+    assert max(abs(e - x) for e, x in zip(extrapolated, exact)) < max(abs(g - x) for g, x in zip(grid, exact)) / 10
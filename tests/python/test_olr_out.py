@@ -0,0 +1,68 @@
+"""Tests of `olr(..., out=...)`. Run with `pytest tests/python` after `maturin develop`."""
+
+import numpy as np
+import pytest
+
+import moebius
+
+W = [0.3, 0.3, 0.4]
+MEANS = [[0.0], [1.5], [4.0]]
+COVS = [[[1.0]], [[0.5]], [[2.0]]]
+
+
+def test_fills_condensed_in_place():
+    expected = moebius.olr(W, MEANS, COVS)
+    out = np.full(3, np.nan)
+
+    assert moebius.olr(W, MEANS, COVS, out=out) is out
+    np.testing.assert_array_equal(out, expected.values)
+
+
+def test_fills_square_in_place():
+    expected = moebius.olr(W, MEANS, COVS)
+    out = np.full((3, 3), np.nan)
+
+    assert moebius.olr(W, MEANS, COVS, out=out) is out
+    np.testing.assert_array_equal(out, expected.matrix)
+
+
+def test_buffer_is_reused_across_calls():
+    out = np.empty(3)
+    for shift in [0.0, 1.0, 0.5]:
+        means = [[m[0] * (1.0 + shift)] for m in MEANS]
+        moebius.olr(W, means, COVS, out=out)
+        np.testing.assert_array_equal(out, moebius.olr(W, means, COVS).values)
+
+
+def test_out_may_alias_the_inputs():
+    # Three components have three pairs, so flat means fit into the output.
+    means = np.array([0.0, 1.5, 4.0])
+    expected = moebius.olr(W, means.copy(), [1.0, 0.5, 2.0]).values
+
+    assert moebius.olr(W, means, [1.0, 0.5, 2.0], out=means) is means
+    np.testing.assert_array_equal(means, expected)
+
+
+@pytest.mark.parametrize("out, match", [
+    (np.empty(4), "shape"),
+    (np.empty((3, 2)), "shape"),
+    (np.empty(3, dtype=np.float32), "float64"),
+    ([0.0, 0.0, 0.0], "numpy array"),
+    (np.empty(6)[::2], "contiguous"),
+])
+def test_rejects_invalid_buffers(out, match):
+    with pytest.raises(ValueError, match=match):
+        moebius.olr(W, MEANS, COVS, out=out)
+
+
+def test_rejects_read_only_buffers():
+    out = np.empty(3)
+    out.flags.writeable = False
+
+    with pytest.raises(ValueError, match="writable"):
+        moebius.olr(W, MEANS, COVS, out=out)
+
+
+def test_rejects_drop_nan():
+    with pytest.raises(ValueError, match="drop_nan"):
+        moebius.olr(W, MEANS, COVS, out=np.empty(3), drop_nan=True)
@@ -0,0 +1,35 @@
+"""Tests of the column-oriented results. Run with `pytest tests/python` after `maturin develop`."""
+
+import numpy as np
+
+import moebius
+
+W = [0.3, 0.3, 0.4]
+MEANS = np.array([[0.0, 0.0], [1.5, 0.5], [6.0, -1.0]])
+COVS = np.array([
+    [[1.0, 0.2], [0.2, 0.8]],
+    [[0.7, -0.1], [-0.1, 1.1]],
+    [[1.5, 0.0], [0.0, 0.5]],
+])
+
+
+def test_columns_match_plain_api():
+    records = moebius.olr_records(W, MEANS, COVS, n_points=1000)
+
+    assert set(records) == {"i", "j", "olr", "ln_peak", "ln_saddle", "error_estimate", "extreme_range", "timed_out"}
+    assert {len(column) for column in records.values()} == {3}
+    assert records["i"].dtype == np.int64 and records["j"].dtype == np.int64
+    for name in ["olr", "ln_peak", "ln_saddle", "error_estimate"]:
+        assert records[name].dtype == np.float64
+    assert records["extreme_range"].dtype == np.bool_ and records["timed_out"].dtype == np.bool_
+
+    np.testing.assert_array_equal(records["i"], [0, 0, 1])
+    np.testing.assert_array_equal(records["j"], [1, 2, 2])
+    np.testing.assert_array_equal(records["olr"], moebius.olr_list(W, MEANS, COVS, n_points=1000))
+
+
+def test_minimal_columns():
+    records = moebius.olr_records(W, MEANS, COVS, minimal=True)
+
+    assert list(records) == ["i", "j", "olr"]
+    np.testing.assert_array_equal(records["olr"], moebius.olr_records(W, MEANS, COVS)["olr"])
@@ -0,0 +1,25 @@
+"""Tests of the condensed pair indices. Run with `pytest tests/python` after `maturin develop`."""
+
+import pytest
+
+import moebius
+
+
+def test_round_trip():
+    n = 5
+    pairs = [(i, j) for i in range(n) for j in range(i + 1, n)]
+
+    assert moebius.condensed_len(n) == len(pairs)
+    for k, (i, j) in enumerate(pairs):
+        assert moebius.pair_to_index(i, j, n) == k
+        assert moebius.pair_to_index(j, i, n) == k
+        assert moebius.index_to_pair(k, n) == (i, j)
+
+
+def test_out_of_range():
+    with pytest.raises(IndexError):
+        moebius.pair_to_index(2, 2, 5)
+    with pytest.raises(IndexError):
+        moebius.pair_to_index(1, 5, 5)
+    with pytest.raises(IndexError):
+        moebius.index_to_pair(10, 5)
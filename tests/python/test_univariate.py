@@ -0,0 +1,37 @@
+"""Tests of flat means and variances of 1D mixtures. Run with `pytest tests/python` after `maturin develop`."""
+
+import numpy as np
+import pytest
+
+import moebius
+
+W = [0.3, 0.3, 0.4]
+NESTED_MEANS = [[0.0], [1.5], [4.0]]
+NESTED_COVS = [[[1.0]], [[0.5]], [[2.0]]]
+
+
+@pytest.mark.parametrize("means", [[0.0, 1.5, 4.0], NESTED_MEANS, np.array([0.0, 1.5, 4.0])])
+@pytest.mark.parametrize("covs", [[1.0, 0.5, 2.0], NESTED_COVS, np.array([1.0, 0.5, 2.0])])
+def test_flat_inputs_match_nested(means, covs):
+    expected = moebius.olr_list(W, NESTED_MEANS, NESTED_COVS)
+
+    assert moebius.olr_list(W, means, covs) == expected
+
+
+def test_flat_means_with_multivariate_covs():
+    covs = np.stack([np.eye(2)] * 3)
+
+    with pytest.raises(ValueError, match="flat means"):
+        moebius.olr_list(W, [0.0, 1.5, 4.0], covs)
+    with pytest.raises(ValueError, match="flat means"):
+        moebius.olr_sparse(W, np.array([0.0, 1.5, 4.0]), covs, 0.5)
+
+
+def test_flat_covs_with_multivariate_means():
+    with pytest.raises(ValueError, match="flat covs"):
+        moebius.olr_list(W, [[0.0, 0.0], [1.5, 0.0], [4.0, 1.0]], [1.0, 0.5, 2.0])
+
+
+def test_nested_form_required_without_promotion():
+    with pytest.raises(TypeError):
+        moebius.olr_list(W, [0.0, 1.5, 4.0], NESTED_COVS, promote_1d=False)